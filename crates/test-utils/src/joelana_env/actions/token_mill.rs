@@ -3,7 +3,10 @@ use crate::{
         get_event_authority, make_address, parse_custom_error, AccountMetaVecExt,
         InstructionGenerator, JoelanaEnv, TokenType, ACTORS,
     },
-    utils::token_mill::{constants::*, curve_generator::Curve},
+    utils::token_mill::{
+        constants::*,
+        curve_generator::{Curve, DEFAULT_PRICES_LENGTH},
+    },
 };
 use anchor_lang::{prelude::AccountMeta, Id, InstructionData};
 use anchor_spl::{
@@ -12,13 +15,19 @@ use anchor_spl::{
 };
 use anyhow::Result;
 use litesvm::types::{FailedTransactionMetadata, TransactionMetadata};
+use mock_transfer_hook::TRANSFER_COUNTER_PDA_SEED;
 use solana_sdk::{instruction::Instruction, pubkey::Pubkey, transaction::TransactionError};
+use spl_transfer_hook_interface::get_extra_account_metas_address;
 use token_mill::{
     errors::TokenMillError,
-    manager::swap_manager::{SwapAmountType, SwapType},
+    manager::swap_manager::{SwapAmountType, SwapBatchMode, SwapLeg, SwapType},
     state::{
-        QuoteTokenBadgeStatus, MARKET_PDA_SEED, MARKET_STAKING_PDA_SEED,
-        QUOTE_TOKEN_BADGE_PDA_SEED, REFERRAL_ACCOUNT_PDA_SEED, STAKING_POSITION_PDA_SEED,
+        BuyTaxRecipient, CurveKind, FeeDiscountTier, LaunchProtection, MarketStatus,
+        PausableOperation, PresaleProof, PriceInterpolation, QuoteTokenBadgeStatus,
+        TransferHookBadgeStatus, DCA_ORDER_PDA_SEED, LAUNCH_ALLOCATION_PDA_SEED,
+        LIMIT_ORDER_PDA_SEED, MARKET_ORACLE_PDA_SEED, MARKET_PDA_SEED, MARKET_STAKING_PDA_SEED,
+        MARKET_STATS_PDA_SEED, QUOTE_TOKEN_BADGE_PDA_SEED, REFERRAL_ACCOUNT_PDA_SEED,
+        STAKING_POSITION_PDA_SEED, TRANSFER_HOOK_BADGE_PDA_SEED,
     },
 };
 
@@ -41,6 +50,15 @@ pub struct TokenMillEnv {
     pub base_token_type: TokenType,
     pub quote_token_mint: Option<Pubkey>,
     pub quote_token_type: TokenType,
+    pub graduation_thresholds: Option<(u64, u64)>,
+    pub prices_length: usize,
+    pub price_interpolation: PriceInterpolation,
+    pub curve_kind: CurveKind,
+    pub trading_start_ts: Option<i64>,
+    pub launch_protection: Option<LaunchProtection>,
+    pub buy_tax: Option<(u16, i64, BuyTaxRecipient)>,
+    pub presale: Option<([u8; 32], i64)>,
+    pub transfer_hook_program: Option<Pubkey>,
 }
 
 impl Default for TokenMillEnv {
@@ -59,6 +77,7 @@ impl TokenMillEnv {
 
         svm.add_token_mill_program();
         svm.add_metadata_program();
+        svm.add_mock_transfer_hook_program();
 
         svm.execute_actions(&[
             &CreateConfigAction::new(),
@@ -74,6 +93,15 @@ impl TokenMillEnv {
             base_token_type: TokenType::Token2022,
             quote_token_mint: None,
             quote_token_type: TokenType::Token,
+            graduation_thresholds: None,
+            prices_length: DEFAULT_PRICES_LENGTH,
+            price_interpolation: PriceInterpolation::Linear,
+            curve_kind: CurveKind::PiecewiseLinear,
+            trading_start_ts: None,
+            launch_protection: None,
+            buy_tax: None,
+            presale: None,
+            transfer_hook_program: None,
         }
     }
 
@@ -82,6 +110,7 @@ impl TokenMillEnv {
 
         svm.set_token_mill_program_from_binary(path);
         svm.add_metadata_program();
+        svm.add_mock_transfer_hook_program();
 
         svm.execute_actions(&[
             &CreateConfigAction::new(),
@@ -97,6 +126,15 @@ impl TokenMillEnv {
             base_token_type: TokenType::Token2022,
             quote_token_mint: None,
             quote_token_type: TokenType::Token,
+            graduation_thresholds: None,
+            prices_length: DEFAULT_PRICES_LENGTH,
+            price_interpolation: PriceInterpolation::Linear,
+            curve_kind: CurveKind::PiecewiseLinear,
+            trading_start_ts: None,
+            launch_protection: None,
+            buy_tax: None,
+            presale: None,
+            transfer_hook_program: None,
         }
     }
 
@@ -107,6 +145,29 @@ impl TokenMillEnv {
     pub fn with_quote_token_mint(mut self, quote_token_type: TokenType, decimals: u8) -> Self {
         let quote_token = self.svm.create_token(quote_token_type, decimals).unwrap();
 
+        self.set_quote_token_mint(quote_token, quote_token_type)
+    }
+
+    /// Creates a Token-2022 quote mint with a transfer fee, along with the badge it requires
+    pub fn with_transfer_fee_quote_token_mint(
+        mut self,
+        decimals: u8,
+        transfer_fee_basis_points: u16,
+        maximum_fee: u64,
+    ) -> Self {
+        let quote_token = self
+            .svm
+            .create_token_with_transfer_fee(decimals, transfer_fee_basis_points, maximum_fee)
+            .unwrap();
+
+        self.svm
+            .execute_actions(&[&CreateQuoteAssetBadgeAction::new(quote_token)])
+            .unwrap();
+
+        self.set_quote_token_mint(quote_token, TokenType::Token2022)
+    }
+
+    fn set_quote_token_mint(mut self, quote_token: Pubkey, quote_token_type: TokenType) -> Self {
         self.svm
             .create_ata(
                 &CreateReferralAccountAction::new().referral_account,
@@ -127,12 +188,92 @@ impl TokenMillEnv {
         self
     }
 
+    /// Must be called before the market is created
+    pub fn with_graduation(mut self, supply_threshold: u64, quote_threshold: u64) -> Self {
+        self.graduation_thresholds = Some((supply_threshold, quote_threshold));
+
+        self
+    }
+
+    /// Must be called before the market is created
+    pub fn with_prices_length(mut self, prices_length: usize) -> Self {
+        self.prices_length = prices_length;
+
+        self
+    }
+
+    /// Must be called before the market is created
+    pub fn with_price_interpolation(mut self, price_interpolation: PriceInterpolation) -> Self {
+        self.price_interpolation = price_interpolation;
+
+        self
+    }
+
+    /// Must be called before the market is created
+    pub fn with_curve_kind(mut self, curve_kind: CurveKind) -> Self {
+        self.curve_kind = curve_kind;
+
+        self
+    }
+
+    /// Must be called before the market is created
+    pub fn with_trading_start(mut self, trading_start_ts: i64) -> Self {
+        self.trading_start_ts = Some(trading_start_ts);
+
+        self
+    }
+
+    /// Must be called before the market is created
+    pub fn with_launch_protection(mut self, window: i64, max_buy_per_wallet: u64) -> Self {
+        self.launch_protection = Some(LaunchProtection {
+            window,
+            max_buy_per_wallet,
+        });
+
+        self
+    }
+
+    /// Must be called before the market is created
+    pub fn with_buy_tax(
+        mut self,
+        start_bps: u16,
+        duration: i64,
+        recipient: BuyTaxRecipient,
+    ) -> Self {
+        self.buy_tax = Some((start_bps, duration, recipient));
+
+        self
+    }
+
+    /// Must be called before the market is created
+    pub fn with_presale(mut self, merkle_root: [u8; 32], end_ts: i64) -> Self {
+        self.presale = Some((merkle_root, end_ts));
+
+        self
+    }
+
+    /// Must be called before the market is created, the base token then has the mock transfer hook
+    pub fn with_transfer_hook(mut self) -> Self {
+        self.transfer_hook_program = Some(mock_transfer_hook::ID);
+
+        self
+    }
+
+    /// Accounts to append to the instructions transferring base tokens
+    pub fn transfer_hook_accounts(&self) -> Vec<AccountMeta> {
+        self.transfer_hook_program
+            .map_or(vec![], |transfer_hook_program| {
+                get_transfer_hook_accounts(&transfer_hook_program, &self.base_token_mint.unwrap())
+            })
+    }
+
     pub fn with_default_market(self) -> Self {
         self.with_market(make_address("base_token_mint"), DEFAULT_TOTAL_SUPPLY)
     }
 
     pub fn with_market(mut self, base_token_mint: Pubkey, total_supply: u64) -> Self {
         self.svm.change_payer("alice");
+        self.base_token_mint = Some(base_token_mint);
 
         let quote_token_mint = self.quote_token_mint.unwrap();
 
@@ -140,29 +281,120 @@ impl TokenMillEnv {
             TokenType::Token => {
                 let mut create_market_action = CreateMarketWithSplAction::new(&self);
                 create_market_action.total_supply = total_supply;
+                create_market_action.trading_start_ts = self.trading_start_ts;
+                create_market_action.launch_protection = self.launch_protection;
 
-                let set_prices_action = SetMarketPricesAction::new(Curve::default());
+                if !self
+                    .svm
+                    .account_exists(&create_market_action.quote_token_badge)
+                {
+                    create_market_action.no_badge();
+                }
 
-                self.svm
-                    .execute_actions(&[create_market_action.no_badge(), &set_prices_action])
-                    .unwrap();
+                self.svm.execute_actions(&[&create_market_action]).unwrap();
 
                 create_market_action.market
             }
             TokenType::Token2022 => {
                 let mut create_market_action = CreateMarketAction::new(&self);
                 create_market_action.total_supply = total_supply;
+                create_market_action.trading_start_ts = self.trading_start_ts;
+                create_market_action.launch_protection = self.launch_protection;
+
+                if !self
+                    .svm
+                    .account_exists(&create_market_action.quote_token_badge)
+                {
+                    create_market_action.no_badge();
+                }
+
+                if let Some(transfer_hook_program) = self.transfer_hook_program {
+                    let create_badge_action = CreateTransferHookBadgeAction::new();
+
+                    if !self
+                        .svm
+                        .account_exists(&create_badge_action.transfer_hook_badge)
+                    {
+                        self.svm.change_payer("admin");
+                        self.svm.execute_actions(&[&create_badge_action]).unwrap();
+                        self.svm.change_payer("alice");
+                    }
+
+                    create_market_action.with_transfer_hook(transfer_hook_program);
+                }
+
+                self.svm.execute_actions(&[&create_market_action]).unwrap();
+
+                if self.transfer_hook_program.is_some() {
+                    self.svm
+                        .execute_actions(&[&InitializeExtraAccountMetaListAction::new(
+                            base_token_mint,
+                        )])
+                        .unwrap();
+                }
 
-                let set_prices_action = SetMarketPricesAction::new(Curve::default());
+                create_market_action.market
+            }
+        };
 
-                self.svm
-                    .execute_actions(&[create_market_action.no_badge(), &set_prices_action])
-                    .unwrap();
+        let price_curve = match self.price_interpolation {
+            PriceInterpolation::Linear => Curve::new(self.prices_length, total_supply),
+            PriceInterpolation::Exponential => Curve::exponential(self.prices_length, total_supply),
+        };
 
-                create_market_action.market
+        let set_curve_action: Box<dyn InstructionGenerator> = match self.curve_kind {
+            CurveKind::PiecewiseLinear => {
+                let mut set_prices_action = SetMarketPricesAction::new(price_curve);
+                set_prices_action.market = market;
+
+                Box::new(set_prices_action)
+            }
+            CurveKind::VirtualConstantProduct {
+                virtual_base,
+                virtual_quote,
+                spread_bps,
+            } => {
+                let mut set_virtual_reserves_action =
+                    SetMarketVirtualReservesAction::new(virtual_base, virtual_quote, spread_bps);
+                set_virtual_reserves_action.market = market;
+
+                Box::new(set_virtual_reserves_action)
             }
         };
 
+        // Settings must be set before the prices
+        let mut settings_actions: Vec<Box<dyn InstructionGenerator>> = Vec::new();
+
+        if let Some((supply_threshold, quote_threshold)) = self.graduation_thresholds {
+            let mut graduation_action =
+                SetMarketGraduationAction::new(supply_threshold, quote_threshold);
+            graduation_action.market = market;
+
+            settings_actions.push(Box::new(graduation_action));
+        }
+
+        if let Some((start_bps, duration, recipient)) = self.buy_tax {
+            let mut buy_tax_action = SetMarketBuyTaxAction::new(start_bps, duration, recipient);
+            buy_tax_action.market = market;
+
+            settings_actions.push(Box::new(buy_tax_action));
+        }
+
+        if let Some((merkle_root, end_ts)) = self.presale {
+            let mut presale_action = SetMarketPresaleAction::new(merkle_root, end_ts);
+            presale_action.market = market;
+
+            settings_actions.push(Box::new(presale_action));
+        }
+
+        let actions = settings_actions
+            .iter()
+            .chain([&set_curve_action])
+            .map(|action| action.as_ref())
+            .collect::<Vec<_>>();
+
+        self.svm.execute_actions(&actions).unwrap();
+
         // Create ATAs
         for actor in ACTORS {
             self.svm
@@ -175,7 +407,6 @@ impl TokenMillEnv {
             .unwrap();
 
         self.market = market;
-        self.base_token_mint = Some(base_token_mint);
 
         self.svm
             .tokens
@@ -415,6 +646,90 @@ impl InstructionGenerator for UpdateDefaultFeeSharesAction {
     }
 }
 
+pub struct UpdateFeeDiscountTiersAction {
+    // Accounts
+    pub config: Pubkey,
+    pub signer: Pubkey,
+    // Args
+    pub fee_discount_tiers: Vec<FeeDiscountTier>,
+}
+
+impl UpdateFeeDiscountTiersAction {
+    pub fn new(fee_discount_tiers: Vec<FeeDiscountTier>) -> Self {
+        Self {
+            config: make_address("config"),
+            signer: make_address("admin"),
+            fee_discount_tiers,
+        }
+    }
+}
+
+impl InstructionGenerator for UpdateFeeDiscountTiersAction {
+    fn accounts(&self) -> Vec<AccountMeta> {
+        let mut accounts = vec![AccountMeta::new(self.config, false)];
+
+        accounts
+            .append_payer(self.signer)
+            .append_cpi_event_accounts(tm_event_authority());
+
+        accounts
+    }
+
+    fn instruction(&self) -> Instruction {
+        let input = token_mill::instruction::UpdateFeeDiscountTiers {
+            fee_discount_tiers: self.fee_discount_tiers.clone(),
+        };
+
+        Instruction {
+            program_id: token_mill::ID,
+            accounts: self.accounts(),
+            data: input.data(),
+        }
+    }
+}
+
+pub struct UpdateSubReferralFeeShareAction {
+    // Accounts
+    pub config: Pubkey,
+    pub signer: Pubkey,
+    // Args
+    pub new_sub_referral_fee_share: u16,
+}
+
+impl UpdateSubReferralFeeShareAction {
+    pub fn new(new_sub_referral_fee_share: u16) -> Self {
+        Self {
+            config: make_address("config"),
+            signer: make_address("admin"),
+            new_sub_referral_fee_share,
+        }
+    }
+}
+
+impl InstructionGenerator for UpdateSubReferralFeeShareAction {
+    fn accounts(&self) -> Vec<AccountMeta> {
+        let mut accounts = vec![AccountMeta::new(self.config, false)];
+
+        accounts
+            .append_payer(self.signer)
+            .append_cpi_event_accounts(tm_event_authority());
+
+        accounts
+    }
+
+    fn instruction(&self) -> Instruction {
+        let input = token_mill::instruction::UpdateSubReferralFeeShare {
+            new_sub_referral_fee_share: self.new_sub_referral_fee_share,
+        };
+
+        Instruction {
+            program_id: token_mill::ID,
+            accounts: self.accounts(),
+            data: input.data(),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct UpdateProtocolFeeRecipientAction {
     // Accounts
@@ -458,6 +773,51 @@ impl InstructionGenerator for UpdateProtocolFeeRecipientAction {
     }
 }
 
+pub struct SetOperationPausedAction {
+    // Accounts
+    pub config: Pubkey,
+    pub signer: Pubkey,
+    // Args
+    pub operation: PausableOperation,
+    pub paused: bool,
+}
+
+impl SetOperationPausedAction {
+    pub fn new(operation: PausableOperation, paused: bool) -> Self {
+        Self {
+            config: make_address("config"),
+            signer: make_address("admin"),
+            operation,
+            paused,
+        }
+    }
+}
+
+impl InstructionGenerator for SetOperationPausedAction {
+    fn accounts(&self) -> Vec<AccountMeta> {
+        let mut accounts = vec![AccountMeta::new(self.config, false)];
+
+        accounts
+            .append_payer(self.signer)
+            .append_cpi_event_accounts(tm_event_authority());
+
+        accounts
+    }
+
+    fn instruction(&self) -> Instruction {
+        let input = token_mill::instruction::SetOperationPaused {
+            operation: self.operation,
+            paused: self.paused,
+        };
+
+        Instruction {
+            program_id: token_mill::ID,
+            accounts: self.accounts(),
+            data: input.data(),
+        }
+    }
+}
+
 pub struct CreateQuoteAssetBadgeAction {
     // Accounts
     pub config: Pubkey,
@@ -577,98 +937,52 @@ impl InstructionGenerator for UpdateQuoteAssetBadgeAction {
     }
 }
 
-pub struct CreateMarketAction {
+pub struct CreateTransferHookBadgeAction {
     // Accounts
     pub config: Pubkey,
-    pub market: Pubkey,
-    pub base_token_mint: Pubkey,
-    pub market_base_token_ata: Pubkey,
-    pub quote_token_badge: Pubkey,
-    pub quote_token_mint: Pubkey,
+    pub transfer_hook_badge: Pubkey,
+    pub transfer_hook_program: Pubkey,
     pub signer: Pubkey,
-    // Args
-    pub total_supply: u64,
 }
 
-impl CreateMarketAction {
-    pub fn new(testing_env: &TokenMillEnv) -> Self {
+impl CreateTransferHookBadgeAction {
+    pub fn new() -> Self {
         let config = make_address("config");
-
-        let base_token_mint = testing_env.base_token_mint.unwrap();
-
-        let market = Pubkey::find_program_address(
-            &[MARKET_PDA_SEED.as_bytes(), &base_token_mint.to_bytes()],
-            &token_mill::ID,
-        )
-        .0;
-
-        let quote_token_mint = testing_env.quote_token_mint.unwrap();
-
-        let quote_asset_badge = Pubkey::find_program_address(
-            &[
-                QUOTE_TOKEN_BADGE_PDA_SEED.as_bytes(),
-                &config.to_bytes(),
-                &quote_token_mint.to_bytes(),
-            ],
-            &token_mill::ID,
-        )
-        .0;
-
-        let market_base_token_ata = get_associated_token_address_with_program_id(
-            &market,
-            &base_token_mint,
-            &spl_token_2022::id(),
-        );
+        let transfer_hook_program = mock_transfer_hook::ID;
 
         Self {
             config,
-            market,
-            base_token_mint,
-            market_base_token_ata,
-            quote_token_mint,
-            quote_token_badge: quote_asset_badge,
-            signer: make_address("alice"),
-            total_supply: DEFAULT_TOTAL_SUPPLY,
+            transfer_hook_badge: transfer_hook_badge_address(&config, &transfer_hook_program),
+            transfer_hook_program,
+            signer: make_address("admin"),
         }
     }
+}
 
-    pub fn no_badge(&mut self) -> &mut Self {
-        self.quote_token_badge = token_mill::ID;
-
-        self
+impl Default for CreateTransferHookBadgeAction {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
-impl InstructionGenerator for CreateMarketAction {
+impl InstructionGenerator for CreateTransferHookBadgeAction {
     fn accounts(&self) -> Vec<AccountMeta> {
         let mut accounts = vec![
             AccountMeta::new_readonly(self.config, false),
-            AccountMeta::new(self.market, false),
-            AccountMeta::new(self.base_token_mint, true),
-            AccountMeta::new(self.market_base_token_ata, false),
-            AccountMeta::new_readonly(self.quote_token_badge, false),
-            AccountMeta::new_readonly(self.quote_token_mint, false),
+            AccountMeta::new(self.transfer_hook_badge, false),
+            AccountMeta::new_readonly(self.transfer_hook_program, false),
         ];
 
         accounts
             .append_payer(self.signer)
             .append_system_program()
-            .append_token_2022_program()
-            .append_associated_token_program()
             .append_cpi_event_accounts(tm_event_authority());
 
         accounts
     }
 
     fn instruction(&self) -> Instruction {
-        let input = token_mill::instruction::CreateMarket {
-            name: "name".to_string(),
-            symbol: "symbol".to_string(),
-            uri: "uri".to_string(),
-            total_supply: self.total_supply,
-            creator_fee_share: DEFAULT_CREATOR_FEE_SHARE,
-            staking_fee_share: DEFAULT_STAKING_FEE_SHARE,
-        };
+        let input = token_mill::instruction::CreateTransferHookBadge {};
 
         Instruction {
             program_id: token_mill::ID,
@@ -678,29 +992,287 @@ impl InstructionGenerator for CreateMarketAction {
     }
 }
 
-pub struct CreateMarketWithSplAction {
+pub struct UpdateTransferHookBadgeAction {
     // Accounts
     pub config: Pubkey,
-    pub market: Pubkey,
-    pub base_token_mint: Pubkey,
-    pub base_token_metadata: Pubkey,
-    pub market_base_token_ata: Pubkey,
-    pub quote_token_badge: Pubkey,
-    pub quote_token_mint: Pubkey,
+    pub transfer_hook_badge: Pubkey,
+    pub transfer_hook_program: Pubkey,
     pub signer: Pubkey,
     // Args
-    pub total_supply: u64,
+    pub status: TransferHookBadgeStatus,
 }
 
-impl CreateMarketWithSplAction {
-    pub fn new(testing_env: &TokenMillEnv) -> Self {
+impl UpdateTransferHookBadgeAction {
+    pub fn new(status: TransferHookBadgeStatus) -> Self {
         let config = make_address("config");
+        let transfer_hook_program = mock_transfer_hook::ID;
 
-        let base_token_mint = testing_env.base_token_mint.unwrap();
-
-        let base_token_metadata = Pubkey::find_program_address(
-            &[
-                "metadata".as_bytes(),
+        Self {
+            config,
+            transfer_hook_badge: transfer_hook_badge_address(&config, &transfer_hook_program),
+            transfer_hook_program,
+            signer: make_address("admin"),
+            status,
+        }
+    }
+}
+
+impl InstructionGenerator for UpdateTransferHookBadgeAction {
+    fn accounts(&self) -> Vec<AccountMeta> {
+        let mut accounts = vec![
+            AccountMeta::new_readonly(self.config, false),
+            AccountMeta::new(self.transfer_hook_badge, false),
+            AccountMeta::new_readonly(self.transfer_hook_program, false),
+        ];
+
+        accounts
+            .append_payer(self.signer)
+            .append_cpi_event_accounts(tm_event_authority());
+
+        accounts
+    }
+
+    fn instruction(&self) -> Instruction {
+        let input = token_mill::instruction::UpdateTransferHookBadge {
+            status: self.status,
+        };
+
+        Instruction {
+            program_id: token_mill::ID,
+            accounts: self.accounts(),
+            data: input.data(),
+        }
+    }
+}
+
+fn transfer_hook_badge_address(config: &Pubkey, transfer_hook_program: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            TRANSFER_HOOK_BADGE_PDA_SEED.as_bytes(),
+            &config.to_bytes(),
+            &transfer_hook_program.to_bytes(),
+        ],
+        &token_mill::ID,
+    )
+    .0
+}
+
+pub fn get_transfer_counter_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[TRANSFER_COUNTER_PDA_SEED.as_bytes(), &mint.to_bytes()],
+        &mock_transfer_hook::ID,
+    )
+    .0
+}
+
+/// Hook program, extra account metas and counter of the mock transfer hook of `mint`
+pub fn get_transfer_hook_accounts(
+    transfer_hook_program: &Pubkey,
+    mint: &Pubkey,
+) -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new_readonly(*transfer_hook_program, false),
+        AccountMeta::new_readonly(
+            get_extra_account_metas_address(mint, transfer_hook_program),
+            false,
+        ),
+        AccountMeta::new(get_transfer_counter_address(mint), false),
+    ]
+}
+
+/// Sets up the extra accounts of the mock transfer hook for `mint`
+pub struct InitializeExtraAccountMetaListAction {
+    // Accounts
+    pub extra_account_meta_list: Pubkey,
+    pub mint: Pubkey,
+    pub counter: Pubkey,
+    pub signer: Pubkey,
+}
+
+impl InitializeExtraAccountMetaListAction {
+    pub fn new(mint: Pubkey) -> Self {
+        Self {
+            extra_account_meta_list: get_extra_account_metas_address(
+                &mint,
+                &mock_transfer_hook::ID,
+            ),
+            mint,
+            counter: get_transfer_counter_address(&mint),
+            signer: make_address("alice"),
+        }
+    }
+}
+
+impl InstructionGenerator for InitializeExtraAccountMetaListAction {
+    fn accounts(&self) -> Vec<AccountMeta> {
+        let mut accounts = vec![
+            AccountMeta::new(self.extra_account_meta_list, false),
+            AccountMeta::new_readonly(self.mint, false),
+            AccountMeta::new(self.counter, false),
+        ];
+
+        accounts.append_payer(self.signer).append_system_program();
+
+        accounts
+    }
+
+    fn instruction(&self) -> Instruction {
+        let input = mock_transfer_hook::instruction::InitializeExtraAccountMetaList {};
+
+        Instruction {
+            program_id: mock_transfer_hook::ID,
+            accounts: self.accounts(),
+            data: input.data(),
+        }
+    }
+}
+
+pub struct CreateMarketAction {
+    // Accounts
+    pub config: Pubkey,
+    pub market: Pubkey,
+    pub base_token_mint: Pubkey,
+    pub market_base_token_ata: Pubkey,
+    pub quote_token_badge: Pubkey,
+    pub transfer_hook_badge: Pubkey,
+    pub quote_token_mint: Pubkey,
+    pub signer: Pubkey,
+    // Args
+    pub total_supply: u64,
+    pub trading_start_ts: Option<i64>,
+    pub launch_protection: Option<LaunchProtection>,
+    pub transfer_hook_program_id: Option<Pubkey>,
+}
+
+impl CreateMarketAction {
+    pub fn new(testing_env: &TokenMillEnv) -> Self {
+        let config = make_address("config");
+
+        let base_token_mint = testing_env.base_token_mint.unwrap();
+
+        let market = Pubkey::find_program_address(
+            &[MARKET_PDA_SEED.as_bytes(), &base_token_mint.to_bytes()],
+            &token_mill::ID,
+        )
+        .0;
+
+        let quote_token_mint = testing_env.quote_token_mint.unwrap();
+
+        let quote_asset_badge = Pubkey::find_program_address(
+            &[
+                QUOTE_TOKEN_BADGE_PDA_SEED.as_bytes(),
+                &config.to_bytes(),
+                &quote_token_mint.to_bytes(),
+            ],
+            &token_mill::ID,
+        )
+        .0;
+
+        let market_base_token_ata = get_associated_token_address_with_program_id(
+            &market,
+            &base_token_mint,
+            &spl_token_2022::id(),
+        );
+
+        Self {
+            config,
+            market,
+            base_token_mint,
+            market_base_token_ata,
+            quote_token_mint,
+            quote_token_badge: quote_asset_badge,
+            transfer_hook_badge: token_mill::ID,
+            signer: make_address("alice"),
+            total_supply: DEFAULT_TOTAL_SUPPLY,
+            trading_start_ts: None,
+            launch_protection: None,
+            transfer_hook_program_id: None,
+        }
+    }
+
+    pub fn no_badge(&mut self) -> &mut Self {
+        self.quote_token_badge = token_mill::ID;
+
+        self
+    }
+
+    pub fn with_transfer_hook(&mut self, transfer_hook_program: Pubkey) -> &mut Self {
+        self.transfer_hook_badge =
+            transfer_hook_badge_address(&self.config, &transfer_hook_program);
+        self.transfer_hook_program_id = Some(transfer_hook_program);
+
+        self
+    }
+}
+
+impl InstructionGenerator for CreateMarketAction {
+    fn accounts(&self) -> Vec<AccountMeta> {
+        let mut accounts = vec![
+            AccountMeta::new_readonly(self.config, false),
+            AccountMeta::new(self.market, false),
+            AccountMeta::new(self.base_token_mint, true),
+            AccountMeta::new(self.market_base_token_ata, false),
+            AccountMeta::new_readonly(self.quote_token_badge, false),
+            AccountMeta::new_readonly(self.transfer_hook_badge, false),
+            AccountMeta::new_readonly(self.quote_token_mint, false),
+        ];
+
+        accounts
+            .append_payer(self.signer)
+            .append_system_program()
+            .append_token_2022_program()
+            .append_associated_token_program()
+            .append_cpi_event_accounts(tm_event_authority());
+
+        accounts
+    }
+
+    fn instruction(&self) -> Instruction {
+        let input = token_mill::instruction::CreateMarket {
+            name: "name".to_string(),
+            symbol: "symbol".to_string(),
+            uri: "uri".to_string(),
+            total_supply: self.total_supply,
+            creator_fee_share: DEFAULT_CREATOR_FEE_SHARE,
+            staking_fee_share: DEFAULT_STAKING_FEE_SHARE,
+            trading_start_ts: self.trading_start_ts,
+            launch_protection: self.launch_protection,
+            transfer_hook_program_id: self.transfer_hook_program_id,
+        };
+
+        Instruction {
+            program_id: token_mill::ID,
+            accounts: self.accounts(),
+            data: input.data(),
+        }
+    }
+}
+
+pub struct CreateMarketWithSplAction {
+    // Accounts
+    pub config: Pubkey,
+    pub market: Pubkey,
+    pub base_token_mint: Pubkey,
+    pub base_token_metadata: Pubkey,
+    pub market_base_token_ata: Pubkey,
+    pub quote_token_badge: Pubkey,
+    pub quote_token_mint: Pubkey,
+    pub signer: Pubkey,
+    // Args
+    pub total_supply: u64,
+    pub trading_start_ts: Option<i64>,
+    pub launch_protection: Option<LaunchProtection>,
+}
+
+impl CreateMarketWithSplAction {
+    pub fn new(testing_env: &TokenMillEnv) -> Self {
+        let config = make_address("config");
+
+        let base_token_mint = testing_env.base_token_mint.unwrap();
+
+        let base_token_metadata = Pubkey::find_program_address(
+            &[
+                "metadata".as_bytes(),
                 &Metadata::id().to_bytes(),
                 &base_token_mint.to_bytes(),
             ],
@@ -742,6 +1314,8 @@ impl CreateMarketWithSplAction {
             quote_token_badge: quote_asset_badge,
             signer: make_address("alice"),
             total_supply: DEFAULT_TOTAL_SUPPLY,
+            trading_start_ts: None,
+            launch_protection: None,
         }
     }
 
@@ -783,6 +1357,8 @@ impl InstructionGenerator for CreateMarketWithSplAction {
             total_supply: self.total_supply,
             creator_fee_share: DEFAULT_CREATOR_FEE_SHARE,
             staking_fee_share: DEFAULT_STAKING_FEE_SHARE,
+            trading_start_ts: self.trading_start_ts,
+            launch_protection: self.launch_protection,
         };
 
         Instruction {
@@ -845,11 +1421,15 @@ impl InstructionGenerator for SetMarketPricesAction {
         let Curve {
             bid_prices,
             ask_prices,
-        } = self.price_curve;
+            supply_breakpoints,
+            price_interpolation,
+        } = self.price_curve.clone();
 
         let input = token_mill::instruction::SetMarketPrices {
             bid_prices,
             ask_prices,
+            supply_breakpoints,
+            price_interpolation,
         };
 
         Instruction {
@@ -860,141 +1440,1797 @@ impl InstructionGenerator for SetMarketPricesAction {
     }
 }
 
-pub struct SwapAction {
+pub struct SetMarketGraduationAction {
     // Accounts
-    pub config: Pubkey,
     pub market: Pubkey,
-    pub base_token_mint: Pubkey,
-    pub quote_token_mint: Pubkey,
-    pub market_base_token_ata: Pubkey,
-    pub market_quote_token_ata: Pubkey,
-    pub user_base_token_ata: Pubkey,
-    pub user_quote_token_ata: Pubkey,
-    pub protocol_quote_token_ata: Pubkey,
-    pub referral_quote_token_ata: Pubkey,
     pub signer: Pubkey,
-    pub base_token_program: Pubkey,
-    pub quote_token_program: Pubkey,
     // Args
-    pub swap_type: SwapType,
-    pub swap_amount_type: SwapAmountType,
-    pub amount: u64,
-    pub other_amount_threshold: u64,
+    pub supply_threshold: u64,
+    pub quote_threshold: u64,
 }
 
-impl SwapAction {
-    pub fn new(
-        token_mill_env: &TokenMillEnv,
-        swap_type: SwapType,
-        swap_amount_type: SwapAmountType,
-        amount: u64,
-        other_amount_threshold: u64,
-        referrer: Option<Pubkey>,
-    ) -> Self {
-        let config = make_address("config");
-        let base_token_mint = token_mill_env.base_token_mint.unwrap();
-        let base_token_program = token_mill_env.base_token_type.program_address();
-
-        let signer = make_address("bob");
-
+impl SetMarketGraduationAction {
+    pub fn new(supply_threshold: u64, quote_threshold: u64) -> Self {
         let market = Pubkey::find_program_address(
-            &[MARKET_PDA_SEED.as_bytes(), &base_token_mint.to_bytes()],
+            &[
+                MARKET_PDA_SEED.as_bytes(),
+                &make_address("base_token_mint").to_bytes(),
+            ],
             &token_mill::ID,
         )
         .0;
 
-        let quote_token_mint = token_mill_env.quote_token_mint.unwrap();
-        let quote_token_program = token_mill_env.quote_token_type.program_address();
+        Self {
+            market,
+            signer: make_address("alice"),
+            supply_threshold,
+            quote_threshold,
+        }
+    }
+}
 
-        let market_base_token_ata = get_associated_token_address_with_program_id(
-            &market,
-            &base_token_mint,
-            &token_mill_env.base_token_type.program_address(),
-        );
+impl InstructionGenerator for SetMarketGraduationAction {
+    fn accounts(&self) -> Vec<AccountMeta> {
+        let mut accounts = vec![AccountMeta::new(self.market, false)];
 
-        let market_quote_token_ata = get_associated_token_address_with_program_id(
-            &market,
-            &quote_token_mint,
-            &quote_token_program,
-        );
+        accounts
+            .append_payer(self.signer)
+            .append_cpi_event_accounts(tm_event_authority());
 
-        let user_base_token_ata = get_associated_token_address_with_program_id(
-            &signer,
-            &base_token_mint,
-            &token_mill_env.base_token_type.program_address(),
-        );
+        accounts
+    }
+
+    fn instruction(&self) -> Instruction {
+        let input = token_mill::instruction::SetMarketGraduation {
+            supply_threshold: self.supply_threshold,
+            quote_threshold: self.quote_threshold,
+        };
+
+        Instruction {
+            program_id: token_mill::ID,
+            accounts: self.accounts(),
+            data: input.data(),
+        }
+    }
+}
+
+pub struct SetMarketBuyTaxAction {
+    // Accounts
+    pub market: Pubkey,
+    pub signer: Pubkey,
+    // Args
+    pub start_bps: u16,
+    pub duration: i64,
+    pub recipient: BuyTaxRecipient,
+}
+
+impl SetMarketBuyTaxAction {
+    pub fn new(start_bps: u16, duration: i64, recipient: BuyTaxRecipient) -> Self {
+        let market = Pubkey::find_program_address(
+            &[
+                MARKET_PDA_SEED.as_bytes(),
+                &make_address("base_token_mint").to_bytes(),
+            ],
+            &token_mill::ID,
+        )
+        .0;
+
+        Self {
+            market,
+            signer: make_address("alice"),
+            start_bps,
+            duration,
+            recipient,
+        }
+    }
+}
+
+impl InstructionGenerator for SetMarketBuyTaxAction {
+    fn accounts(&self) -> Vec<AccountMeta> {
+        let mut accounts = vec![AccountMeta::new(self.market, false)];
+
+        accounts
+            .append_payer(self.signer)
+            .append_cpi_event_accounts(tm_event_authority());
+
+        accounts
+    }
+
+    fn instruction(&self) -> Instruction {
+        let input = token_mill::instruction::SetMarketBuyTax {
+            start_bps: self.start_bps,
+            duration: self.duration,
+            recipient: self.recipient,
+        };
+
+        Instruction {
+            program_id: token_mill::ID,
+            accounts: self.accounts(),
+            data: input.data(),
+        }
+    }
+}
+
+pub struct SetMarketPresaleAction {
+    // Accounts
+    pub market: Pubkey,
+    pub signer: Pubkey,
+    // Args
+    pub merkle_root: [u8; 32],
+    pub end_ts: i64,
+}
+
+impl SetMarketPresaleAction {
+    pub fn new(merkle_root: [u8; 32], end_ts: i64) -> Self {
+        let market = Pubkey::find_program_address(
+            &[
+                MARKET_PDA_SEED.as_bytes(),
+                &make_address("base_token_mint").to_bytes(),
+            ],
+            &token_mill::ID,
+        )
+        .0;
+
+        Self {
+            market,
+            signer: make_address("alice"),
+            merkle_root,
+            end_ts,
+        }
+    }
+}
+
+impl InstructionGenerator for SetMarketPresaleAction {
+    fn accounts(&self) -> Vec<AccountMeta> {
+        let mut accounts = vec![AccountMeta::new(self.market, false)];
+
+        accounts
+            .append_payer(self.signer)
+            .append_cpi_event_accounts(tm_event_authority());
+
+        accounts
+    }
+
+    fn instruction(&self) -> Instruction {
+        let input = token_mill::instruction::SetMarketPresale {
+            merkle_root: self.merkle_root,
+            end_ts: self.end_ts,
+        };
+
+        Instruction {
+            program_id: token_mill::ID,
+            accounts: self.accounts(),
+            data: input.data(),
+        }
+    }
+}
+
+pub struct SetMarketVirtualReservesAction {
+    // Accounts
+    pub market: Pubkey,
+    pub signer: Pubkey,
+    // Args
+    pub virtual_base: u64,
+    pub virtual_quote: u64,
+    pub spread_bps: u16,
+}
+
+impl SetMarketVirtualReservesAction {
+    pub fn new(virtual_base: u64, virtual_quote: u64, spread_bps: u16) -> Self {
+        let market = Pubkey::find_program_address(
+            &[
+                MARKET_PDA_SEED.as_bytes(),
+                &make_address("base_token_mint").to_bytes(),
+            ],
+            &token_mill::ID,
+        )
+        .0;
+
+        Self {
+            market,
+            signer: make_address("alice"),
+            virtual_base,
+            virtual_quote,
+            spread_bps,
+        }
+    }
+}
+
+impl InstructionGenerator for SetMarketVirtualReservesAction {
+    fn accounts(&self) -> Vec<AccountMeta> {
+        let mut accounts = vec![AccountMeta::new(self.market, false)];
+
+        accounts
+            .append_payer(self.signer)
+            .append_cpi_event_accounts(tm_event_authority());
+
+        accounts
+    }
+
+    fn instruction(&self) -> Instruction {
+        let input = token_mill::instruction::SetMarketVirtualReserves {
+            virtual_base: self.virtual_base,
+            virtual_quote: self.virtual_quote,
+            spread_bps: self.spread_bps,
+        };
+
+        Instruction {
+            program_id: token_mill::ID,
+            accounts: self.accounts(),
+            data: input.data(),
+        }
+    }
+}
+
+pub struct GraduateMarketAction {
+    // Accounts
+    pub market: Pubkey,
+}
+
+impl GraduateMarketAction {
+    pub fn new(token_mill_env: &TokenMillEnv) -> Self {
+        Self {
+            market: token_mill_env.market,
+        }
+    }
+}
+
+impl InstructionGenerator for GraduateMarketAction {
+    fn accounts(&self) -> Vec<AccountMeta> {
+        let mut accounts = vec![AccountMeta::new(self.market, false)];
+
+        accounts.append_cpi_event_accounts(tm_event_authority());
+
+        accounts
+    }
+
+    fn instruction(&self) -> Instruction {
+        let input = token_mill::instruction::GraduateMarket {};
+
+        Instruction {
+            program_id: token_mill::ID,
+            accounts: self.accounts(),
+            data: input.data(),
+        }
+    }
+}
+
+pub struct SwapAction {
+    // Accounts
+    pub config: Pubkey,
+    pub market: Pubkey,
+    pub base_token_mint: Pubkey,
+    pub quote_token_mint: Pubkey,
+    pub market_base_token_ata: Pubkey,
+    pub market_quote_token_ata: Pubkey,
+    pub user_base_token_ata: Pubkey,
+    pub user_quote_token_ata: Pubkey,
+    pub referral_quote_token_ata: Pubkey,
+    pub referral_account: Pubkey,
+    pub parent_referral_quote_token_ata: Pubkey,
+    pub market_oracle: Pubkey,
+    pub market_stats: Pubkey,
+    pub launch_allocation: Pubkey,
+    pub stake_position: Pubkey,
+    pub signer: Pubkey,
+    pub base_token_program: Pubkey,
+    pub quote_token_program: Pubkey,
+    pub transfer_hook_accounts: Vec<AccountMeta>,
+    // Args
+    pub swap_type: SwapType,
+    pub swap_amount_type: SwapAmountType,
+    pub amount: u64,
+    pub other_amount_threshold: u64,
+    pub valid_until: Option<i64>,
+    pub limit_price: Option<u64>,
+    pub presale_proof: Option<PresaleProof>,
+}
+
+impl SwapAction {
+    pub fn new(
+        token_mill_env: &TokenMillEnv,
+        swap_type: SwapType,
+        swap_amount_type: SwapAmountType,
+        amount: u64,
+        other_amount_threshold: u64,
+        referrer: Option<Pubkey>,
+    ) -> Self {
+        let config = make_address("config");
+        let base_token_mint = token_mill_env.base_token_mint.unwrap();
+        let base_token_program = token_mill_env.base_token_type.program_address();
+
+        let signer = make_address("bob");
+
+        let market = Pubkey::find_program_address(
+            &[MARKET_PDA_SEED.as_bytes(), &base_token_mint.to_bytes()],
+            &token_mill::ID,
+        )
+        .0;
+
+        let quote_token_mint = token_mill_env.quote_token_mint.unwrap();
+        let quote_token_program = token_mill_env.quote_token_type.program_address();
+
+        let market_base_token_ata = get_associated_token_address_with_program_id(
+            &market,
+            &base_token_mint,
+            &token_mill_env.base_token_type.program_address(),
+        );
+
+        let market_quote_token_ata = get_associated_token_address_with_program_id(
+            &market,
+            &quote_token_mint,
+            &quote_token_program,
+        );
+
+        let user_base_token_ata = get_associated_token_address_with_program_id(
+            &signer,
+            &base_token_mint,
+            &token_mill_env.base_token_type.program_address(),
+        );
+
+        let user_quote_token_ata = get_associated_token_address_with_program_id(
+            &signer,
+            &quote_token_mint,
+            &quote_token_program,
+        );
+
+        let referral_account = referrer.map_or(token_mill::ID, |referrer| {
+            get_referral_account_address(&referrer)
+        });
+
+        let referral_quote_token_ata = if referrer.is_some() {
+            get_associated_token_address_with_program_id(
+                &referral_account,
+                &quote_token_mint,
+                &quote_token_program,
+            )
+        } else {
+            token_mill::ID
+        };
+
+        Self {
+            config,
+            market,
+            base_token_mint,
+            quote_token_mint,
+            market_base_token_ata,
+            market_quote_token_ata,
+            user_base_token_ata,
+            user_quote_token_ata,
+            referral_quote_token_ata,
+            referral_account,
+            parent_referral_quote_token_ata: token_mill::ID,
+            market_oracle: token_mill::ID,
+            market_stats: token_mill::ID,
+            launch_allocation: token_mill::ID,
+            stake_position: token_mill::ID,
+            signer,
+            base_token_program,
+            quote_token_program,
+            swap_type,
+            swap_amount_type,
+            amount,
+            other_amount_threshold,
+            valid_until: None,
+            limit_price: None,
+            presale_proof: None,
+            transfer_hook_accounts: token_mill_env.transfer_hook_accounts(),
+        }
+    }
+
+    /// Swaps on the market of `base_token_mint`, which must have the same token types
+    pub fn with_custom_base_token_mint(mut self, base_token_mint: Pubkey) -> Self {
+        let market = Pubkey::find_program_address(
+            &[MARKET_PDA_SEED.as_bytes(), &base_token_mint.to_bytes()],
+            &token_mill::ID,
+        )
+        .0;
+
+        self.market = market;
+        self.base_token_mint = base_token_mint;
+        self.market_base_token_ata = get_associated_token_address_with_program_id(
+            &market,
+            &base_token_mint,
+            &self.base_token_program,
+        );
+        self.market_quote_token_ata = get_associated_token_address_with_program_id(
+            &market,
+            &self.quote_token_mint,
+            &self.quote_token_program,
+        );
+        self.user_base_token_ata = get_associated_token_address_with_program_id(
+            &self.signer,
+            &base_token_mint,
+            &self.base_token_program,
+        );
+
+        if let Some(transfer_hook_program) = self.transfer_hook_accounts.first() {
+            self.transfer_hook_accounts =
+                get_transfer_hook_accounts(&transfer_hook_program.pubkey, &base_token_mint);
+        }
+
+        self
+    }
+
+    /// Updates the oracle of the market, which must have been created
+    pub fn with_market_oracle(mut self) -> Self {
+        self.market_oracle = get_market_oracle_address(&self.market);
+
+        self
+    }
+
+    /// Updates the stats of the market, which must have been created
+    pub fn with_market_stats(mut self) -> Self {
+        self.market_stats = get_market_stats_address(&self.market);
+
+        self
+    }
+
+    /// Records buys in the signer's launch allocation, which must have been created
+    pub fn with_launch_allocation(mut self) -> Self {
+        self.launch_allocation = get_launch_allocation_address(&self.market, &self.signer);
+
+        self
+    }
+
+    /// Applies the fee share override of `referrer`'s referral account
+    pub fn with_referral_account(mut self, referrer: Pubkey) -> Self {
+        self.referral_account = get_referral_account_address(&referrer);
+
+        self
+    }
+
+    /// Leaves out the referral account of the referrer, keeping its referral token account
+    pub fn without_referral_account(mut self) -> Self {
+        self.referral_account = token_mill::ID;
+
+        self
+    }
+
+    /// Pays the parent of `referrer`'s referral account its share of the referral fee
+    pub fn with_parent_referrer(mut self, referrer: Pubkey, parent_referrer: Pubkey) -> Self {
+        self.referral_account = get_referral_account_address(&referrer);
+        self.parent_referral_quote_token_ata = get_associated_token_address_with_program_id(
+            &get_referral_account_address(&parent_referrer),
+            &self.quote_token_mint,
+            &self.quote_token_program,
+        );
+
+        self
+    }
+
+    /// Discounts the swap fee with the signer's stake position, which must have been created
+    pub fn with_stake_position(mut self) -> Self {
+        self.stake_position = Pubkey::find_program_address(
+            &[
+                STAKING_POSITION_PDA_SEED.as_bytes(),
+                &self.market.to_bytes(),
+                &self.signer.to_bytes(),
+            ],
+            &token_mill::ID,
+        )
+        .0;
+
+        self
+    }
+
+    /// Proves the signer is allowlisted for the presale of the market
+    pub fn with_presale_proof(mut self, presale_proof: PresaleProof) -> Self {
+        self.presale_proof = Some(presale_proof);
+
+        self
+    }
+}
+
+impl InstructionGenerator for SwapAction {
+    fn accounts(&self) -> Vec<AccountMeta> {
+        let mut accounts = vec![
+            AccountMeta::new_readonly(self.config, false),
+            AccountMeta::new(self.market, false),
+            AccountMeta::new_readonly(self.base_token_mint, false),
+            AccountMeta::new_readonly(self.quote_token_mint, false),
+            AccountMeta::new(self.market_base_token_ata, false),
+            AccountMeta::new(self.market_quote_token_ata, false),
+            AccountMeta::new(self.user_base_token_ata, false),
+            AccountMeta::new(self.user_quote_token_ata, false),
+            AccountMeta::new(self.referral_quote_token_ata, false),
+            AccountMeta::new_readonly(self.referral_account, false),
+            AccountMeta::new(self.parent_referral_quote_token_ata, false),
+            AccountMeta::new(self.market_oracle, false),
+            AccountMeta::new(self.market_stats, false),
+            AccountMeta::new(self.launch_allocation, false),
+            AccountMeta::new_readonly(self.stake_position, false),
+        ];
+
+        accounts.append_payer(self.signer);
+
+        accounts.push(AccountMeta::new_readonly(self.base_token_program, false));
+        accounts.push(AccountMeta::new_readonly(self.quote_token_program, false));
+
+        accounts.append_cpi_event_accounts(tm_event_authority());
+        accounts.extend_from_slice(&self.transfer_hook_accounts);
+
+        accounts
+    }
+
+    fn instruction(&self) -> Instruction {
+        let input = token_mill::instruction::Swap {
+            swap_type: self.swap_type,
+            swap_amount_type: self.swap_amount_type,
+            amount: self.amount,
+            other_amount_threshold: self.other_amount_threshold,
+            valid_until: self.valid_until,
+            limit_price: self.limit_price,
+            presale_proof: self.presale_proof.clone(),
+        };
+
+        Instruction {
+            program_id: token_mill::ID,
+            accounts: self.accounts(),
+            data: input.data(),
+        }
+    }
+}
+
+pub struct SwapBatchAction {
+    // Accounts
+    pub signer: Pubkey,
+    // Args
+    pub legs: Vec<SwapAction>,
+    pub mode: SwapBatchMode,
+}
+
+impl SwapBatchAction {
+    pub fn new(legs: Vec<SwapAction>, mode: SwapBatchMode) -> Self {
+        Self {
+            signer: make_address("bob"),
+            legs,
+            mode,
+        }
+    }
+}
+
+impl InstructionGenerator for SwapBatchAction {
+    fn accounts(&self) -> Vec<AccountMeta> {
+        let mut accounts = vec![];
+
+        accounts.append_payer(self.signer);
+
+        for leg in &self.legs {
+            accounts.extend(leg.accounts());
+        }
+
+        accounts
+    }
+
+    fn instruction(&self) -> Instruction {
+        let input = token_mill::instruction::SwapBatch {
+            legs: self
+                .legs
+                .iter()
+                .map(|leg| SwapLeg {
+                    swap_type: leg.swap_type,
+                    swap_amount_type: leg.swap_amount_type,
+                    amount: leg.amount,
+                    other_amount_threshold: leg.other_amount_threshold,
+                    transfer_hook_accounts: leg.transfer_hook_accounts.len() as u8,
+                })
+                .collect(),
+            mode: self.mode,
+        };
+
+        Instruction {
+            program_id: token_mill::ID,
+            accounts: self.accounts(),
+            data: input.data(),
+        }
+    }
+}
+
+pub struct SwapRouteAction {
+    // Accounts
+    pub config: Pubkey,
+    pub market_in: Pubkey,
+    pub market_out: Pubkey,
+    pub base_token_mint_in: Pubkey,
+    pub base_token_mint_out: Pubkey,
+    pub quote_token_mint: Pubkey,
+    pub market_in_base_token_ata: Pubkey,
+    pub market_in_quote_token_ata: Pubkey,
+    pub market_out_base_token_ata: Pubkey,
+    pub market_out_quote_token_ata: Pubkey,
+    pub user_base_token_ata_in: Pubkey,
+    pub user_base_token_ata_out: Pubkey,
+    pub user_quote_token_ata: Pubkey,
+    pub referral_quote_token_ata: Pubkey,
+    pub referral_account: Pubkey,
+    pub parent_referral_quote_token_ata: Pubkey,
+    pub market_oracle_in: Pubkey,
+    pub market_oracle_out: Pubkey,
+    pub market_stats_in: Pubkey,
+    pub market_stats_out: Pubkey,
+    pub stake_position_out: Pubkey,
+    pub signer: Pubkey,
+    pub base_token_program: Pubkey,
+    pub quote_token_program: Pubkey,
+    pub transfer_hook_accounts: Vec<AccountMeta>,
+    // Args
+    pub swap_amount_type: SwapAmountType,
+    pub amount: u64,
+    pub other_amount_threshold: u64,
+}
+
+impl SwapRouteAction {
+    /// Both markets must have been created by `token_mill_env`, which sets their token types.
+    pub fn new(
+        token_mill_env: &TokenMillEnv,
+        base_token_mint_in: Pubkey,
+        base_token_mint_out: Pubkey,
+        swap_amount_type: SwapAmountType,
+        amount: u64,
+        other_amount_threshold: u64,
+        referrer: Option<Pubkey>,
+    ) -> Self {
+        let swap_action_in = SwapAction::new(
+            token_mill_env,
+            SwapType::Sell,
+            swap_amount_type,
+            amount,
+            other_amount_threshold,
+            referrer,
+        )
+        .with_custom_base_token_mint(base_token_mint_in);
+        let swap_action_out = SwapAction::new(
+            token_mill_env,
+            SwapType::Buy,
+            swap_amount_type,
+            amount,
+            other_amount_threshold,
+            referrer,
+        )
+        .with_custom_base_token_mint(base_token_mint_out);
+
+        Self {
+            config: swap_action_in.config,
+            market_in: swap_action_in.market,
+            market_out: swap_action_out.market,
+            base_token_mint_in,
+            base_token_mint_out,
+            quote_token_mint: swap_action_in.quote_token_mint,
+            market_in_base_token_ata: swap_action_in.market_base_token_ata,
+            market_in_quote_token_ata: swap_action_in.market_quote_token_ata,
+            market_out_base_token_ata: swap_action_out.market_base_token_ata,
+            market_out_quote_token_ata: swap_action_out.market_quote_token_ata,
+            user_base_token_ata_in: swap_action_in.user_base_token_ata,
+            user_base_token_ata_out: swap_action_out.user_base_token_ata,
+            user_quote_token_ata: swap_action_in.user_quote_token_ata,
+            referral_quote_token_ata: swap_action_in.referral_quote_token_ata,
+            referral_account: swap_action_in.referral_account,
+            parent_referral_quote_token_ata: token_mill::ID,
+            market_oracle_in: token_mill::ID,
+            market_oracle_out: token_mill::ID,
+            market_stats_in: token_mill::ID,
+            market_stats_out: token_mill::ID,
+            stake_position_out: token_mill::ID,
+            signer: swap_action_in.signer,
+            base_token_program: swap_action_in.base_token_program,
+            quote_token_program: swap_action_in.quote_token_program,
+            swap_amount_type,
+            amount,
+            other_amount_threshold,
+            transfer_hook_accounts: [
+                swap_action_in.transfer_hook_accounts,
+                swap_action_out.transfer_hook_accounts,
+            ]
+            .concat(),
+        }
+    }
+
+    /// Pays the parent of `referrer`'s referral account its share of the referral fees
+    pub fn with_parent_referrer(mut self, referrer: Pubkey, parent_referrer: Pubkey) -> Self {
+        self.referral_account = get_referral_account_address(&referrer);
+        self.parent_referral_quote_token_ata = get_associated_token_address_with_program_id(
+            &get_referral_account_address(&parent_referrer),
+            &self.quote_token_mint,
+            &self.quote_token_program,
+        );
+
+        self
+    }
+
+    /// Discounts the buy with the signer's stake position in the market out, which must have been created
+    pub fn with_stake_position(mut self) -> Self {
+        self.stake_position_out = Pubkey::find_program_address(
+            &[
+                STAKING_POSITION_PDA_SEED.as_bytes(),
+                &self.market_out.to_bytes(),
+                &self.signer.to_bytes(),
+            ],
+            &token_mill::ID,
+        )
+        .0;
+
+        self
+    }
+}
+
+impl InstructionGenerator for SwapRouteAction {
+    fn accounts(&self) -> Vec<AccountMeta> {
+        let mut accounts = vec![
+            AccountMeta::new_readonly(self.config, false),
+            AccountMeta::new(self.market_in, false),
+            AccountMeta::new(self.market_out, false),
+            AccountMeta::new_readonly(self.base_token_mint_in, false),
+            AccountMeta::new_readonly(self.base_token_mint_out, false),
+            AccountMeta::new_readonly(self.quote_token_mint, false),
+            AccountMeta::new(self.market_in_base_token_ata, false),
+            AccountMeta::new(self.market_in_quote_token_ata, false),
+            AccountMeta::new(self.market_out_base_token_ata, false),
+            AccountMeta::new(self.market_out_quote_token_ata, false),
+            AccountMeta::new(self.user_base_token_ata_in, false),
+            AccountMeta::new(self.user_base_token_ata_out, false),
+            AccountMeta::new(self.user_quote_token_ata, false),
+            AccountMeta::new(self.referral_quote_token_ata, false),
+            AccountMeta::new_readonly(self.referral_account, false),
+            AccountMeta::new(self.parent_referral_quote_token_ata, false),
+            AccountMeta::new(self.market_oracle_in, false),
+            AccountMeta::new(self.market_oracle_out, false),
+            AccountMeta::new(self.market_stats_in, false),
+            AccountMeta::new(self.market_stats_out, false),
+            AccountMeta::new_readonly(self.stake_position_out, false),
+        ];
+
+        accounts.append_payer(self.signer);
+
+        accounts.push(AccountMeta::new_readonly(self.base_token_program, false));
+        accounts.push(AccountMeta::new_readonly(self.base_token_program, false));
+        accounts.push(AccountMeta::new_readonly(self.quote_token_program, false));
+
+        accounts.append_cpi_event_accounts(tm_event_authority());
+        accounts.extend_from_slice(&self.transfer_hook_accounts);
+
+        accounts
+    }
+
+    fn instruction(&self) -> Instruction {
+        let input = token_mill::instruction::SwapRoute {
+            swap_amount_type: self.swap_amount_type,
+            amount: self.amount,
+            other_amount_threshold: self.other_amount_threshold,
+        };
+
+        Instruction {
+            program_id: token_mill::ID,
+            accounts: self.accounts(),
+            data: input.data(),
+        }
+    }
+}
+
+pub struct QuoteSwapAction {
+    // Accounts
+    pub config: Pubkey,
+    pub market: Pubkey,
+    pub quote_token_mint: Pubkey,
+    pub referral_account: Pubkey,
+    pub stake_position: Pubkey,
+    // Args
+    pub swap_type: SwapType,
+    pub swap_amount_type: SwapAmountType,
+    pub amount: u64,
+}
+
+impl QuoteSwapAction {
+    pub fn new(
+        token_mill_env: &TokenMillEnv,
+        swap_type: SwapType,
+        swap_amount_type: SwapAmountType,
+        amount: u64,
+    ) -> Self {
+        Self {
+            config: make_address("config"),
+            market: token_mill_env.market,
+            quote_token_mint: token_mill_env.quote_token_mint.unwrap(),
+            referral_account: token_mill::ID,
+            stake_position: token_mill::ID,
+            swap_type,
+            swap_amount_type,
+            amount,
+        }
+    }
+
+    /// Quotes a swap referred by `referrer`'s referral account
+    pub fn with_referral_account(mut self, referrer: Pubkey) -> Self {
+        self.referral_account = get_referral_account_address(&referrer);
+
+        self
+    }
+
+    /// Quotes the fee discount of `user`'s stake position, which must have been created
+    pub fn with_stake_position(mut self, user: Pubkey) -> Self {
+        self.stake_position = Pubkey::find_program_address(
+            &[
+                STAKING_POSITION_PDA_SEED.as_bytes(),
+                &self.market.to_bytes(),
+                &user.to_bytes(),
+            ],
+            &token_mill::ID,
+        )
+        .0;
+
+        self
+    }
+
+    /// Quotes without the config, ignoring paused operations
+    pub fn without_config(mut self) -> Self {
+        self.config = token_mill::ID;
+
+        self
+    }
+
+    /// Quotes without the quote token mint, ignoring its transfer fee
+    pub fn without_quote_token_mint(mut self) -> Self {
+        self.quote_token_mint = token_mill::ID;
+
+        self
+    }
+}
+
+impl InstructionGenerator for QuoteSwapAction {
+    fn accounts(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new_readonly(self.config, false),
+            AccountMeta::new_readonly(self.market, false),
+            AccountMeta::new_readonly(self.quote_token_mint, false),
+            AccountMeta::new_readonly(self.referral_account, false),
+            AccountMeta::new_readonly(self.stake_position, false),
+        ]
+    }
+
+    fn instruction(&self) -> Instruction {
+        let input = token_mill::instruction::QuoteSwap {
+            swap_type: self.swap_type,
+            swap_amount_type: self.swap_amount_type,
+            amount: self.amount,
+        };
+
+        Instruction {
+            program_id: token_mill::ID,
+            accounts: self.accounts(),
+            data: input.data(),
+        }
+    }
+}
+
+pub struct UpdateCreatorAction {
+    // Accounts
+    pub market: Pubkey,
+    pub signer: Pubkey,
+    // Args
+    pub new_creator: Pubkey,
+}
+
+impl UpdateCreatorAction {
+    pub fn new(new_creator: Pubkey) -> Self {
+        let market = Pubkey::find_program_address(
+            &[
+                MARKET_PDA_SEED.as_bytes(),
+                &make_address("base_token_mint").to_bytes(),
+            ],
+            &token_mill::ID,
+        )
+        .0;
+
+        Self {
+            market,
+            signer: make_address("alice"),
+            new_creator,
+        }
+    }
+}
+
+impl InstructionGenerator for UpdateCreatorAction {
+    fn accounts(&self) -> Vec<AccountMeta> {
+        let mut accounts = vec![AccountMeta::new(self.market, false)];
+
+        accounts
+            .append_payer(self.signer)
+            .append_cpi_event_accounts(tm_event_authority());
+
+        accounts
+    }
+
+    fn instruction(&self) -> Instruction {
+        let input = token_mill::instruction::UpdateCreator {
+            new_creator: self.new_creator,
+        };
+
+        Instruction {
+            program_id: token_mill::ID,
+            accounts: self.accounts(),
+            data: input.data(),
+        }
+    }
+}
+
+pub struct UpdateMarketStatusAction {
+    // Accounts
+    pub market: Pubkey,
+    pub signer: Pubkey,
+    // Args
+    pub new_status: MarketStatus,
+}
+
+impl UpdateMarketStatusAction {
+    pub fn new(testing_env: &TokenMillEnv, new_status: MarketStatus) -> Self {
+        Self {
+            market: testing_env.market,
+            signer: make_address("alice"),
+            new_status,
+        }
+    }
+}
+
+impl InstructionGenerator for UpdateMarketStatusAction {
+    fn accounts(&self) -> Vec<AccountMeta> {
+        let mut accounts = vec![AccountMeta::new(self.market, false)];
+
+        accounts
+            .append_payer(self.signer)
+            .append_cpi_event_accounts(tm_event_authority());
+
+        accounts
+    }
+
+    fn instruction(&self) -> Instruction {
+        let input = token_mill::instruction::UpdateMarketStatus {
+            new_status: self.new_status,
+        };
+
+        Instruction {
+            program_id: token_mill::ID,
+            accounts: self.accounts(),
+            data: input.data(),
+        }
+    }
+}
+
+pub struct SetMarketStatusAction {
+    // Accounts
+    pub config: Pubkey,
+    pub market: Pubkey,
+    pub signer: Pubkey,
+    // Args
+    pub new_status: MarketStatus,
+}
+
+impl SetMarketStatusAction {
+    pub fn new(testing_env: &TokenMillEnv, new_status: MarketStatus) -> Self {
+        Self {
+            config: testing_env.config,
+            market: testing_env.market,
+            signer: make_address("admin"),
+            new_status,
+        }
+    }
+}
+
+impl InstructionGenerator for SetMarketStatusAction {
+    fn accounts(&self) -> Vec<AccountMeta> {
+        let mut accounts = vec![
+            AccountMeta::new_readonly(self.config, false),
+            AccountMeta::new(self.market, false),
+        ];
+
+        accounts
+            .append_payer(self.signer)
+            .append_cpi_event_accounts(tm_event_authority());
+
+        accounts
+    }
+
+    fn instruction(&self) -> Instruction {
+        let input = token_mill::instruction::SetMarketStatus {
+            new_status: self.new_status,
+        };
+
+        Instruction {
+            program_id: token_mill::ID,
+            accounts: self.accounts(),
+            data: input.data(),
+        }
+    }
+}
+
+pub struct SetReferralFeeShareOverrideAction {
+    // Accounts
+    pub config: Pubkey,
+    pub referral_account: Pubkey,
+    pub signer: Pubkey,
+    // Args
+    pub fee_share_override: Option<u16>,
+}
+
+impl SetReferralFeeShareOverrideAction {
+    pub fn new(referrer: Pubkey, fee_share_override: Option<u16>) -> Self {
+        Self {
+            config: make_address("config"),
+            referral_account: get_referral_account_address(&referrer),
+            signer: make_address("admin"),
+            fee_share_override,
+        }
+    }
+}
+
+impl InstructionGenerator for SetReferralFeeShareOverrideAction {
+    fn accounts(&self) -> Vec<AccountMeta> {
+        let mut accounts = vec![
+            AccountMeta::new_readonly(self.config, false),
+            AccountMeta::new(self.referral_account, false),
+        ];
+
+        accounts
+            .append_payer(self.signer)
+            .append_cpi_event_accounts(tm_event_authority());
+
+        accounts
+    }
+
+    fn instruction(&self) -> Instruction {
+        let input = token_mill::instruction::SetReferralFeeShareOverride {
+            fee_share_override: self.fee_share_override,
+        };
+
+        Instruction {
+            program_id: token_mill::ID,
+            accounts: self.accounts(),
+            data: input.data(),
+        }
+    }
+}
+
+pub struct UpdateMarketFeeSharesAction {
+    // Accounts
+    pub market: Pubkey,
+    pub signer: Pubkey,
+    // Args
+    pub new_creator_fee_share: u16,
+    pub new_staking_fee_share: u16,
+}
+
+impl UpdateMarketFeeSharesAction {
+    pub fn new(new_creator_fee_share: u16, new_staking_fee_share: u16) -> Self {
+        let market = Pubkey::find_program_address(
+            &[
+                MARKET_PDA_SEED.as_bytes(),
+                &make_address("base_token_mint").to_bytes(),
+            ],
+            &token_mill::ID,
+        )
+        .0;
+
+        Self {
+            market,
+            signer: make_address("alice"),
+            new_creator_fee_share,
+            new_staking_fee_share,
+        }
+    }
+}
+
+impl InstructionGenerator for UpdateMarketFeeSharesAction {
+    fn accounts(&self) -> Vec<AccountMeta> {
+        let mut accounts = vec![AccountMeta::new(self.market, false)];
+
+        accounts
+            .append_payer(self.signer)
+            .append_cpi_event_accounts(tm_event_authority());
+
+        accounts
+    }
+
+    fn instruction(&self) -> Instruction {
+        let input = token_mill::instruction::UpdateMarketFeeShares {
+            new_creator_fee_share: self.new_creator_fee_share,
+            new_staking_fee_share: self.new_staking_fee_share,
+        };
+
+        Instruction {
+            program_id: token_mill::ID,
+            accounts: self.accounts(),
+            data: input.data(),
+        }
+    }
+}
+
+pub struct ClaimCreatorFeesAction {
+    // Accounts
+    pub market: Pubkey,
+    pub quote_token_mint: Pubkey,
+    pub market_quote_token_ata: Pubkey,
+    pub creator_quote_token_ata: Pubkey,
+    pub signer: Pubkey,
+    pub quote_token_program: Pubkey,
+}
+
+impl ClaimCreatorFeesAction {
+    pub fn new(token_mill_env: &TokenMillEnv) -> Self {
+        let market = Pubkey::find_program_address(
+            &[
+                MARKET_PDA_SEED.as_bytes(),
+                &token_mill_env.base_token_mint.unwrap().to_bytes(),
+            ],
+            &token_mill::ID,
+        )
+        .0;
+
+        let signer = make_address("alice");
+
+        let quote_token_mint = token_mill_env.quote_token_mint.unwrap();
+        let quote_token_program = token_mill_env.quote_token_type.program_address();
+
+        let market_quote_token_ata = get_associated_token_address_with_program_id(
+            &market,
+            &quote_token_mint,
+            &quote_token_program,
+        );
+
+        let creator_quote_token_ata = get_associated_token_address_with_program_id(
+            &signer,
+            &quote_token_mint,
+            &quote_token_program,
+        );
+
+        Self {
+            market,
+            quote_token_mint,
+            market_quote_token_ata,
+            creator_quote_token_ata,
+            signer,
+            quote_token_program,
+        }
+    }
+}
+
+impl InstructionGenerator for ClaimCreatorFeesAction {
+    fn accounts(&self) -> Vec<AccountMeta> {
+        let mut accounts = vec![
+            AccountMeta::new(self.market, false),
+            AccountMeta::new_readonly(self.quote_token_mint, false),
+            AccountMeta::new(self.market_quote_token_ata, false),
+            AccountMeta::new(self.creator_quote_token_ata, false),
+        ];
+
+        accounts.append_payer(self.signer);
+
+        match self.quote_token_program {
+            spl_token::ID => accounts.append_token_program(),
+            spl_token_2022::ID => accounts.append_token_2022_program(),
+            _ => unreachable!(),
+        };
+
+        accounts.append_cpi_event_accounts(tm_event_authority());
+
+        accounts
+    }
+
+    fn instruction(&self) -> Instruction {
+        let input = token_mill::instruction::ClaimCreatorFees {};
+
+        Instruction {
+            program_id: token_mill::ID,
+            accounts: self.accounts(),
+            data: input.data(),
+        }
+    }
+}
+
+pub struct ClaimProtocolFeesAction {
+    // Accounts
+    pub config: Pubkey,
+    pub market: Pubkey,
+    pub quote_token_mint: Pubkey,
+    pub market_quote_token_ata: Pubkey,
+    pub authority_quote_token_ata: Pubkey,
+    pub signer: Pubkey,
+    pub quote_token_program: Pubkey,
+}
+
+impl ClaimProtocolFeesAction {
+    pub fn new(token_mill_env: &TokenMillEnv) -> Self {
+        let market = Pubkey::find_program_address(
+            &[
+                MARKET_PDA_SEED.as_bytes(),
+                &token_mill_env.base_token_mint.unwrap().to_bytes(),
+            ],
+            &token_mill::ID,
+        )
+        .0;
+
+        let signer = make_address("dave");
+
+        let quote_token_mint = token_mill_env.quote_token_mint.unwrap();
+        let quote_token_program = token_mill_env.quote_token_type.program_address();
+
+        let market_quote_token_ata = get_associated_token_address_with_program_id(
+            &market,
+            &quote_token_mint,
+            &quote_token_program,
+        );
+
+        let authority_quote_token_ata = get_associated_token_address_with_program_id(
+            &signer,
+            &quote_token_mint,
+            &quote_token_program,
+        );
+
+        Self {
+            config: token_mill_env.config,
+            market,
+            quote_token_mint,
+            market_quote_token_ata,
+            authority_quote_token_ata,
+            signer,
+            quote_token_program,
+        }
+    }
+}
+
+impl InstructionGenerator for ClaimProtocolFeesAction {
+    fn accounts(&self) -> Vec<AccountMeta> {
+        let mut accounts = vec![
+            AccountMeta::new_readonly(self.config, false),
+            AccountMeta::new(self.market, false),
+            AccountMeta::new_readonly(self.quote_token_mint, false),
+            AccountMeta::new(self.market_quote_token_ata, false),
+            AccountMeta::new(self.authority_quote_token_ata, false),
+        ];
+
+        accounts.append_payer(self.signer);
+
+        match self.quote_token_program {
+            spl_token::ID => accounts.append_token_program(),
+            spl_token_2022::ID => accounts.append_token_2022_program(),
+            _ => unreachable!(),
+        };
+
+        accounts.append_cpi_event_accounts(tm_event_authority());
+
+        accounts
+    }
+
+    fn instruction(&self) -> Instruction {
+        let input = token_mill::instruction::ClaimProtocolFees {};
+
+        Instruction {
+            program_id: token_mill::ID,
+            accounts: self.accounts(),
+            data: input.data(),
+        }
+    }
+}
+
+pub struct CreateLimitOrderAction {
+    // Accounts
+    pub market: Pubkey,
+    pub limit_order: Pubkey,
+    pub escrow_token_mint: Pubkey,
+    pub limit_order_escrow: Pubkey,
+    pub owner_token_account: Pubkey,
+    pub signer: Pubkey,
+    pub escrow_token_program: Pubkey,
+    pub transfer_hook_accounts: Vec<AccountMeta>,
+    // Args
+    pub order_id: u64,
+    pub swap_type: SwapType,
+    pub amount: u64,
+    pub limit_price: u64,
+}
+
+impl CreateLimitOrderAction {
+    pub fn new(
+        token_mill_env: &TokenMillEnv,
+        order_id: u64,
+        swap_type: SwapType,
+        amount: u64,
+        limit_price: u64,
+    ) -> Self {
+        let market = token_mill_env.market;
+        let signer = make_address("bob");
+
+        let limit_order = Pubkey::find_program_address(
+            &[
+                LIMIT_ORDER_PDA_SEED.as_bytes(),
+                &market.to_bytes(),
+                &signer.to_bytes(),
+                &order_id.to_le_bytes(),
+            ],
+            &token_mill::ID,
+        )
+        .0;
+
+        let (escrow_token_mint, escrow_token_program) = match swap_type {
+            SwapType::Buy => (
+                token_mill_env.quote_token_mint.unwrap(),
+                token_mill_env.quote_token_type.program_address(),
+            ),
+            SwapType::Sell => (
+                token_mill_env.base_token_mint.unwrap(),
+                token_mill_env.base_token_type.program_address(),
+            ),
+        };
+
+        let limit_order_escrow = get_associated_token_address_with_program_id(
+            &limit_order,
+            &escrow_token_mint,
+            &escrow_token_program,
+        );
+
+        let owner_token_account = get_associated_token_address_with_program_id(
+            &signer,
+            &escrow_token_mint,
+            &escrow_token_program,
+        );
+
+        Self {
+            market,
+            limit_order,
+            escrow_token_mint,
+            limit_order_escrow,
+            owner_token_account,
+            signer,
+            escrow_token_program,
+            order_id,
+            swap_type,
+            amount,
+            limit_price,
+            transfer_hook_accounts: token_mill_env.transfer_hook_accounts(),
+        }
+    }
+}
+
+impl InstructionGenerator for CreateLimitOrderAction {
+    fn accounts(&self) -> Vec<AccountMeta> {
+        let mut accounts = vec![
+            AccountMeta::new_readonly(self.market, false),
+            AccountMeta::new(self.limit_order, false),
+            AccountMeta::new_readonly(self.escrow_token_mint, false),
+            AccountMeta::new(self.limit_order_escrow, false),
+            AccountMeta::new(self.owner_token_account, false),
+        ];
+
+        accounts.append_payer(self.signer);
+
+        accounts.push(AccountMeta::new_readonly(self.escrow_token_program, false));
+
+        accounts
+            .append_associated_token_program()
+            .append_system_program()
+            .append_cpi_event_accounts(tm_event_authority());
+        accounts.extend_from_slice(&self.transfer_hook_accounts);
+
+        accounts
+    }
+
+    fn instruction(&self) -> Instruction {
+        let input = token_mill::instruction::CreateLimitOrder {
+            order_id: self.order_id,
+            swap_type: self.swap_type,
+            amount: self.amount,
+            limit_price: self.limit_price,
+        };
+
+        Instruction {
+            program_id: token_mill::ID,
+            accounts: self.accounts(),
+            data: input.data(),
+        }
+    }
+}
+
+pub struct FillLimitOrderAction {
+    // Accounts
+    pub config: Pubkey,
+    pub market: Pubkey,
+    pub limit_order: Pubkey,
+    pub base_token_mint: Pubkey,
+    pub quote_token_mint: Pubkey,
+    pub market_base_token_ata: Pubkey,
+    pub market_quote_token_ata: Pubkey,
+    pub limit_order_escrow: Pubkey,
+    pub owner_token_account: Pubkey,
+    pub keeper_quote_token_account: Pubkey,
+    pub market_oracle: Pubkey,
+    pub market_stats: Pubkey,
+    pub signer: Pubkey,
+    pub base_token_program: Pubkey,
+    pub quote_token_program: Pubkey,
+    pub transfer_hook_accounts: Vec<AccountMeta>,
+    // Args
+    pub amount: u64,
+}
+
+impl FillLimitOrderAction {
+    pub fn new(
+        token_mill_env: &TokenMillEnv,
+        create_limit_order_action: &CreateLimitOrderAction,
+        amount: u64,
+    ) -> Self {
+        let market = token_mill_env.market;
+        let signer = make_address("carol");
+
+        let base_token_mint = token_mill_env.base_token_mint.unwrap();
+        let base_token_program = token_mill_env.base_token_type.program_address();
+        let quote_token_mint = token_mill_env.quote_token_mint.unwrap();
+        let quote_token_program = token_mill_env.quote_token_type.program_address();
+
+        let market_base_token_ata = get_associated_token_address_with_program_id(
+            &market,
+            &base_token_mint,
+            &base_token_program,
+        );
+
+        let market_quote_token_ata = get_associated_token_address_with_program_id(
+            &market,
+            &quote_token_mint,
+            &quote_token_program,
+        );
+
+        let (mint_out, token_program_out) = match create_limit_order_action.swap_type {
+            SwapType::Buy => (base_token_mint, base_token_program),
+            SwapType::Sell => (quote_token_mint, quote_token_program),
+        };
+
+        let owner_token_account = get_associated_token_address_with_program_id(
+            &create_limit_order_action.signer,
+            &mint_out,
+            &token_program_out,
+        );
+
+        let keeper_quote_token_account = get_associated_token_address_with_program_id(
+            &signer,
+            &quote_token_mint,
+            &quote_token_program,
+        );
+
+        Self {
+            config: token_mill_env.config,
+            market,
+            limit_order: create_limit_order_action.limit_order,
+            base_token_mint,
+            quote_token_mint,
+            market_base_token_ata,
+            market_quote_token_ata,
+            limit_order_escrow: create_limit_order_action.limit_order_escrow,
+            owner_token_account,
+            keeper_quote_token_account,
+            market_oracle: token_mill::ID,
+            market_stats: token_mill::ID,
+            signer,
+            base_token_program,
+            quote_token_program,
+            amount,
+            transfer_hook_accounts: token_mill_env.transfer_hook_accounts(),
+        }
+    }
+}
+
+impl InstructionGenerator for FillLimitOrderAction {
+    fn accounts(&self) -> Vec<AccountMeta> {
+        let mut accounts = vec![
+            AccountMeta::new_readonly(self.config, false),
+            AccountMeta::new(self.market, false),
+            AccountMeta::new(self.limit_order, false),
+            AccountMeta::new_readonly(self.base_token_mint, false),
+            AccountMeta::new_readonly(self.quote_token_mint, false),
+            AccountMeta::new(self.market_base_token_ata, false),
+            AccountMeta::new(self.market_quote_token_ata, false),
+            AccountMeta::new(self.limit_order_escrow, false),
+            AccountMeta::new(self.owner_token_account, false),
+            AccountMeta::new(self.keeper_quote_token_account, false),
+            AccountMeta::new(self.market_oracle, false),
+            AccountMeta::new(self.market_stats, false),
+        ];
 
-        let user_quote_token_ata = get_associated_token_address_with_program_id(
+        accounts.append_payer(self.signer);
+
+        accounts.push(AccountMeta::new_readonly(self.base_token_program, false));
+        accounts.push(AccountMeta::new_readonly(self.quote_token_program, false));
+
+        accounts.append_cpi_event_accounts(tm_event_authority());
+        accounts.extend_from_slice(&self.transfer_hook_accounts);
+
+        accounts
+    }
+
+    fn instruction(&self) -> Instruction {
+        let input = token_mill::instruction::FillLimitOrder {
+            amount: self.amount,
+        };
+
+        Instruction {
+            program_id: token_mill::ID,
+            accounts: self.accounts(),
+            data: input.data(),
+        }
+    }
+}
+
+pub struct CancelLimitOrderAction {
+    // Accounts
+    pub limit_order: Pubkey,
+    pub escrow_token_mint: Pubkey,
+    pub limit_order_escrow: Pubkey,
+    pub owner_token_account: Pubkey,
+    pub signer: Pubkey,
+    pub escrow_token_program: Pubkey,
+    pub transfer_hook_accounts: Vec<AccountMeta>,
+}
+
+impl CancelLimitOrderAction {
+    pub fn new(create_limit_order_action: &CreateLimitOrderAction) -> Self {
+        Self {
+            limit_order: create_limit_order_action.limit_order,
+            escrow_token_mint: create_limit_order_action.escrow_token_mint,
+            limit_order_escrow: create_limit_order_action.limit_order_escrow,
+            owner_token_account: create_limit_order_action.owner_token_account,
+            signer: create_limit_order_action.signer,
+            escrow_token_program: create_limit_order_action.escrow_token_program,
+            transfer_hook_accounts: create_limit_order_action.transfer_hook_accounts.clone(),
+        }
+    }
+}
+
+impl InstructionGenerator for CancelLimitOrderAction {
+    fn accounts(&self) -> Vec<AccountMeta> {
+        let mut accounts = vec![
+            AccountMeta::new(self.limit_order, false),
+            AccountMeta::new_readonly(self.escrow_token_mint, false),
+            AccountMeta::new(self.limit_order_escrow, false),
+            AccountMeta::new(self.owner_token_account, false),
+        ];
+
+        accounts.append_payer(self.signer);
+
+        accounts.push(AccountMeta::new_readonly(self.escrow_token_program, false));
+
+        accounts.append_cpi_event_accounts(tm_event_authority());
+        accounts.extend_from_slice(&self.transfer_hook_accounts);
+
+        accounts
+    }
+
+    fn instruction(&self) -> Instruction {
+        let input = token_mill::instruction::CancelLimitOrder {};
+
+        Instruction {
+            program_id: token_mill::ID,
+            accounts: self.accounts(),
+            data: input.data(),
+        }
+    }
+}
+
+pub struct CreateDcaOrderAction {
+    // Accounts
+    pub market: Pubkey,
+    pub dca_order: Pubkey,
+    pub escrow_token_mint: Pubkey,
+    pub dca_order_escrow: Pubkey,
+    pub owner_token_account: Pubkey,
+    pub signer: Pubkey,
+    pub escrow_token_program: Pubkey,
+    pub transfer_hook_accounts: Vec<AccountMeta>,
+    // Args
+    pub order_id: u64,
+    pub swap_type: SwapType,
+    pub amount: u64,
+    pub amount_per_execution: u64,
+    pub interval: i64,
+    pub limit_price: u64,
+}
+
+impl CreateDcaOrderAction {
+    pub fn new(
+        token_mill_env: &TokenMillEnv,
+        order_id: u64,
+        swap_type: SwapType,
+        amount: u64,
+        amount_per_execution: u64,
+        interval: i64,
+    ) -> Self {
+        let market = token_mill_env.market;
+        let signer = make_address("bob");
+
+        let dca_order = Pubkey::find_program_address(
+            &[
+                DCA_ORDER_PDA_SEED.as_bytes(),
+                &market.to_bytes(),
+                &signer.to_bytes(),
+                &order_id.to_le_bytes(),
+            ],
+            &token_mill::ID,
+        )
+        .0;
+
+        let (escrow_token_mint, escrow_token_program) = match swap_type {
+            SwapType::Buy => (
+                token_mill_env.quote_token_mint.unwrap(),
+                token_mill_env.quote_token_type.program_address(),
+            ),
+            SwapType::Sell => (
+                token_mill_env.base_token_mint.unwrap(),
+                token_mill_env.base_token_type.program_address(),
+            ),
+        };
+
+        let dca_order_escrow = get_associated_token_address_with_program_id(
+            &dca_order,
+            &escrow_token_mint,
+            &escrow_token_program,
+        );
+
+        // No price limit by default
+        let limit_price = match swap_type {
+            SwapType::Buy => u64::MAX,
+            SwapType::Sell => 0,
+        };
+
+        let owner_token_account = get_associated_token_address_with_program_id(
             &signer,
-            &quote_token_mint,
-            &quote_token_program,
+            &escrow_token_mint,
+            &escrow_token_program,
+        );
+
+        Self {
+            market,
+            dca_order,
+            escrow_token_mint,
+            dca_order_escrow,
+            owner_token_account,
+            signer,
+            escrow_token_program,
+            order_id,
+            swap_type,
+            amount,
+            amount_per_execution,
+            interval,
+            limit_price,
+            transfer_hook_accounts: token_mill_env.transfer_hook_accounts(),
+        }
+    }
+}
+
+impl InstructionGenerator for CreateDcaOrderAction {
+    fn accounts(&self) -> Vec<AccountMeta> {
+        let mut accounts = vec![
+            AccountMeta::new_readonly(self.market, false),
+            AccountMeta::new(self.dca_order, false),
+            AccountMeta::new_readonly(self.escrow_token_mint, false),
+            AccountMeta::new(self.dca_order_escrow, false),
+            AccountMeta::new(self.owner_token_account, false),
+        ];
+
+        accounts.append_payer(self.signer);
+
+        accounts.push(AccountMeta::new_readonly(self.escrow_token_program, false));
+
+        accounts
+            .append_associated_token_program()
+            .append_system_program()
+            .append_cpi_event_accounts(tm_event_authority());
+        accounts.extend_from_slice(&self.transfer_hook_accounts);
+
+        accounts
+    }
+
+    fn instruction(&self) -> Instruction {
+        let input = token_mill::instruction::CreateDcaOrder {
+            order_id: self.order_id,
+            swap_type: self.swap_type,
+            amount: self.amount,
+            amount_per_execution: self.amount_per_execution,
+            interval: self.interval,
+            limit_price: self.limit_price,
+        };
+
+        Instruction {
+            program_id: token_mill::ID,
+            accounts: self.accounts(),
+            data: input.data(),
+        }
+    }
+}
+
+pub struct ExecuteDcaAction {
+    // Accounts
+    pub config: Pubkey,
+    pub market: Pubkey,
+    pub dca_order: Pubkey,
+    pub base_token_mint: Pubkey,
+    pub quote_token_mint: Pubkey,
+    pub market_base_token_ata: Pubkey,
+    pub market_quote_token_ata: Pubkey,
+    pub dca_order_escrow: Pubkey,
+    pub owner_token_account: Pubkey,
+    pub market_oracle: Pubkey,
+    pub market_stats: Pubkey,
+    pub signer: Pubkey,
+    pub base_token_program: Pubkey,
+    pub quote_token_program: Pubkey,
+    pub transfer_hook_accounts: Vec<AccountMeta>,
+}
+
+impl ExecuteDcaAction {
+    pub fn new(
+        token_mill_env: &TokenMillEnv,
+        create_dca_order_action: &CreateDcaOrderAction,
+    ) -> Self {
+        let market = token_mill_env.market;
+        let signer = make_address("carol");
+
+        let base_token_mint = token_mill_env.base_token_mint.unwrap();
+        let base_token_program = token_mill_env.base_token_type.program_address();
+        let quote_token_mint = token_mill_env.quote_token_mint.unwrap();
+        let quote_token_program = token_mill_env.quote_token_type.program_address();
+
+        let market_base_token_ata = get_associated_token_address_with_program_id(
+            &market,
+            &base_token_mint,
+            &base_token_program,
         );
 
-        let protocol_quote_token_ata = get_associated_token_address_with_program_id(
-            &make_address("dave"),
+        let market_quote_token_ata = get_associated_token_address_with_program_id(
+            &market,
             &quote_token_mint,
             &quote_token_program,
         );
 
-        let referral_account = if let Some(referrer) = referrer {
-            Pubkey::find_program_address(
-                &[
-                    REFERRAL_ACCOUNT_PDA_SEED.as_bytes(),
-                    &config.to_bytes(),
-                    &referrer.to_bytes(),
-                ],
-                &token_mill::ID,
-            )
-            .0
-        } else {
-            Pubkey::new_unique()
+        let (mint_out, token_program_out) = match create_dca_order_action.swap_type {
+            SwapType::Buy => (base_token_mint, base_token_program),
+            SwapType::Sell => (quote_token_mint, quote_token_program),
         };
 
-        let referral_quote_token_ata = if referrer.is_some() {
-            get_associated_token_address_with_program_id(
-                &referral_account,
-                &quote_token_mint,
-                &quote_token_program,
-            )
-        } else {
-            token_mill::ID
-        };
+        let owner_token_account = get_associated_token_address_with_program_id(
+            &create_dca_order_action.signer,
+            &mint_out,
+            &token_program_out,
+        );
 
         Self {
-            config,
+            config: token_mill_env.config,
             market,
+            dca_order: create_dca_order_action.dca_order,
             base_token_mint,
             quote_token_mint,
             market_base_token_ata,
             market_quote_token_ata,
-            user_base_token_ata,
-            user_quote_token_ata,
-            protocol_quote_token_ata,
-            referral_quote_token_ata,
+            dca_order_escrow: create_dca_order_action.dca_order_escrow,
+            owner_token_account,
+            market_oracle: token_mill::ID,
+            market_stats: token_mill::ID,
             signer,
             base_token_program,
             quote_token_program,
-            swap_type,
-            swap_amount_type,
-            amount,
-            other_amount_threshold,
+            transfer_hook_accounts: token_mill_env.transfer_hook_accounts(),
         }
     }
 }
 
-impl InstructionGenerator for SwapAction {
+impl InstructionGenerator for ExecuteDcaAction {
     fn accounts(&self) -> Vec<AccountMeta> {
         let mut accounts = vec![
             AccountMeta::new_readonly(self.config, false),
             AccountMeta::new(self.market, false),
+            AccountMeta::new(self.dca_order, false),
             AccountMeta::new_readonly(self.base_token_mint, false),
             AccountMeta::new_readonly(self.quote_token_mint, false),
             AccountMeta::new(self.market_base_token_ata, false),
             AccountMeta::new(self.market_quote_token_ata, false),
-            AccountMeta::new(self.user_base_token_ata, false),
-            AccountMeta::new(self.user_quote_token_ata, false),
-            AccountMeta::new(self.protocol_quote_token_ata, false),
-            AccountMeta::new(self.referral_quote_token_ata, false),
+            AccountMeta::new(self.dca_order_escrow, false),
+            AccountMeta::new(self.owner_token_account, false),
+            AccountMeta::new(self.market_oracle, false),
+            AccountMeta::new(self.market_stats, false),
         ];
 
         accounts.append_payer(self.signer);
@@ -1003,17 +3239,121 @@ impl InstructionGenerator for SwapAction {
         accounts.push(AccountMeta::new_readonly(self.quote_token_program, false));
 
         accounts.append_cpi_event_accounts(tm_event_authority());
+        accounts.extend_from_slice(&self.transfer_hook_accounts);
 
         accounts
     }
 
     fn instruction(&self) -> Instruction {
-        let input = token_mill::instruction::Swap {
-            swap_type: self.swap_type,
-            swap_amount_type: self.swap_amount_type,
-            amount: self.amount,
-            other_amount_threshold: self.other_amount_threshold,
-        };
+        let input = token_mill::instruction::ExecuteDca {};
+
+        Instruction {
+            program_id: token_mill::ID,
+            accounts: self.accounts(),
+            data: input.data(),
+        }
+    }
+}
+
+pub struct CancelDcaOrderAction {
+    // Accounts
+    pub dca_order: Pubkey,
+    pub escrow_token_mint: Pubkey,
+    pub dca_order_escrow: Pubkey,
+    pub owner_token_account: Pubkey,
+    pub signer: Pubkey,
+    pub escrow_token_program: Pubkey,
+    pub transfer_hook_accounts: Vec<AccountMeta>,
+}
+
+impl CancelDcaOrderAction {
+    pub fn new(create_dca_order_action: &CreateDcaOrderAction) -> Self {
+        Self {
+            dca_order: create_dca_order_action.dca_order,
+            escrow_token_mint: create_dca_order_action.escrow_token_mint,
+            dca_order_escrow: create_dca_order_action.dca_order_escrow,
+            owner_token_account: create_dca_order_action.owner_token_account,
+            signer: create_dca_order_action.signer,
+            escrow_token_program: create_dca_order_action.escrow_token_program,
+            transfer_hook_accounts: create_dca_order_action.transfer_hook_accounts.clone(),
+        }
+    }
+}
+
+impl InstructionGenerator for CancelDcaOrderAction {
+    fn accounts(&self) -> Vec<AccountMeta> {
+        let mut accounts = vec![
+            AccountMeta::new(self.dca_order, false),
+            AccountMeta::new_readonly(self.escrow_token_mint, false),
+            AccountMeta::new(self.dca_order_escrow, false),
+            AccountMeta::new(self.owner_token_account, false),
+        ];
+
+        accounts.append_payer(self.signer);
+
+        accounts.push(AccountMeta::new_readonly(self.escrow_token_program, false));
+
+        accounts.append_cpi_event_accounts(tm_event_authority());
+        accounts.extend_from_slice(&self.transfer_hook_accounts);
+
+        accounts
+    }
+
+    fn instruction(&self) -> Instruction {
+        let input = token_mill::instruction::CancelDcaOrder {};
+
+        Instruction {
+            program_id: token_mill::ID,
+            accounts: self.accounts(),
+            data: input.data(),
+        }
+    }
+}
+
+pub fn get_market_oracle_address(market: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[MARKET_ORACLE_PDA_SEED.as_bytes(), &market.to_bytes()],
+        &token_mill::ID,
+    )
+    .0
+}
+
+pub struct CreateMarketOracleAction {
+    // Accounts
+    pub config: Pubkey,
+    pub market: Pubkey,
+    pub market_oracle: Pubkey,
+    pub signer: Pubkey,
+}
+
+impl CreateMarketOracleAction {
+    pub fn new(token_mill_env: &TokenMillEnv) -> Self {
+        let market = token_mill_env.market;
+
+        Self {
+            config: token_mill_env.config,
+            market,
+            market_oracle: get_market_oracle_address(&market),
+            signer: make_address("alice"),
+        }
+    }
+}
+
+impl InstructionGenerator for CreateMarketOracleAction {
+    fn accounts(&self) -> Vec<AccountMeta> {
+        let mut accounts = vec![
+            AccountMeta::new_readonly(self.config, false),
+            AccountMeta::new(self.market, false),
+            AccountMeta::new(self.market_oracle, false),
+        ];
+
+        accounts.append_payer(self.signer).append_system_program();
+
+        accounts
+    }
+
+    fn instruction(&self) -> Instruction {
+        let input = token_mill::instruction::CreateMarketOracle {};
 
         Instruction {
             program_id: token_mill::ID,
@@ -1023,47 +3363,30 @@ impl InstructionGenerator for SwapAction {
     }
 }
 
-pub struct UpdateCreatorAction {
+pub struct GetTwapAction {
     // Accounts
-    pub market: Pubkey,
-    pub signer: Pubkey,
+    pub market_oracle: Pubkey,
     // Args
-    pub new_creator: Pubkey,
+    pub window: i64,
 }
 
-impl UpdateCreatorAction {
-    pub fn new(new_creator: Pubkey) -> Self {
-        let market = Pubkey::find_program_address(
-            &[
-                MARKET_PDA_SEED.as_bytes(),
-                &make_address("base_token_mint").to_bytes(),
-            ],
-            &token_mill::ID,
-        )
-        .0;
-
+impl GetTwapAction {
+    pub fn new(token_mill_env: &TokenMillEnv, window: i64) -> Self {
         Self {
-            market,
-            signer: make_address("alice"),
-            new_creator,
+            market_oracle: get_market_oracle_address(&token_mill_env.market),
+            window,
         }
     }
 }
 
-impl InstructionGenerator for UpdateCreatorAction {
+impl InstructionGenerator for GetTwapAction {
     fn accounts(&self) -> Vec<AccountMeta> {
-        let mut accounts = vec![AccountMeta::new(self.market, false)];
-
-        accounts
-            .append_payer(self.signer)
-            .append_cpi_event_accounts(tm_event_authority());
-
-        accounts
+        vec![AccountMeta::new_readonly(self.market_oracle, false)]
     }
 
     fn instruction(&self) -> Instruction {
-        let input = token_mill::instruction::UpdateCreator {
-            new_creator: self.new_creator,
+        let input = token_mill::instruction::GetTwap {
+            window: self.window,
         };
 
         Instruction {
@@ -1074,51 +3397,50 @@ impl InstructionGenerator for UpdateCreatorAction {
     }
 }
 
-pub struct UpdateMarketFeeSharesAction {
+pub fn get_market_stats_address(market: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[MARKET_STATS_PDA_SEED.as_bytes(), &market.to_bytes()],
+        &token_mill::ID,
+    )
+    .0
+}
+
+pub struct CreateMarketStatsAction {
     // Accounts
+    pub config: Pubkey,
     pub market: Pubkey,
+    pub market_stats: Pubkey,
     pub signer: Pubkey,
-    // Args
-    pub new_creator_fee_share: u16,
-    pub new_staking_fee_share: u16,
 }
 
-impl UpdateMarketFeeSharesAction {
-    pub fn new(new_creator_fee_share: u16, new_staking_fee_share: u16) -> Self {
-        let market = Pubkey::find_program_address(
-            &[
-                MARKET_PDA_SEED.as_bytes(),
-                &make_address("base_token_mint").to_bytes(),
-            ],
-            &token_mill::ID,
-        )
-        .0;
+impl CreateMarketStatsAction {
+    pub fn new(token_mill_env: &TokenMillEnv) -> Self {
+        let market = token_mill_env.market;
 
         Self {
+            config: token_mill_env.config,
             market,
+            market_stats: get_market_stats_address(&market),
             signer: make_address("alice"),
-            new_creator_fee_share,
-            new_staking_fee_share,
         }
     }
 }
 
-impl InstructionGenerator for UpdateMarketFeeSharesAction {
+impl InstructionGenerator for CreateMarketStatsAction {
     fn accounts(&self) -> Vec<AccountMeta> {
-        let mut accounts = vec![AccountMeta::new(self.market, false)];
+        let mut accounts = vec![
+            AccountMeta::new_readonly(self.config, false),
+            AccountMeta::new(self.market, false),
+            AccountMeta::new(self.market_stats, false),
+        ];
 
-        accounts
-            .append_payer(self.signer)
-            .append_cpi_event_accounts(tm_event_authority());
+        accounts.append_payer(self.signer).append_system_program();
 
         accounts
     }
 
     fn instruction(&self) -> Instruction {
-        let input = token_mill::instruction::UpdateMarketFeeShares {
-            new_creator_fee_share: self.new_creator_fee_share,
-            new_staking_fee_share: self.new_staking_fee_share,
-        };
+        let input = token_mill::instruction::CreateMarketStats {};
 
         Instruction {
             program_id: token_mill::ID,
@@ -1128,79 +3450,52 @@ impl InstructionGenerator for UpdateMarketFeeSharesAction {
     }
 }
 
-pub struct ClaimCreatorFeesAction {
+pub fn get_launch_allocation_address(market: &Pubkey, user: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            LAUNCH_ALLOCATION_PDA_SEED.as_bytes(),
+            &market.to_bytes(),
+            &user.to_bytes(),
+        ],
+        &token_mill::ID,
+    )
+    .0
+}
+
+pub struct CreateLaunchAllocationAction {
     // Accounts
     pub market: Pubkey,
-    pub quote_token_mint: Pubkey,
-    pub market_quote_token_ata: Pubkey,
-    pub creator_quote_token_ata: Pubkey,
+    pub launch_allocation: Pubkey,
     pub signer: Pubkey,
-    pub quote_token_program: Pubkey,
 }
 
-impl ClaimCreatorFeesAction {
+impl CreateLaunchAllocationAction {
     pub fn new(token_mill_env: &TokenMillEnv) -> Self {
-        let market = Pubkey::find_program_address(
-            &[
-                MARKET_PDA_SEED.as_bytes(),
-                &token_mill_env.base_token_mint.unwrap().to_bytes(),
-            ],
-            &token_mill::ID,
-        )
-        .0;
-
-        let signer = make_address("alice");
-
-        let quote_token_mint = token_mill_env.quote_token_mint.unwrap();
-        let quote_token_program = token_mill_env.quote_token_type.program_address();
-
-        let market_quote_token_ata = get_associated_token_address_with_program_id(
-            &market,
-            &quote_token_mint,
-            &quote_token_program,
-        );
-
-        let creator_quote_token_ata = get_associated_token_address_with_program_id(
-            &signer,
-            &quote_token_mint,
-            &quote_token_program,
-        );
+        let market = token_mill_env.market;
+        let signer = make_address("bob");
 
         Self {
             market,
-            quote_token_mint,
-            market_quote_token_ata,
-            creator_quote_token_ata,
+            launch_allocation: get_launch_allocation_address(&market, &signer),
             signer,
-            quote_token_program,
         }
     }
 }
 
-impl InstructionGenerator for ClaimCreatorFeesAction {
+impl InstructionGenerator for CreateLaunchAllocationAction {
     fn accounts(&self) -> Vec<AccountMeta> {
         let mut accounts = vec![
-            AccountMeta::new(self.market, false),
-            AccountMeta::new_readonly(self.quote_token_mint, false),
-            AccountMeta::new(self.market_quote_token_ata, false),
-            AccountMeta::new(self.creator_quote_token_ata, false),
+            AccountMeta::new_readonly(self.market, false),
+            AccountMeta::new(self.launch_allocation, false),
         ];
 
-        accounts.append_payer(self.signer);
-
-        match self.quote_token_program {
-            spl_token::ID => accounts.append_token_program(),
-            spl_token_2022::ID => accounts.append_token_2022_program(),
-            _ => unreachable!(),
-        };
-
-        accounts.append_cpi_event_accounts(tm_event_authority());
+        accounts.append_payer(self.signer).append_system_program();
 
         accounts
     }
 
     fn instruction(&self) -> Instruction {
-        let input = token_mill::instruction::ClaimCreatorFees {};
+        let input = token_mill::instruction::CreateLaunchAllocation {};
 
         Instruction {
             program_id: token_mill::ID,
@@ -1210,17 +3505,6 @@ impl InstructionGenerator for ClaimCreatorFeesAction {
     }
 }
 
-pub struct ClaimProtocolFeesAction {
-    // Accounts
-    pub config: Pubkey,
-    pub market: Pubkey,
-    pub quote_token_mint: Pubkey,
-    pub market_quote_token_ata: Pubkey,
-    pub authority_quote_token_ata: Pubkey,
-    pub signer: Pubkey,
-    pub quote_token_program: Pubkey,
-}
-
 pub struct CreateStakingAction {
     // Accounts
     pub market: Pubkey,
@@ -1345,6 +3629,7 @@ impl InstructionGenerator for CreateStakePositionAction {
 
 pub struct DepositAction {
     // Accounts
+    pub config: Pubkey,
     pub market: Pubkey,
     pub market_staking: Pubkey,
     pub stake_position: Pubkey,
@@ -1353,6 +3638,7 @@ pub struct DepositAction {
     pub market_base_token_ata: Pubkey,
     pub user_base_token_ata: Pubkey,
     pub signer: Pubkey,
+    pub transfer_hook_accounts: Vec<AccountMeta>,
     // Args
     pub amount: u64,
 }
@@ -1398,6 +3684,7 @@ impl DepositAction {
         );
 
         Self {
+            config: testing_env.config,
             market,
             market_staking,
             stake_position,
@@ -1407,6 +3694,7 @@ impl DepositAction {
             user_base_token_ata,
             signer,
             amount,
+            transfer_hook_accounts: testing_env.transfer_hook_accounts(),
         }
     }
 }
@@ -1414,6 +3702,7 @@ impl DepositAction {
 impl InstructionGenerator for DepositAction {
     fn accounts(&self) -> Vec<AccountMeta> {
         let mut accounts = vec![
+            AccountMeta::new_readonly(self.config, false),
             AccountMeta::new(self.market, false),
             AccountMeta::new(self.market_staking, false),
             AccountMeta::new(self.stake_position, false),
@@ -1425,6 +3714,7 @@ impl InstructionGenerator for DepositAction {
         accounts.append_payer(self.signer);
         accounts.push(AccountMeta::new_readonly(self.base_token_program, false));
         accounts.append_cpi_event_accounts(tm_event_authority());
+        accounts.extend_from_slice(&self.transfer_hook_accounts);
 
         accounts
     }
@@ -1444,6 +3734,7 @@ impl InstructionGenerator for DepositAction {
 
 pub struct WithdrawAction {
     // Accounts
+    pub config: Pubkey,
     pub market: Pubkey,
     pub market_staking: Pubkey,
     pub stake_position: Pubkey,
@@ -1452,6 +3743,7 @@ pub struct WithdrawAction {
     pub market_base_token_ata: Pubkey,
     pub user_base_token_ata: Pubkey,
     pub signer: Pubkey,
+    pub transfer_hook_accounts: Vec<AccountMeta>,
     // Args
     pub amount: u64,
 }
@@ -1461,6 +3753,7 @@ impl WithdrawAction {
         let deposit_action = DepositAction::new(testing_env, amount);
 
         Self {
+            config: deposit_action.config,
             market: deposit_action.market,
             market_staking: deposit_action.market_staking,
             stake_position: deposit_action.stake_position,
@@ -1470,6 +3763,7 @@ impl WithdrawAction {
             user_base_token_ata: deposit_action.user_base_token_ata,
             signer: deposit_action.signer,
             amount,
+            transfer_hook_accounts: deposit_action.transfer_hook_accounts,
         }
     }
 }
@@ -1477,6 +3771,7 @@ impl WithdrawAction {
 impl InstructionGenerator for WithdrawAction {
     fn accounts(&self) -> Vec<AccountMeta> {
         let mut accounts = vec![
+            AccountMeta::new_readonly(self.config, false),
             AccountMeta::new(self.market, false),
             AccountMeta::new(self.market_staking, false),
             AccountMeta::new(self.stake_position, false),
@@ -1488,6 +3783,7 @@ impl InstructionGenerator for WithdrawAction {
         accounts.append_payer(self.signer);
         accounts.push(AccountMeta::new_readonly(self.base_token_program, false));
         accounts.append_cpi_event_accounts(tm_event_authority());
+        accounts.extend_from_slice(&self.transfer_hook_accounts);
 
         accounts
     }
@@ -1607,10 +3903,23 @@ impl InstructionGenerator for ClaimStakingRewardsAction {
     }
 }
 
+pub fn get_referral_account_address(referrer: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            REFERRAL_ACCOUNT_PDA_SEED.as_bytes(),
+            make_address("config").as_ref(),
+            referrer.as_ref(),
+        ],
+        &token_mill::ID,
+    )
+    .0
+}
+
 pub struct CreateReferralAccountAction {
     // Accounts
     pub config: Pubkey,
     pub referral_account: Pubkey,
+    pub parent_referral_account: Pubkey,
     pub signer: Pubkey,
     // Args
     pub referrer: Pubkey,
@@ -1624,27 +3933,30 @@ impl Default for CreateReferralAccountAction {
 
 impl CreateReferralAccountAction {
     pub fn new() -> Self {
-        let config = make_address("config");
-
         let referrer = make_address("carol");
 
-        let referral_account = Pubkey::find_program_address(
-            &[
-                REFERRAL_ACCOUNT_PDA_SEED.as_bytes(),
-                (config.as_ref()),
-                (referrer.as_ref()),
-            ],
-            &token_mill::ID,
-        )
-        .0;
-
         Self {
-            config,
-            referral_account,
+            config: make_address("config"),
+            referral_account: get_referral_account_address(&referrer),
+            parent_referral_account: token_mill::ID,
             signer: make_address("admin"),
             referrer,
         }
     }
+
+    pub fn with_referrer(mut self, referrer: Pubkey) -> Self {
+        self.referral_account = get_referral_account_address(&referrer);
+        self.referrer = referrer;
+
+        self
+    }
+
+    /// Records the referral account of `parent_referrer`, which must have been created, as the parent
+    pub fn with_parent_referrer(mut self, parent_referrer: Pubkey) -> Self {
+        self.parent_referral_account = get_referral_account_address(&parent_referrer);
+
+        self
+    }
 }
 
 impl InstructionGenerator for CreateReferralAccountAction {
@@ -1652,6 +3964,7 @@ impl InstructionGenerator for CreateReferralAccountAction {
         let mut accounts = vec![
             AccountMeta::new_readonly(self.config, false),
             AccountMeta::new(self.referral_account, false),
+            AccountMeta::new_readonly(self.parent_referral_account, false),
         ];
 
         accounts
@@ -1690,15 +4003,7 @@ impl ClaimReferralFeesAction {
         let config = make_address("config");
         let signer = make_address("carol");
 
-        let referral_account = Pubkey::find_program_address(
-            &[
-                REFERRAL_ACCOUNT_PDA_SEED.as_bytes(),
-                (config.as_ref()),
-                (signer.as_ref()),
-            ],
-            &token_mill::ID,
-        )
-        .0;
+        let referral_account = get_referral_account_address(&signer);
 
         let quote_token_mint = token_mill_env.quote_token_mint.unwrap();
         let quote_token_program = token_mill_env.quote_token_type.program_address();
@@ -1725,6 +4030,23 @@ impl ClaimReferralFeesAction {
             quote_token_program,
         }
     }
+
+    pub fn with_referrer(mut self, referrer: Pubkey) -> Self {
+        self.referral_account = get_referral_account_address(&referrer);
+        self.referral_account_quote_token_ata = get_associated_token_address_with_program_id(
+            &self.referral_account,
+            &self.quote_token_mint,
+            &self.quote_token_program,
+        );
+        self.referrer_quote_token_ata = get_associated_token_address_with_program_id(
+            &referrer,
+            &self.quote_token_mint,
+            &self.quote_token_program,
+        );
+        self.signer = referrer;
+
+        self
+    }
 }
 
 impl InstructionGenerator for ClaimReferralFeesAction {
@@ -1760,8 +4082,117 @@ impl InstructionGenerator for ClaimReferralFeesAction {
     }
 }
 
+pub struct MigrateConfigAction {
+    pub config: Pubkey,
+    pub signer: Pubkey,
+}
+
+impl Default for MigrateConfigAction {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MigrateConfigAction {
+    pub fn new() -> Self {
+        Self {
+            config: make_address("config"),
+            signer: make_address("admin"),
+        }
+    }
+}
+
+impl InstructionGenerator for MigrateConfigAction {
+    fn accounts(&self) -> Vec<AccountMeta> {
+        let mut accounts = vec![AccountMeta::new(self.config, false)];
+
+        accounts.append_payer(self.signer).append_system_program();
+
+        accounts
+    }
+
+    fn instruction(&self) -> Instruction {
+        let input = token_mill::instruction::MigrateConfig {};
+
+        Instruction {
+            program_id: token_mill::ID,
+            accounts: self.accounts(),
+            data: input.data(),
+        }
+    }
+}
+
+pub struct MigrateMarketAction {
+    pub market: Pubkey,
+    pub signer: Pubkey,
+}
+
+impl MigrateMarketAction {
+    pub fn new(token_mill_env: &TokenMillEnv) -> Self {
+        Self {
+            market: token_mill_env.market,
+            signer: make_address("admin"),
+        }
+    }
+}
+
+impl InstructionGenerator for MigrateMarketAction {
+    fn accounts(&self) -> Vec<AccountMeta> {
+        let mut accounts = vec![AccountMeta::new(self.market, false)];
+
+        accounts.append_payer(self.signer).append_system_program();
+
+        accounts
+    }
+
+    fn instruction(&self) -> Instruction {
+        let input = token_mill::instruction::MigrateMarket {};
+
+        Instruction {
+            program_id: token_mill::ID,
+            accounts: self.accounts(),
+            data: input.data(),
+        }
+    }
+}
+
+pub struct MigrateReferralAccountAction {
+    pub referral_account: Pubkey,
+    pub signer: Pubkey,
+}
+
+impl MigrateReferralAccountAction {
+    pub fn new(referrer: Pubkey) -> Self {
+        Self {
+            referral_account: get_referral_account_address(&referrer),
+            signer: make_address("admin"),
+        }
+    }
+}
+
+impl InstructionGenerator for MigrateReferralAccountAction {
+    fn accounts(&self) -> Vec<AccountMeta> {
+        let mut accounts = vec![AccountMeta::new(self.referral_account, false)];
+
+        accounts.append_payer(self.signer).append_system_program();
+
+        accounts
+    }
+
+    fn instruction(&self) -> Instruction {
+        let input = token_mill::instruction::MigrateReferralAccount {};
+
+        Instruction {
+            program_id: token_mill::ID,
+            accounts: self.accounts(),
+            data: input.data(),
+        }
+    }
+}
+
 pub struct CreateVestingPlanAction {
     // Accounts
+    pub config: Pubkey,
     pub market: Pubkey,
     pub staking: Pubkey,
     pub staking_position: Pubkey,
@@ -1771,6 +4202,7 @@ pub struct CreateVestingPlanAction {
     pub market_base_token_ata: Pubkey,
     pub user_base_token_ata: Pubkey,
     pub signer: Pubkey,
+    pub transfer_hook_accounts: Vec<AccountMeta>,
     // Args
     pub vesting_amount: u64,
     pub start: i64,
@@ -1827,6 +4259,7 @@ impl CreateVestingPlanAction {
         );
 
         Self {
+            config: token_mill_env.config,
             market,
             staking,
             staking_position,
@@ -1840,6 +4273,7 @@ impl CreateVestingPlanAction {
             start,
             vesting_duration,
             cliff_duration,
+            transfer_hook_accounts: token_mill_env.transfer_hook_accounts(),
         }
     }
 }
@@ -1847,6 +4281,7 @@ impl CreateVestingPlanAction {
 impl InstructionGenerator for CreateVestingPlanAction {
     fn accounts(&self) -> Vec<AccountMeta> {
         let mut accounts = vec![
+            AccountMeta::new_readonly(self.config, false),
             AccountMeta::new(self.market, false),
             AccountMeta::new(self.staking, false),
             AccountMeta::new(self.staking_position, false),
@@ -1861,6 +4296,7 @@ impl InstructionGenerator for CreateVestingPlanAction {
         accounts
             .append_system_program()
             .append_cpi_event_accounts(tm_event_authority());
+        accounts.extend_from_slice(&self.transfer_hook_accounts);
 
         accounts
     }
@@ -1892,6 +4328,7 @@ pub struct ReleaseAction {
     pub market_base_token_ata: Pubkey,
     pub user_base_token_ata: Pubkey,
     pub signer: Pubkey,
+    pub transfer_hook_accounts: Vec<AccountMeta>,
 }
 
 impl ReleaseAction {
@@ -1908,6 +4345,7 @@ impl ReleaseAction {
             market_base_token_ata: create_vesting_plan_action.market_base_token_ata,
             user_base_token_ata: create_vesting_plan_action.user_base_token_ata,
             signer: create_vesting_plan_action.signer,
+            transfer_hook_accounts: create_vesting_plan_action.transfer_hook_accounts,
         }
     }
 }
@@ -1927,6 +4365,7 @@ impl InstructionGenerator for ReleaseAction {
         accounts.append_payer(self.signer);
         accounts.push(AccountMeta::new_readonly(self.base_token_program, false));
         accounts.append_cpi_event_accounts(tm_event_authority());
+        accounts.extend_from_slice(&self.transfer_hook_accounts);
 
         accounts
     }
@@ -9,7 +9,11 @@ use anchor_spl::{
 use anchor_spl::{
     metadata::Metadata,
     token_interface::spl_token_2022::{
-        extension::StateWithExtensions, solana_program::program_pack::Pack,
+        extension::{
+            transfer_fee::instruction::initialize_transfer_fee_config, ExtensionType,
+            StateWithExtensions,
+        },
+        solana_program::program_pack::Pack,
         state::Account as SplAccount,
     },
 };
@@ -27,14 +31,19 @@ use solana_sdk::{
     native_token::sol_to_lamports,
     pubkey::Pubkey,
     signature::Keypair,
+    system_instruction,
     transaction::{Transaction, TransactionError},
 };
 use std::collections::HashMap;
 
 pub use token_mill::{
     errors::TokenMillError,
-    manager::swap_manager::{SwapAmountType, SwapType},
-    state::QuoteTokenBadgeStatus,
+    manager::swap_manager::{SwapAmountType, SwapBatchMode, SwapLeg, SwapLegResult, SwapType},
+    state::{
+        BuyTaxRecipient, CurveKind, FeeDiscountTier, LaunchProtection, MarketStatus,
+        PausableOperation, PresaleProof, PriceInterpolation, QuoteTokenBadgeStatus,
+        TransferHookBadgeStatus,
+    },
 };
 
 pub mod actions;
@@ -113,6 +122,15 @@ impl JoelanaEnv {
             .unwrap();
     }
 
+    pub fn add_mock_transfer_hook_program(&mut self) {
+        self.svm_engine
+            .add_program_from_file(
+                mock_transfer_hook::id(),
+                "../../target/deploy/mock_transfer_hook.so",
+            )
+            .unwrap();
+    }
+
     pub fn add_metadata_program(&mut self) {
         self.svm_engine
             .add_program_from_file(
@@ -151,6 +169,10 @@ impl JoelanaEnv {
             .unwrap();
     }
 
+    pub fn get_unix_timestamp(&self) -> i64 {
+        self.svm_engine.get_sysvar::<Clock>().unix_timestamp
+    }
+
     pub fn warp(&mut self, time: i64) {
         let mut clock = self.svm_engine.get_sysvar::<Clock>();
         clock.unix_timestamp += time;
@@ -168,13 +190,79 @@ impl JoelanaEnv {
             .send()
             .unwrap();
 
+        self.mint_to_actors(&payer, &token_address, token_type)?;
+
+        Ok(token_address)
+    }
+
+    /// Creates a Token-2022 mint with a `TransferFeeConfig` extension
+    pub fn create_token_with_transfer_fee(
+        &mut self,
+        decimals: u8,
+        transfer_fee_basis_points: u16,
+        maximum_fee: u64,
+    ) -> Result<Pubkey> {
+        let payer = Keypair::new();
+        let mint = Keypair::new();
+
+        self.airdrop(&payer.pubkey());
+
+        let space = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[
+            ExtensionType::TransferFeeConfig,
+        ])?;
+
+        let instructions = [
+            system_instruction::create_account(
+                &payer.pubkey(),
+                &mint.pubkey(),
+                self.svm_engine.minimum_balance_for_rent_exemption(space),
+                space as u64,
+                &spl_token_2022::id(),
+            ),
+            initialize_transfer_fee_config(
+                &spl_token_2022::id(),
+                &mint.pubkey(),
+                Some(&payer.pubkey()),
+                Some(&payer.pubkey()),
+                transfer_fee_basis_points,
+                maximum_fee,
+            )?,
+            spl_token_2022::instruction::initialize_mint2(
+                &spl_token_2022::id(),
+                &mint.pubkey(),
+                &payer.pubkey(),
+                None,
+                decimals,
+            )?,
+        ];
+
+        let tx = Transaction::new_signed_with_payer(
+            &instructions,
+            Some(&payer.pubkey()),
+            &[&payer, &mint],
+            self.svm_engine.latest_blockhash(),
+        );
+
+        self.svm_engine.send_transaction(tx).unwrap();
+
+        self.mint_to_actors(&payer, &mint.pubkey(), TokenType::Token2022)?;
+
+        Ok(mint.pubkey())
+    }
+
+    fn mint_to_actors(
+        &mut self,
+        mint_authority: &Keypair,
+        token_address: &Pubkey,
+        token_type: TokenType,
+    ) -> Result<()> {
         for actor in ACTORS {
-            let actor_ata = self.create_ata(&make_address(actor), &token_address, token_type)?;
+            let actor_ata = self.create_ata(&make_address(actor), token_address, token_type)?;
 
             MintTo::new(
                 &mut self.svm_engine,
-                &payer,
-                &token_address,
+                mint_authority,
+                token_address,
                 &actor_ata,
                 (u64::MAX - 1) / ACTORS.len() as u64,
             )
@@ -183,9 +271,9 @@ impl JoelanaEnv {
             .unwrap();
         }
 
-        self.tokens.insert(token_address, token_type);
+        self.tokens.insert(*token_address, token_type);
 
-        Ok(token_address)
+        Ok(())
     }
 
     pub fn create_ata(
@@ -214,6 +302,16 @@ impl JoelanaEnv {
             .expect("Account not found")
     }
 
+    pub fn set_account(&mut self, pubkey: &Pubkey, account: Account) {
+        self.svm_engine.set_account(*pubkey, account).unwrap();
+    }
+
+    pub fn account_exists(&self, pubkey: &Pubkey) -> bool {
+        self.svm_engine
+            .get_account(pubkey)
+            .is_some_and(|account| account.lamports > 0)
+    }
+
     pub fn get_parsed_account<T>(&self, pubkey: &Pubkey) -> T
     where
         T: AccountDeserialize,
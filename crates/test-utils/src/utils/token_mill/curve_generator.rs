@@ -1,31 +1,67 @@
-use token_mill::constant::{PRICES_LENGTH, SCALE};
+use token_mill::{constant::SCALE, state::PriceInterpolation};
+
+use crate::joelana_env::actions::token_mill::DEFAULT_TOTAL_SUPPLY;
 
 const SCALE_EVM: u128 = 1_000_000_000_000_000_000; //1e18
+pub const DEFAULT_PRICES_LENGTH: usize = 11;
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Clone)]
 pub struct Curve {
-    pub bid_prices: [u64; PRICES_LENGTH],
-    pub ask_prices: [u64; PRICES_LENGTH],
+    pub bid_prices: Vec<u64>,
+    pub ask_prices: Vec<u64>,
+    pub supply_breakpoints: Vec<u64>,
+    pub price_interpolation: PriceInterpolation,
 }
 
 impl Default for Curve {
     fn default() -> Self {
-        let mut bid_prices = [0; PRICES_LENGTH];
-        let mut ask_prices = [0; PRICES_LENGTH];
+        Self::new(DEFAULT_PRICES_LENGTH, DEFAULT_TOTAL_SUPPLY)
+    }
+}
 
-        for i in 0..PRICES_LENGTH {
-            bid_prices[i] = i as u64 * SCALE as u64 * 9 / 10_000;
-            ask_prices[i] = i as u64 * SCALE as u64 / 1_000;
+impl Curve {
+    /// Linear curve going from 0 to 0.01 quote per base token with evenly spaced breakpoints,
+    /// bid prices being 90% of ask prices
+    pub fn new(prices_length: usize, total_supply: u64) -> Self {
+        let interval_number = prices_length as u64 - 1;
+
+        let bid_prices = (0..prices_length as u64)
+            .map(|i| i * SCALE as u64 * 9 / (1_000 * interval_number))
+            .collect();
+        let ask_prices = (0..prices_length as u64)
+            .map(|i| i * SCALE as u64 / (100 * interval_number))
+            .collect();
+        let supply_breakpoints = (0..prices_length as u64)
+            .map(|i| (i as u128 * total_supply as u128 / interval_number as u128) as u64)
+            .collect();
+
+        Self {
+            bid_prices,
+            ask_prices,
+            supply_breakpoints,
+            price_interpolation: PriceInterpolation::Linear,
         }
+    }
+
+    /// Exponential curve going from 0.0001 to 0.1 quote per base token with evenly spaced breakpoints,
+    /// bid prices being 90% of ask prices
+    pub fn exponential(prices_length: usize, total_supply: u64) -> Self {
+        let interval_number = prices_length as f64 - 1.0;
+
+        let ask_prices: Vec<u64> = (0..prices_length)
+            .map(|i| (1e6 * 1_000f64.powf(i as f64 / interval_number)) as u64)
+            .collect();
+        let bid_prices = ask_prices.iter().map(|p| p * 9 / 10).collect();
 
         Self {
             bid_prices,
             ask_prices,
+            price_interpolation: PriceInterpolation::Exponential,
+            ..Self::new(prices_length, total_supply)
         }
     }
-}
 
-impl Curve {
+    /// Only valid for evenly spaced breakpoints, the EVM implementation doesn't support others
     pub fn to_evm(&self) -> (Vec<u128>, Vec<u128>) {
         (
             self.bid_prices
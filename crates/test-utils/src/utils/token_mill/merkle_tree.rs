@@ -0,0 +1,66 @@
+use solana_sdk::pubkey::Pubkey;
+use token_mill::state::PresaleProof;
+
+/// Presale allowlist, each leaf allowing a user to buy up to `max_amount` (0 if uncapped)
+#[derive(Debug, Clone)]
+pub struct MerkleTree {
+    pub leaves: Vec<(Pubkey, u64)>,
+    /// Hashes of each level of the tree, from the leaves to the root
+    layers: Vec<Vec<[u8; 32]>>,
+}
+
+impl MerkleTree {
+    pub fn new(leaves: &[(Pubkey, u64)]) -> Self {
+        let mut layers = vec![leaves
+            .iter()
+            .map(|(user, max_amount)| PresaleProof::get_leaf(user, *max_amount))
+            .collect::<Vec<_>>()];
+
+        while layers.last().unwrap().len() > 1 {
+            // The last node of an odd layer is carried to the next one
+            let layer = layers
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| match pair {
+                    [a, b] => PresaleProof::hash_nodes(a, b),
+                    [a] => *a,
+                    _ => unreachable!(),
+                })
+                .collect();
+
+            layers.push(layer);
+        }
+
+        Self {
+            leaves: leaves.to_vec(),
+            layers,
+        }
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        self.layers.last().unwrap()[0]
+    }
+
+    /// Proof of the leaf of `user`, which must be allowlisted
+    pub fn get_proof(&self, user: &Pubkey) -> PresaleProof {
+        let mut index = self
+            .leaves
+            .iter()
+            .position(|(leaf_user, _)| leaf_user == user)
+            .unwrap();
+        let max_amount = self.leaves[index].1;
+
+        let mut proof = Vec::new();
+
+        for layer in &self.layers[..self.layers.len() - 1] {
+            if let Some(sibling) = layer.get(index ^ 1) {
+                proof.push(*sibling);
+            }
+
+            index /= 2;
+        }
+
+        PresaleProof { max_amount, proof }
+    }
+}
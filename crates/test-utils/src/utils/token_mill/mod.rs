@@ -1,2 +1,3 @@
 pub mod constants;
 pub mod curve_generator;
+pub mod merkle_tree;
@@ -0,0 +1,6 @@
+pub const MAX_PRICES_LENGTH: usize = 64;
+pub const MAX_EXP_INTERVALS_PER_SWAP: usize = 2; // Bounds the compute units of swaps on exponential curves
+pub const BASE_PRECISION: u64 = 1_000_000; // 1e6
+pub const SCALE: u128 = 10_000_000_000; // 1e10
+pub const MAX_BPS: u64 = 10_000;
+pub const GRADUATED_POOL_FEE_BPS: u64 = 100; // 1%
@@ -0,0 +1,59 @@
+use std::{fmt, num::TryFromIntError};
+
+pub type Result<T> = std::result::Result<T, TokenMillError>;
+
+/// Subset of the program errors that quoting can return, plus `InvalidAccountData`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TokenMillError {
+    InvalidAmount,
+    MathError,
+    MarketGraduationPending,
+    /// The account data isn't a `Market` account
+    InvalidAccountData,
+}
+
+impl TokenMillError {
+    /// Index of the error in the program's `TokenMillError`, `None` for the SDK only errors
+    pub fn program_error_index(&self) -> Option<u32> {
+        match self {
+            TokenMillError::InvalidAmount => Some(5),
+            TokenMillError::MathError => Some(6),
+            TokenMillError::MarketGraduationPending => Some(24),
+            TokenMillError::InvalidAccountData => None,
+        }
+    }
+}
+
+impl fmt::Display for TokenMillError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+impl std::error::Error for TokenMillError {}
+
+impl From<TryFromIntError> for TokenMillError {
+    fn from(_: TryFromIntError) -> Self {
+        TokenMillError::MathError
+    }
+}
+
+/// Lets the program propagate the errors of the shared math with `?`, under its own error codes
+#[cfg(feature = "anchor")]
+impl From<TokenMillError> for anchor_lang::error::Error {
+    fn from(error: TokenMillError) -> Self {
+        use anchor_lang::error::{AnchorError, ErrorCode, ERROR_CODE_OFFSET};
+
+        match error.program_error_index() {
+            Some(index) => AnchorError {
+                error_name: error.to_string(),
+                error_code_number: ERROR_CODE_OFFSET + index,
+                error_msg: error.to_string(),
+                error_origin: None,
+                compared_values: None,
+            }
+            .into(),
+            None => ErrorCode::AccountDidNotDeserialize.into(),
+        }
+    }
+}
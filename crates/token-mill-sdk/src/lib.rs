@@ -0,0 +1,21 @@
+//! Swap math of Token Mill markets, shared by the program and off-chain quoting.
+//!
+//! Decodes `Market` accounts from their raw data and runs the swap math the program itself runs,
+//! without depending on the Anchor runtime or any Solana syscall.
+//! The `anchor` feature converts its errors into the program's ones.
+
+pub mod constant;
+pub mod errors;
+pub mod market;
+pub mod math;
+pub mod swap_manager;
+
+pub use errors::{Result, TokenMillError};
+pub use market::*;
+pub use swap_manager::{
+    get_sub_referral_fee, quote_swap, swap, swap_with_buy_tax, swap_with_fee_discount,
+    swap_with_transfer_fee, ReferralFeeShares, SwapAmountType, SwapQuote, SwapResult, SwapType,
+    TransferFee,
+};
+
+pub type Pubkey = [u8; 32];
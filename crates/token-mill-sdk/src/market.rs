@@ -0,0 +1,954 @@
+use std::{cmp::min, mem::size_of};
+
+use bytemuck::{Pod, Zeroable};
+
+use crate::{
+    constant::*,
+    errors::{Result, TokenMillError},
+    math::{
+        div, get_amount_in, get_amount_out, get_amount_with_fee, get_delta_base_in,
+        get_delta_base_out, get_exp_delta_base_in, get_exp_delta_base_out, get_exp_price,
+        get_exp_quote_amount, get_fee_amount, get_virtual_base_amount_in,
+        get_virtual_base_amount_out, get_virtual_price, get_virtual_quote_amount, mul_div,
+        Rounding,
+    },
+    swap_manager::{SwapAmountType, SwapType},
+    Pubkey,
+};
+
+/// Anchor discriminator of the `Market` account, i.e. `sha256("account:Market")[..8]`
+pub const MARKET_DISCRIMINATOR: [u8; 8] = [219, 190, 213, 55, 0, 227, 198, 154];
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+pub struct MarketFees {
+    /// staking_fee_share + creator_fee_share + protocol_fee_share = 100%
+    pub staking_fee_share: u16,
+    pub creator_fee_share: u16,
+    _space: u32,
+
+    pub pending_staking_fees: u64,
+    pub pending_creator_fees: u64,
+    /// Claimed by the config's protocol fee recipient
+    pub pending_protocol_fees: u64,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CurveKind {
+    /// Bid and ask price tables, set with `set_market_prices`
+    PiecewiseLinear,
+    /// Constant product curve over virtual reserves, bid quotes being `spread_bps` lower than ask quotes
+    VirtualConstantProduct {
+        virtual_base: u64,
+        virtual_quote: u64,
+        spread_bps: u16,
+    },
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+pub struct MarketVirtualReserves {
+    /// Virtual base reserve when the circulating supply is 0, greater than the total supply
+    pub virtual_base: u64,
+    /// Virtual quote reserve when the circulating supply is 0
+    pub virtual_quote: u64,
+    pub spread_bps: u16,
+    _space: [u8; 6],
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum PriceInterpolation {
+    /// Prices grow linearly between two breakpoints
+    Linear,
+    /// Prices grow geometrically between two breakpoints
+    Exponential,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum GraduationState {
+    BondingCurve,
+    Graduated,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum MarketStatus {
+    /// Waiting for the creator to set the prices
+    Created,
+    /// Prices are set, trading opens at `trading_start_ts`
+    PricesSet,
+    Trading,
+    /// Only sells are allowed
+    SellOnly,
+    /// No swap is allowed anymore
+    Closed,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+pub struct MarketGraduation {
+    /// Circulating supply freezing the bonding curve, 0 if disabled
+    pub supply_threshold: u64,
+    /// Quote amount raised by the bonding curve freezing it, 0 if disabled
+    pub quote_threshold: u64,
+    /// Quote reserve of the constant product pool, only used once graduated
+    pub quote_reserve: u64,
+    /// `GraduationState` of the market
+    pub state: u8,
+    _space: [u8; 7],
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+pub struct MarketLaunchProtection {
+    /// Duration of the launch window in seconds, 0 if disabled
+    pub window: i64,
+    /// Base amount each wallet can buy during the launch window
+    pub max_buy_per_wallet: u64,
+    /// Timestamp at which the launch window ends, set once the prices are set
+    pub end_ts: i64,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BuyTaxRecipient {
+    /// Shared between the creator, stakers and the protocol like the swap fee
+    Fees,
+    Stakers,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+pub struct MarketBuyTax {
+    /// Duration over which the tax decays linearly to 0, 0 if disabled
+    pub duration: i64,
+    /// Timestamp from which the tax decays, set once the prices are set
+    pub start_ts: i64,
+    /// Tax rate when trading opens, in bps of the quote amount paid by buyers
+    pub start_bps: u16,
+    /// `BuyTaxRecipient` of the tax
+    pub recipient: u8,
+    _space: [u8; 5],
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+pub struct MarketPresale {
+    /// Root of the merkle tree of the allowlisted `(user, max_amount)` leaves
+    pub merkle_root: [u8; 32],
+    /// Timestamp until which only allowlisted users can buy, 0 if disabled
+    pub end_ts: i64,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+pub struct Market {
+    pub config: Pubkey,
+    pub creator: Pubkey,
+
+    pub base_token_mint: Pubkey,
+    pub quote_token_mint: Pubkey,
+
+    pub base_reserve: u64,
+
+    /// Only the first `prices_length` prices and breakpoints are used
+    pub bid_prices: [u64; MAX_PRICES_LENGTH],
+    pub ask_prices: [u64; MAX_PRICES_LENGTH],
+    /// Circulating supply at which each price applies, from 0 to `total_supply`
+    pub supply_breakpoints: [u64; MAX_PRICES_LENGTH],
+
+    pub total_supply: u64,
+
+    pub fees: MarketFees,
+    pub graduation: MarketGraduation,
+    /// Only used by `CurveKind::VirtualConstantProduct` markets
+    pub virtual_reserves: MarketVirtualReserves,
+    /// Timestamp from which a `MarketStatus::PricesSet` market is trading, 0 if unscheduled
+    pub trading_start_ts: i64,
+    pub launch_protection: MarketLaunchProtection,
+    pub buy_tax: MarketBuyTax,
+    pub presale: MarketPresale,
+
+    pub quote_token_decimals: u8,
+    pub bump: u8,
+    /// Number of points of the price curves, 0 until prices are set
+    pub prices_length: u8,
+    /// `PriceInterpolation` of the price curves
+    pub price_interpolation: u8,
+    /// 0 for `CurveKind::PiecewiseLinear`, 1 for `CurveKind::VirtualConstantProduct`
+    pub curve_kind: u8,
+    /// 1 once a `MarketOracle` tracks the market, swaps must then update it
+    pub has_oracle: u8,
+    /// 1 once a `MarketStats` tracks the market, swaps must then update it
+    pub has_stats: u8,
+    /// `MarketStatus` of the market, see `get_status` for the effective one
+    pub status: u8,
+}
+
+impl MarketFees {
+    pub fn distribute_fee(
+        &mut self,
+        swap_fee: u64,
+        referral_fee_share: Option<u16>,
+    ) -> Result<(u64, u64, u64, u64)> {
+        let creator_fee = u64::try_from(
+            u128::from(swap_fee) * u128::from(self.creator_fee_share) / MAX_BPS as u128,
+        )?;
+        let staking_fee = u64::try_from(
+            u128::from(swap_fee) * u128::from(self.staking_fee_share) / MAX_BPS as u128,
+        )?;
+        let remaining_fee = swap_fee - creator_fee - staking_fee;
+
+        let referral_fee = if let Some(referral_fee_share) = referral_fee_share {
+            u64::try_from(
+                u128::from(remaining_fee) * u128::from(referral_fee_share) / MAX_BPS as u128,
+            )?
+        } else {
+            0
+        };
+
+        let protocol_fee = remaining_fee - referral_fee;
+
+        self.pending_creator_fees += creator_fee;
+        self.pending_staking_fees += staking_fee;
+        self.pending_protocol_fees += protocol_fee;
+
+        Ok((creator_fee, staking_fee, protocol_fee, referral_fee))
+    }
+}
+
+impl Market {
+    /// Decodes a `Market` from the raw data of its account, discriminator included.
+    pub fn from_account_data(data: &[u8]) -> Result<Market> {
+        let discriminator_length = MARKET_DISCRIMINATOR.len();
+
+        if data.len() < discriminator_length + size_of::<Market>()
+            || data[..discriminator_length] != MARKET_DISCRIMINATOR
+        {
+            return Err(TokenMillError::InvalidAccountData);
+        }
+
+        bytemuck::try_pod_read_unaligned(
+            &data[discriminator_length..discriminator_length + size_of::<Market>()],
+        )
+        .map_err(|_| TokenMillError::InvalidAccountData)
+    }
+
+    pub fn are_prices_set(&self) -> bool {
+        self.prices_length != 0 || self.curve_kind != 0
+    }
+
+    pub fn curve_kind(&self) -> CurveKind {
+        match self.curve_kind {
+            0 => CurveKind::PiecewiseLinear,
+            _ => CurveKind::VirtualConstantProduct {
+                virtual_base: self.virtual_reserves.virtual_base,
+                virtual_quote: self.virtual_reserves.virtual_quote,
+                spread_bps: self.virtual_reserves.spread_bps,
+            },
+        }
+    }
+
+    fn is_exponential(&self) -> bool {
+        self.price_interpolation == PriceInterpolation::Exponential as u8
+    }
+
+    /// Number of intervals a swap can cross, swaps stopping at the last one like at the end of the curve
+    fn max_swap_intervals(&self) -> usize {
+        if self.is_exponential() {
+            MAX_EXP_INTERVALS_PER_SWAP
+        } else {
+            MAX_PRICES_LENGTH
+        }
+    }
+
+    /// Base amount a swap can buy or sell from the circulating supply within `max_swap_intervals`
+    fn get_max_swap_base_amount(&self, swap_type: SwapType) -> u64 {
+        if !self.is_exponential() {
+            return u64::MAX;
+        }
+
+        let circulating_supply = self.circulating_supply();
+        let supply_breakpoints = self.supply_breakpoints();
+
+        match swap_type {
+            SwapType::Buy => {
+                let i = supply_breakpoints.partition_point(|b| *b <= circulating_supply) - 1;
+                let last_breakpoint =
+                    min(i + self.max_swap_intervals(), supply_breakpoints.len() - 1);
+
+                supply_breakpoints[last_breakpoint] - circulating_supply
+            }
+            SwapType::Sell => {
+                let i = supply_breakpoints.partition_point(|b| *b < circulating_supply);
+                let last_breakpoint = i.saturating_sub(self.max_swap_intervals());
+
+                circulating_supply - supply_breakpoints[last_breakpoint]
+            }
+        }
+    }
+
+    fn supply_breakpoints(&self) -> &[u64] {
+        &self.supply_breakpoints[..usize::from(self.prices_length)]
+    }
+
+    /// Normalized supply at breakpoint `i`
+    fn breakpoint_scaled(&self, i: usize) -> u128 {
+        u128::from(self.supply_breakpoints[i]) * SCALE / u128::from(BASE_PRECISION)
+    }
+
+    /// Normalized width of the interval between breakpoints `i` and `i + 1`
+    fn width_scaled(&self, i: usize) -> u128 {
+        self.breakpoint_scaled(i + 1) - self.breakpoint_scaled(i)
+    }
+
+    pub fn is_graduated(&self) -> bool {
+        self.graduation.state == GraduationState::Graduated as u8
+    }
+
+    /// Status of the market at `current_time`, a `PricesSet` market trades once its start is reached.
+    /// Quotes don't check it, swaps fail unless the market is trading or is selling only for sells.
+    pub fn get_status(&self, current_time: i64) -> MarketStatus {
+        match self.status {
+            0 => MarketStatus::Created,
+            1 if current_time < self.trading_start_ts => MarketStatus::PricesSet,
+            1 | 2 => MarketStatus::Trading,
+            3 => MarketStatus::SellOnly,
+            _ => MarketStatus::Closed,
+        }
+    }
+
+    /// Buys are capped per wallet until the launch window ends, quotes don't check the cap.
+    pub fn is_launch_window_open(&self, current_time: i64) -> bool {
+        current_time < self.launch_protection.end_ts
+    }
+
+    /// Only allowlisted users can buy until the presale ends, quotes don't check the allowlist.
+    pub fn is_presale_open(&self, current_time: i64) -> bool {
+        current_time < self.presale.end_ts
+    }
+
+    /// Buy tax rate at `current_time`, decaying linearly from `start_bps` to 0
+    pub fn get_buy_tax_bps(&self, current_time: i64) -> u64 {
+        let buy_tax = &self.buy_tax;
+        let elapsed = (current_time - buy_tax.start_ts).max(0);
+
+        if elapsed >= buy_tax.duration {
+            return 0;
+        }
+
+        u64::from(buy_tax.start_bps) * (buy_tax.duration - elapsed).unsigned_abs()
+            / buy_tax.duration.unsigned_abs()
+    }
+
+    /// Distributes the swap fee and the buy tax, the tax going to stakers or being shared like the fee
+    pub fn distribute_fees(
+        &mut self,
+        swap_fee: u64,
+        buy_tax: u64,
+        referral_fee_share: Option<u16>,
+    ) -> Result<(u64, u64, u64, u64)> {
+        if self.buy_tax.recipient == BuyTaxRecipient::Stakers as u8 {
+            let (creator_fee, staking_fee, protocol_fee, referral_fee) =
+                self.fees.distribute_fee(swap_fee, referral_fee_share)?;
+
+            self.fees.pending_staking_fees += buy_tax;
+
+            Ok((
+                creator_fee,
+                staking_fee + buy_tax,
+                protocol_fee,
+                referral_fee,
+            ))
+        } else {
+            self.fees
+                .distribute_fee(swap_fee + buy_tax, referral_fee_share)
+        }
+    }
+
+    /// Quote amount backing the circulating supply, i.e. its value on the bid curve.
+    /// The rest of the market quote balance is made of fees.
+    pub fn quote_raised(&self) -> Result<u64> {
+        let (_, quote_amount) = self.get_curve_quote_amount_with_parameters(
+            0,
+            self.circulating_supply(),
+            SwapAmountType::ExactInput,
+            Rounding::Down,
+        )?;
+
+        Ok(quote_amount)
+    }
+
+    pub fn is_graduation_threshold_reached(&self) -> Result<bool> {
+        let graduation = &self.graduation;
+
+        if graduation.supply_threshold > 0
+            && self.circulating_supply() >= graduation.supply_threshold
+        {
+            return Ok(true);
+        }
+
+        Ok(graduation.quote_threshold > 0 && self.quote_raised()? >= graduation.quote_threshold)
+    }
+
+    /// Base amount buys on the bonding curve can take, buys stopping at the supply threshold of a market
+    /// set to graduate and always leaving a base reserve to seed the pool with.
+    pub fn get_max_buy_base_amount(&self) -> u64 {
+        let graduation = &self.graduation;
+
+        let min_base_reserve = if graduation.supply_threshold > 0 {
+            self.total_supply - graduation.supply_threshold
+        } else if graduation.quote_threshold > 0 {
+            1
+        } else {
+            0
+        };
+
+        self.base_reserve.saturating_sub(min_base_reserve)
+    }
+
+    /// Returns (base_amount, quote_amount, swap_fee) of a swap against the constant product pool.
+    /// The fee is taken on the quote side, it is paid on top of the quote amount in for buys
+    /// and deducted from the quote amount out for sells.
+    pub fn get_pool_amounts(
+        &self,
+        swap_type: SwapType,
+        swap_amount_type: SwapAmountType,
+        amount: u64,
+    ) -> Result<(u64, u64, u64)> {
+        let base_reserve = self.base_reserve;
+        let quote_reserve = self.graduation.quote_reserve;
+
+        match (swap_type, swap_amount_type) {
+            (SwapType::Buy, SwapAmountType::ExactInput) => {
+                let swap_fee = get_fee_amount(amount, GRADUATED_POOL_FEE_BPS)?;
+                let base_amount = get_amount_out(amount - swap_fee, quote_reserve, base_reserve)?;
+
+                Ok((base_amount, amount, swap_fee))
+            }
+            (SwapType::Buy, SwapAmountType::ExactOutput) => {
+                let base_amount = min(amount, base_reserve - 1);
+                let quote_amount = get_amount_in(base_amount, quote_reserve, base_reserve)?;
+                let quote_amount_with_fee =
+                    get_amount_with_fee(quote_amount, GRADUATED_POOL_FEE_BPS)?;
+
+                Ok((
+                    base_amount,
+                    quote_amount_with_fee,
+                    quote_amount_with_fee - quote_amount,
+                ))
+            }
+            (SwapType::Sell, SwapAmountType::ExactInput) => {
+                let quote_amount = get_amount_out(amount, base_reserve, quote_reserve)?;
+                let swap_fee = get_fee_amount(quote_amount, GRADUATED_POOL_FEE_BPS)?;
+
+                Ok((amount, quote_amount - swap_fee, swap_fee))
+            }
+            (SwapType::Sell, SwapAmountType::ExactOutput) => {
+                let quote_amount_with_fee = min(
+                    get_amount_with_fee(amount, GRADUATED_POOL_FEE_BPS)?,
+                    quote_reserve.saturating_sub(1),
+                );
+                let swap_fee = get_fee_amount(quote_amount_with_fee, GRADUATED_POOL_FEE_BPS)?;
+                let base_amount =
+                    get_amount_in(quote_amount_with_fee, base_reserve, quote_reserve)?;
+
+                Ok((base_amount, quote_amount_with_fee - swap_fee, swap_fee))
+            }
+        }
+    }
+
+    /// Quote per whole base token of the pool reserves, normalized and scaled by `SCALE`
+    fn get_pool_price(&self) -> Result<u64> {
+        let quote_precision = u128::pow(10, u32::from(self.quote_token_decimals));
+
+        let price = u128::from(self.graduation.quote_reserve) * SCALE * u128::from(BASE_PRECISION)
+            / (u128::from(self.base_reserve) * quote_precision);
+
+        Ok(u64::try_from(price)?)
+    }
+
+    pub fn circulating_supply(&self) -> u64 {
+        self.total_supply - self.base_reserve
+    }
+
+    /// Price of the curve at the circulating supply, from the ask prices for buys and the bid prices for sells.
+    /// Only defined for markets with prices set. Once graduated, the price of the pool, excluding its fee.
+    pub fn get_spot_price(&self, swap_type: SwapType) -> Result<u64> {
+        if self.is_graduated() {
+            return self.get_pool_price();
+        }
+
+        if let CurveKind::VirtualConstantProduct {
+            virtual_base,
+            virtual_quote,
+            spread_bps,
+        } = self.curve_kind()
+        {
+            let ask_price = get_virtual_price(
+                virtual_base,
+                virtual_quote,
+                self.circulating_supply(),
+                self.quote_token_decimals,
+            )?;
+
+            return match swap_type {
+                SwapType::Buy => Ok(ask_price),
+                SwapType::Sell => Ok(u64::try_from(
+                    u128::from(ask_price) * u128::from(MAX_BPS - u64::from(spread_bps))
+                        / u128::from(MAX_BPS),
+                )?),
+            };
+        }
+
+        let price_curve = match swap_type {
+            SwapType::Buy => &self.ask_prices,
+            SwapType::Sell => &self.bid_prices,
+        };
+
+        let supply = self.circulating_supply();
+
+        let i = self
+            .supply_breakpoints()
+            .partition_point(|breakpoint| *breakpoint <= supply)
+            - 1;
+
+        if i + 1 == usize::from(self.prices_length) {
+            return Ok(price_curve[i]);
+        }
+
+        let price_0 = u128::from(price_curve[i]);
+        let price_1 = u128::from(price_curve[i + 1]);
+        let width_scaled = self.width_scaled(i);
+        let interval_supply_already_used =
+            u128::from(supply) * SCALE / u128::from(BASE_PRECISION) - self.breakpoint_scaled(i);
+
+        let price = if self.is_exponential() {
+            get_exp_price(price_0, price_1, width_scaled, interval_supply_already_used)?
+        } else {
+            price_0
+                + mul_div(
+                    price_1 - price_0,
+                    interval_supply_already_used,
+                    width_scaled,
+                    Rounding::Down,
+                )
+                .ok_or(TokenMillError::MathError)?
+        };
+
+        Ok(u64::try_from(price)?)
+    }
+
+    pub fn get_quote_amount(
+        &self,
+        base_amount: u64,
+        swap_amount_type: SwapAmountType,
+    ) -> Result<(u64, u64)> {
+        let circulating_supply = self.circulating_supply();
+
+        let (base_amount, supply, rounding) = match swap_amount_type {
+            SwapAmountType::ExactInput => {
+                let base_amount = min(base_amount, self.get_max_swap_base_amount(SwapType::Sell));
+
+                (
+                    base_amount,
+                    circulating_supply - base_amount,
+                    Rounding::Down,
+                )
+            }
+            SwapAmountType::ExactOutput => {
+                let base_amount = min(base_amount, self.get_max_swap_base_amount(SwapType::Buy));
+
+                (base_amount, circulating_supply, Rounding::Up)
+            }
+        };
+
+        self.get_quote_amount_with_parameters(supply, base_amount, swap_amount_type, rounding)
+    }
+
+    pub fn get_quote_amount_with_parameters(
+        &self,
+        supply: u64,
+        base_amount: u64,
+        swap_amount_type: SwapAmountType,
+        rounding: Rounding,
+    ) -> Result<(u64, u64)> {
+        let price_curve = match swap_amount_type {
+            SwapAmountType::ExactInput => &self.bid_prices,
+            SwapAmountType::ExactOutput => &self.ask_prices,
+        };
+
+        let normalized_supply = u128::from(supply) * SCALE / u128::from(BASE_PRECISION);
+
+        let mut normalized_base_amount_left =
+            u128::from(base_amount) * SCALE / u128::from(BASE_PRECISION);
+
+        let mut normalized_quote_amount = 0;
+
+        // Interval containing the supply, the last breakpoint if the supply is the total supply
+        let mut i = self
+            .supply_breakpoints()
+            .partition_point(|breakpoint| *breakpoint <= supply)
+            - 1;
+        let mut interval_supply_already_used = normalized_supply - self.breakpoint_scaled(i);
+
+        let mut price_0 = price_curve[i];
+        i += 1;
+
+        while normalized_base_amount_left > 0 && i < usize::from(self.prices_length) {
+            let price_1 = price_curve[i];
+            let width_scaled = self.width_scaled(i - 1);
+
+            let delta_base = min(
+                normalized_base_amount_left,
+                width_scaled - interval_supply_already_used,
+            );
+
+            let delta_quote = if self.is_exponential() {
+                get_exp_quote_amount(
+                    price_0.into(),
+                    price_1.into(),
+                    width_scaled,
+                    interval_supply_already_used,
+                    delta_base,
+                    rounding,
+                )?
+            } else {
+                mul_div(
+                    delta_base,
+                    u128::from(price_1 - price_0) * (delta_base + 2 * interval_supply_already_used)
+                        + 2 * u128::from(price_0) * width_scaled,
+                    2 * SCALE * width_scaled,
+                    rounding,
+                )
+                .ok_or(TokenMillError::MathError)?
+            };
+
+            normalized_base_amount_left -= delta_base;
+            normalized_quote_amount += delta_quote;
+
+            interval_supply_already_used = 0;
+            price_0 = price_1;
+
+            i += 1;
+        }
+
+        let base_amount_swapped = base_amount
+            - div(
+                normalized_base_amount_left * u128::from(BASE_PRECISION),
+                SCALE,
+                rounding,
+            )?;
+
+        let quote_amount_swapped = div(
+            normalized_quote_amount * u128::pow(10, u32::from(self.quote_token_decimals)),
+            SCALE,
+            rounding,
+        )?;
+
+        Ok((base_amount_swapped, quote_amount_swapped))
+    }
+
+    pub fn get_base_amount_in(&self, quote_amount: u64) -> Result<(u64, u64)> {
+        let price_curve = &self.bid_prices;
+        let circulating_supply = self.circulating_supply();
+
+        let normalized_supply = u128::from(circulating_supply) * SCALE / u128::from(BASE_PRECISION);
+
+        let quote_precision = u128::pow(10, u32::from(self.quote_token_decimals));
+        let mut normalized_quote_amount_left = u128::from(quote_amount) * SCALE / quote_precision;
+        let mut normalized_base_amount = 0;
+
+        // First breakpoint at or above the supply, the supply is sold starting from the interval below it
+        let mut i = self
+            .supply_breakpoints()
+            .partition_point(|breakpoint| *breakpoint < circulating_supply);
+
+        let mut price_1 = price_curve[i];
+
+        let get_delta_base_in = if self.is_exponential() {
+            get_exp_delta_base_in
+        } else {
+            get_delta_base_in
+        };
+
+        let last_interval = i.saturating_sub(self.max_swap_intervals());
+
+        while normalized_quote_amount_left > 0 && i > last_interval {
+            let price_0 = price_curve[i - 1];
+
+            // Only the first interval can be partially filled
+            let interval_supply_available =
+                min(normalized_supply, self.breakpoint_scaled(i)) - self.breakpoint_scaled(i - 1);
+
+            let (delta_base, delta_quote) = get_delta_base_in(
+                price_0.into(),
+                price_1.into(),
+                self.width_scaled(i - 1),
+                interval_supply_available,
+                normalized_quote_amount_left,
+            )?;
+
+            normalized_base_amount += delta_base;
+            normalized_quote_amount_left -= delta_quote;
+
+            price_1 = price_0;
+
+            i -= 1;
+        }
+
+        let base_amount_swapped = div(
+            normalized_base_amount * u128::from(BASE_PRECISION),
+            SCALE,
+            Rounding::Up,
+        )?;
+
+        let quote_amount_swapped = quote_amount
+            - div(
+                normalized_quote_amount_left * quote_precision,
+                SCALE,
+                Rounding::Up,
+            )?;
+
+        Ok((base_amount_swapped, quote_amount_swapped))
+    }
+
+    pub fn get_base_amount_out(&self, quote_amount: u64) -> Result<(u64, u64)> {
+        let price_curve = &self.ask_prices;
+        let circulating_supply = self.circulating_supply();
+
+        let normalized_supply = u128::from(circulating_supply) * SCALE / u128::from(BASE_PRECISION);
+
+        let quote_precision = u128::pow(10, u32::from(self.quote_token_decimals));
+        let mut normalized_quote_amount_left = u128::from(quote_amount) * SCALE / quote_precision;
+        let mut normalized_base_amount = 0;
+
+        // Interval containing the supply, the last breakpoint if the supply is the total supply
+        let mut i = self
+            .supply_breakpoints()
+            .partition_point(|breakpoint| *breakpoint <= circulating_supply)
+            - 1;
+        let mut interval_supply_already_used = normalized_supply - self.breakpoint_scaled(i);
+
+        let mut price_0 = price_curve[i];
+
+        let get_delta_base_out = if self.is_exponential() {
+            get_exp_delta_base_out
+        } else {
+            get_delta_base_out
+        };
+
+        let last_interval = min(
+            i + self.max_swap_intervals(),
+            usize::from(self.prices_length) - 1,
+        );
+
+        while normalized_quote_amount_left > 0 && i < last_interval {
+            let price_1 = price_curve[i + 1];
+
+            let (delta_base, delta_quote) = get_delta_base_out(
+                price_0.into(),
+                price_1.into(),
+                self.width_scaled(i),
+                interval_supply_already_used,
+                normalized_quote_amount_left,
+            )?;
+
+            normalized_base_amount += delta_base;
+            normalized_quote_amount_left -= delta_quote;
+
+            interval_supply_already_used = 0;
+            price_0 = price_1;
+
+            i += 1;
+        }
+
+        let base_amount_swapped = div(
+            normalized_base_amount * u128::from(BASE_PRECISION),
+            SCALE,
+            Rounding::Down,
+        )?;
+
+        let quote_amount_swapped = quote_amount
+            - div(
+                normalized_quote_amount_left * quote_precision,
+                SCALE,
+                Rounding::Down,
+            )?;
+
+        Ok((base_amount_swapped, quote_amount_swapped))
+    }
+
+    /// Dispatches to the quote function of the market's `CurveKind`
+    pub fn get_curve_quote_amount_with_parameters(
+        &self,
+        supply: u64,
+        base_amount: u64,
+        swap_amount_type: SwapAmountType,
+        rounding: Rounding,
+    ) -> Result<(u64, u64)> {
+        match self.curve_kind() {
+            CurveKind::PiecewiseLinear => self.get_quote_amount_with_parameters(
+                supply,
+                base_amount,
+                swap_amount_type,
+                rounding,
+            ),
+            CurveKind::VirtualConstantProduct { .. } => self
+                .get_virtual_quote_amount_with_parameters(
+                    supply,
+                    base_amount,
+                    swap_amount_type,
+                    rounding,
+                ),
+        }
+    }
+
+    pub fn get_virtual_quote_amount(
+        &self,
+        base_amount: u64,
+        swap_amount_type: SwapAmountType,
+    ) -> Result<(u64, u64)> {
+        let circulating_supply = self.circulating_supply();
+
+        let (supply, rounding) = match swap_amount_type {
+            SwapAmountType::ExactInput => (circulating_supply - base_amount, Rounding::Down),
+            SwapAmountType::ExactOutput => (circulating_supply, Rounding::Up),
+        };
+
+        self.get_virtual_quote_amount_with_parameters(
+            supply,
+            base_amount,
+            swap_amount_type,
+            rounding,
+        )
+    }
+
+    pub fn get_virtual_quote_amount_with_parameters(
+        &self,
+        supply: u64,
+        base_amount: u64,
+        swap_amount_type: SwapAmountType,
+        rounding: Rounding,
+    ) -> Result<(u64, u64)> {
+        let virtual_reserves = &self.virtual_reserves;
+
+        let base_amount = min(base_amount, self.total_supply - supply);
+
+        let ask_quote_amount = get_virtual_quote_amount(
+            virtual_reserves.virtual_base,
+            virtual_reserves.virtual_quote,
+            supply,
+            supply + base_amount,
+            rounding,
+        )?;
+
+        let quote_amount = match swap_amount_type {
+            SwapAmountType::ExactInput => {
+                self.get_virtual_bid_amount(ask_quote_amount, rounding)?
+            }
+            SwapAmountType::ExactOutput => ask_quote_amount,
+        };
+
+        Ok((base_amount, quote_amount))
+    }
+
+    pub fn get_virtual_base_amount_in(&self, quote_amount: u64) -> Result<(u64, u64)> {
+        let virtual_reserves = &self.virtual_reserves;
+        let circulating_supply = self.circulating_supply();
+
+        let (_, max_quote_amount) = self.get_virtual_quote_amount_with_parameters(
+            0,
+            circulating_supply,
+            SwapAmountType::ExactInput,
+            Rounding::Down,
+        )?;
+
+        if quote_amount >= max_quote_amount {
+            return Ok((circulating_supply, max_quote_amount));
+        }
+
+        // Ask quote amount whose bid quote amount is `quote_amount`
+        let ask_quote_amount = mul_div(
+            quote_amount.into(),
+            MAX_BPS.into(),
+            (MAX_BPS - u64::from(virtual_reserves.spread_bps)).into(),
+            Rounding::Up,
+        )
+        .ok_or(TokenMillError::MathError)?;
+
+        let base_amount = get_virtual_base_amount_in(
+            virtual_reserves.virtual_base,
+            virtual_reserves.virtual_quote,
+            circulating_supply,
+            u64::try_from(ask_quote_amount)?,
+        )?;
+
+        Ok((min(base_amount, circulating_supply), quote_amount))
+    }
+
+    pub fn get_virtual_base_amount_out(&self, quote_amount: u64) -> Result<(u64, u64)> {
+        let virtual_reserves = &self.virtual_reserves;
+        let circulating_supply = self.circulating_supply();
+
+        let base_amount = get_virtual_base_amount_out(
+            virtual_reserves.virtual_base,
+            virtual_reserves.virtual_quote,
+            circulating_supply,
+            quote_amount,
+        )?;
+
+        if base_amount > self.base_reserve {
+            return self.get_virtual_quote_amount_with_parameters(
+                circulating_supply,
+                self.base_reserve,
+                SwapAmountType::ExactOutput,
+                Rounding::Up,
+            );
+        }
+
+        Ok((base_amount, quote_amount))
+    }
+
+    fn get_virtual_bid_amount(&self, ask_quote_amount: u64, rounding: Rounding) -> Result<u64> {
+        let bid_quote_amount = mul_div(
+            ask_quote_amount.into(),
+            (MAX_BPS - u64::from(self.virtual_reserves.spread_bps)).into(),
+            MAX_BPS.into(),
+            rounding,
+        )
+        .ok_or(TokenMillError::MathError)?;
+
+        Ok(u64::try_from(bid_quote_amount)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact_input_buy_on_wide_interval() {
+        let mut market = Market::zeroed();
+
+        // The quote of the whole interval largely exceeds `u64::MAX`
+        market.total_supply = 1_000_000_000 * BASE_PRECISION;
+        market.base_reserve = market.total_supply;
+        market.quote_token_decimals = 6;
+        market.prices_length = 2;
+        market.bid_prices[..2].copy_from_slice(&[1_000_000_000_000_000, 2_000_000_000_000_000]);
+        market.ask_prices[..2].copy_from_slice(&[1_000_000_000_000_000, 2_000_000_000_000_000]);
+        market.supply_breakpoints[1] = market.total_supply;
+
+        let quote_amount = 1_000_000;
+
+        let (base_amount, quote_amount_swapped) = market.get_base_amount_out(quote_amount).unwrap();
+
+        assert!(base_amount > 0);
+        assert_eq!(quote_amount_swapped, quote_amount);
+
+        let (_, exact_output_quote_amount) = market
+            .get_quote_amount(base_amount, SwapAmountType::ExactOutput)
+            .unwrap();
+
+        assert!(exact_output_quote_amount <= quote_amount);
+    }
+}
@@ -0,0 +1,421 @@
+use ruint::aliases::U256;
+
+use super::Rounding;
+use crate::{
+    constant::SCALE,
+    errors::{Result, TokenMillError},
+};
+
+// Prices of an exponential interval grow geometrically from `price_0` to `price_1`:
+// price(s) = price_0 * e^(s * L / width), with L = ln(price_1 / price_0)
+//
+// Transcendental functions are computed with 1e18 fixed point numbers and are accurate to ~1e-16.
+// Every result is pushed by a relative 1e-12 margin in the direction given by the rounding,
+// so that users never pay less, or receive more, than the exact integral of the curve.
+
+const WAD: u128 = 1_000_000_000_000_000_000; // 1e18
+const LN_2_WAD: u128 = 693_147_180_559_945_309; // ln(2) * 1e18, rounded down
+const ERROR_MARGIN_DIVISOR: u128 = 1_000_000_000_000; // 1e12
+const SERIES_THRESHOLD: u128 = WAD / 10;
+
+/// Quote amount of `delta_base`, starting `interval_supply_already_used` into the interval.
+pub fn get_exp_quote_amount(
+    price_0: u128,
+    price_1: u128,
+    width_scaled: u128,
+    interval_supply_already_used: u128,
+    delta_base: u128,
+    rounding: Rounding,
+) -> Result<u128> {
+    let ln_ratio = get_ln_ratio(price_0, price_1);
+    let price = get_price_wad(
+        price_0,
+        ln_ratio,
+        width_scaled,
+        interval_supply_already_used,
+    );
+
+    // quote = price * delta_base * (e^x - 1) / x, with x = delta_base * L / width
+    let x = U256::from(delta_base) * ln_ratio / U256::from(width_scaled);
+    let quote = price * exprel_wad(x) * U256::from(delta_base);
+
+    to_rounded(quote, U256::from(WAD * WAD) * U256::from(SCALE), rounding)
+}
+
+/// Price `supply` into the interval, rounded down.
+pub fn get_exp_price(
+    price_0: u128,
+    price_1: u128,
+    width_scaled: u128,
+    supply: u128,
+) -> Result<u128> {
+    let ln_ratio = get_ln_ratio(price_0, price_1);
+    let price = get_price_wad(price_0, ln_ratio, width_scaled, supply) / U256::from(WAD);
+
+    u128::try_from(price).map_err(|_| TokenMillError::MathError)
+}
+
+/// Exponential counterpart of `get_delta_base_in`.
+pub fn get_exp_delta_base_in(
+    price_0: u128,
+    price_1: u128,
+    width_scaled: u128,
+    interval_supply_available: u128,
+    remaining_quote: u128,
+) -> Result<(u128, u128)> {
+    let current_quote = get_exp_quote_amount(
+        price_0,
+        price_1,
+        width_scaled,
+        0,
+        interval_supply_available,
+        Rounding::Down,
+    )?;
+
+    if remaining_quote >= current_quote {
+        return Ok((interval_supply_available, current_quote));
+    }
+
+    let ln_ratio = get_ln_ratio(price_0, price_1);
+    let price = get_price_wad(price_0, ln_ratio, width_scaled, interval_supply_available);
+
+    // Solves price * (width / L) * (1 - e^(-delta_base * L / width)) = quote, i.e.
+    // delta_base = quote / price * -ln(1 - y) / y, with y = quote * L / (price * width)
+    let y = U256::from(remaining_quote) * U256::from(SCALE) * ln_ratio * U256::from(WAD)
+        / (price * U256::from(width_scaled));
+
+    if y >= U256::from(WAD) {
+        return Ok((interval_supply_available, remaining_quote));
+    }
+
+    let delta_base = U256::from(remaining_quote) * U256::from(SCALE) * neg_lnrel_wad(y) / price;
+    let delta_base = delta_base + delta_base / U256::from(ERROR_MARGIN_DIVISOR) + U256::from(1);
+
+    let delta_base = u128::try_from(delta_base).map_err(|_| TokenMillError::MathError)?;
+
+    Ok((delta_base.min(interval_supply_available), remaining_quote))
+}
+
+/// Exponential counterpart of `get_delta_base_out`.
+pub fn get_exp_delta_base_out(
+    price_0: u128,
+    price_1: u128,
+    width_scaled: u128,
+    interval_supply_already_used: u128,
+    remaining_quote: u128,
+) -> Result<(u128, u128)> {
+    let interval_supply_left = width_scaled - interval_supply_already_used;
+
+    let max_quote = get_exp_quote_amount(
+        price_0,
+        price_1,
+        width_scaled,
+        interval_supply_already_used,
+        interval_supply_left,
+        Rounding::Up,
+    )?;
+
+    if remaining_quote >= max_quote {
+        return Ok((interval_supply_left, max_quote));
+    }
+
+    let ln_ratio = get_ln_ratio(price_0, price_1);
+    let price = get_price_wad(
+        price_0,
+        ln_ratio,
+        width_scaled,
+        interval_supply_already_used,
+    );
+
+    // Solves price * (width / L) * (e^(delta_base * L / width) - 1) = quote, i.e.
+    // delta_base = quote / price * ln(1 + y) / y, with y = quote * L / (price * width)
+    let y = U256::from(remaining_quote) * U256::from(SCALE) * ln_ratio * U256::from(WAD)
+        / (price * U256::from(width_scaled));
+
+    let delta_base = U256::from(remaining_quote) * U256::from(SCALE) * lnrel_wad(y) / price;
+    let delta_base =
+        delta_base.saturating_sub(delta_base / U256::from(ERROR_MARGIN_DIVISOR) + U256::from(1));
+
+    let delta_base = u128::try_from(delta_base).map_err(|_| TokenMillError::MathError)?;
+
+    Ok((delta_base.min(interval_supply_left), remaining_quote))
+}
+
+/// ln(price_1 / price_0), prices being strictly increasing and positive
+fn get_ln_ratio(price_0: u128, price_1: u128) -> U256 {
+    ln_wad(U256::from(price_1) * U256::from(WAD) / U256::from(price_0))
+}
+
+/// Price at `supply` into the interval, multiplied by 1e18
+fn get_price_wad(price_0: u128, ln_ratio: U256, width_scaled: u128, supply: u128) -> U256 {
+    U256::from(price_0) * exp_wad(U256::from(supply) * ln_ratio / U256::from(width_scaled))
+}
+
+fn to_rounded(value: U256, denominator: U256, rounding: Rounding) -> Result<u128> {
+    let margin = value / U256::from(ERROR_MARGIN_DIVISOR);
+
+    let result = match rounding {
+        Rounding::Up => (value + margin).div_ceil(denominator),
+        Rounding::Down => (value - margin) / denominator,
+    };
+
+    u128::try_from(result).map_err(|_| TokenMillError::MathError)
+}
+
+/// ln(x) for x >= 1
+fn ln_wad(x: U256) -> U256 {
+    let wad = U256::from(WAD);
+
+    // x = 2^k * m, with m in [1, 2)
+    let k = (x / wad).bit_len() - 1;
+    let m = x >> k;
+
+    // ln(m) = 2 * atanh(z) = 2 * (z + z^3 / 3 + z^5 / 5 + ...), with z = (m - 1) / (m + 1) < 1/3
+    let z = (m - wad) * wad / (m + wad);
+    let z_squared = z * z / wad;
+
+    let mut term = z;
+    let mut sum = z;
+    let mut n = 1u64;
+
+    while !term.is_zero() {
+        term = term * z_squared / wad;
+        n += 2;
+        sum += term / U256::from(n);
+    }
+
+    U256::from(k) * U256::from(LN_2_WAD) + sum * U256::from(2)
+}
+
+/// e^x for x >= 0
+fn exp_wad(x: U256) -> U256 {
+    let wad = U256::from(WAD);
+
+    // x = k * ln(2) + r, with r in [0, ln(2))
+    let k = x / U256::from(LN_2_WAD);
+    let r = x - k * U256::from(LN_2_WAD);
+
+    let mut term = wad;
+    let mut sum = wad;
+    let mut n = 1u64;
+
+    while !term.is_zero() {
+        term = term * r / (wad * U256::from(n));
+        sum += term;
+        n += 1;
+    }
+
+    sum << k.to::<usize>()
+}
+
+/// (e^x - 1) / x for x >= 0
+fn exprel_wad(x: U256) -> U256 {
+    let wad = U256::from(WAD);
+
+    if x >= U256::from(SERIES_THRESHOLD) {
+        return (exp_wad(x) - wad) * wad / x;
+    }
+
+    // 1 + x / 2! + x^2 / 3! + ...
+    let mut term = wad;
+    let mut sum = wad;
+    let mut n = 2u64;
+
+    while !term.is_zero() {
+        term = term * x / (wad * U256::from(n));
+        sum += term;
+        n += 1;
+    }
+
+    sum
+}
+
+/// ln(1 + y) / y for y >= 0
+fn lnrel_wad(y: U256) -> U256 {
+    let wad = U256::from(WAD);
+
+    if y >= U256::from(SERIES_THRESHOLD) {
+        return ln_wad(wad + y) * wad / y;
+    }
+
+    // 1 - y / 2 + y^2 / 3 - ...
+    let mut power = wad;
+    let mut positive_sum = wad;
+    let mut negative_sum = U256::ZERO;
+    let mut n = 1u64;
+    let mut is_negative = false;
+
+    while !power.is_zero() {
+        power = power * y / wad;
+        n += 1;
+        is_negative = !is_negative;
+
+        if is_negative {
+            negative_sum += power / U256::from(n);
+        } else {
+            positive_sum += power / U256::from(n);
+        }
+    }
+
+    positive_sum - negative_sum
+}
+
+/// -ln(1 - y) / y for 0 <= y < 1
+fn neg_lnrel_wad(y: U256) -> U256 {
+    let wad = U256::from(WAD);
+
+    if y >= U256::from(SERIES_THRESHOLD) {
+        return ln_wad(wad * wad / (wad - y)) * wad / y;
+    }
+
+    // 1 + y / 2 + y^2 / 3 + ...
+    let mut power = wad;
+    let mut sum = wad;
+    let mut n = 1u64;
+
+    while !power.is_zero() {
+        power = power * y / wad;
+        n += 1;
+        sum += power / U256::from(n);
+    }
+
+    sum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_f64(x: U256) -> f64 {
+        u128::try_from(x).unwrap() as f64 / WAD as f64
+    }
+
+    fn from_f64(x: f64) -> U256 {
+        U256::from((x * WAD as f64) as u128)
+    }
+
+    #[test]
+    fn transcendental_functions() {
+        for x in [1.0f64, 1.000001, 1.5, 2.0, 3.7, 1e3, 1e9, 1e18] {
+            let expected = x.ln();
+            let actual = to_f64(ln_wad(from_f64(x)));
+
+            assert!((actual - expected).abs() <= 1e-12 * expected.max(1.0));
+        }
+
+        for x in [0.0f64, 1e-9, 0.05, 0.5, 1.0, 10.0, 41.0] {
+            let expected = x.exp();
+            let actual = to_f64(exp_wad(from_f64(x)));
+
+            assert!((actual - expected).abs() <= 1e-12 * expected);
+
+            let expected = if x == 0.0 { 1.0 } else { x.exp_m1() / x };
+            let actual = to_f64(exprel_wad(from_f64(x)));
+
+            assert!((actual - expected).abs() <= 1e-12 * expected);
+
+            let expected = if x == 0.0 { 1.0 } else { x.ln_1p() / x };
+            let actual = to_f64(lnrel_wad(from_f64(x)));
+
+            assert!((actual - expected).abs() <= 1e-12 * expected);
+        }
+
+        for y in [0.0f64, 1e-9, 0.05, 0.5, 0.9, 0.999999] {
+            let expected = if y == 0.0 { 1.0 } else { -(-y).ln_1p() / y };
+            let actual = to_f64(neg_lnrel_wad(from_f64(y)));
+
+            assert!((actual - expected).abs() <= 1e-9 * expected);
+        }
+    }
+
+    #[test]
+    fn quote_amount_rounding() {
+        let width_scaled = 100_000_000_000_000; // 1e4 tokens
+
+        for (price_0, price_1) in [(1, 2), (1_000, 1_001), (10_000_000, 40_000_000_000)] {
+            for (already_used, delta_base) in [
+                (0, width_scaled),
+                (0, 1),
+                (width_scaled / 3, width_scaled / 3),
+                (width_scaled - 1, 1),
+            ] {
+                let up = get_exp_quote_amount(
+                    price_0,
+                    price_1,
+                    width_scaled,
+                    already_used,
+                    delta_base,
+                    Rounding::Up,
+                )
+                .unwrap();
+                let down = get_exp_quote_amount(
+                    price_0,
+                    price_1,
+                    width_scaled,
+                    already_used,
+                    delta_base,
+                    Rounding::Down,
+                )
+                .unwrap();
+
+                let ln_ratio = (price_1 as f64 / price_0 as f64).ln();
+                let x = delta_base as f64 * ln_ratio / width_scaled as f64;
+                let expected = price_0 as f64
+                    * (already_used as f64 * ln_ratio / width_scaled as f64).exp()
+                    * delta_base as f64
+                    * (x.exp_m1() / x)
+                    / SCALE as f64;
+
+                assert!(up as f64 >= expected * (1.0 - 1e-14));
+                assert!(down as f64 <= expected * (1.0 + 1e-14));
+                assert!(up >= down);
+            }
+        }
+    }
+
+    #[test]
+    fn inverse_solves() {
+        let width_scaled = 100_000_000_000_000; // 1e4 tokens
+        let (price_0, price_1) = (10_000_000, 40_000_000_000);
+
+        for remaining_quote in [1_000, 1_000_000_000, 100_000_000_000_000_000] {
+            for supply in [0, width_scaled / 2] {
+                // Base bought with the quote amount costs at most that quote amount
+                let (delta_base, quote) =
+                    get_exp_delta_base_out(price_0, price_1, width_scaled, supply, remaining_quote)
+                        .unwrap();
+
+                let cost = get_exp_quote_amount(
+                    price_0,
+                    price_1,
+                    width_scaled,
+                    supply,
+                    delta_base,
+                    Rounding::Up,
+                )
+                .unwrap();
+
+                assert!(cost <= quote);
+            }
+
+            for supply in [width_scaled / 2, width_scaled] {
+                // Base sold for the quote amount is worth at least that quote amount
+                let (delta_base, quote) =
+                    get_exp_delta_base_in(price_0, price_1, width_scaled, supply, remaining_quote)
+                        .unwrap();
+
+                let value = get_exp_quote_amount(
+                    price_0,
+                    price_1,
+                    width_scaled,
+                    supply - delta_base,
+                    delta_base,
+                    Rounding::Down,
+                )
+                .unwrap();
+
+                assert!(value >= quote);
+            }
+        }
+    }
+}
@@ -0,0 +1,254 @@
+use ruint::aliases::U256;
+
+use crate::{
+    constant::{BASE_PRECISION, MAX_BPS, SCALE},
+    errors::{Result, TokenMillError},
+};
+
+mod exponential;
+
+pub use exponential::*;
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum Rounding {
+    Up,
+    Down,
+}
+
+pub fn get_delta_base_in(
+    price_0: u128,
+    price_1: u128,
+    width_scaled: u128,
+    interval_supply_available: u128,
+    remaining_quote: u128,
+) -> Result<(u128, u128)> {
+    let price_diff = price_1 - price_0;
+
+    let current_quote = mul_div(
+        interval_supply_available,
+        price_diff * interval_supply_available + 2 * price_0 * width_scaled,
+        2 * SCALE * width_scaled,
+        Rounding::Down,
+    )
+    .unwrap();
+
+    if remaining_quote >= current_quote {
+        Ok((interval_supply_available, current_quote))
+    } else {
+        let sqrt_discriminant = get_sqrt_discriminant(
+            price_diff,
+            price_0,
+            width_scaled,
+            current_quote - remaining_quote,
+        )?;
+
+        let rl = price_0 * width_scaled + price_diff * interval_supply_available;
+        let delta_base = div(rl - sqrt_discriminant, price_diff, Rounding::Up)?;
+
+        Ok((delta_base.into(), remaining_quote))
+    }
+}
+
+pub fn get_delta_base_out(
+    price_0: u128,
+    price_1: u128,
+    width_scaled: u128,
+    interval_supply_already_used: u128,
+    remaining_quote: u128,
+) -> Result<(u128, u128)> {
+    let price_diff = price_1 - price_0;
+
+    let current_quote = mul_div(
+        interval_supply_already_used,
+        price_diff * interval_supply_already_used + 2 * price_0 * width_scaled,
+        2 * SCALE * width_scaled,
+        Rounding::Down,
+    )
+    .unwrap();
+
+    // The quote of a whole interval can exceed `u64::MAX` on wide intervals at high prices
+    let next_quote = mul_div(price_0 + price_1, width_scaled, 2 * SCALE, Rounding::Up)
+        .ok_or(TokenMillError::MathError)?;
+
+    let max_quote = next_quote - current_quote;
+
+    if remaining_quote >= max_quote {
+        Ok((width_scaled - interval_supply_already_used, max_quote))
+    } else {
+        let sqrt_discriminant = get_sqrt_discriminant(
+            price_diff,
+            price_0,
+            width_scaled,
+            current_quote + remaining_quote,
+        )?;
+
+        let rr = price_0 * width_scaled + price_diff * interval_supply_already_used;
+        let delta_base = div(sqrt_discriminant - rr, price_diff, Rounding::Down)?;
+
+        Ok((delta_base.into(), remaining_quote))
+    }
+}
+
+pub fn get_sqrt_discriminant(
+    price_diff: u128,
+    price_0: u128,
+    width_scaled: u128,
+    current_quote: u128,
+) -> Result<u128> {
+    let dl = U256::from(width_scaled * price_diff) * U256::from(current_quote * 2 * SCALE);
+    let dr = U256::from(price_0 * width_scaled) * U256::from(price_0 * width_scaled);
+    let d = dl + dr;
+    let sqrt_discriminant = d.root(2);
+    Ok(sqrt_discriminant.try_into().unwrap())
+}
+
+/// Constant product output amount for an exact input, rounded down.
+pub fn get_amount_out(amount_in: u64, reserve_in: u64, reserve_out: u64) -> Result<u64> {
+    let amount_out = mul_div(
+        amount_in.into(),
+        reserve_out.into(),
+        u128::from(reserve_in) + u128::from(amount_in),
+        Rounding::Down,
+    )
+    .ok_or(TokenMillError::MathError)?;
+
+    Ok(u64::try_from(amount_out)?)
+}
+
+/// Constant product input amount for an exact output, rounded up.
+/// `amount_out` must be strictly lower than `reserve_out`.
+pub fn get_amount_in(amount_out: u64, reserve_in: u64, reserve_out: u64) -> Result<u64> {
+    let amount_in = mul_div(
+        reserve_in.into(),
+        amount_out.into(),
+        u128::from(reserve_out - amount_out),
+        Rounding::Up,
+    )
+    .ok_or(TokenMillError::MathError)?;
+
+    Ok(u64::try_from(amount_in)?)
+}
+
+/// Fee taken from `amount`, rounded up.
+pub fn get_fee_amount(amount: u64, fee_bps: u64) -> Result<u64> {
+    let fee = mul_div(amount.into(), fee_bps.into(), MAX_BPS.into(), Rounding::Up)
+        .ok_or(TokenMillError::MathError)?;
+
+    Ok(u64::try_from(fee)?)
+}
+
+/// Amount which, once the fee is taken, leaves `amount`, rounded up.
+pub fn get_amount_with_fee(amount: u64, fee_bps: u64) -> Result<u64> {
+    let amount_with_fee = mul_div(
+        amount.into(),
+        MAX_BPS.into(),
+        (MAX_BPS - fee_bps).into(),
+        Rounding::Up,
+    )
+    .ok_or(TokenMillError::MathError)?;
+
+    Ok(u64::try_from(amount_with_fee)?)
+}
+
+/// Quote amount of a virtual constant product curve between `supply_0` and `supply_1`,
+/// i.e. k / (virtual_base - supply_1) - k / (virtual_base - supply_0), with k = virtual_base * virtual_quote
+pub fn get_virtual_quote_amount(
+    virtual_base: u64,
+    virtual_quote: u64,
+    supply_0: u64,
+    supply_1: u64,
+    rounding: Rounding,
+) -> Result<u64> {
+    let numerator =
+        U256::from(virtual_base) * U256::from(virtual_quote) * U256::from(supply_1 - supply_0);
+    let denominator = U256::from(virtual_base - supply_0) * U256::from(virtual_base - supply_1);
+
+    let quote_amount = match rounding {
+        Rounding::Up => numerator.div_ceil(denominator),
+        Rounding::Down => numerator / denominator,
+    };
+
+    u64::try_from(quote_amount).map_err(|_| TokenMillError::MathError)
+}
+
+/// Base amount bought from `supply` with `quote_amount` on a virtual constant product curve, rounded down.
+pub fn get_virtual_base_amount_out(
+    virtual_base: u64,
+    virtual_quote: u64,
+    supply: u64,
+    quote_amount: u64,
+) -> Result<u64> {
+    let base_reserve = U256::from(virtual_base - supply);
+
+    let numerator = U256::from(quote_amount) * base_reserve * base_reserve;
+    let denominator = U256::from(virtual_base) * U256::from(virtual_quote)
+        + U256::from(quote_amount) * base_reserve;
+
+    u64::try_from(numerator / denominator).map_err(|_| TokenMillError::MathError)
+}
+
+/// Base amount sold from `supply` for `quote_amount` on a virtual constant product curve, rounded up.
+/// `quote_amount` must be strictly lower than the value of the whole supply.
+pub fn get_virtual_base_amount_in(
+    virtual_base: u64,
+    virtual_quote: u64,
+    supply: u64,
+    quote_amount: u64,
+) -> Result<u64> {
+    let base_reserve = U256::from(virtual_base - supply);
+
+    let numerator = U256::from(quote_amount) * base_reserve * base_reserve;
+    let denominator = U256::from(virtual_base) * U256::from(virtual_quote)
+        - U256::from(quote_amount) * base_reserve;
+
+    u64::try_from(numerator.div_ceil(denominator)).map_err(|_| TokenMillError::MathError)
+}
+
+/// Marginal price of a virtual constant product curve at `supply`, i.e. k / (virtual_base - supply)^2,
+/// in quote per whole base token normalized and scaled by `SCALE`.
+pub fn get_virtual_price(
+    virtual_base: u64,
+    virtual_quote: u64,
+    supply: u64,
+    quote_token_decimals: u8,
+) -> Result<u64> {
+    let base_reserve = U256::from(virtual_base - supply);
+
+    let numerator = U256::from(virtual_base)
+        * U256::from(virtual_quote)
+        * U256::from(SCALE)
+        * U256::from(BASE_PRECISION);
+    let denominator =
+        base_reserve * base_reserve * U256::from(10u64).pow(U256::from(quote_token_decimals));
+
+    u64::try_from(numerator / denominator).map_err(|_| TokenMillError::MathError)
+}
+
+pub fn mul_div(x: u128, y: u128, denominator: u128, rounding: Rounding) -> Option<u128> {
+    if denominator == 0 {
+        return None;
+    }
+
+    let x = U256::from(x);
+    let y = U256::from(y);
+    let denominator = U256::from(denominator);
+
+    let prod = x.checked_mul(y)?;
+
+    match rounding {
+        Rounding::Up => prod.div_ceil(denominator).try_into().ok(),
+        Rounding::Down => {
+            let (quotient, _) = prod.div_rem(denominator);
+            quotient.try_into().ok()
+        }
+    }
+}
+
+pub fn div(a: u128, b: u128, rounding: Rounding) -> Result<u64> {
+    let quotient = match rounding {
+        Rounding::Up => a.div_ceil(b),
+        Rounding::Down => a / b,
+    };
+
+    Ok(u64::try_from(quotient)?)
+}
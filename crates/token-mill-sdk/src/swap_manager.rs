@@ -0,0 +1,449 @@
+use crate::{
+    constant::MAX_BPS,
+    errors::{Result, TokenMillError},
+    market::{CurveKind, Market},
+    math::{get_amount_with_fee, get_fee_amount, Rounding},
+};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SwapType {
+    Buy,  // Buy base token
+    Sell, // Sell base token
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SwapAmountType {
+    ExactInput,
+    ExactOutput,
+}
+
+/// Transfer fee of a Token-2022 quote mint at the current epoch, as in its `TransferFeeConfig` extension
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TransferFee {
+    pub transfer_fee_basis_points: u16,
+    pub maximum_fee: u64,
+}
+
+impl TransferFee {
+    /// Fee withheld from a transfer of `amount`
+    pub fn get_fee_amount(&self, amount: u64) -> Result<u64> {
+        let fee = get_fee_amount(amount, self.transfer_fee_basis_points.into())?;
+
+        Ok(fee.min(self.maximum_fee))
+    }
+
+    /// Amount to transfer for `amount` to be received
+    pub fn get_amount_with_fee(&self, amount: u64) -> Result<u64> {
+        let transfer_fee_basis_points = u64::from(self.transfer_fee_basis_points);
+
+        if transfer_fee_basis_points == 0 || amount == 0 {
+            return Ok(amount);
+        }
+
+        if transfer_fee_basis_points == MAX_BPS {
+            return amount
+                .checked_add(self.maximum_fee)
+                .ok_or(TokenMillError::MathError);
+        }
+
+        let amount_with_fee = get_amount_with_fee(amount, transfer_fee_basis_points)?;
+
+        if amount_with_fee - amount >= self.maximum_fee {
+            amount
+                .checked_add(self.maximum_fee)
+                .ok_or(TokenMillError::MathError)
+        } else {
+            Ok(amount_with_fee)
+        }
+    }
+}
+
+/// Amounts of a swap, the quote amount being the one reaching or leaving the market
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct SwapResult {
+    pub base_amount: u64,
+    /// Includes the buy tax and is net of the fee discount
+    pub quote_amount: u64,
+    pub swap_fee: u64,
+    /// Part of `quote_amount` paid as buy tax
+    pub buy_tax: u64,
+    /// Quote token transfer fee paid by the user on top of `quote_amount` on buys and deducted from it on sells
+    pub transfer_fee: u64,
+    /// Spread fee discounted from `quote_amount` and `swap_fee`
+    pub fee_discount: u64,
+}
+
+/// Referral fee shares of a referred swap
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ReferralFeeShares {
+    /// `fee_share_override` of the referral account if set, the config's `referral_fee_share` otherwise
+    pub referral_fee_share: u16,
+    /// The config's `sub_referral_fee_share` if the referral account has a parent, 0 otherwise
+    pub sub_referral_fee_share: u16,
+}
+
+/// Same amounts as the `quote_swap` instruction returns
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SwapQuote {
+    pub base_amount: u64,
+    pub quote_amount: u64,
+    pub swap_fee: u64,
+    pub creator_fee: u64,
+    pub staking_fee: u64,
+    pub protocol_fee: u64,
+    pub referral_fee: u64,
+    /// Part of `referral_fee` paid to the parent of the referral account
+    pub sub_referral_fee: u64,
+    /// Part of `quote_amount` paid as buy tax, included in the fees it is distributed to
+    pub buy_tax: u64,
+    /// Quote token transfer fee paid by the user, part of `quote_amount` on buys and deducted from it on sells
+    pub transfer_fee: u64,
+    /// Spread fee discounted from `quote_amount` and `swap_fee` of stakers' buys
+    pub fee_discount: u64,
+    /// Buys are reserved to allowlisted users, the swap requiring their presale proof
+    pub presale_proof_required: bool,
+    /// Buys are capped per wallet, the swap requiring the user's launch allocation
+    pub launch_allocation_required: bool,
+}
+
+/// Quotes a swap against `market` at `current_time` without modifying it.
+/// `referral_fee_shares` are the shares of the referral account when quoting a referred swap, `None` otherwise.
+/// `quote_transfer_fee` is the transfer fee of the quote mint, `None` if it has none.
+/// `fee_discount_bps` is the config's fee discount of the user's stake position, 0 without one.
+#[allow(clippy::too_many_arguments)]
+pub fn quote_swap(
+    market: &Market,
+    swap_type: SwapType,
+    swap_amount_type: SwapAmountType,
+    amount: u64,
+    referral_fee_shares: Option<ReferralFeeShares>,
+    current_time: i64,
+    quote_transfer_fee: Option<&TransferFee>,
+    fee_discount_bps: u64,
+) -> Result<SwapQuote> {
+    if amount == 0 {
+        return Err(TokenMillError::InvalidAmount);
+    }
+
+    let mut market = Box::new(*market);
+
+    let SwapResult {
+        base_amount,
+        quote_amount,
+        swap_fee,
+        buy_tax,
+        transfer_fee,
+        fee_discount,
+    } = swap_with_transfer_fee(
+        &mut market,
+        swap_type,
+        swap_amount_type,
+        amount,
+        current_time,
+        quote_transfer_fee,
+        fee_discount_bps,
+    )?;
+
+    let (creator_fee, staking_fee, protocol_fee, referral_fee) = market.distribute_fees(
+        swap_fee,
+        buy_tax,
+        referral_fee_shares.map(|shares| shares.referral_fee_share),
+    )?;
+
+    let sub_referral_fee = match referral_fee_shares {
+        Some(shares) => get_sub_referral_fee(referral_fee, shares.sub_referral_fee_share)?,
+        None => 0,
+    };
+
+    let quote_amount = match swap_type {
+        SwapType::Buy => quote_amount + transfer_fee,
+        SwapType::Sell => quote_amount - transfer_fee,
+    };
+
+    // Presale buys are capped by the allowlist, a capped proof also requiring the launch allocation
+    let is_buy = swap_type == SwapType::Buy;
+    let presale_proof_required = is_buy && market.is_presale_open(current_time);
+    let launch_allocation_required =
+        is_buy && !presale_proof_required && market.is_launch_window_open(current_time);
+
+    Ok(SwapQuote {
+        base_amount,
+        quote_amount,
+        swap_fee,
+        creator_fee,
+        staking_fee,
+        protocol_fee,
+        referral_fee,
+        sub_referral_fee,
+        buy_tax,
+        transfer_fee,
+        fee_discount,
+        presale_proof_required,
+        launch_allocation_required,
+    })
+}
+
+/// Part of `referral_fee` paid to the parent of the referral account
+pub fn get_sub_referral_fee(referral_fee: u64, sub_referral_fee_share: u16) -> Result<u64> {
+    Ok(u64::try_from(
+        u128::from(referral_fee) * u128::from(sub_referral_fee_share) / u128::from(MAX_BPS),
+    )?)
+}
+
+pub fn swap(
+    market: &mut Market,
+    swap_type: SwapType,
+    swap_amount_type: SwapAmountType,
+    amount: u64,
+) -> Result<(u64, u64, u64)> {
+    if market.is_graduated() {
+        return swap_pool(market, swap_type, swap_amount_type, amount);
+    }
+
+    // Sells can bring the market back below its threshold, buys wait for the graduation
+    if swap_type == SwapType::Buy && market.is_graduation_threshold_reached()? {
+        return Err(TokenMillError::MarketGraduationPending);
+    }
+
+    let (base_amount, quote_amount) = match market.curve_kind() {
+        CurveKind::PiecewiseLinear => match (swap_type, swap_amount_type) {
+            (SwapType::Buy, SwapAmountType::ExactInput) => market.get_base_amount_out(amount)?,
+            (SwapType::Buy, SwapAmountType::ExactOutput) => {
+                market.get_quote_amount(amount, swap_amount_type)?
+            }
+            (SwapType::Sell, SwapAmountType::ExactInput) => {
+                market.get_quote_amount(amount, swap_amount_type)?
+            }
+            (SwapType::Sell, SwapAmountType::ExactOutput) => market.get_base_amount_in(amount)?,
+        },
+        CurveKind::VirtualConstantProduct { .. } => match (swap_type, swap_amount_type) {
+            (SwapType::Buy, SwapAmountType::ExactInput) => {
+                market.get_virtual_base_amount_out(amount)?
+            }
+            (SwapType::Buy, SwapAmountType::ExactOutput) => {
+                market.get_virtual_quote_amount(amount, swap_amount_type)?
+            }
+            (SwapType::Sell, SwapAmountType::ExactInput) => {
+                market.get_virtual_quote_amount(amount, swap_amount_type)?
+            }
+            (SwapType::Sell, SwapAmountType::ExactOutput) => {
+                market.get_virtual_base_amount_in(amount)?
+            }
+        },
+    };
+
+    // Buys crossing the graduation threshold are capped at the base reserve the pool is seeded with
+    let max_base_amount = market.get_max_buy_base_amount();
+
+    let (base_amount, quote_amount) = if swap_type == SwapType::Buy && base_amount > max_base_amount
+    {
+        market.get_curve_quote_amount_with_parameters(
+            market.circulating_supply(),
+            max_base_amount,
+            SwapAmountType::ExactOutput,
+            Rounding::Up,
+        )?
+    } else {
+        (base_amount, quote_amount)
+    };
+
+    let mut swap_fee = 0;
+
+    match swap_type {
+        SwapType::Buy => {
+            let (_, buyback_amount) = market.get_curve_quote_amount_with_parameters(
+                market.circulating_supply(),
+                base_amount,
+                SwapAmountType::ExactInput,
+                Rounding::Up,
+            )?;
+
+            if quote_amount > buyback_amount {
+                swap_fee = quote_amount - buyback_amount;
+            }
+
+            market.base_reserve -= base_amount;
+        }
+        SwapType::Sell => {
+            market.base_reserve += base_amount;
+        }
+    }
+
+    Ok((base_amount, quote_amount, swap_fee))
+}
+
+/// Swaps like `swap`, buyers on the curve being discounted `fee_discount_bps` of the spread fee.
+/// The discount lowers the price paid, the buyback amount of the bid curve is left untouched.
+/// Returns the amounts of `swap`, net of the discount, and the discount.
+pub fn swap_with_fee_discount(
+    market: &mut Market,
+    swap_type: SwapType,
+    swap_amount_type: SwapAmountType,
+    amount: u64,
+    fee_discount_bps: u64,
+) -> Result<SwapResult> {
+    let is_graduated = market.is_graduated();
+
+    let (base_amount, quote_amount, swap_fee) = swap(market, swap_type, swap_amount_type, amount)?;
+
+    let fee_discount = if swap_type == SwapType::Sell || is_graduated {
+        0
+    } else {
+        get_fee_amount(swap_fee, fee_discount_bps)?
+    };
+
+    Ok(SwapResult {
+        base_amount,
+        quote_amount: quote_amount - fee_discount,
+        swap_fee: swap_fee - fee_discount,
+        fee_discount,
+        ..Default::default()
+    })
+}
+
+/// Swaps like `swap_with_fee_discount`, buyers also paying the market's buy tax at `current_time` on top of the curve.
+/// Returns the amounts of `swap_with_fee_discount`, the quote amount including the buy tax.
+pub fn swap_with_buy_tax(
+    market: &mut Market,
+    swap_type: SwapType,
+    swap_amount_type: SwapAmountType,
+    amount: u64,
+    current_time: i64,
+    fee_discount_bps: u64,
+) -> Result<SwapResult> {
+    let buy_tax_bps = match swap_type {
+        SwapType::Buy => market.get_buy_tax_bps(current_time),
+        SwapType::Sell => 0,
+    };
+
+    // The tax is a share of the quote amount paid, only the rest of an exact input reaches the curve
+    let curve_amount = match swap_amount_type {
+        SwapAmountType::ExactInput => amount - get_fee_amount(amount, buy_tax_bps)?,
+        SwapAmountType::ExactOutput => amount,
+    };
+
+    let swap_result = swap_with_fee_discount(
+        market,
+        swap_type,
+        swap_amount_type,
+        curve_amount,
+        fee_discount_bps,
+    )?;
+    let quote_amount = swap_result.quote_amount;
+
+    let buy_tax = if swap_amount_type == SwapAmountType::ExactInput && quote_amount == curve_amount
+    {
+        amount - curve_amount
+    } else {
+        get_amount_with_fee(quote_amount, buy_tax_bps)? - quote_amount
+    };
+
+    Ok(SwapResult {
+        quote_amount: quote_amount + buy_tax,
+        buy_tax,
+        ..swap_result
+    })
+}
+
+/// Swaps like `swap_with_buy_tax`, the quote tokens sent or received by the user paying `quote_transfer_fee`.
+/// `amount` is the amount sent or received by the user, the curve only sees the quote amount reaching or leaving the market.
+/// Returns the amounts of `swap_with_buy_tax`, the quote amount being the market's one, and the transfer fee paid by the user,
+/// who sends the quote amount plus the transfer fee on buys and receives the quote amount minus the transfer fee on sells.
+pub fn swap_with_transfer_fee(
+    market: &mut Market,
+    swap_type: SwapType,
+    swap_amount_type: SwapAmountType,
+    amount: u64,
+    current_time: i64,
+    quote_transfer_fee: Option<&TransferFee>,
+    fee_discount_bps: u64,
+) -> Result<SwapResult> {
+    let curve_amount = match (swap_type, swap_amount_type) {
+        (SwapType::Buy, SwapAmountType::ExactInput) => {
+            amount - get_transfer_fee_amount(quote_transfer_fee, amount)?
+        }
+        (SwapType::Sell, SwapAmountType::ExactOutput) => {
+            get_transfer_amount_with_fee(quote_transfer_fee, amount)?
+        }
+        _ => amount,
+    };
+
+    let swap_result = swap_with_buy_tax(
+        market,
+        swap_type,
+        swap_amount_type,
+        curve_amount,
+        current_time,
+        fee_discount_bps,
+    )?;
+    let quote_amount = swap_result.quote_amount;
+
+    let transfer_fee = match swap_type {
+        SwapType::Buy
+            if swap_amount_type == SwapAmountType::ExactInput && quote_amount == curve_amount =>
+        {
+            amount - curve_amount
+        }
+        SwapType::Buy => {
+            get_transfer_amount_with_fee(quote_transfer_fee, quote_amount)? - quote_amount
+        }
+        SwapType::Sell => get_transfer_fee_amount(quote_transfer_fee, quote_amount)?,
+    };
+
+    Ok(SwapResult {
+        transfer_fee,
+        ..swap_result
+    })
+}
+
+fn get_transfer_fee_amount(transfer_fee: Option<&TransferFee>, amount: u64) -> Result<u64> {
+    match transfer_fee {
+        Some(transfer_fee) => transfer_fee.get_fee_amount(amount),
+        None => Ok(0),
+    }
+}
+
+fn get_transfer_amount_with_fee(transfer_fee: Option<&TransferFee>, amount: u64) -> Result<u64> {
+    match transfer_fee {
+        Some(transfer_fee) => transfer_fee.get_amount_with_fee(amount),
+        None => Ok(amount),
+    }
+}
+
+fn swap_pool(
+    market: &mut Market,
+    swap_type: SwapType,
+    swap_amount_type: SwapAmountType,
+    amount: u64,
+) -> Result<(u64, u64, u64)> {
+    let (base_amount, quote_amount, swap_fee) =
+        market.get_pool_amounts(swap_type, swap_amount_type, amount)?;
+
+    // Fees leave the pool reserves, they are distributed like curve fees
+    let (base_reserve, quote_reserve) = match swap_type {
+        SwapType::Buy => (
+            market.base_reserve.checked_sub(base_amount),
+            quote_amount
+                .checked_sub(swap_fee)
+                .and_then(|quote_amount_in| {
+                    market.graduation.quote_reserve.checked_add(quote_amount_in)
+                }),
+        ),
+        SwapType::Sell => (
+            market.base_reserve.checked_add(base_amount),
+            quote_amount
+                .checked_add(swap_fee)
+                .and_then(|quote_amount_out| {
+                    market
+                        .graduation
+                        .quote_reserve
+                        .checked_sub(quote_amount_out)
+                }),
+        ),
+    };
+
+    market.base_reserve = base_reserve.ok_or(TokenMillError::MathError)?;
+    market.graduation.quote_reserve = quote_reserve.ok_or(TokenMillError::MathError)?;
+
+    Ok((base_amount, quote_amount, swap_fee))
+}
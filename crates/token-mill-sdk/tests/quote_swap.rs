@@ -0,0 +1,405 @@
+use joelana_test_utils::{
+    joelana_env::{
+        actions::token_mill::{
+            GraduateMarketAction, QuoteSwapAction, SwapAction, TokenMillEnv, DEFAULT_TOTAL_SUPPLY,
+        },
+        make_address, BuyTaxRecipient, CurveKind, PriceInterpolation, SwapAmountType, SwapType,
+    },
+    utils::token_mill::constants::DEFAULT_REFERRAL_FEE_SHARE,
+};
+use rstest::rstest;
+use token_mill_sdk::{
+    Market, MarketStatus, ReferralFeeShares, SwapQuote, TokenMillError, TransferFee,
+    MARKET_DISCRIMINATOR,
+};
+
+const SUPPLY_THRESHOLD: u64 = DEFAULT_TOTAL_SUPPLY / 2;
+
+#[derive(Debug, Clone, Copy)]
+enum MarketSetup {
+    Linear,
+    Exponential,
+    VirtualConstantProduct,
+    Graduated,
+}
+
+fn setup_env(market_setup: MarketSetup) -> TokenMillEnv {
+    let testing_env = TokenMillEnv::new().with_default_quote_token_mint();
+
+    let mut testing_env = match market_setup {
+        MarketSetup::Linear => testing_env,
+        MarketSetup::Exponential => {
+            testing_env.with_price_interpolation(PriceInterpolation::Exponential)
+        }
+        MarketSetup::VirtualConstantProduct => {
+            testing_env.with_curve_kind(CurveKind::VirtualConstantProduct {
+                virtual_base: DEFAULT_TOTAL_SUPPLY * 11 / 10,
+                virtual_quote: 30_000_000_000,
+                spread_bps: 100,
+            })
+        }
+        MarketSetup::Graduated => testing_env.with_graduation(SUPPLY_THRESHOLD, 0),
+    }
+    .with_default_market();
+
+    testing_env.svm.change_payer("bob");
+
+    // Gives bob some base tokens to sell
+    let buy_amount = match market_setup {
+        MarketSetup::Graduated => SUPPLY_THRESHOLD,
+        _ => 100_000_000_000,
+    };
+
+    testing_env
+        .svm
+        .execute_actions(&[&SwapAction::new(
+            &testing_env,
+            SwapType::Buy,
+            SwapAmountType::ExactOutput,
+            buy_amount,
+            u64::MAX,
+            None,
+        )])
+        .unwrap();
+
+    if let MarketSetup::Graduated = market_setup {
+        testing_env
+            .svm
+            .execute_actions(&[&GraduateMarketAction::new(&testing_env)])
+            .unwrap();
+    }
+
+    testing_env
+}
+
+fn get_market(testing_env: &TokenMillEnv) -> Market {
+    let account = testing_env.svm.get_account(&testing_env.market);
+
+    Market::from_account_data(&account.data).unwrap()
+}
+
+/// Shares of a referral account without parent nor override
+fn referral_fee_shares(referral_fee_share: u16) -> ReferralFeeShares {
+    ReferralFeeShares {
+        referral_fee_share,
+        sub_referral_fee_share: 0,
+    }
+}
+
+/// The instruction returns the borsh encoded `SwapQuote`, i.e. its 11 amounts in little endian followed by its 2 flags
+fn parse_quote(return_data: &[u8]) -> SwapQuote {
+    let (amounts, flags) = return_data.split_at(11 * 8);
+
+    let amounts = amounts
+        .chunks_exact(8)
+        .map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap()))
+        .collect::<Vec<_>>();
+
+    assert_eq!(flags.len(), 2);
+
+    SwapQuote {
+        base_amount: amounts[0],
+        quote_amount: amounts[1],
+        swap_fee: amounts[2],
+        creator_fee: amounts[3],
+        staking_fee: amounts[4],
+        protocol_fee: amounts[5],
+        referral_fee: amounts[6],
+        sub_referral_fee: amounts[7],
+        buy_tax: amounts[8],
+        transfer_fee: amounts[9],
+        fee_discount: amounts[10],
+        presale_proof_required: flags[0] != 0,
+        launch_allocation_required: flags[1] != 0,
+    }
+}
+
+#[rstest]
+fn quote_swap(
+    #[values(
+        MarketSetup::Linear,
+        MarketSetup::Exponential,
+        MarketSetup::VirtualConstantProduct,
+        MarketSetup::Graduated
+    )]
+    market_setup: MarketSetup,
+    #[values(SwapType::Buy, SwapType::Sell)] swap_type: SwapType,
+    #[values(SwapAmountType::ExactInput, SwapAmountType::ExactOutput)]
+    swap_amount_type: SwapAmountType,
+    #[values(None, Some(DEFAULT_REFERRAL_FEE_SHARE))] referral_fee_share: Option<u16>,
+) {
+    let mut testing_env = setup_env(market_setup);
+    let market = get_market(&testing_env);
+
+    // Sell amounts stay below bob's balance, like the program the SDK panics on underflows
+    for amount in [1, 1_000_000, 1_000_000_000, 50_000_000_000] {
+        let sdk_quote = token_mill_sdk::quote_swap(
+            &market,
+            swap_type.into(),
+            swap_amount_type.into(),
+            amount,
+            referral_fee_share.map(referral_fee_shares),
+            testing_env.svm.get_unix_timestamp(),
+            None,
+            0,
+        );
+
+        let mut action = QuoteSwapAction::new(&testing_env, swap_type, swap_amount_type, amount);
+
+        if referral_fee_share.is_some() {
+            action = action.with_referral_account(make_address("carol"));
+        }
+
+        match testing_env.svm.execute_actions(&[&action]) {
+            Ok(result) => assert_eq!(sdk_quote.unwrap(), parse_quote(&result.return_data.data)),
+            Err(_) => assert!(sdk_quote.is_err()),
+        }
+    }
+}
+
+#[rstest]
+fn swap_matches_program(
+    #[values(MarketSetup::Linear, MarketSetup::VirtualConstantProduct)] market_setup: MarketSetup,
+    #[values(SwapType::Buy, SwapType::Sell)] swap_type: SwapType,
+) {
+    let mut testing_env = setup_env(market_setup);
+    let mut market = get_market(&testing_env);
+
+    let (base_amount, quote_amount, swap_fee) = token_mill_sdk::swap(
+        &mut market,
+        swap_type.into(),
+        token_mill_sdk::SwapAmountType::ExactInput,
+        10_000_000_000,
+    )
+    .unwrap();
+    market.fees.distribute_fee(swap_fee, None).unwrap();
+
+    let bob = testing_env.svm.payer;
+    let base_token_mint = testing_env.base_token_mint.unwrap();
+    let quote_token_mint = testing_env.quote_token_mint.unwrap();
+
+    let base_balance_before = testing_env.svm.get_balance(&base_token_mint, &bob);
+    let quote_balance_before = testing_env.svm.get_balance(&quote_token_mint, &bob);
+
+    testing_env
+        .svm
+        .execute_actions(&[&SwapAction::new(
+            &testing_env,
+            swap_type,
+            SwapAmountType::ExactInput,
+            10_000_000_000,
+            0,
+            None,
+        )])
+        .unwrap();
+
+    assert_eq!(
+        testing_env
+            .svm
+            .get_balance(&base_token_mint, &bob)
+            .abs_diff(base_balance_before),
+        base_amount
+    );
+    assert_eq!(
+        testing_env
+            .svm
+            .get_balance(&quote_token_mint, &bob)
+            .abs_diff(quote_balance_before),
+        quote_amount
+    );
+
+    // The SDK market follows the on-chain market
+    let program_market = get_market(&testing_env);
+
+    assert_eq!(market.base_reserve, program_market.base_reserve);
+    assert_eq!(
+        market.fees.pending_creator_fees,
+        program_market.fees.pending_creator_fees
+    );
+    assert_eq!(
+        market.fees.pending_staking_fees,
+        program_market.fees.pending_staking_fees
+    );
+}
+
+#[rstest]
+fn quote_swap_with_buy_tax(
+    #[values(BuyTaxRecipient::Fees, BuyTaxRecipient::Stakers)] recipient: BuyTaxRecipient,
+    #[values(SwapAmountType::ExactInput, SwapAmountType::ExactOutput)]
+    swap_amount_type: SwapAmountType,
+) {
+    let duration = 3_600;
+
+    let mut testing_env = TokenMillEnv::new()
+        .with_default_quote_token_mint()
+        .with_buy_tax(2_000, duration, recipient)
+        .with_default_market();
+
+    testing_env.svm.change_payer("bob");
+
+    let market = get_market(&testing_env);
+    let start_ts = testing_env.svm.get_unix_timestamp();
+
+    // The tax decays from its start rate to 0
+    for elapsed in [0, duration / 3, duration] {
+        testing_env
+            .svm
+            .warp(start_ts + elapsed - testing_env.svm.get_unix_timestamp());
+
+        let sdk_quote = token_mill_sdk::quote_swap(
+            &market,
+            token_mill_sdk::SwapType::Buy,
+            swap_amount_type.into(),
+            1_000_000_000,
+            Some(referral_fee_shares(DEFAULT_REFERRAL_FEE_SHARE)),
+            testing_env.svm.get_unix_timestamp(),
+            None,
+            0,
+        )
+        .unwrap();
+
+        let action =
+            QuoteSwapAction::new(&testing_env, SwapType::Buy, swap_amount_type, 1_000_000_000)
+                .with_referral_account(make_address("carol"));
+
+        let result = testing_env.svm.execute_actions(&[&action]).unwrap();
+
+        assert_eq!(sdk_quote, parse_quote(&result.return_data.data));
+        assert_eq!(sdk_quote.buy_tax == 0, elapsed == duration);
+    }
+}
+
+#[rstest]
+fn quote_swap_with_transfer_fee(
+    #[values(SwapType::Buy, SwapType::Sell)] swap_type: SwapType,
+    #[values(SwapAmountType::ExactInput, SwapAmountType::ExactOutput)]
+    swap_amount_type: SwapAmountType,
+    #[values(u64::MAX, 5_000_000)] maximum_fee: u64,
+) {
+    let transfer_fee = TransferFee {
+        transfer_fee_basis_points: 150,
+        maximum_fee,
+    };
+
+    let mut testing_env = TokenMillEnv::new()
+        .with_transfer_fee_quote_token_mint(
+            9,
+            transfer_fee.transfer_fee_basis_points,
+            transfer_fee.maximum_fee,
+        )
+        .with_default_market();
+
+    testing_env.svm.change_payer("bob");
+
+    // Gives bob some base tokens to sell
+    testing_env
+        .svm
+        .execute_actions(&[&SwapAction::new(
+            &testing_env,
+            SwapType::Buy,
+            SwapAmountType::ExactOutput,
+            100_000_000_000,
+            u64::MAX,
+            None,
+        )])
+        .unwrap();
+
+    let market = get_market(&testing_env);
+
+    for amount in [1_000_000, 1_000_000_000] {
+        let sdk_quote = token_mill_sdk::quote_swap(
+            &market,
+            swap_type.into(),
+            swap_amount_type.into(),
+            amount,
+            None,
+            testing_env.svm.get_unix_timestamp(),
+            Some(&transfer_fee),
+            0,
+        )
+        .unwrap();
+
+        let action = QuoteSwapAction::new(&testing_env, swap_type, swap_amount_type, amount);
+
+        let result = testing_env.svm.execute_actions(&[&action]).unwrap();
+
+        assert_eq!(sdk_quote, parse_quote(&result.return_data.data));
+        assert!(sdk_quote.transfer_fee > 0);
+    }
+}
+
+#[test]
+fn quote_swap_with_invalid_amount() {
+    let testing_env = setup_env(MarketSetup::Linear);
+
+    let result = token_mill_sdk::quote_swap(
+        &get_market(&testing_env),
+        token_mill_sdk::SwapType::Buy,
+        token_mill_sdk::SwapAmountType::ExactInput,
+        0,
+        None,
+        0,
+        None,
+        0,
+    );
+
+    assert_eq!(result, Err(TokenMillError::InvalidAmount));
+}
+
+#[test]
+fn from_account_data() {
+    let testing_env = setup_env(MarketSetup::Linear);
+    let account = testing_env.svm.get_account(&testing_env.market);
+
+    let market = Market::from_account_data(&account.data).unwrap();
+    let program_market = testing_env
+        .svm
+        .get_parsed_account::<token_mill::state::Market>(&testing_env.market);
+
+    assert_eq!(market.total_supply, program_market.total_supply);
+    assert_eq!(market.base_reserve, program_market.base_reserve);
+    assert_eq!(
+        market.quote_token_mint,
+        program_market.quote_token_mint.to_bytes()
+    );
+    assert_eq!(market.circulating_supply(), 100_000_000_000);
+    assert_eq!(market.status, program_market.status);
+    assert_eq!(market.get_status(0), MarketStatus::Trading);
+
+    let mut data = account.data.clone();
+    data[..MARKET_DISCRIMINATOR.len()].copy_from_slice(&[0; 8]);
+
+    assert_eq!(
+        Market::from_account_data(&data).unwrap_err(),
+        TokenMillError::InvalidAccountData
+    );
+    assert_eq!(
+        Market::from_account_data(&account.data[..account.data.len() - 1]).unwrap_err(),
+        TokenMillError::InvalidAccountData
+    );
+}
+
+#[rstest]
+fn get_spot_price(
+    #[values(
+        MarketSetup::Linear,
+        MarketSetup::Exponential,
+        MarketSetup::VirtualConstantProduct,
+        MarketSetup::Graduated
+    )]
+    market_setup: MarketSetup,
+    #[values(SwapType::Buy, SwapType::Sell)] swap_type: SwapType,
+) {
+    let testing_env = setup_env(market_setup);
+
+    let program_market = testing_env
+        .svm
+        .get_parsed_account::<token_mill::state::Market>(&testing_env.market);
+
+    assert_eq!(
+        get_market(&testing_env)
+            .get_spot_price(swap_type.into())
+            .unwrap(),
+        program_market.get_spot_price(swap_type).unwrap()
+    );
+}
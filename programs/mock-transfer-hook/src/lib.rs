@@ -0,0 +1,152 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{create_account, CreateAccount},
+};
+use anchor_spl::token_interface::{Mint, TokenAccount};
+use spl_tlv_account_resolution::{
+    account::ExtraAccountMeta, seeds::Seed, state::ExtraAccountMetaList,
+};
+use spl_transfer_hook_interface::instruction::{ExecuteInstruction, TransferHookInstruction};
+
+declare_id!("6ZuNTcyrbfmRm9295t3yawmifZz2JmWmCbBQ1a1HFmyf");
+
+pub const EXTRA_ACCOUNT_METAS_PDA_SEED: &str = "extra-account-metas";
+pub const TRANSFER_COUNTER_PDA_SEED: &str = "counter";
+
+/// Transfer hook counting the transfers of a mint, with the counter as its only extra account
+#[program]
+pub mod mock_transfer_hook {
+    use super::*;
+
+    pub fn initialize_extra_account_meta_list(
+        ctx: Context<InitializeExtraAccountMetaList>,
+    ) -> Result<()> {
+        // The mint is the second account of the `Execute` instruction
+        let extra_account_metas = [ExtraAccountMeta::new_with_seeds(
+            &[
+                Seed::Literal {
+                    bytes: TRANSFER_COUNTER_PDA_SEED.as_bytes().to_vec(),
+                },
+                Seed::AccountKey { index: 1 },
+            ],
+            false,
+            true,
+        )?];
+
+        let account_size = ExtraAccountMetaList::size_of(extra_account_metas.len())?;
+
+        let mint_key = ctx.accounts.mint.key();
+        let extra_account_meta_list_seeds = [
+            EXTRA_ACCOUNT_METAS_PDA_SEED.as_bytes(),
+            mint_key.as_ref(),
+            &[ctx.bumps.extra_account_meta_list],
+        ];
+
+        create_account(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                CreateAccount {
+                    from: ctx.accounts.payer.to_account_info(),
+                    to: ctx.accounts.extra_account_meta_list.to_account_info(),
+                },
+                &[&extra_account_meta_list_seeds],
+            ),
+            Rent::get()?.minimum_balance(account_size),
+            account_size as u64,
+            ctx.program_id,
+        )?;
+
+        ExtraAccountMetaList::init::<ExecuteInstruction>(
+            &mut ctx.accounts.extra_account_meta_list.try_borrow_mut_data()?,
+            &extra_account_metas,
+        )?;
+
+        Ok(())
+    }
+
+    pub fn transfer_hook(ctx: Context<TransferHook>, amount: u64) -> Result<()> {
+        let counter = &mut ctx.accounts.counter;
+
+        counter.transfers += 1;
+        counter.amount += amount;
+
+        Ok(())
+    }
+
+    /// Routes the `Execute` instruction of the transfer hook interface to `transfer_hook`
+    pub fn fallback<'info>(
+        program_id: &Pubkey,
+        accounts: &'info [AccountInfo<'info>],
+        data: &[u8],
+    ) -> Result<()> {
+        match TransferHookInstruction::unpack(data)? {
+            TransferHookInstruction::Execute { amount } => {
+                __private::__global::transfer_hook(program_id, accounts, &amount.to_le_bytes())
+            }
+            _ => Err(ProgramError::InvalidInstructionData.into()),
+        }
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct TransferCounter {
+    pub transfers: u64,
+    pub amount: u64,
+}
+
+#[derive(Accounts)]
+pub struct InitializeExtraAccountMetaList<'info> {
+    /// CHECK: Created by the instruction
+    #[account(
+        mut,
+        seeds = [EXTRA_ACCOUNT_METAS_PDA_SEED.as_bytes(), mint.key().as_ref()],
+        bump
+    )]
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        seeds = [TRANSFER_COUNTER_PDA_SEED.as_bytes(), mint.key().as_ref()],
+        bump,
+        payer = payer,
+        space = 8 + TransferCounter::INIT_SPACE
+    )]
+    pub counter: Account<'info, TransferCounter>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts of the `Execute` instruction, followed by the extra accounts
+#[derive(Accounts)]
+pub struct TransferHook<'info> {
+    #[account(token::mint = mint)]
+    pub source_token: InterfaceAccount<'info, TokenAccount>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(token::mint = mint)]
+    pub destination_token: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: Owner or delegate of the source token account
+    pub owner: UncheckedAccount<'info>,
+
+    /// CHECK: Read by the token program to resolve the extra accounts
+    #[account(
+        seeds = [EXTRA_ACCOUNT_METAS_PDA_SEED.as_bytes(), mint.key().as_ref()],
+        bump
+    )]
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [TRANSFER_COUNTER_PDA_SEED.as_bytes(), mint.key().as_ref()],
+        bump
+    )]
+    pub counter: Account<'info, TransferCounter>,
+}
@@ -1,9 +1,17 @@
-pub const PRICES_LENGTH: usize = 11;
-pub const INTERVAL_NUMBER: u64 = PRICES_LENGTH as u64 - 1;
+// Constants of the swap math, shared with the SDK
+pub use token_mill_sdk::constant::{
+    BASE_PRECISION, GRADUATED_POOL_FEE_BPS, MAX_BPS, MAX_EXP_INTERVALS_PER_SWAP, MAX_PRICES_LENGTH,
+    SCALE,
+};
+
+pub const MIN_PRICES_LENGTH: usize = 2;
 pub const MAX_TOTAL_SUPPLY: u64 = 1_000_000_000_000_000; // 1e9 * 1e6
 pub const MAX_PRICE: u64 = 1_000_000_000_000_000_000; // 1e18
 pub const MILL_TOKEN_DECIMALS: u8 = 6;
-pub const BASE_PRECISION: u64 = 1_000_000; // 1e6
-pub const SCALE: u128 = 10_000_000_000; // 1e10
 pub const STAKING_SCALE: u128 = 1_000_000_000_000_000_000; // 1e18
-pub const MAX_BPS: u64 = 10_000;
+pub const LIMIT_ORDER_KEEPER_TIP_BPS: u64 = 10; // 0.1%
+pub const MARKET_ORACLE_OBSERVATIONS: usize = 32;
+pub const MAX_LAUNCH_WINDOW: i64 = 86_400; // 1 day
+pub const MAX_BUY_TAX_BPS: u16 = 5_000; // 50%
+pub const MAX_BUY_TAX_DURATION: i64 = 604_800; // 1 week
+pub const MAX_FEE_DISCOUNT_TIERS: usize = 4;
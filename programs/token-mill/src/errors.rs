@@ -25,4 +25,61 @@ pub enum TokenMillError {
     InvalidStakePosition,
     InvalidVestingDuration,
     InvalidVestingStartTime,
+    InvalidGraduationThreshold,
+    GraduationThresholdNotReached,
+    MarketGraduationPending,
+    MarketGraduated,
+    InvalidPricesLength,
+    InvalidSupplyBreakpoints,
+    InvalidPriceInterpolation,
+    InvalidVirtualReserves,
+    SwapExpired,
+    PriceLimitExceeded,
+    LimitOrderNotFillable,
+    InvalidDcaInterval,
+    DcaExecutionTooEarly,
+    MissingMarketOracle,
+    InvalidTwapWindow,
+    MissingMarketStats,
+    MarketNotTrading,
+    InvalidMarketStatus,
+    OperationPaused,
+    InvalidLaunchProtection,
+    MissingLaunchAllocation,
+    LaunchAllocationExceeded,
+    InvalidBuyTax,
+    InvalidPresale,
+    MissingPresaleProof,
+    InvalidPresaleProof,
+    InvalidTransferHookBadge,
+    InvalidFeeDiscountTiers,
+    AccountAlreadyMigrated,
+    MissingTransferHookAccounts,
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::error::Error;
+
+    use super::TokenMillError;
+
+    #[test]
+    fn sdk_error_codes() {
+        for (sdk_error, error) in [
+            (
+                token_mill_sdk::TokenMillError::InvalidAmount,
+                TokenMillError::InvalidAmount,
+            ),
+            (
+                token_mill_sdk::TokenMillError::MathError,
+                TokenMillError::MathError,
+            ),
+            (
+                token_mill_sdk::TokenMillError::MarketGraduationPending,
+                TokenMillError::MarketGraduationPending,
+            ),
+        ] {
+            assert_eq!(Error::from(sdk_error), Error::from(error));
+        }
+    }
 }
@@ -1,9 +1,11 @@
 use anchor_lang::event;
 use anchor_lang::prelude::*;
 
-use crate::constant::PRICES_LENGTH;
 use crate::manager::swap_manager::SwapType;
-use crate::QuoteTokenBadgeStatus;
+use crate::{
+    BuyTaxRecipient, FeeDiscountTier, LaunchProtection, MarketStatus, PausableOperation,
+    PriceInterpolation, QuoteTokenBadgeStatus, TransferHookBadgeStatus,
+};
 
 #[event]
 pub struct TokenMillConfigCreationEvent {
@@ -19,6 +21,19 @@ pub struct TokenMillConfigOwnershipTransferEvent {
     pub new_authority: Pubkey,
 }
 
+#[event]
+pub struct TokenMillOperationPauseEvent {
+    pub config: Pubkey,
+    pub operation: PausableOperation,
+    pub paused: bool,
+}
+
+#[event]
+pub struct TokenMillFeeDiscountTiersUpdateEvent {
+    pub config: Pubkey,
+    pub fee_discount_tiers: Vec<FeeDiscountTier>,
+}
+
 #[event]
 pub struct TokenMillQuoteTokenBadgeEvent {
     pub config: Pubkey,
@@ -26,6 +41,13 @@ pub struct TokenMillQuoteTokenBadgeEvent {
     pub quote_asset_badge_status: QuoteTokenBadgeStatus,
 }
 
+#[event]
+pub struct TokenMillTransferHookBadgeEvent {
+    pub config: Pubkey,
+    pub transfer_hook_program: Pubkey,
+    pub transfer_hook_badge_status: TransferHookBadgeStatus,
+}
+
 #[event]
 pub struct TokenMillMarketCreationEvent {
     pub config: Pubkey,
@@ -38,13 +60,56 @@ pub struct TokenMillMarketCreationEvent {
     pub referral_fee_share: u16,
     pub creator_fee_share: u16,
     pub staking_fee_share: u16,
+    pub trading_start_ts: i64,
+    pub launch_protection: Option<LaunchProtection>,
+    pub transfer_hook_program_id: Option<Pubkey>,
 }
 
 #[event]
 pub struct TokenMillMarketPriceSetEvent {
     pub market: Pubkey,
-    pub bid_prices: [u64; PRICES_LENGTH],
-    pub ask_prices: [u64; PRICES_LENGTH],
+    pub bid_prices: Vec<u64>,
+    pub ask_prices: Vec<u64>,
+    pub supply_breakpoints: Vec<u64>,
+    pub price_interpolation: PriceInterpolation,
+}
+
+#[event]
+pub struct TokenMillMarketVirtualReservesSetEvent {
+    pub market: Pubkey,
+    pub virtual_base: u64,
+    pub virtual_quote: u64,
+    pub spread_bps: u16,
+}
+
+#[event]
+pub struct TokenMillGraduationSetEvent {
+    pub market: Pubkey,
+    pub supply_threshold: u64,
+    pub quote_threshold: u64,
+}
+
+#[event]
+pub struct TokenMillBuyTaxSetEvent {
+    pub market: Pubkey,
+    pub start_bps: u16,
+    pub duration: i64,
+    pub recipient: BuyTaxRecipient,
+}
+
+#[event]
+pub struct TokenMillPresaleSetEvent {
+    pub market: Pubkey,
+    pub merkle_root: [u8; 32],
+    pub end_ts: i64,
+}
+
+#[event]
+pub struct TokenMillGraduationEvent {
+    pub market: Pubkey,
+    pub circulating_supply: u64,
+    pub base_reserve: u64,
+    pub quote_reserve: u64,
 }
 
 #[event]
@@ -59,6 +124,12 @@ pub struct TokenMillSwapEvent {
     pub staking_fee: u64,
     pub protocol_fee: u64,
     pub referral_fee: u64,
+    /// Part of `referral_fee` paid to the parent referral account
+    pub sub_referral_fee: u64,
+    /// Part of `quote_amount` paid as buy tax, included in the fees it is distributed to
+    pub buy_tax: u64,
+    /// Spread fee waived for the user's stake position, already deducted from `quote_amount` and the fees
+    pub fee_discount: u64,
 }
 
 #[event]
@@ -68,6 +139,13 @@ pub struct TokenMillCreatorFeeClaimEvent {
     pub fees_distributed: u64,
 }
 
+#[event]
+pub struct TokenMillProtocolFeeClaimEvent {
+    pub market: Pubkey,
+    pub protocol_fee_recipient: Pubkey,
+    pub fees_distributed: u64,
+}
+
 #[event]
 pub struct TokenMillDefaultFeeSharesUpdateEvent {
     pub config: Pubkey,
@@ -75,6 +153,19 @@ pub struct TokenMillDefaultFeeSharesUpdateEvent {
     pub new_referral_fee_share: u16,
 }
 
+#[event]
+pub struct TokenMillSubReferralFeeShareUpdateEvent {
+    pub config: Pubkey,
+    pub new_sub_referral_fee_share: u16,
+}
+
+#[event]
+pub struct TokenMillReferralFeeShareOverrideEvent {
+    pub config: Pubkey,
+    pub referral_account: Pubkey,
+    pub fee_share_override: Option<u16>,
+}
+
 #[event]
 pub struct TokenMillProtocolFeeRecipientUpdateEvent {
     pub config: Pubkey,
@@ -87,6 +178,13 @@ pub struct TokenMillCreatorUpdateEvent {
     pub new_creator: Pubkey,
 }
 
+#[event]
+pub struct TokenMillMarketStatusUpdateEvent {
+    pub market: Pubkey,
+    pub old_status: MarketStatus,
+    pub new_status: MarketStatus,
+}
+
 #[event]
 pub struct TokenMillMarketFeeSharesUpdateEvent {
     pub market: Pubkey,
@@ -138,3 +236,57 @@ pub struct TokenMillVestingPlanReleaseEvent {
     pub vesting_plan: Pubkey,
     pub amount_released: u64,
 }
+
+#[event]
+pub struct TokenMillLimitOrderCreationEvent {
+    pub market: Pubkey,
+    pub owner: Pubkey,
+    pub limit_order: Pubkey,
+    pub swap_type: SwapType,
+    pub amount: u64,
+    pub limit_price: u64,
+}
+
+#[event]
+pub struct TokenMillLimitOrderFillEvent {
+    pub limit_order: Pubkey,
+    pub keeper: Pubkey,
+    pub base_amount: u64,
+    pub quote_amount: u64,
+    pub keeper_tip: u64,
+    pub amount_remaining: u64,
+}
+
+#[event]
+pub struct TokenMillLimitOrderCancellationEvent {
+    pub limit_order: Pubkey,
+    pub amount_refunded: u64,
+}
+
+#[event]
+pub struct TokenMillDcaOrderCreationEvent {
+    pub market: Pubkey,
+    pub owner: Pubkey,
+    pub dca_order: Pubkey,
+    pub swap_type: SwapType,
+    pub amount: u64,
+    pub amount_per_execution: u64,
+    pub interval: i64,
+    pub limit_price: u64,
+}
+
+#[event]
+pub struct TokenMillDcaExecutionEvent {
+    pub dca_order: Pubkey,
+    pub executor: Pubkey,
+    pub base_amount: u64,
+    pub quote_amount: u64,
+    pub amount_remaining: u64,
+    pub next_execution_ts: i64,
+}
+
+#[event]
+pub struct TokenMillDcaOrderCancellationEvent {
+    pub dca_order: Pubkey,
+    pub amount_refunded: u64,
+}
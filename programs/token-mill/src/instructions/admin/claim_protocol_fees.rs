@@ -0,0 +1,158 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::{
+    errors::TokenMillError, events::TokenMillProtocolFeeClaimEvent,
+    manager::token_manager::transfer_from_pda, state::Market, TokenMillConfig, MARKET_PDA_SEED,
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimProtocolFees<'info> {
+    #[account(has_one = protocol_fee_recipient @ TokenMillError::InvalidAuthority)]
+    pub config: Account<'info, TokenMillConfig>,
+
+    #[account(
+        mut,
+        has_one = config @ TokenMillError::InvalidConfigAccount,
+        has_one = quote_token_mint @ TokenMillError::InvalidQuoteTokenMint
+    )]
+    pub market: AccountLoader<'info, Market>,
+
+    pub quote_token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = quote_token_mint,
+        associated_token::authority = market,
+        associated_token::token_program = quote_token_program
+    )]
+    pub market_quote_token_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut, token::mint = quote_token_mint)]
+    pub protocol_fee_recipient_quote_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub protocol_fee_recipient: Signer<'info>,
+
+    pub quote_token_program: Interface<'info, TokenInterface>,
+}
+
+pub fn handler(ctx: Context<ClaimProtocolFees>) -> Result<()> {
+    let (pending_fees, base_token_mint, bump) = {
+        let market = &mut ctx.accounts.market.load_mut()?;
+
+        let pending_fees = market.fees.pending_protocol_fees;
+        market.fees.pending_protocol_fees = 0;
+
+        (pending_fees, market.base_token_mint, market.bump)
+    };
+
+    let market_seeds = [
+        MARKET_PDA_SEED.as_bytes(),
+        base_token_mint.as_ref(),
+        &[bump],
+    ];
+
+    transfer_from_pda(
+        &ctx.accounts.quote_token_mint,
+        ctx.accounts.market.to_account_info(),
+        &ctx.accounts.market_quote_token_ata,
+        &ctx.accounts.protocol_fee_recipient_quote_token_account,
+        &ctx.accounts.quote_token_program,
+        pending_fees,
+        &market_seeds,
+    )?;
+
+    emit_cpi!(TokenMillProtocolFeeClaimEvent {
+        market: ctx.accounts.market.key(),
+        protocol_fee_recipient: ctx.accounts.protocol_fee_recipient.key(),
+        fees_distributed: pending_fees,
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Market;
+    use joelana_test_utils::joelana_env::{
+        actions::token_mill::{
+            tm_parse_error, ClaimProtocolFeesAction, SwapAction, TokenMillEnv, DEFAULT_TOTAL_SUPPLY,
+        },
+        make_address, SwapAmountType, SwapType, TokenMillError, TokenType,
+    };
+    use rstest::rstest;
+
+    fn setup_env(token_type: TokenType) -> (TokenMillEnv, ClaimProtocolFeesAction) {
+        let mut testing_env = TokenMillEnv::new()
+            .with_quote_token_mint(token_type, 9)
+            .with_default_market();
+
+        testing_env.svm.change_payer("bob");
+
+        let swap_action = SwapAction::new(
+            &testing_env,
+            SwapType::Buy,
+            SwapAmountType::ExactOutput,
+            DEFAULT_TOTAL_SUPPLY / 2,
+            u64::MAX,
+            None,
+        );
+
+        testing_env.svm.execute_actions(&[&swap_action]).unwrap();
+
+        testing_env.svm.change_payer("dave");
+
+        let action = ClaimProtocolFeesAction::new(&testing_env);
+
+        (testing_env, action)
+    }
+
+    #[rstest]
+    fn claim_protocol_fees(
+        #[values(TokenType::Token, TokenType::Token2022)] token_type: TokenType,
+    ) {
+        let (mut testing_env, action) = setup_env(token_type);
+
+        let market = testing_env.svm.get_parsed_account::<Market>(&action.market);
+        let pending_protocol_fees = market.fees.pending_protocol_fees;
+
+        assert!(pending_protocol_fees > 0);
+
+        let quote_token_mint = testing_env.quote_token_mint.unwrap();
+        let protocol_fee_recipient = make_address("dave");
+        let balance_before = testing_env
+            .svm
+            .get_balance(&quote_token_mint, &protocol_fee_recipient);
+
+        let result = testing_env.svm.execute_actions(&[&action]);
+
+        assert!(result.is_ok());
+
+        let market = testing_env.svm.get_parsed_account::<Market>(&action.market);
+
+        assert_eq!(market.fees.pending_protocol_fees, 0);
+        assert_eq!(
+            testing_env
+                .svm
+                .get_balance(&quote_token_mint, &protocol_fee_recipient)
+                - balance_before,
+            pending_protocol_fees
+        );
+    }
+
+    #[test]
+    fn claim_protocol_fees_with_invalid_signer() {
+        let (mut testing_env, mut action) = setup_env(TokenType::Token);
+
+        action.signer = testing_env.svm.change_payer("mallory");
+
+        let result = testing_env.svm.execute_actions(&[&action]);
+
+        assert!(result.is_err());
+
+        let error = tm_parse_error(result).unwrap();
+
+        assert_eq!(error, TokenMillError::InvalidAuthority);
+    }
+}
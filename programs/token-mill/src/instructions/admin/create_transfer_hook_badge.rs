@@ -0,0 +1,92 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::TokenMillError,
+    events::TokenMillTransferHookBadgeEvent,
+    state::{TokenMillConfig, TransferHookBadge},
+    TRANSFER_HOOK_BADGE_PDA_SEED,
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CreateTransferHookBadge<'info> {
+    #[account(has_one = authority @ TokenMillError::InvalidAuthority)]
+    pub config: Account<'info, TokenMillConfig>,
+
+    #[account(
+        init,
+        seeds = [
+            TRANSFER_HOOK_BADGE_PDA_SEED.as_bytes(),
+            config.key().as_ref(),
+            transfer_hook_program.key().as_ref(),
+        ],
+        bump,
+        payer = authority,
+        space = 8 + TransferHookBadge::INIT_SPACE
+    )]
+    pub transfer_hook_badge: Account<'info, TransferHookBadge>,
+
+    /// CHECK: Program vetted by the config authority
+    #[account(executable)]
+    pub transfer_hook_program: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<CreateTransferHookBadge>) -> Result<()> {
+    let transfer_hook_badge = &mut ctx.accounts.transfer_hook_badge;
+
+    transfer_hook_badge.initialize(ctx.bumps.transfer_hook_badge)?;
+
+    emit_cpi!(TokenMillTransferHookBadgeEvent {
+        config: ctx.accounts.config.key(),
+        transfer_hook_program: ctx.accounts.transfer_hook_program.key(),
+        transfer_hook_badge_status: transfer_hook_badge.status,
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{TransferHookBadge, TransferHookBadgeStatus};
+    use joelana_test_utils::joelana_env::{
+        actions::token_mill::{tm_parse_error, CreateTransferHookBadgeAction, TokenMillEnv},
+        TokenMillError,
+    };
+
+    #[test]
+    fn create_transfer_hook_badge() {
+        let mut testing_env = TokenMillEnv::new();
+        let action = CreateTransferHookBadgeAction::new();
+
+        let result = testing_env.svm.execute_actions(&[&action]);
+
+        assert!(result.is_ok());
+
+        let transfer_hook_badge = testing_env
+            .svm
+            .get_parsed_account::<TransferHookBadge>(&action.transfer_hook_badge);
+
+        assert_eq!(transfer_hook_badge.status, TransferHookBadgeStatus::Enabled);
+    }
+
+    #[test]
+    fn create_transfer_hook_badge_with_invalid_signer() {
+        let mut testing_env = TokenMillEnv::new();
+        let mut action = CreateTransferHookBadgeAction::new();
+
+        action.signer = testing_env.svm.change_payer("mallory");
+
+        let result = testing_env.svm.execute_actions(&[&action]);
+
+        assert!(result.is_err());
+
+        let error = tm_parse_error(result).unwrap();
+
+        assert_eq!(error, TokenMillError::InvalidAuthority);
+    }
+}
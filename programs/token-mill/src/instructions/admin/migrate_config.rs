@@ -0,0 +1,163 @@
+use anchor_lang::{prelude::*, Discriminator};
+
+use crate::{
+    constant::MAX_FEE_DISCOUNT_TIERS,
+    errors::TokenMillError,
+    manager::migration_manager::realloc_account,
+    state::{FeeDiscountTier, TokenMillConfig},
+};
+
+/// Size of the configs created before the sub-referral fee share, the pause flags and the fee discount tiers were added
+pub const LEGACY_CONFIG_SIZE: usize = 8 + LegacyTokenMillConfig::INIT_SPACE;
+
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace)]
+pub struct LegacyTokenMillConfig {
+    pub authority: Pubkey,
+    pub pending_authority: Option<Pubkey>,
+    pub protocol_fee_recipient: Pubkey,
+    pub default_protocol_fee_share: u16,
+    pub referral_fee_share: u16,
+}
+
+#[derive(Accounts)]
+pub struct MigrateConfig<'info> {
+    /// CHECK: Legacy configs can't be deserialized, the discriminator and size are checked in the handler
+    #[account(mut, owner = crate::ID @ TokenMillError::InvalidConfigAccount)]
+    pub config: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Grows a legacy config to the current size, with no sub-referral fee share, no paused operation and no fee discount tier.
+/// The config is serialized again, as the bytes following a cleared pending authority may be stale.
+pub fn handler(ctx: Context<MigrateConfig>) -> Result<()> {
+    let config = ctx.accounts.config.to_account_info();
+
+    let legacy_config = {
+        let data = config.try_borrow_data()?;
+
+        require!(
+            data.starts_with(&TokenMillConfig::DISCRIMINATOR),
+            TokenMillError::InvalidConfigAccount
+        );
+        require!(
+            data.len() == LEGACY_CONFIG_SIZE,
+            TokenMillError::AccountAlreadyMigrated
+        );
+
+        LegacyTokenMillConfig::deserialize(&mut &data[8..])?
+    };
+
+    realloc_account(
+        &config,
+        &ctx.accounts.payer,
+        &ctx.accounts.system_program,
+        8 + TokenMillConfig::INIT_SPACE,
+    )?;
+
+    let migrated_config = TokenMillConfig {
+        authority: legacy_config.authority,
+        pending_authority: legacy_config.pending_authority,
+        protocol_fee_recipient: legacy_config.protocol_fee_recipient,
+        default_protocol_fee_share: legacy_config.default_protocol_fee_share,
+        referral_fee_share: legacy_config.referral_fee_share,
+        sub_referral_fee_share: 0,
+        paused_operations: 0,
+        fee_discount_tiers: [FeeDiscountTier::default(); MAX_FEE_DISCOUNT_TIERS],
+    };
+
+    let mut data = config.try_borrow_mut_data()?;
+    data.fill(0);
+
+    migrated_config.try_serialize(&mut &mut data[..])?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::{AnchorSerialize, Discriminator, Space};
+    use joelana_test_utils::joelana_env::{
+        actions::token_mill::{tm_parse_error, MigrateConfigAction, TokenMillEnv},
+        make_address,
+    };
+
+    use super::{LegacyTokenMillConfig, LEGACY_CONFIG_SIZE};
+    use crate::{errors::TokenMillError, TokenMillConfig};
+
+    #[test]
+    fn migrate_config() {
+        let mut testing_env = TokenMillEnv::new();
+
+        let migrate_config_action = MigrateConfigAction::new();
+
+        let config = testing_env
+            .svm
+            .get_parsed_account::<TokenMillConfig>(&migrate_config_action.config);
+
+        let legacy_config = LegacyTokenMillConfig {
+            authority: config.authority,
+            pending_authority: None,
+            protocol_fee_recipient: config.protocol_fee_recipient,
+            default_protocol_fee_share: config.default_protocol_fee_share,
+            referral_fee_share: config.referral_fee_share,
+        };
+
+        // Rewrites the config in the legacy layout, leaving the stale bytes of a cleared pending authority
+        let mut data = TokenMillConfig::DISCRIMINATOR.to_vec();
+        legacy_config.serialize(&mut data).unwrap();
+        data.resize(LEGACY_CONFIG_SIZE, u8::MAX);
+
+        let mut account = testing_env.svm.get_account(&migrate_config_action.config);
+        account.data = data;
+        testing_env
+            .svm
+            .set_account(&migrate_config_action.config, account);
+
+        testing_env
+            .svm
+            .execute_actions(&[&migrate_config_action])
+            .unwrap();
+
+        assert_eq!(
+            testing_env
+                .svm
+                .get_account(&migrate_config_action.config)
+                .data
+                .len(),
+            8 + TokenMillConfig::INIT_SPACE
+        );
+
+        let migrated_config = testing_env
+            .svm
+            .get_parsed_account::<TokenMillConfig>(&migrate_config_action.config);
+
+        assert_eq!(migrated_config.authority, make_address("admin"));
+        assert_eq!(migrated_config.pending_authority, None);
+        assert_eq!(
+            migrated_config.protocol_fee_recipient,
+            config.protocol_fee_recipient
+        );
+        assert_eq!(
+            migrated_config.default_protocol_fee_share,
+            config.default_protocol_fee_share
+        );
+        assert_eq!(
+            migrated_config.referral_fee_share,
+            config.referral_fee_share
+        );
+        assert_eq!(migrated_config.sub_referral_fee_share, 0);
+        assert_eq!(migrated_config.paused_operations, 0);
+        assert_eq!(migrated_config.get_fee_discount_bps(u64::MAX), 0);
+
+        let result = testing_env.svm.execute_actions(&[&migrate_config_action]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::AccountAlreadyMigrated
+        );
+    }
+}
@@ -1,13 +1,28 @@
 pub mod accept_config_ownership;
+pub mod claim_protocol_fees;
 pub mod create_config;
 pub mod create_quote_asset_badge;
+pub mod create_transfer_hook_badge;
+pub mod migrate_config;
+pub mod set_market_status;
+pub mod set_operation_paused;
+pub mod set_referral_fee_share_override;
 pub mod transfer_config_ownership;
 pub mod update_default_fee_shares;
+pub mod update_fee_discount_tiers;
 pub mod update_protocol_fee_recipient;
 pub mod update_quote_asset_badge;
+pub mod update_sub_referral_fee_share;
+pub mod update_transfer_hook_badge;
 
 pub use accept_config_ownership::*;
+pub use claim_protocol_fees::*;
 pub use create_config::*;
 pub use create_quote_asset_badge::*;
+pub use create_transfer_hook_badge::*;
+pub use migrate_config::*;
+pub use set_market_status::*;
+pub use set_referral_fee_share_override::*;
 pub use transfer_config_ownership::*;
 pub use update_quote_asset_badge::*;
+pub use update_transfer_hook_badge::*;
@@ -0,0 +1,182 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::TokenMillError,
+    events::TokenMillMarketStatusUpdateEvent,
+    state::{Market, MarketStatus, TokenMillConfig},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct MarketStatusUpdate<'info> {
+    #[account(has_one = authority @ TokenMillError::InvalidAuthority)]
+    pub config: Account<'info, TokenMillConfig>,
+
+    #[account(mut, has_one = config @ TokenMillError::InvalidConfigAccount)]
+    pub market: AccountLoader<'info, Market>,
+
+    pub authority: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<MarketStatusUpdate>, new_status: MarketStatus) -> Result<()> {
+    let market = &mut ctx.accounts.market.load_mut()?;
+
+    let old_status = market.update_status_as_authority(new_status, Clock::get()?.unix_timestamp)?;
+
+    emit_cpi!(TokenMillMarketStatusUpdateEvent {
+        market: ctx.accounts.market.key(),
+        old_status,
+        new_status,
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use joelana_test_utils::joelana_env::{
+        actions::token_mill::{tm_parse_error, SetMarketStatusAction, SwapAction, TokenMillEnv},
+        MarketStatus, SwapAmountType, SwapType, TokenMillError,
+    };
+    use rstest::rstest;
+
+    use crate::{state, Market};
+
+    fn setup_env() -> (TokenMillEnv, SwapAction, SwapAction) {
+        let mut testing_env = TokenMillEnv::default();
+
+        let buy_action = SwapAction::new(
+            &testing_env,
+            SwapType::Buy,
+            SwapAmountType::ExactOutput,
+            10_000_000_000,
+            u64::MAX,
+            None,
+        );
+        let sell_action = SwapAction::new(
+            &testing_env,
+            SwapType::Sell,
+            SwapAmountType::ExactInput,
+            1_000_000_000,
+            0,
+            None,
+        );
+
+        testing_env.svm.change_payer("bob");
+        testing_env.svm.execute_actions(&[&buy_action]).unwrap();
+
+        (testing_env, buy_action, sell_action)
+    }
+
+    fn set_market_status(
+        testing_env: &mut TokenMillEnv,
+        new_status: MarketStatus,
+    ) -> Result<(), TokenMillError> {
+        testing_env.svm.change_payer("admin");
+
+        let action = SetMarketStatusAction::new(testing_env, new_status);
+
+        let result = testing_env.svm.execute_actions(&[&action]);
+
+        testing_env.svm.change_payer("bob");
+
+        if result.is_ok() {
+            Ok(())
+        } else {
+            Err(tm_parse_error(result).unwrap())
+        }
+    }
+
+    #[test]
+    fn halt_and_resume_market() {
+        let (mut testing_env, buy_action, sell_action) = setup_env();
+
+        set_market_status(&mut testing_env, MarketStatus::SellOnly).unwrap();
+
+        let market = testing_env
+            .svm
+            .get_parsed_account::<Market>(&testing_env.market);
+
+        assert_eq!(market.get_status(0), state::MarketStatus::SellOnly);
+
+        let result = testing_env.svm.execute_actions(&[&buy_action]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::MarketNotTrading
+        );
+
+        let result = testing_env.svm.execute_actions(&[&sell_action]);
+
+        assert!(result.is_ok());
+
+        set_market_status(&mut testing_env, MarketStatus::Trading).unwrap();
+
+        let result = testing_env.svm.execute_actions(&[&buy_action]);
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn close_market() {
+        let (mut testing_env, buy_action, sell_action) = setup_env();
+
+        // Sellers get a chance to exit before the market closes
+        set_market_status(&mut testing_env, MarketStatus::SellOnly).unwrap();
+        set_market_status(&mut testing_env, MarketStatus::Closed).unwrap();
+
+        for action in [&buy_action, &sell_action] {
+            let result = testing_env.svm.execute_actions(&[action]);
+
+            assert_eq!(
+                tm_parse_error(result).unwrap(),
+                TokenMillError::MarketNotTrading
+            );
+        }
+
+        // Closing is final
+        for new_status in [
+            MarketStatus::Trading,
+            MarketStatus::SellOnly,
+            MarketStatus::Closed,
+        ] {
+            assert_eq!(
+                set_market_status(&mut testing_env, new_status),
+                Err(TokenMillError::InvalidMarketStatus)
+            );
+        }
+    }
+
+    #[rstest]
+    fn set_market_status_with_invalid_transition(
+        #[values(
+            MarketStatus::Created,
+            MarketStatus::PricesSet,
+            MarketStatus::Trading,
+            MarketStatus::Closed
+        )]
+        new_status: MarketStatus,
+    ) {
+        let (mut testing_env, _, _) = setup_env();
+
+        assert_eq!(
+            set_market_status(&mut testing_env, new_status),
+            Err(TokenMillError::InvalidMarketStatus)
+        );
+    }
+
+    #[test]
+    fn set_market_status_with_invalid_signer() {
+        let (mut testing_env, _, _) = setup_env();
+
+        let mut action = SetMarketStatusAction::new(&testing_env, MarketStatus::SellOnly);
+        action.signer = testing_env.svm.change_payer("mallory");
+
+        let result = testing_env.svm.execute_actions(&[&action]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::InvalidAuthority
+        );
+    }
+}
@@ -0,0 +1,184 @@
+use anchor_lang::prelude::*;
+
+use super::ConfigUpdate;
+use crate::{events::TokenMillOperationPauseEvent, state::PausableOperation};
+
+pub fn handler(
+    ctx: Context<ConfigUpdate>,
+    operation: PausableOperation,
+    paused: bool,
+) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+
+    config.set_paused(operation, paused);
+
+    emit_cpi!(TokenMillOperationPauseEvent {
+        config: ctx.accounts.config.key(),
+        operation,
+        paused,
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use joelana_test_utils::joelana_env::{
+        actions::token_mill::{
+            tm_parse_error, CreateMarketAction, CreateVestingPlanAction, DepositAction,
+            SetOperationPausedAction, SwapAction, TokenMillEnv, WithdrawAction,
+        },
+        PausableOperation, SwapAmountType, SwapType, TokenMillError,
+    };
+
+    use crate::state::{self, TokenMillConfig};
+
+    const STAKE_AMOUNT: u64 = 100_000_000;
+
+    fn set_operation_paused(
+        testing_env: &mut TokenMillEnv,
+        operation: PausableOperation,
+        paused: bool,
+        payer: &str,
+    ) {
+        testing_env.svm.change_payer("admin");
+
+        testing_env
+            .svm
+            .execute_actions(&[&SetOperationPausedAction::new(operation, paused)])
+            .unwrap();
+
+        testing_env.svm.change_payer(payer);
+    }
+
+    #[test]
+    fn pause_and_unpause_buys() {
+        let mut testing_env = TokenMillEnv::default();
+
+        let buy_action = SwapAction::new(
+            &testing_env,
+            SwapType::Buy,
+            SwapAmountType::ExactOutput,
+            10_000_000_000,
+            u64::MAX,
+            None,
+        );
+        let sell_action = SwapAction::new(
+            &testing_env,
+            SwapType::Sell,
+            SwapAmountType::ExactInput,
+            1_000_000_000,
+            0,
+            None,
+        );
+
+        testing_env.svm.change_payer("bob");
+        testing_env.svm.execute_actions(&[&buy_action]).unwrap();
+
+        set_operation_paused(&mut testing_env, PausableOperation::Buy, true, "bob");
+
+        let config = testing_env
+            .svm
+            .get_parsed_account::<TokenMillConfig>(&testing_env.config);
+
+        assert!(config.is_paused(state::PausableOperation::Buy));
+        assert!(!config.is_paused(state::PausableOperation::Sell));
+
+        let result = testing_env.svm.execute_actions(&[&buy_action]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::OperationPaused
+        );
+
+        let result = testing_env.svm.execute_actions(&[&sell_action]);
+
+        assert!(result.is_ok());
+
+        set_operation_paused(&mut testing_env, PausableOperation::Buy, false, "bob");
+
+        let result = testing_env.svm.execute_actions(&[&buy_action]);
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn pause_market_creation() {
+        let mut testing_env = TokenMillEnv::new().with_default_quote_token_mint();
+
+        set_operation_paused(
+            &mut testing_env,
+            PausableOperation::MarketCreation,
+            true,
+            "alice",
+        );
+
+        let mut create_market_action = CreateMarketAction::new(&testing_env);
+
+        let result = testing_env
+            .svm
+            .execute_actions(&[create_market_action.no_badge()]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::OperationPaused
+        );
+    }
+
+    #[test]
+    fn pause_staking_deposits() {
+        let mut testing_env = TokenMillEnv::default().with_staking(STAKE_AMOUNT);
+
+        let deposit_action = DepositAction::new(&testing_env, STAKE_AMOUNT / 2);
+
+        testing_env.svm.execute_actions(&[&deposit_action]).unwrap();
+
+        set_operation_paused(&mut testing_env, PausableOperation::Staking, true, "bob");
+
+        let result = testing_env.svm.execute_actions(&[&deposit_action]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::OperationPaused
+        );
+
+        // Withdrawals are never paused
+        let withdraw_action = WithdrawAction::new(&testing_env, STAKE_AMOUNT / 2);
+
+        let result = testing_env.svm.execute_actions(&[&withdraw_action]);
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn pause_vesting() {
+        let mut testing_env = TokenMillEnv::default().with_staking(STAKE_AMOUNT);
+
+        set_operation_paused(&mut testing_env, PausableOperation::Vesting, true, "bob");
+
+        let create_vesting_action =
+            CreateVestingPlanAction::new(&testing_env, STAKE_AMOUNT, 0, 300, 60);
+
+        let result = testing_env.svm.execute_actions(&[&create_vesting_action]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::OperationPaused
+        );
+    }
+
+    #[test]
+    fn set_operation_paused_with_invalid_signer() {
+        let mut testing_env = TokenMillEnv::default();
+
+        let mut action = SetOperationPausedAction::new(PausableOperation::Buy, true);
+        action.signer = testing_env.svm.change_payer("mallory");
+
+        let result = testing_env.svm.execute_actions(&[&action]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::InvalidAuthority
+        );
+    }
+}
@@ -0,0 +1,123 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    constant::MAX_BPS,
+    errors::TokenMillError,
+    events::TokenMillReferralFeeShareOverrideEvent,
+    state::{ReferralAccount, TokenMillConfig},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ReferralFeeShareOverrideUpdate<'info> {
+    #[account(has_one = authority @ TokenMillError::InvalidAuthority)]
+    pub config: Account<'info, TokenMillConfig>,
+
+    #[account(mut, has_one = config @ TokenMillError::InvalidConfigAccount)]
+    pub referral_account: Account<'info, ReferralAccount>,
+
+    pub authority: Signer<'info>,
+}
+
+/// `None` reverts the referral account to the config's `referral_fee_share`
+pub fn handler(
+    ctx: Context<ReferralFeeShareOverrideUpdate>,
+    fee_share_override: Option<u16>,
+) -> Result<()> {
+    if let Some(fee_share_override) = fee_share_override {
+        require!(
+            fee_share_override <= MAX_BPS as u16,
+            TokenMillError::InvalidFeeShare
+        );
+    }
+
+    let referral_account = &mut ctx.accounts.referral_account;
+
+    referral_account.fee_share_override = fee_share_override;
+
+    emit_cpi!(TokenMillReferralFeeShareOverrideEvent {
+        config: ctx.accounts.config.key(),
+        referral_account: ctx.accounts.referral_account.key(),
+        fee_share_override,
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{constant::MAX_BPS, ReferralAccount};
+    use joelana_test_utils::joelana_env::{
+        actions::token_mill::{tm_parse_error, SetReferralFeeShareOverrideAction, TokenMillEnv},
+        make_address, TokenMillError,
+    };
+
+    const FEE_SHARE_OVERRIDE: u16 = 8_000;
+
+    fn setup_env() -> (TokenMillEnv, SetReferralFeeShareOverrideAction) {
+        let testing_env = TokenMillEnv::new();
+
+        let action =
+            SetReferralFeeShareOverrideAction::new(make_address("carol"), Some(FEE_SHARE_OVERRIDE));
+
+        (testing_env, action)
+    }
+
+    #[test]
+    fn set_referral_fee_share_override() {
+        let (mut testing_env, mut action) = setup_env();
+
+        let result = testing_env.svm.execute_actions(&[&action]);
+
+        assert!(result.is_ok());
+
+        let referral_account = testing_env
+            .svm
+            .get_parsed_account::<ReferralAccount>(&action.referral_account);
+
+        assert_eq!(
+            referral_account.fee_share_override,
+            Some(FEE_SHARE_OVERRIDE)
+        );
+
+        action.fee_share_override = None;
+
+        testing_env.svm.execute_actions(&[&action]).unwrap();
+
+        let referral_account = testing_env
+            .svm
+            .get_parsed_account::<ReferralAccount>(&action.referral_account);
+
+        assert_eq!(referral_account.fee_share_override, None);
+    }
+
+    #[test]
+    fn set_referral_fee_share_override_with_invalid_value() {
+        let (mut testing_env, mut action) = setup_env();
+
+        action.fee_share_override = Some(MAX_BPS as u16 + 1);
+
+        let result = testing_env.svm.execute_actions(&[&action]);
+
+        assert!(result.is_err());
+
+        let error = tm_parse_error(result).unwrap();
+
+        assert_eq!(error, TokenMillError::InvalidFeeShare);
+    }
+
+    #[test]
+    fn set_referral_fee_share_override_with_invalid_signer() {
+        let (mut testing_env, mut action) = setup_env();
+
+        action.signer = testing_env.svm.change_payer("mallory");
+
+        let result = testing_env.svm.execute_actions(&[&action]);
+
+        assert!(result.is_err());
+
+        let error = tm_parse_error(result).unwrap();
+
+        assert_eq!(error, TokenMillError::InvalidAuthority);
+    }
+}
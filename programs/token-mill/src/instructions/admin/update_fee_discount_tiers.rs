@@ -0,0 +1,111 @@
+use anchor_lang::prelude::*;
+
+use super::ConfigUpdate;
+use crate::{events::TokenMillFeeDiscountTiersUpdateEvent, state::FeeDiscountTier};
+
+pub fn handler(ctx: Context<ConfigUpdate>, fee_discount_tiers: Vec<FeeDiscountTier>) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+
+    config.set_fee_discount_tiers(&fee_discount_tiers)?;
+
+    emit_cpi!(TokenMillFeeDiscountTiersUpdateEvent {
+        config: ctx.accounts.config.key(),
+        fee_discount_tiers,
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{constant::MAX_BPS, TokenMillConfig};
+    use joelana_test_utils::joelana_env::{
+        actions::token_mill::{tm_parse_error, TokenMillEnv, UpdateFeeDiscountTiersAction},
+        FeeDiscountTier, TokenMillError,
+    };
+    use rstest::rstest;
+
+    fn setup_env() -> (TokenMillEnv, UpdateFeeDiscountTiersAction) {
+        let testing_env = TokenMillEnv::new();
+
+        let action = UpdateFeeDiscountTiersAction::new(vec![
+            FeeDiscountTier {
+                min_amount: 1_000_000,
+                discount_bps: 1_000,
+            },
+            FeeDiscountTier {
+                min_amount: 100_000_000,
+                discount_bps: 5_000,
+            },
+        ]);
+
+        (testing_env, action)
+    }
+
+    #[test]
+    fn update_fee_discount_tiers() {
+        let (mut testing_env, action) = setup_env();
+
+        let result = testing_env.svm.execute_actions(&[&action]);
+
+        assert!(result.is_ok());
+
+        let config = testing_env
+            .svm
+            .get_parsed_account::<TokenMillConfig>(&action.config);
+
+        let fee_discount_tiers = config
+            .fee_discount_tiers
+            .iter()
+            .map(|tier| (tier.min_amount, tier.discount_bps))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            fee_discount_tiers,
+            [(1_000_000, 1_000), (100_000_000, 5_000), (0, 0), (0, 0)]
+        );
+
+        assert_eq!(config.get_fee_discount_bps(999_999), 0);
+        assert_eq!(config.get_fee_discount_bps(1_000_000), 1_000);
+        assert_eq!(config.get_fee_discount_bps(u64::MAX), 5_000);
+    }
+
+    #[rstest]
+    #[case(1_000_000, 6_000)]
+    #[case(200_000_000, 500)]
+    #[case(200_000_000, MAX_BPS as u16 + 1)]
+    fn update_fee_discount_tiers_with_invalid_tiers(
+        #[case] min_amount: u64,
+        #[case] discount_bps: u16,
+    ) {
+        let (mut testing_env, mut action) = setup_env();
+
+        action.fee_discount_tiers.push(FeeDiscountTier {
+            min_amount,
+            discount_bps,
+        });
+
+        let result = testing_env.svm.execute_actions(&[&action]);
+
+        assert!(result.is_err());
+
+        let error = tm_parse_error(result).unwrap();
+
+        assert_eq!(error, TokenMillError::InvalidFeeDiscountTiers);
+    }
+
+    #[test]
+    fn update_fee_discount_tiers_with_invalid_signer() {
+        let (mut testing_env, mut action) = setup_env();
+
+        action.signer = testing_env.svm.change_payer("mallory");
+
+        let result = testing_env.svm.execute_actions(&[&action]);
+
+        assert!(result.is_err());
+
+        let error = tm_parse_error(result).unwrap();
+
+        assert_eq!(error, TokenMillError::InvalidAuthority);
+    }
+}
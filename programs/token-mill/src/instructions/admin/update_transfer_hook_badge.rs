@@ -0,0 +1,109 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::TokenMillError,
+    events::TokenMillTransferHookBadgeEvent,
+    state::{TokenMillConfig, TransferHookBadge, TransferHookBadgeStatus},
+    TRANSFER_HOOK_BADGE_PDA_SEED,
+};
+
+/// Disabling a badge prevents new markets from using the hook, existing mints keep it
+#[event_cpi]
+#[derive(Accounts)]
+pub struct UpdateTransferHookBadge<'info> {
+    #[account(has_one = authority @ TokenMillError::InvalidAuthority)]
+    pub config: Account<'info, TokenMillConfig>,
+
+    #[account(
+        mut,
+        seeds = [
+            TRANSFER_HOOK_BADGE_PDA_SEED.as_bytes(),
+            config.key().as_ref(),
+            transfer_hook_program.key().as_ref(),
+        ],
+        bump = transfer_hook_badge.bump,
+    )]
+    pub transfer_hook_badge: Account<'info, TransferHookBadge>,
+
+    /// CHECK: Only used to derive the badge address
+    pub transfer_hook_program: UncheckedAccount<'info>,
+
+    pub authority: Signer<'info>,
+}
+
+pub fn handler(
+    ctx: Context<UpdateTransferHookBadge>,
+    status: TransferHookBadgeStatus,
+) -> Result<()> {
+    let transfer_hook_badge = &mut ctx.accounts.transfer_hook_badge;
+
+    transfer_hook_badge.status = status;
+
+    emit_cpi!(TokenMillTransferHookBadgeEvent {
+        config: ctx.accounts.config.key(),
+        transfer_hook_program: ctx.accounts.transfer_hook_program.key(),
+        transfer_hook_badge_status: transfer_hook_badge.status,
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{TransferHookBadge, TransferHookBadgeStatus};
+    use joelana_test_utils::joelana_env::{
+        actions::token_mill::{
+            tm_parse_error, CreateTransferHookBadgeAction, TokenMillEnv,
+            UpdateTransferHookBadgeAction,
+        },
+        TokenMillError,
+    };
+
+    fn setup_env() -> (TokenMillEnv, UpdateTransferHookBadgeAction) {
+        let mut testing_env = TokenMillEnv::new();
+
+        testing_env
+            .svm
+            .execute_actions(&[&CreateTransferHookBadgeAction::new()])
+            .unwrap();
+
+        let action = UpdateTransferHookBadgeAction::new(
+            joelana_test_utils::joelana_env::TransferHookBadgeStatus::Disabled,
+        );
+
+        (testing_env, action)
+    }
+
+    #[test]
+    fn update_transfer_hook_badge() {
+        let (mut testing_env, action) = setup_env();
+
+        let result = testing_env.svm.execute_actions(&[&action]);
+
+        assert!(result.is_ok());
+
+        let transfer_hook_badge = testing_env
+            .svm
+            .get_parsed_account::<TransferHookBadge>(&action.transfer_hook_badge);
+
+        assert_eq!(
+            transfer_hook_badge.status,
+            TransferHookBadgeStatus::Disabled
+        );
+    }
+
+    #[test]
+    fn update_transfer_hook_badge_with_invalid_signer() {
+        let (mut testing_env, mut action) = setup_env();
+
+        action.signer = testing_env.svm.change_payer("mallory");
+
+        let result = testing_env.svm.execute_actions(&[&action]);
+
+        assert!(result.is_err());
+
+        let error = tm_parse_error(result).unwrap();
+
+        assert_eq!(error, TokenMillError::InvalidAuthority);
+    }
+}
@@ -0,0 +1,240 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::TokenMillError,
+    state::{LaunchAllocation, Market, LAUNCH_ALLOCATION_PDA_SEED},
+};
+
+#[derive(Accounts)]
+pub struct CreateLaunchAllocation<'info> {
+    pub market: AccountLoader<'info, Market>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + LaunchAllocation::INIT_SPACE,
+        seeds = [LAUNCH_ALLOCATION_PDA_SEED.as_bytes(), market.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub launch_allocation: Account<'info, LaunchAllocation>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<CreateLaunchAllocation>) -> Result<()> {
+    let market = ctx.accounts.market.load()?;
+
+    // Only markets with a launch window or a presale cap buys per wallet
+    if market.launch_protection.window == 0 && market.presale.end_ts == 0 {
+        return Err(TokenMillError::InvalidMarket.into());
+    }
+
+    ctx.accounts.launch_allocation.initialize(
+        ctx.bumps.launch_allocation,
+        ctx.accounts.market.key(),
+        ctx.accounts.user.key(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use joelana_test_utils::joelana_env::{
+        actions::token_mill::{
+            tm_parse_error, CreateLaunchAllocationAction, CreateMarketAction, SwapAction,
+            TokenMillEnv,
+        },
+        LaunchProtection, SwapAmountType, SwapType, TokenMillError,
+    };
+    use rstest::rstest;
+
+    use crate::{constant::MAX_LAUNCH_WINDOW, state::LaunchAllocation, Market};
+
+    const LAUNCH_WINDOW: i64 = 60;
+    const MAX_BUY_PER_WALLET: u64 = 10_000_000_000;
+
+    fn setup_env() -> TokenMillEnv {
+        let mut testing_env = TokenMillEnv::new()
+            .with_default_quote_token_mint()
+            .with_launch_protection(LAUNCH_WINDOW, MAX_BUY_PER_WALLET)
+            .with_default_market();
+
+        testing_env.svm.change_payer("bob");
+
+        testing_env
+    }
+
+    fn buy_action(testing_env: &TokenMillEnv, amount: u64) -> SwapAction {
+        SwapAction::new(
+            testing_env,
+            SwapType::Buy,
+            SwapAmountType::ExactOutput,
+            amount,
+            u64::MAX,
+            None,
+        )
+    }
+
+    #[test]
+    fn create_launch_allocation() {
+        let mut testing_env = setup_env();
+
+        let market = testing_env
+            .svm
+            .get_parsed_account::<Market>(&testing_env.market);
+
+        assert_eq!(market.launch_protection.window, LAUNCH_WINDOW);
+        assert_eq!(
+            market.launch_protection.max_buy_per_wallet,
+            MAX_BUY_PER_WALLET
+        );
+        assert_eq!(market.launch_protection.end_ts, LAUNCH_WINDOW);
+
+        let action = CreateLaunchAllocationAction::new(&testing_env);
+
+        testing_env.svm.execute_actions(&[&action]).unwrap();
+
+        let launch_allocation = testing_env
+            .svm
+            .get_parsed_account::<LaunchAllocation>(&action.launch_allocation);
+
+        assert_eq!(launch_allocation.market, testing_env.market);
+        assert_eq!(launch_allocation.user, action.signer);
+        assert_eq!(launch_allocation.amount_bought, 0);
+    }
+
+    #[test]
+    fn create_launch_allocation_without_launch_protection() {
+        let mut testing_env = TokenMillEnv::default();
+        testing_env.svm.change_payer("bob");
+
+        let action = CreateLaunchAllocationAction::new(&testing_env);
+
+        let result = testing_env.svm.execute_actions(&[&action]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::InvalidMarket
+        );
+    }
+
+    #[test]
+    fn buy_during_launch_window() {
+        let mut testing_env = setup_env();
+
+        // Buying requires a launch allocation during the window
+        let result = testing_env
+            .svm
+            .execute_actions(&[&buy_action(&testing_env, MAX_BUY_PER_WALLET)]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::MissingLaunchAllocation
+        );
+
+        let create_action = CreateLaunchAllocationAction::new(&testing_env);
+
+        testing_env.svm.execute_actions(&[&create_action]).unwrap();
+
+        let capped_buy_action =
+            buy_action(&testing_env, MAX_BUY_PER_WALLET / 2).with_launch_allocation();
+
+        testing_env
+            .svm
+            .execute_actions(&[&capped_buy_action, &capped_buy_action])
+            .unwrap();
+
+        let launch_allocation = testing_env
+            .svm
+            .get_parsed_account::<LaunchAllocation>(&create_action.launch_allocation);
+
+        assert_eq!(launch_allocation.amount_bought, MAX_BUY_PER_WALLET);
+
+        let result = testing_env.svm.execute_actions(&[&capped_buy_action]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::LaunchAllocationExceeded
+        );
+
+        // Sells aren't capped
+        let sell_action = SwapAction::new(
+            &testing_env,
+            SwapType::Sell,
+            SwapAmountType::ExactInput,
+            MAX_BUY_PER_WALLET / 2,
+            0,
+            None,
+        );
+
+        testing_env.svm.execute_actions(&[&sell_action]).unwrap();
+
+        // Buys are uncapped once the window ends
+        testing_env.svm.warp(LAUNCH_WINDOW);
+
+        let result = testing_env
+            .svm
+            .execute_actions(&[&buy_action(&testing_env, MAX_BUY_PER_WALLET * 2)]);
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn launch_window_starts_with_trading() {
+        let trading_start_ts = 1_000;
+
+        let mut testing_env = TokenMillEnv::new()
+            .with_default_quote_token_mint()
+            .with_trading_start(trading_start_ts)
+            .with_launch_protection(LAUNCH_WINDOW, MAX_BUY_PER_WALLET)
+            .with_default_market();
+
+        let market = testing_env
+            .svm
+            .get_parsed_account::<Market>(&testing_env.market);
+
+        assert_eq!(
+            market.launch_protection.end_ts,
+            trading_start_ts + LAUNCH_WINDOW
+        );
+
+        testing_env.svm.change_payer("bob");
+        testing_env.svm.warp(trading_start_ts + LAUNCH_WINDOW - 1);
+
+        let result = testing_env
+            .svm
+            .execute_actions(&[&buy_action(&testing_env, MAX_BUY_PER_WALLET)]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::MissingLaunchAllocation
+        );
+    }
+
+    #[rstest]
+    #[case(0, MAX_BUY_PER_WALLET)]
+    #[case(MAX_LAUNCH_WINDOW + 1, MAX_BUY_PER_WALLET)]
+    #[case(LAUNCH_WINDOW, 0)]
+    fn create_market_with_invalid_launch_protection(
+        #[case] window: i64,
+        #[case] max_buy_per_wallet: u64,
+    ) {
+        let mut testing_env = TokenMillEnv::new().with_default_quote_token_mint();
+        testing_env.svm.change_payer("alice");
+
+        let mut action = CreateMarketAction::new(&testing_env);
+        action.launch_protection = Some(LaunchProtection {
+            window,
+            max_buy_per_wallet,
+        });
+
+        let result = testing_env.svm.execute_actions(&[action.no_badge()]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::InvalidLaunchProtection
+        );
+    }
+}
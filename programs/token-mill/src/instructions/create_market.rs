@@ -6,7 +6,8 @@ use anchor_spl::{
     associated_token::AssociatedToken,
     token_2022::{spl_token_2022::instruction::AuthorityType, Token2022},
     token_interface::{
-        self, token_metadata_initialize, Mint, TokenAccount, TokenMetadataInitialize,
+        self, token_metadata_initialize, transfer_hook_update, Mint, TokenAccount,
+        TokenMetadataInitialize, TransferHookUpdate,
     },
 };
 
@@ -15,12 +16,26 @@ use crate::{
     errors::TokenMillError,
     events::TokenMillMarketCreationEvent,
     manager::token_manager::check_mint_extensions,
-    state::{Market, TokenMillConfig},
+    state::{
+        LaunchProtection, Market, PausableOperation, TokenMillConfig, TransferHookBadge,
+        TransferHookBadgeStatus, TRANSFER_HOOK_BADGE_PDA_SEED,
+    },
     QuoteTokenBadge, QuoteTokenBadgeStatus, MARKET_PDA_SEED, QUOTE_TOKEN_BADGE_PDA_SEED,
 };
 
 #[event_cpi]
 #[derive(Accounts)]
+#[instruction(
+    name: String,
+    symbol: String,
+    uri: String,
+    total_supply: u64,
+    creator_fee_share: u16,
+    staking_fee_share: u16,
+    trading_start_ts: Option<i64>,
+    launch_protection: Option<LaunchProtection>,
+    transfer_hook_program_id: Option<Pubkey>,
+)]
 pub struct CreateMarket<'info> {
     pub config: Account<'info, TokenMillConfig>,
 
@@ -41,6 +56,7 @@ pub struct CreateMarket<'info> {
         mint::decimals = MILL_TOKEN_DECIMALS,
         extensions::metadata_pointer::authority = market,
         extensions::metadata_pointer::metadata_address = base_token_mint,
+        extensions::transfer_hook::authority = market,
     )]
     pub base_token_mint: Box<InterfaceAccount<'info, Mint>>,
 
@@ -64,6 +80,18 @@ pub struct CreateMarket<'info> {
     )]
     pub quote_token_badge: Option<Account<'info, QuoteTokenBadge>>,
 
+    /// Required when the base token has a transfer hook
+    #[account(
+        seeds = [
+            TRANSFER_HOOK_BADGE_PDA_SEED.as_bytes(),
+            config.key().as_ref(),
+            transfer_hook_program_id.unwrap_or_default().as_ref(),
+        ],
+        bump = transfer_hook_badge.bump,
+        constraint = transfer_hook_badge.status == TransferHookBadgeStatus::Enabled @ TokenMillError::InvalidTransferHookBadge,
+    )]
+    pub transfer_hook_badge: Option<Account<'info, TransferHookBadge>>,
+
     pub quote_token_mint: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(mut)]
@@ -74,6 +102,7 @@ pub struct CreateMarket<'info> {
     pub associated_token_program: Program<'info, AssociatedToken>,
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn handler(
     ctx: Context<CreateMarket>,
     name: String,
@@ -82,20 +111,34 @@ pub fn handler(
     total_supply: u64,
     creator_fee_share: u16,
     staking_fee_share: u16,
+    trading_start_ts: Option<i64>,
+    launch_protection: Option<LaunchProtection>,
+    transfer_hook_program_id: Option<Pubkey>,
 ) -> Result<()> {
     let config = &ctx.accounts.config;
 
+    config.check_not_paused(PausableOperation::MarketCreation)?;
+
     require_eq!(
         creator_fee_share + staking_fee_share + config.default_protocol_fee_share,
         MAX_BPS as u16,
         TokenMillError::InvalidFeeShare
     );
 
+    // Quote tokens with a transfer fee must be badged
     require!(
-        check_mint_extensions(&ctx.accounts.quote_token_mint)?,
+        check_mint_extensions(
+            &ctx.accounts.quote_token_mint,
+            ctx.accounts.quote_token_badge.is_some()
+        )?,
         TokenMillError::UnsupportedTokenMint
     );
 
+    require!(
+        transfer_hook_program_id.is_none() || ctx.accounts.transfer_hook_badge.is_some(),
+        TokenMillError::InvalidTransferHookBadge
+    );
+
     {
         let mut market = ctx.accounts.market.load_init()?;
 
@@ -109,6 +152,8 @@ pub fn handler(
             total_supply,
             creator_fee_share,
             staking_fee_share,
+            trading_start_ts,
+            launch_protection,
         )?;
     }
 
@@ -122,6 +167,9 @@ pub fn handler(
     ctx.accounts
         .initialize_token_metadata(name, symbol, uri, &market_seeds)?;
 
+    ctx.accounts
+        .set_transfer_hook(transfer_hook_program_id, &market_seeds)?;
+
     ctx.accounts
         .mint_supply_and_remove_authority(total_supply, &market_seeds)?;
 
@@ -136,12 +184,54 @@ pub fn handler(
         referral_fee_share: config.referral_fee_share,
         creator_fee_share,
         staking_fee_share,
+        trading_start_ts: trading_start_ts.unwrap_or_default(),
+        launch_protection,
+        transfer_hook_program_id,
     });
 
     Ok(())
 }
 
 impl<'info> CreateMarket<'info> {
+    /// The hook program is set once at creation, the extension authority is then removed
+    fn set_transfer_hook(
+        &self,
+        transfer_hook_program_id: Option<Pubkey>,
+        market_seeds: &[&[u8]],
+    ) -> Result<()> {
+        if transfer_hook_program_id.is_some() {
+            let cpi_accounts = TransferHookUpdate {
+                token_program_id: self.token_program.to_account_info(),
+                mint: self.base_token_mint.to_account_info(),
+                authority: self.market.to_account_info(),
+            };
+
+            transfer_hook_update(
+                CpiContext::new_with_signer(
+                    self.token_program.to_account_info(),
+                    cpi_accounts,
+                    &[market_seeds],
+                ),
+                transfer_hook_program_id,
+            )?;
+        }
+
+        let cpi_accounts = token_interface::SetAuthority {
+            account_or_mint: self.base_token_mint.to_account_info(),
+            current_authority: self.market.to_account_info(),
+        };
+
+        token_interface::set_authority(
+            CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                cpi_accounts,
+                &[market_seeds],
+            ),
+            AuthorityType::TransferHookProgramId,
+            None,
+        )
+    }
+
     fn initialize_token_metadata(
         &self,
         name: String,
@@ -229,15 +319,24 @@ impl<'info> CreateMarket<'info> {
 #[cfg(test)]
 mod tests {
     use crate::{
-        constant::{INTERVAL_NUMBER, MAX_TOTAL_SUPPLY},
+        constant::{BASE_PRECISION, MAX_TOTAL_SUPPLY},
         Market,
     };
+    use anchor_lang::Id;
+    use anchor_spl::{
+        metadata::Metadata,
+        token_2022::spl_token_2022::{
+            extension::{transfer_hook, StateWithExtensions},
+            state::Mint,
+        },
+    };
     use joelana_test_utils::joelana_env::{
         actions::token_mill::{
-            tm_parse_error, CreateMarketAction, CreateQuoteAssetBadgeAction, TokenMillEnv,
-            UpdateQuoteAssetBadgeAction, DEFAULT_TOTAL_SUPPLY,
+            tm_parse_error, CreateMarketAction, CreateQuoteAssetBadgeAction,
+            CreateTransferHookBadgeAction, TokenMillEnv, UpdateQuoteAssetBadgeAction,
+            UpdateTransferHookBadgeAction, DEFAULT_TOTAL_SUPPLY,
         },
-        TokenMillError, TokenType,
+        TokenMillError, TokenType, TransferHookBadgeStatus,
     };
     use rstest::rstest;
 
@@ -322,8 +421,7 @@ mod tests {
 
     #[rstest]
     fn create_market_with_invalid_supply(
-        #[values(10 * INTERVAL_NUMBER, MAX_TOTAL_SUPPLY + INTERVAL_NUMBER, DEFAULT_TOTAL_SUPPLY + 1)]
-        total_supply: u64,
+        #[values(BASE_PRECISION - 1, MAX_TOTAL_SUPPLY + 1)] total_supply: u64,
     ) {
         let (mut testing_env, mut action) = setup_env(TokenType::Token, 6);
 
@@ -337,4 +435,120 @@ mod tests {
 
         assert_eq!(error, TokenMillError::InvalidTotalSupply);
     }
+
+    #[test]
+    fn create_market_with_transfer_fee_quote_token() {
+        let mut testing_env = TokenMillEnv::new().with_transfer_fee_quote_token_mint(9, 100, 1_000);
+        testing_env.svm.change_payer("alice");
+
+        let mut action = CreateMarketAction::new(&testing_env);
+
+        // Quote tokens with a transfer fee must be badged
+        let result = testing_env.svm.execute_actions(&[action.no_badge()]);
+
+        assert!(result.is_err());
+
+        let error = tm_parse_error(result).unwrap();
+
+        assert_eq!(error, TokenMillError::UnsupportedTokenMint);
+
+        let action = CreateMarketAction::new(&testing_env);
+
+        let result = testing_env.svm.execute_actions(&[&action]);
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn create_market_with_transfer_hook() {
+        let (mut testing_env, mut action) = setup_env(TokenType::Token, 6);
+
+        let transfer_hook_program = mock_transfer_hook::ID;
+
+        action.with_transfer_hook(transfer_hook_program);
+        action.transfer_hook_badge = crate::ID;
+
+        // The hook program must be badged
+        let result = testing_env.svm.execute_actions(&[&action]);
+
+        assert!(result.is_err());
+
+        let error = tm_parse_error(result).unwrap();
+
+        assert_eq!(error, TokenMillError::InvalidTransferHookBadge);
+
+        testing_env.svm.change_payer("admin");
+
+        testing_env
+            .svm
+            .execute_actions(&[&CreateTransferHookBadgeAction::new()])
+            .unwrap();
+
+        testing_env.svm.change_payer("alice");
+
+        let result = testing_env
+            .svm
+            .execute_actions(&[action.with_transfer_hook(transfer_hook_program)]);
+
+        assert!(result.is_ok());
+
+        let mint_account = testing_env.svm.get_account(&action.base_token_mint);
+        let mint = StateWithExtensions::<Mint>::unpack(&mint_account.data).unwrap();
+
+        assert_eq!(
+            transfer_hook::get_program_id(&mint),
+            Some(transfer_hook_program)
+        );
+    }
+
+    #[test]
+    fn create_market_with_disabled_transfer_hook_badge() {
+        let (mut testing_env, mut action) = setup_env(TokenType::Token, 6);
+
+        testing_env.svm.change_payer("admin");
+
+        testing_env
+            .svm
+            .execute_actions(&[
+                &CreateTransferHookBadgeAction::new(),
+                &UpdateTransferHookBadgeAction::new(TransferHookBadgeStatus::Disabled),
+            ])
+            .unwrap();
+
+        action.signer = testing_env.svm.change_payer("alice");
+
+        let result = testing_env
+            .svm
+            .execute_actions(&[action.with_transfer_hook(mock_transfer_hook::ID)]);
+
+        assert!(result.is_err());
+
+        let error = tm_parse_error(result).unwrap();
+
+        assert_eq!(error, TokenMillError::InvalidTransferHookBadge);
+    }
+
+    #[test]
+    fn create_market_with_badge_of_other_transfer_hook() {
+        let (mut testing_env, mut action) = setup_env(TokenType::Token, 6);
+
+        testing_env.svm.change_payer("admin");
+
+        let create_badge_action = CreateTransferHookBadgeAction::new();
+
+        testing_env
+            .svm
+            .execute_actions(&[&create_badge_action])
+            .unwrap();
+
+        action.signer = testing_env.svm.change_payer("alice");
+
+        // The badge of the mock hook doesn't allow another hook program
+        action.with_transfer_hook(Metadata::id());
+        action.transfer_hook_badge = create_badge_action.transfer_hook_badge;
+
+        let result = testing_env.svm.execute_actions(&[&action]);
+
+        assert!(result.is_err());
+    }
 }
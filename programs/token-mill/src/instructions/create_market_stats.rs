@@ -0,0 +1,137 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::TokenMillError,
+    state::{Market, MarketStats, TokenMillConfig, MARKET_STATS_PDA_SEED},
+};
+
+#[derive(Accounts)]
+pub struct CreateMarketStats<'info> {
+    pub config: Account<'info, TokenMillConfig>,
+
+    #[account(mut, has_one = config @ TokenMillError::InvalidConfigAccount)]
+    pub market: AccountLoader<'info, Market>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + MarketStats::INIT_SPACE,
+        seeds = [MARKET_STATS_PDA_SEED.as_bytes(), market.key().as_ref()],
+        bump
+    )]
+    pub market_stats: Account<'info, MarketStats>,
+
+    /// Market creator or config authority, as every swap then pays for the stats update
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<CreateMarketStats>) -> Result<()> {
+    let market = &mut ctx.accounts.market.load_mut()?;
+    let payer = ctx.accounts.payer.key();
+
+    require!(
+        payer == market.creator || payer == ctx.accounts.config.authority,
+        TokenMillError::InvalidAuthority
+    );
+
+    ctx.accounts.market_stats.initialize(
+        ctx.bumps.market_stats,
+        ctx.accounts.market.key(),
+        market,
+    )?;
+
+    market.has_stats = 1;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        manager::swap_manager,
+        state::{Market, MarketStats},
+    };
+    use joelana_test_utils::joelana_env::{
+        actions::token_mill::{tm_parse_error, CreateMarketStatsAction, SwapAction, TokenMillEnv},
+        SwapAmountType, SwapType, TokenMillError,
+    };
+
+    #[test]
+    fn create_market_stats() {
+        let mut testing_env = TokenMillEnv::default();
+        testing_env.svm.change_payer("bob");
+
+        let swap_action = SwapAction::new(
+            &testing_env,
+            SwapType::Buy,
+            SwapAmountType::ExactOutput,
+            10_000_000_000,
+            u64::MAX,
+            None,
+        );
+
+        testing_env.svm.execute_actions(&[&swap_action]).unwrap();
+
+        testing_env.svm.change_payer("alice");
+
+        let create_market_stats_action = CreateMarketStatsAction::new(&testing_env);
+
+        testing_env
+            .svm
+            .execute_actions(&[&create_market_stats_action])
+            .unwrap();
+
+        let market = testing_env
+            .svm
+            .get_parsed_account::<Market>(&testing_env.market);
+        let market_stats = testing_env
+            .svm
+            .get_parsed_account::<MarketStats>(&create_market_stats_action.market_stats);
+
+        // Stats start from the current state of the market
+        assert!(market.are_stats_enabled());
+        assert_eq!(market_stats.market, testing_env.market);
+        assert_eq!(market_stats.trade_count, 0);
+        assert_eq!(market_stats.buy_base_volume, 0);
+        assert_eq!(market_stats.max_circulating_supply, 10_000_000_000);
+        assert_eq!(
+            market_stats.max_circulating_supply_ask_price,
+            market.get_spot_price(swap_manager::SwapType::Buy).unwrap()
+        );
+        assert_eq!(market_stats.last_trade_ts, 0);
+
+        let result = testing_env
+            .svm
+            .execute_actions(&[&create_market_stats_action]);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn create_market_stats_with_invalid_signer() {
+        let mut testing_env = TokenMillEnv::default();
+
+        let mut create_market_stats_action = CreateMarketStatsAction::new(&testing_env);
+        create_market_stats_action.signer = testing_env.svm.change_payer("mallory");
+
+        let result = testing_env
+            .svm
+            .execute_actions(&[&create_market_stats_action]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::InvalidAuthority
+        );
+
+        // The config authority can also create them
+        create_market_stats_action.signer = testing_env.svm.change_payer("admin");
+
+        testing_env
+            .svm
+            .execute_actions(&[&create_market_stats_action])
+            .unwrap();
+    }
+}
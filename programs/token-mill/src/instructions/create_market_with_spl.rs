@@ -17,7 +17,7 @@ use crate::{
     errors::TokenMillError,
     events::TokenMillMarketCreationEvent,
     manager::token_manager::check_mint_extensions,
-    state::{Market, TokenMillConfig},
+    state::{LaunchProtection, Market, PausableOperation, TokenMillConfig},
     QuoteTokenBadge, QuoteTokenBadgeStatus, MARKET_PDA_SEED, QUOTE_TOKEN_BADGE_PDA_SEED,
 };
 
@@ -78,6 +78,7 @@ pub struct CreateMarketWithSpl<'info> {
     pub associated_token_program: Program<'info, AssociatedToken>,
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn handler(
     ctx: Context<CreateMarketWithSpl>,
     name: String,
@@ -86,17 +87,25 @@ pub fn handler(
     total_supply: u64,
     creator_fee_share: u16,
     staking_fee_share: u16,
+    trading_start_ts: Option<i64>,
+    launch_protection: Option<LaunchProtection>,
 ) -> Result<()> {
     let config = &ctx.accounts.config;
 
+    config.check_not_paused(PausableOperation::MarketCreation)?;
+
     require_eq!(
         creator_fee_share + staking_fee_share + config.default_protocol_fee_share,
         MAX_BPS as u16,
         TokenMillError::InvalidFeeShare
     );
 
+    // Quote tokens with a transfer fee must be badged
     require!(
-        check_mint_extensions(&ctx.accounts.quote_token_mint)?,
+        check_mint_extensions(
+            &ctx.accounts.quote_token_mint,
+            ctx.accounts.quote_token_badge.is_some()
+        )?,
         TokenMillError::UnsupportedTokenMint
     );
 
@@ -113,6 +122,8 @@ pub fn handler(
             total_supply,
             creator_fee_share,
             staking_fee_share,
+            trading_start_ts,
+            launch_protection,
         )?;
     }
 
@@ -140,6 +151,9 @@ pub fn handler(
         referral_fee_share: config.referral_fee_share,
         creator_fee_share,
         staking_fee_share,
+        trading_start_ts: trading_start_ts.unwrap_or_default(),
+        launch_protection,
+        transfer_hook_program_id: None,
     });
 
     Ok(())
@@ -228,7 +242,7 @@ impl<'info> CreateMarketWithSpl<'info> {
 #[cfg(test)]
 mod tests {
     use crate::{
-        constant::{INTERVAL_NUMBER, MAX_TOTAL_SUPPLY},
+        constant::{BASE_PRECISION, MAX_TOTAL_SUPPLY},
         Market,
     };
     use joelana_test_utils::joelana_env::{
@@ -321,8 +335,7 @@ mod tests {
 
     #[rstest]
     fn create_market_with_invalid_supply(
-        #[values(10 * INTERVAL_NUMBER, MAX_TOTAL_SUPPLY + INTERVAL_NUMBER, DEFAULT_TOTAL_SUPPLY + 1)]
-        total_supply: u64,
+        #[values(BASE_PRECISION - 1, MAX_TOTAL_SUPPLY + 1)] total_supply: u64,
     ) {
         let (mut testing_env, mut action) = setup_env(TokenType::Token, 6);
 
@@ -1,7 +1,12 @@
 pub mod claim_creator_fees;
+pub mod set_market_buy_tax;
+pub mod set_market_graduation;
+pub mod set_market_presale;
 pub mod set_market_prices;
+pub mod set_market_virtual_reserves;
 pub mod update_creator;
 pub mod update_market_fee_shares;
+pub mod update_market_status;
 
 pub use claim_creator_fees::*;
 pub use set_market_prices::*;
@@ -0,0 +1,234 @@
+use anchor_lang::prelude::*;
+
+use crate::{events::TokenMillBuyTaxSetEvent, state::BuyTaxRecipient};
+
+use super::MarketSettingsUpdate;
+
+pub fn handler(
+    ctx: Context<MarketSettingsUpdate>,
+    start_bps: u16,
+    duration: i64,
+    recipient: BuyTaxRecipient,
+) -> Result<()> {
+    let market = &mut ctx.accounts.market.load_mut()?;
+
+    market.set_buy_tax(start_bps, duration, recipient)?;
+
+    emit_cpi!(TokenMillBuyTaxSetEvent {
+        market: ctx.accounts.market.key(),
+        start_bps,
+        duration,
+        recipient,
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::AnchorDeserialize;
+    use joelana_test_utils::{
+        joelana_env::{
+            actions::token_mill::{
+                tm_parse_error, CreateMarketAction, CreateQuoteAssetBadgeAction, QuoteSwapAction,
+                SetMarketBuyTaxAction, SetMarketPricesAction, SwapAction, TokenMillEnv,
+            },
+            BuyTaxRecipient, SwapAmountType, SwapType, TokenMillError,
+        },
+        utils::token_mill::curve_generator::Curve,
+    };
+    use rstest::rstest;
+
+    use crate::{
+        constant::{MAX_BUY_TAX_BPS, MAX_BUY_TAX_DURATION},
+        instructions::SwapQuote,
+        Market,
+    };
+
+    const START_BPS: u16 = 2_000;
+    const DURATION: i64 = 3_600;
+
+    fn setup_env() -> (TokenMillEnv, SetMarketBuyTaxAction) {
+        let mut testing_env = TokenMillEnv::new().with_default_quote_token_mint();
+
+        let action = CreateQuoteAssetBadgeAction::new(testing_env.quote_token_mint.unwrap());
+
+        testing_env.svm.execute_actions(&[&action]).unwrap();
+
+        testing_env.svm.change_payer("alice");
+
+        let create_market_action = CreateMarketAction::new(&testing_env);
+
+        testing_env
+            .svm
+            .execute_actions(&[&create_market_action])
+            .unwrap();
+
+        let action = SetMarketBuyTaxAction::new(START_BPS, DURATION, BuyTaxRecipient::Stakers);
+
+        (testing_env, action)
+    }
+
+    #[test]
+    fn set_market_buy_tax() {
+        let (mut testing_env, action) = setup_env();
+
+        testing_env.svm.warp(100);
+
+        testing_env
+            .svm
+            .execute_actions(&[&action, &SetMarketPricesAction::new(Curve::default())])
+            .unwrap();
+
+        let market = testing_env.svm.get_parsed_account::<Market>(&action.market);
+
+        assert_eq!(market.buy_tax.start_bps, START_BPS);
+        assert_eq!(market.buy_tax.duration, DURATION);
+        assert_eq!(
+            market.buy_tax.recipient,
+            crate::state::BuyTaxRecipient::Stakers as u8
+        );
+        // The decay starts when trading opens
+        assert_eq!(market.buy_tax.start_ts, 100);
+    }
+
+    #[rstest]
+    fn buy_tax_decays(
+        #[values(BuyTaxRecipient::Fees, BuyTaxRecipient::Stakers)] recipient: BuyTaxRecipient,
+    ) {
+        let mut testing_env = TokenMillEnv::new()
+            .with_default_quote_token_mint()
+            .with_buy_tax(START_BPS, DURATION, recipient)
+            .with_default_market();
+
+        testing_env.svm.change_payer("bob");
+
+        let amount = 1_000_000_000;
+
+        for (elapsed, buy_tax) in [(0, amount / 5), (DURATION / 2, amount / 10), (DURATION, 0)] {
+            testing_env
+                .svm
+                .warp(elapsed - testing_env.svm.get_unix_timestamp());
+
+            let action = QuoteSwapAction::new(
+                &testing_env,
+                SwapType::Buy,
+                SwapAmountType::ExactInput,
+                amount,
+            );
+
+            let result = testing_env.svm.execute_actions(&[&action]).unwrap();
+            let swap_quote = SwapQuote::try_from_slice(&result.return_data.data).unwrap();
+
+            assert_eq!(swap_quote.quote_amount, amount);
+            assert_eq!(swap_quote.buy_tax, buy_tax);
+
+            assert_eq!(
+                swap_quote.creator_fee + swap_quote.staking_fee + swap_quote.protocol_fee,
+                swap_quote.swap_fee + buy_tax
+            );
+
+            if recipient == BuyTaxRecipient::Stakers {
+                assert!(swap_quote.staking_fee >= buy_tax);
+            }
+        }
+
+        // Buyers pay the tax on top of the curve
+        testing_env.svm.warp(-DURATION);
+
+        let bob = testing_env.svm.payer;
+        let quote_token_mint = testing_env.quote_token_mint.unwrap();
+        let quote_balance_before = testing_env.svm.get_balance(&quote_token_mint, &bob);
+
+        testing_env
+            .svm
+            .execute_actions(&[&SwapAction::new(
+                &testing_env,
+                SwapType::Buy,
+                SwapAmountType::ExactInput,
+                amount,
+                0,
+                None,
+            )])
+            .unwrap();
+
+        assert_eq!(
+            quote_balance_before - testing_env.svm.get_balance(&quote_token_mint, &bob),
+            amount
+        );
+
+        let market = testing_env
+            .svm
+            .get_parsed_account::<Market>(&testing_env.market);
+
+        if recipient == BuyTaxRecipient::Stakers {
+            assert!(market.fees.pending_staking_fees >= amount / 5);
+        }
+
+        // Sells are never taxed
+
+        let action = QuoteSwapAction::new(
+            &testing_env,
+            SwapType::Sell,
+            SwapAmountType::ExactInput,
+            1_000_000,
+        );
+
+        let result = testing_env.svm.execute_actions(&[&action]).unwrap();
+        let swap_quote = SwapQuote::try_from_slice(&result.return_data.data).unwrap();
+
+        assert_eq!(swap_quote.buy_tax, 0);
+    }
+
+    #[test]
+    fn set_market_buy_tax_after_prices_set() {
+        let (mut testing_env, action) = setup_env();
+
+        testing_env
+            .svm
+            .execute_actions(&[&SetMarketPricesAction::new(Curve::default())])
+            .unwrap();
+
+        let result = testing_env.svm.execute_actions(&[&action]);
+
+        assert!(result.is_err());
+
+        let error = tm_parse_error(result).unwrap();
+
+        assert_eq!(error, TokenMillError::PricesAlreadySet);
+    }
+
+    #[rstest]
+    #[case(MAX_BUY_TAX_BPS + 1, DURATION)]
+    #[case(START_BPS, -1)]
+    #[case(START_BPS, MAX_BUY_TAX_DURATION + 1)]
+    fn set_market_buy_tax_with_invalid_parameters(#[case] start_bps: u16, #[case] duration: i64) {
+        let (mut testing_env, mut action) = setup_env();
+
+        action.start_bps = start_bps;
+        action.duration = duration;
+
+        let result = testing_env.svm.execute_actions(&[&action]);
+
+        assert!(result.is_err());
+
+        let error = tm_parse_error(result).unwrap();
+
+        assert_eq!(error, TokenMillError::InvalidBuyTax);
+    }
+
+    #[test]
+    fn set_market_buy_tax_with_invalid_signer() {
+        let (mut testing_env, mut action) = setup_env();
+
+        action.signer = testing_env.svm.change_payer("mallory");
+
+        let result = testing_env.svm.execute_actions(&[&action]);
+
+        assert!(result.is_err());
+
+        let error = tm_parse_error(result).unwrap();
+
+        assert_eq!(error, TokenMillError::InvalidAuthority);
+    }
+}
@@ -0,0 +1,123 @@
+use anchor_lang::prelude::*;
+
+use crate::events::TokenMillGraduationSetEvent;
+
+use super::MarketSettingsUpdate;
+
+pub fn handler(
+    ctx: Context<MarketSettingsUpdate>,
+    supply_threshold: u64,
+    quote_threshold: u64,
+) -> Result<()> {
+    let market = &mut ctx.accounts.market.load_mut()?;
+
+    market.set_graduation_thresholds(supply_threshold, quote_threshold)?;
+
+    emit_cpi!(TokenMillGraduationSetEvent {
+        market: ctx.accounts.market.key(),
+        supply_threshold,
+        quote_threshold,
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Market;
+    use joelana_test_utils::{
+        joelana_env::{
+            actions::token_mill::{
+                tm_parse_error, CreateMarketAction, CreateQuoteAssetBadgeAction,
+                SetMarketGraduationAction, SetMarketPricesAction, TokenMillEnv,
+                DEFAULT_TOTAL_SUPPLY,
+            },
+            TokenMillError,
+        },
+        utils::token_mill::curve_generator::Curve,
+    };
+
+    fn setup_env() -> (TokenMillEnv, SetMarketGraduationAction) {
+        let mut testing_env = TokenMillEnv::new().with_default_quote_token_mint();
+
+        let action = CreateQuoteAssetBadgeAction::new(testing_env.quote_token_mint.unwrap());
+
+        testing_env.svm.execute_actions(&[&action]).unwrap();
+
+        testing_env.svm.change_payer("alice");
+
+        let create_market_action = CreateMarketAction::new(&testing_env);
+
+        testing_env
+            .svm
+            .execute_actions(&[&create_market_action])
+            .unwrap();
+
+        let action = SetMarketGraduationAction::new(DEFAULT_TOTAL_SUPPLY / 2, 0);
+
+        (testing_env, action)
+    }
+
+    #[test]
+    fn set_market_graduation() {
+        let (mut testing_env, action) = setup_env();
+
+        let result = testing_env.svm.execute_actions(&[&action]);
+
+        assert!(result.is_ok());
+
+        let market = testing_env.svm.get_parsed_account::<Market>(&action.market);
+
+        assert_eq!(market.graduation.supply_threshold, DEFAULT_TOTAL_SUPPLY / 2);
+        assert_eq!(market.graduation.quote_threshold, 0);
+        assert!(!market.is_graduated());
+    }
+
+    #[test]
+    fn set_market_graduation_after_prices_set() {
+        let (mut testing_env, action) = setup_env();
+
+        testing_env
+            .svm
+            .execute_actions(&[&SetMarketPricesAction::new(Curve::default())])
+            .unwrap();
+
+        let result = testing_env.svm.execute_actions(&[&action]);
+
+        assert!(result.is_err());
+
+        let error = tm_parse_error(result).unwrap();
+
+        assert_eq!(error, TokenMillError::PricesAlreadySet);
+    }
+
+    #[test]
+    fn set_market_graduation_with_invalid_threshold() {
+        let (mut testing_env, mut action) = setup_env();
+
+        action.supply_threshold = DEFAULT_TOTAL_SUPPLY;
+
+        let result = testing_env.svm.execute_actions(&[&action]);
+
+        assert!(result.is_err());
+
+        let error = tm_parse_error(result).unwrap();
+
+        assert_eq!(error, TokenMillError::InvalidGraduationThreshold);
+    }
+
+    #[test]
+    fn set_market_graduation_with_invalid_signer() {
+        let (mut testing_env, mut action) = setup_env();
+
+        action.signer = testing_env.svm.change_payer("mallory");
+
+        let result = testing_env.svm.execute_actions(&[&action]);
+
+        assert!(result.is_err());
+
+        let error = tm_parse_error(result).unwrap();
+
+        assert_eq!(error, TokenMillError::InvalidAuthority);
+    }
+}
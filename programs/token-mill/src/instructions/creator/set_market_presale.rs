@@ -0,0 +1,307 @@
+use anchor_lang::prelude::*;
+
+use crate::events::TokenMillPresaleSetEvent;
+
+use super::MarketSettingsUpdate;
+
+pub fn handler(
+    ctx: Context<MarketSettingsUpdate>,
+    merkle_root: [u8; 32],
+    end_ts: i64,
+) -> Result<()> {
+    let market = &mut ctx.accounts.market.load_mut()?;
+
+    market.set_presale(merkle_root, end_ts, Clock::get()?.unix_timestamp)?;
+
+    emit_cpi!(TokenMillPresaleSetEvent {
+        market: ctx.accounts.market.key(),
+        merkle_root,
+        end_ts,
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use joelana_test_utils::{
+        joelana_env::{
+            actions::token_mill::{
+                tm_parse_error, CreateLaunchAllocationAction, CreateMarketAction,
+                CreateQuoteAssetBadgeAction, SetMarketPresaleAction, SetMarketPricesAction,
+                SwapAction, TokenMillEnv,
+            },
+            make_address, SwapAmountType, SwapType, TokenMillError, ACTORS,
+        },
+        utils::token_mill::{curve_generator::Curve, merkle_tree::MerkleTree},
+    };
+    use rstest::rstest;
+
+    use crate::{state::LaunchAllocation, Market};
+
+    const PRESALE_END_TS: i64 = 1_000;
+    const MAX_AMOUNT: u64 = 10_000_000_000;
+
+    /// Allowlists every actor but mallory, bob being capped to `bob_max_amount`
+    fn allowlist(bob_max_amount: u64) -> MerkleTree {
+        let leaves = ACTORS
+            .iter()
+            .filter(|actor| **actor != "mallory")
+            .map(|actor| {
+                let max_amount = if *actor == "bob" { bob_max_amount } else { 0 };
+
+                (make_address(actor), max_amount)
+            })
+            .collect::<Vec<_>>();
+
+        MerkleTree::new(&leaves)
+    }
+
+    fn setup_env(merkle_tree: &MerkleTree) -> TokenMillEnv {
+        let mut testing_env = TokenMillEnv::new()
+            .with_default_quote_token_mint()
+            .with_presale(merkle_tree.root(), PRESALE_END_TS)
+            .with_default_market();
+
+        testing_env.svm.change_payer("bob");
+
+        testing_env
+    }
+
+    fn buy_action(testing_env: &TokenMillEnv, amount: u64) -> SwapAction {
+        SwapAction::new(
+            testing_env,
+            SwapType::Buy,
+            SwapAmountType::ExactOutput,
+            amount,
+            u64::MAX,
+            None,
+        )
+    }
+
+    #[test]
+    fn set_market_presale() {
+        let mut testing_env = TokenMillEnv::new().with_default_quote_token_mint();
+
+        let action = CreateQuoteAssetBadgeAction::new(testing_env.quote_token_mint.unwrap());
+
+        testing_env.svm.execute_actions(&[&action]).unwrap();
+
+        testing_env.svm.change_payer("alice");
+
+        testing_env
+            .svm
+            .execute_actions(&[&CreateMarketAction::new(&testing_env)])
+            .unwrap();
+
+        let merkle_tree = allowlist(MAX_AMOUNT);
+        let action = SetMarketPresaleAction::new(merkle_tree.root(), PRESALE_END_TS);
+
+        testing_env.svm.execute_actions(&[&action]).unwrap();
+
+        let market = testing_env.svm.get_parsed_account::<Market>(&action.market);
+
+        assert_eq!(market.presale.merkle_root, merkle_tree.root());
+        assert_eq!(market.presale.end_ts, PRESALE_END_TS);
+
+        // Settings can't change once the prices are set
+        testing_env
+            .svm
+            .execute_actions(&[&SetMarketPricesAction::new(Curve::default())])
+            .unwrap();
+
+        let result = testing_env.svm.execute_actions(&[&action]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::PricesAlreadySet
+        );
+    }
+
+    #[test]
+    fn presale_buy_with_invalid_proof() {
+        let merkle_tree = allowlist(MAX_AMOUNT);
+        let mut testing_env = setup_env(&merkle_tree);
+
+        let result = testing_env
+            .svm
+            .execute_actions(&[&buy_action(&testing_env, MAX_AMOUNT)]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::MissingPresaleProof
+        );
+
+        // Proof of another allowlisted user
+        let alice_proof = merkle_tree.get_proof(&make_address("alice"));
+
+        let result = testing_env.svm.execute_actions(&[
+            &buy_action(&testing_env, MAX_AMOUNT).with_presale_proof(alice_proof)
+        ]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::InvalidPresaleProof
+        );
+
+        // Proof claiming an uncapped leaf
+        let mut forged_proof = merkle_tree.get_proof(&make_address("bob"));
+        forged_proof.max_amount = 0;
+
+        let result = testing_env.svm.execute_actions(&[
+            &buy_action(&testing_env, MAX_AMOUNT).with_presale_proof(forged_proof)
+        ]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::InvalidPresaleProof
+        );
+
+        // Mallory isn't allowlisted, no leaf matches a tree of her own
+        let mallory_tree = MerkleTree::new(&[(make_address("mallory"), 0)]);
+        let mallory_proof = mallory_tree.get_proof(&make_address("mallory"));
+
+        let mut action = buy_action(&testing_env, MAX_AMOUNT).with_presale_proof(mallory_proof);
+        action.signer = make_address("mallory");
+        action.user_base_token_ata = testing_env
+            .svm
+            .get_ata_address(&action.base_token_mint, &action.signer);
+        action.user_quote_token_ata = testing_env
+            .svm
+            .get_ata_address(&action.quote_token_mint, &action.signer);
+
+        testing_env.svm.change_payer("mallory");
+
+        let result = testing_env.svm.execute_actions(&[&action]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::InvalidPresaleProof
+        );
+    }
+
+    #[test]
+    fn presale_buy_capped() {
+        let merkle_tree = allowlist(MAX_AMOUNT);
+        let mut testing_env = setup_env(&merkle_tree);
+
+        let bob_proof = merkle_tree.get_proof(&make_address("bob"));
+
+        // Capped leaves record buys in the launch allocation
+        let result = testing_env.svm.execute_actions(&[
+            &buy_action(&testing_env, MAX_AMOUNT).with_presale_proof(bob_proof.clone())
+        ]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::MissingLaunchAllocation
+        );
+
+        let create_action = CreateLaunchAllocationAction::new(&testing_env);
+
+        testing_env.svm.execute_actions(&[&create_action]).unwrap();
+
+        let capped_buy_action = buy_action(&testing_env, MAX_AMOUNT / 2)
+            .with_launch_allocation()
+            .with_presale_proof(bob_proof);
+
+        testing_env
+            .svm
+            .execute_actions(&[&capped_buy_action, &capped_buy_action])
+            .unwrap();
+
+        let launch_allocation = testing_env
+            .svm
+            .get_parsed_account::<LaunchAllocation>(&create_action.launch_allocation);
+
+        assert_eq!(launch_allocation.amount_bought, MAX_AMOUNT);
+
+        let result = testing_env.svm.execute_actions(&[&capped_buy_action]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::LaunchAllocationExceeded
+        );
+
+        // Sells don't need a proof
+        let sell_action = SwapAction::new(
+            &testing_env,
+            SwapType::Sell,
+            SwapAmountType::ExactInput,
+            MAX_AMOUNT / 2,
+            0,
+            None,
+        );
+
+        testing_env.svm.execute_actions(&[&sell_action]).unwrap();
+
+        // Anyone can buy once the presale ends
+        testing_env.svm.warp(PRESALE_END_TS);
+
+        let result = testing_env
+            .svm
+            .execute_actions(&[&buy_action(&testing_env, MAX_AMOUNT * 2)]);
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn presale_buy_uncapped() {
+        let merkle_tree = allowlist(0);
+        let mut testing_env = setup_env(&merkle_tree);
+
+        let bob_proof = merkle_tree.get_proof(&make_address("bob"));
+
+        let result = testing_env.svm.execute_actions(&[
+            &buy_action(&testing_env, MAX_AMOUNT * 2).with_presale_proof(bob_proof)
+        ]);
+
+        assert!(result.is_ok());
+    }
+
+    #[rstest]
+    #[case([0; 32], PRESALE_END_TS)]
+    #[case([1; 32], 0)]
+    fn set_market_presale_with_invalid_parameters(
+        #[case] merkle_root: [u8; 32],
+        #[case] end_ts: i64,
+    ) {
+        let mut testing_env = TokenMillEnv::new().with_default_quote_token_mint();
+        testing_env.svm.change_payer("alice");
+
+        testing_env
+            .svm
+            .execute_actions(&[CreateMarketAction::new(&testing_env).no_badge()])
+            .unwrap();
+
+        let result = testing_env
+            .svm
+            .execute_actions(&[&SetMarketPresaleAction::new(merkle_root, end_ts)]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::InvalidPresale
+        );
+    }
+
+    #[test]
+    fn set_market_presale_with_invalid_signer() {
+        let mut testing_env = TokenMillEnv::new().with_default_quote_token_mint();
+        testing_env.svm.change_payer("alice");
+
+        testing_env
+            .svm
+            .execute_actions(&[CreateMarketAction::new(&testing_env).no_badge()])
+            .unwrap();
+
+        let mut action = SetMarketPresaleAction::new([1; 32], PRESALE_END_TS);
+        action.signer = testing_env.svm.change_payer("mallory");
+
+        let result = testing_env.svm.execute_actions(&[&action]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::InvalidAuthority
+        );
+    }
+}
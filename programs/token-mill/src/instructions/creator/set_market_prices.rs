@@ -1,8 +1,9 @@
 use anchor_lang::prelude::*;
 
 use crate::{
-    constant::PRICES_LENGTH, errors::TokenMillError, events::TokenMillMarketPriceSetEvent,
-    state::Market,
+    errors::TokenMillError,
+    events::TokenMillMarketPriceSetEvent,
+    state::{Market, PriceInterpolation},
 };
 
 #[event_cpi]
@@ -16,17 +17,27 @@ pub struct MarketSettingsUpdate<'info> {
 
 pub fn handler(
     ctx: Context<MarketSettingsUpdate>,
-    bid_prices: [u64; PRICES_LENGTH],
-    ask_prices: [u64; PRICES_LENGTH],
+    bid_prices: Vec<u64>,
+    ask_prices: Vec<u64>,
+    supply_breakpoints: Vec<u64>,
+    price_interpolation: PriceInterpolation,
 ) -> Result<()> {
     let market = &mut ctx.accounts.market.load_mut()?;
 
-    market.check_and_set_prices(bid_prices, ask_prices)?;
+    market.check_and_set_prices(
+        &bid_prices,
+        &ask_prices,
+        &supply_breakpoints,
+        price_interpolation,
+    )?;
+    market.schedule_launch(Clock::get()?.unix_timestamp);
 
     emit_cpi!(TokenMillMarketPriceSetEvent {
         market: ctx.accounts.market.key(),
         bid_prices,
         ask_prices,
+        supply_breakpoints,
+        price_interpolation,
     });
 
     Ok(())
@@ -35,19 +46,22 @@ pub fn handler(
 #[cfg(test)]
 mod tests {
     use crate::{
-        constant::{INTERVAL_NUMBER, MAX_PRICE},
+        constant::{BASE_PRECISION, MAX_PRICE, MAX_PRICES_LENGTH},
+        manager::swap_manager::SwapAmountType,
+        math::Rounding,
         Market,
     };
     use joelana_test_utils::{
         joelana_env::{
             actions::token_mill::{
                 tm_parse_error, CreateMarketAction, CreateQuoteAssetBadgeAction,
-                SetMarketPricesAction, TokenMillEnv,
+                SetMarketPricesAction, TokenMillEnv, DEFAULT_TOTAL_SUPPLY,
             },
-            TokenMillError,
+            PriceInterpolation, TokenMillError,
         },
-        utils::token_mill::curve_generator::Curve,
+        utils::token_mill::curve_generator::{Curve, DEFAULT_PRICES_LENGTH},
     };
+    use rstest::rstest;
 
     fn setup_env() -> (TokenMillEnv, SetMarketPricesAction) {
         let mut testing_env = TokenMillEnv::new().with_default_quote_token_mint();
@@ -84,6 +98,187 @@ mod tests {
 
         assert_eq!(market.bid_prices[2], action.price_curve.bid_prices[2]);
         assert_eq!(market.ask_prices[2], action.price_curve.ask_prices[2]);
+        assert_eq!(
+            market.supply_breakpoints[2],
+            action.price_curve.supply_breakpoints[2]
+        );
+    }
+
+    #[rstest]
+    fn set_market_prices_with_variable_length(
+        #[values(2, 5, 33, MAX_PRICES_LENGTH)] prices_length: usize,
+    ) {
+        let (mut testing_env, mut action) = setup_env();
+
+        action.price_curve = Curve::new(prices_length, DEFAULT_TOTAL_SUPPLY);
+
+        let result = testing_env.svm.execute_actions(&[&action]);
+
+        assert!(result.is_ok());
+
+        let market = testing_env.svm.get_parsed_account::<Market>(&action.market);
+
+        assert!(market.are_prices_set());
+
+        assert_eq!(usize::from(market.prices_length), prices_length);
+        assert_eq!(
+            market.ask_prices[prices_length - 1],
+            action.price_curve.ask_prices[prices_length - 1]
+        );
+        assert!(market.ask_prices[prices_length..].iter().all(|p| *p == 0));
+    }
+
+    #[test]
+    fn set_market_prices_with_non_uniform_breakpoints() {
+        let (mut testing_env, mut action) = setup_env();
+
+        // Dense segment in the first 5% of the supply
+        action.price_curve.supply_breakpoints = [0, 1, 2, 3, 4, 5, 20, 40, 60, 80, 100]
+            .iter()
+            .map(|percent| DEFAULT_TOTAL_SUPPLY / 100 * percent)
+            .collect();
+
+        let result = testing_env.svm.execute_actions(&[&action]);
+
+        assert!(result.is_ok());
+
+        let market = testing_env.svm.get_parsed_account::<Market>(&action.market);
+
+        assert_eq!(market.supply_breakpoints[5], DEFAULT_TOTAL_SUPPLY / 20);
+        assert_eq!(market.supply_breakpoints[10], DEFAULT_TOTAL_SUPPLY);
+
+        // The whole dense segment is bought at the average of its ask prices
+        let (base_amount, quote_amount) = market
+            .get_quote_amount(DEFAULT_TOTAL_SUPPLY / 20, SwapAmountType::ExactOutput)
+            .unwrap();
+
+        let expected_quote_amount = (0..5)
+            .map(|i| {
+                (action.price_curve.ask_prices[i] + action.price_curve.ask_prices[i + 1]) as u128
+                    * (DEFAULT_TOTAL_SUPPLY / 100) as u128
+                    / 2
+            })
+            .sum::<u128>()
+            * 1_000_000_000
+            / (BASE_PRECISION as u128 * 10_000_000_000);
+
+        assert_eq!(base_amount, DEFAULT_TOTAL_SUPPLY / 20);
+        assert_eq!(quote_amount as u128, expected_quote_amount);
+    }
+
+    #[test]
+    fn set_market_prices_with_exponential_interpolation() {
+        let (mut testing_env, mut action) = setup_env();
+
+        action.price_curve = Curve::exponential(DEFAULT_PRICES_LENGTH, DEFAULT_TOTAL_SUPPLY);
+
+        let result = testing_env.svm.execute_actions(&[&action]);
+
+        assert!(result.is_ok());
+
+        let market = testing_env.svm.get_parsed_account::<Market>(&action.market);
+
+        assert_eq!(
+            market.price_interpolation,
+            PriceInterpolation::Exponential as u8
+        );
+
+        // The first interval is bought at (p1 - p0) / ln(p1 / p0) per base token
+        let width = DEFAULT_TOTAL_SUPPLY / (DEFAULT_PRICES_LENGTH as u64 - 1);
+
+        let (base_amount, quote_amount) = market
+            .get_quote_amount(width, SwapAmountType::ExactOutput)
+            .unwrap();
+
+        let (price_0, price_1) = (
+            action.price_curve.ask_prices[0] as f64,
+            action.price_curve.ask_prices[1] as f64,
+        );
+        let expected_quote_amount =
+            (price_1 - price_0) / (price_1 / price_0).ln() * width as f64 / 10_000_000_000.0;
+
+        assert_eq!(base_amount, width);
+        assert!((quote_amount as f64 - expected_quote_amount).abs() <= 1.0);
+
+        // Selling it back on the bid curve returns less
+        let (_, bid_quote_amount) = market
+            .get_quote_amount_with_parameters(0, width, SwapAmountType::ExactInput, Rounding::Down)
+            .unwrap();
+
+        assert!(bid_quote_amount < quote_amount);
+    }
+
+    #[test]
+    fn set_market_prices_with_exponential_interpolation_from_zero() {
+        let (mut testing_env, mut action) = setup_env();
+
+        action.price_curve.price_interpolation = PriceInterpolation::Exponential;
+
+        let result = testing_env.svm.execute_actions(&[&action]);
+
+        assert!(result.is_err());
+
+        let error = tm_parse_error(result).unwrap();
+
+        assert_eq!(error, TokenMillError::InvalidPriceInterpolation);
+    }
+
+    #[rstest]
+    fn set_market_prices_with_invalid_length(
+        #[values(
+            Curve { bid_prices: vec![0], ask_prices: vec![1], supply_breakpoints: vec![DEFAULT_TOTAL_SUPPLY], ..Curve::default() },
+            Curve::new(MAX_PRICES_LENGTH + 1, DEFAULT_TOTAL_SUPPLY)
+        )]
+        price_curve: Curve,
+    ) {
+        let (mut testing_env, mut action) = setup_env();
+
+        action.price_curve = price_curve;
+
+        let result = testing_env.svm.execute_actions(&[&action]);
+
+        assert!(result.is_err());
+
+        let error = tm_parse_error(result).unwrap();
+
+        assert_eq!(error, TokenMillError::InvalidPricesLength);
+    }
+
+    #[test]
+    fn set_market_prices_with_length_mismatch() {
+        let (mut testing_env, mut action) = setup_env();
+
+        action.price_curve.ask_prices.pop();
+
+        let result = testing_env.svm.execute_actions(&[&action]);
+
+        assert!(result.is_err());
+
+        let error = tm_parse_error(result).unwrap();
+
+        assert_eq!(error, TokenMillError::InvalidPricesLength);
+    }
+
+    #[rstest]
+    #[case::first_breakpoint_not_zero(0, 1)]
+    #[case::last_breakpoint_not_total_supply(10, DEFAULT_TOTAL_SUPPLY - 1)]
+    #[case::decreasing_breakpoints(2, DEFAULT_TOTAL_SUPPLY / 20)]
+    #[case::interval_too_narrow(2, DEFAULT_TOTAL_SUPPLY / 10 + BASE_PRECISION - 1)]
+    fn set_market_prices_with_invalid_supply_breakpoints(
+        #[case] index: usize,
+        #[case] breakpoint: u64,
+    ) {
+        let (mut testing_env, mut action) = setup_env();
+
+        action.price_curve.supply_breakpoints[index] = breakpoint;
+
+        let result = testing_env.svm.execute_actions(&[&action]);
+
+        assert!(result.is_err());
+
+        let error = tm_parse_error(result).unwrap();
+
+        assert_eq!(error, TokenMillError::InvalidSupplyBreakpoints);
     }
 
     #[test]
@@ -153,7 +348,8 @@ mod tests {
     fn set_market_prices_with_price_too_high() {
         let (mut testing_env, mut action) = setup_env();
 
-        action.price_curve.ask_prices[INTERVAL_NUMBER as usize] = MAX_PRICE + 1;
+        let last_index = action.price_curve.ask_prices.len() - 1;
+        action.price_curve.ask_prices[last_index] = MAX_PRICE + 1;
 
         let result = testing_env.svm.execute_actions(&[&action]);
 
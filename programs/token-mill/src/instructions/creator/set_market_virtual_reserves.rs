@@ -0,0 +1,160 @@
+use anchor_lang::prelude::*;
+
+use crate::events::TokenMillMarketVirtualReservesSetEvent;
+
+use super::MarketSettingsUpdate;
+
+pub fn handler(
+    ctx: Context<MarketSettingsUpdate>,
+    virtual_base: u64,
+    virtual_quote: u64,
+    spread_bps: u16,
+) -> Result<()> {
+    let market = &mut ctx.accounts.market.load_mut()?;
+
+    market.check_and_set_virtual_reserves(virtual_base, virtual_quote, spread_bps)?;
+    market.schedule_launch(Clock::get()?.unix_timestamp);
+
+    emit_cpi!(TokenMillMarketVirtualReservesSetEvent {
+        market: ctx.accounts.market.key(),
+        virtual_base,
+        virtual_quote,
+        spread_bps,
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{constant::MAX_BPS, CurveKind, Market};
+    use joelana_test_utils::{
+        joelana_env::{
+            actions::token_mill::{
+                tm_parse_error, CreateMarketAction, CreateQuoteAssetBadgeAction,
+                SetMarketPricesAction, SetMarketVirtualReservesAction, TokenMillEnv,
+                DEFAULT_TOTAL_SUPPLY,
+            },
+            TokenMillError,
+        },
+        utils::token_mill::curve_generator::Curve,
+    };
+    use rstest::rstest;
+
+    const VIRTUAL_BASE: u64 = DEFAULT_TOTAL_SUPPLY * 11 / 10;
+    const VIRTUAL_QUOTE: u64 = 30_000_000_000;
+
+    fn setup_env() -> (TokenMillEnv, SetMarketVirtualReservesAction) {
+        let mut testing_env = TokenMillEnv::new().with_default_quote_token_mint();
+
+        let action = CreateQuoteAssetBadgeAction::new(testing_env.quote_token_mint.unwrap());
+
+        testing_env.svm.execute_actions(&[&action]).unwrap();
+
+        testing_env.svm.change_payer("alice");
+
+        let create_market_action = CreateMarketAction::new(&testing_env);
+
+        testing_env
+            .svm
+            .execute_actions(&[&create_market_action])
+            .unwrap();
+
+        let action = SetMarketVirtualReservesAction::new(VIRTUAL_BASE, VIRTUAL_QUOTE, 100);
+
+        (testing_env, action)
+    }
+
+    #[test]
+    fn set_market_virtual_reserves() {
+        let (mut testing_env, action) = setup_env();
+
+        let result = testing_env.svm.execute_actions(&[&action]);
+
+        assert!(result.is_ok());
+
+        let market = testing_env.svm.get_parsed_account::<Market>(&action.market);
+
+        assert!(market.are_prices_set());
+        assert_eq!(
+            market.curve_kind(),
+            CurveKind::VirtualConstantProduct {
+                virtual_base: VIRTUAL_BASE,
+                virtual_quote: VIRTUAL_QUOTE,
+                spread_bps: 100,
+            }
+        );
+    }
+
+    #[test]
+    fn set_market_virtual_reserves_after_prices_set() {
+        let (mut testing_env, action) = setup_env();
+
+        testing_env
+            .svm
+            .execute_actions(&[&SetMarketPricesAction::new(Curve::default())])
+            .unwrap();
+
+        let result = testing_env.svm.execute_actions(&[&action]);
+
+        assert!(result.is_err());
+
+        let error = tm_parse_error(result).unwrap();
+
+        assert_eq!(error, TokenMillError::PricesAlreadySet);
+
+        // Prices can't be set either once the virtual reserves are set
+        let (mut testing_env, action) = setup_env();
+
+        testing_env.svm.execute_actions(&[&action]).unwrap();
+
+        let result = testing_env
+            .svm
+            .execute_actions(&[&SetMarketPricesAction::new(Curve::default())]);
+
+        assert!(result.is_err());
+
+        let error = tm_parse_error(result).unwrap();
+
+        assert_eq!(error, TokenMillError::PricesAlreadySet);
+    }
+
+    #[rstest]
+    #[case::virtual_base_equal_to_total_supply(DEFAULT_TOTAL_SUPPLY, VIRTUAL_QUOTE, 100)]
+    #[case::zero_virtual_quote(VIRTUAL_BASE, 0, 100)]
+    #[case::spread_too_high(VIRTUAL_BASE, VIRTUAL_QUOTE, MAX_BPS as u16)]
+    fn set_market_virtual_reserves_with_invalid_parameters(
+        #[case] virtual_base: u64,
+        #[case] virtual_quote: u64,
+        #[case] spread_bps: u16,
+    ) {
+        let (mut testing_env, mut action) = setup_env();
+
+        action.virtual_base = virtual_base;
+        action.virtual_quote = virtual_quote;
+        action.spread_bps = spread_bps;
+
+        let result = testing_env.svm.execute_actions(&[&action]);
+
+        assert!(result.is_err());
+
+        let error = tm_parse_error(result).unwrap();
+
+        assert_eq!(error, TokenMillError::InvalidVirtualReserves);
+    }
+
+    #[test]
+    fn set_market_virtual_reserves_with_invalid_signer() {
+        let (mut testing_env, mut action) = setup_env();
+
+        action.signer = testing_env.svm.change_payer("mallory");
+
+        let result = testing_env.svm.execute_actions(&[&action]);
+
+        assert!(result.is_err());
+
+        let error = tm_parse_error(result).unwrap();
+
+        assert_eq!(error, TokenMillError::InvalidAuthority);
+    }
+}
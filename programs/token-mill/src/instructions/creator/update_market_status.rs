@@ -0,0 +1,221 @@
+use anchor_lang::prelude::*;
+
+use crate::{events::TokenMillMarketStatusUpdateEvent, state::MarketStatus};
+
+use super::MarketSettingsUpdate;
+
+pub fn handler(ctx: Context<MarketSettingsUpdate>, new_status: MarketStatus) -> Result<()> {
+    let market = &mut ctx.accounts.market.load_mut()?;
+
+    let old_status = market.update_status_as_creator(new_status, Clock::get()?.unix_timestamp)?;
+
+    emit_cpi!(TokenMillMarketStatusUpdateEvent {
+        market: ctx.accounts.market.key(),
+        old_status,
+        new_status,
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use joelana_test_utils::joelana_env::{
+        actions::token_mill::{tm_parse_error, SwapAction, TokenMillEnv, UpdateMarketStatusAction},
+        BuyTaxRecipient, MarketStatus, SwapAmountType, SwapType, TokenMillError,
+    };
+    use rstest::rstest;
+
+    use crate::{state, Market};
+
+    const TRADING_START: i64 = 1_000;
+
+    fn setup_env() -> TokenMillEnv {
+        TokenMillEnv::new()
+            .with_default_quote_token_mint()
+            .with_trading_start(TRADING_START)
+            .with_default_market()
+    }
+
+    fn buy_action(testing_env: &TokenMillEnv) -> SwapAction {
+        SwapAction::new(
+            testing_env,
+            SwapType::Buy,
+            SwapAmountType::ExactOutput,
+            10_000_000_000,
+            u64::MAX,
+            None,
+        )
+    }
+
+    fn sell_action(testing_env: &TokenMillEnv) -> SwapAction {
+        SwapAction::new(
+            testing_env,
+            SwapType::Sell,
+            SwapAmountType::ExactInput,
+            5_000_000_000,
+            0,
+            None,
+        )
+    }
+
+    #[test]
+    fn scheduled_trading_start() {
+        let mut testing_env = setup_env();
+
+        let market = testing_env
+            .svm
+            .get_parsed_account::<Market>(&testing_env.market);
+
+        assert_eq!(market.trading_start_ts, TRADING_START);
+        assert_eq!(market.get_status(0), state::MarketStatus::PricesSet);
+
+        testing_env.svm.change_payer("bob");
+
+        let buy_action = buy_action(&testing_env);
+
+        let result = testing_env.svm.execute_actions(&[&buy_action]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::MarketNotTrading
+        );
+
+        testing_env.svm.warp(TRADING_START);
+
+        let result = testing_env.svm.execute_actions(&[&buy_action]);
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn open_trading_before_start() {
+        let mut testing_env = setup_env();
+
+        testing_env.svm.change_payer("alice");
+
+        let action = UpdateMarketStatusAction::new(&testing_env, MarketStatus::Trading);
+
+        let result = testing_env.svm.execute_actions(&[&action]);
+
+        assert!(result.is_ok());
+
+        let market = testing_env
+            .svm
+            .get_parsed_account::<Market>(&testing_env.market);
+
+        assert_eq!(market.get_status(0), state::MarketStatus::Trading);
+
+        testing_env.svm.change_payer("bob");
+
+        let buy_action = buy_action(&testing_env);
+
+        let result = testing_env.svm.execute_actions(&[&buy_action]);
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn open_trading_before_start_starts_launch() {
+        const LAUNCH_WINDOW: i64 = 600;
+        const OPEN_TIME: i64 = 100;
+
+        let mut testing_env = TokenMillEnv::new()
+            .with_default_quote_token_mint()
+            .with_trading_start(TRADING_START)
+            .with_launch_protection(LAUNCH_WINDOW, 1_000_000_000)
+            .with_buy_tax(1_000, LAUNCH_WINDOW, BuyTaxRecipient::Fees)
+            .with_default_market();
+
+        testing_env.svm.warp(OPEN_TIME);
+        testing_env.svm.change_payer("alice");
+
+        testing_env
+            .svm
+            .execute_actions(&[&UpdateMarketStatusAction::new(
+                &testing_env,
+                MarketStatus::Trading,
+            )])
+            .unwrap();
+
+        let market = testing_env
+            .svm
+            .get_parsed_account::<Market>(&testing_env.market);
+
+        // The launch starts when trading opens, not at the scheduled start
+        assert_eq!(market.trading_start_ts, OPEN_TIME);
+        assert_eq!(market.launch_protection.end_ts, OPEN_TIME + LAUNCH_WINDOW);
+        assert_eq!(market.buy_tax.start_ts, OPEN_TIME);
+    }
+
+    #[test]
+    fn wind_down_to_sell_only() {
+        let mut testing_env = TokenMillEnv::default();
+
+        let buy_action = buy_action(&testing_env);
+        let sell_action = sell_action(&testing_env);
+
+        testing_env.svm.change_payer("bob");
+        testing_env.svm.execute_actions(&[&buy_action]).unwrap();
+
+        testing_env.svm.change_payer("alice");
+
+        let action = UpdateMarketStatusAction::new(&testing_env, MarketStatus::SellOnly);
+
+        let result = testing_env.svm.execute_actions(&[&action]);
+
+        assert!(result.is_ok());
+
+        testing_env.svm.change_payer("bob");
+
+        let result = testing_env.svm.execute_actions(&[&buy_action]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::MarketNotTrading
+        );
+
+        let result = testing_env.svm.execute_actions(&[&sell_action]);
+
+        assert!(result.is_ok());
+    }
+
+    #[rstest]
+    fn update_market_status_with_invalid_transition(
+        #[values(
+            MarketStatus::Created,
+            MarketStatus::PricesSet,
+            MarketStatus::Trading,
+            MarketStatus::Closed
+        )]
+        new_status: MarketStatus,
+    ) {
+        let mut testing_env = TokenMillEnv::default();
+
+        testing_env.svm.change_payer("alice");
+
+        let action = UpdateMarketStatusAction::new(&testing_env, new_status);
+
+        let result = testing_env.svm.execute_actions(&[&action]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::InvalidMarketStatus
+        );
+    }
+
+    #[test]
+    fn update_market_status_with_invalid_signer() {
+        let mut testing_env = TokenMillEnv::default();
+
+        let mut action = UpdateMarketStatusAction::new(&testing_env, MarketStatus::SellOnly);
+        action.signer = testing_env.svm.change_payer("mallory");
+
+        let result = testing_env.svm.execute_actions(&[&action]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::InvalidAuthority
+        );
+    }
+}
@@ -0,0 +1,168 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::{
+    errors::TokenMillError,
+    events::TokenMillDcaOrderCancellationEvent,
+    manager::token_manager::{close_pda_token_account, transfer_from_pda_with_hook},
+    state::{DcaOrder, DCA_ORDER_PDA_SEED},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CancelDcaOrder<'info> {
+    #[account(
+        mut,
+        has_one = owner @ TokenMillError::InvalidAuthority,
+        has_one = escrow_token_mint @ TokenMillError::InvalidMintAccount,
+        close = owner
+    )]
+    pub dca_order: Account<'info, DcaOrder>,
+
+    pub escrow_token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = escrow_token_mint,
+        associated_token::authority = dca_order,
+        associated_token::token_program = escrow_token_program
+    )]
+    pub dca_order_escrow: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut, token::mint = escrow_token_mint)]
+    pub owner_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub escrow_token_program: Interface<'info, TokenInterface>,
+}
+
+pub fn handler<'info>(ctx: Context<'_, '_, 'info, 'info, CancelDcaOrder<'info>>) -> Result<()> {
+    let dca_order = &ctx.accounts.dca_order;
+    let dca_order_escrow = &ctx.accounts.dca_order_escrow;
+    let amount_refunded = dca_order_escrow.amount;
+
+    let order_id_bytes = dca_order.order_id.to_le_bytes();
+    let dca_order_seeds = [
+        DCA_ORDER_PDA_SEED.as_bytes(),
+        dca_order.market.as_ref(),
+        dca_order.owner.as_ref(),
+        order_id_bytes.as_ref(),
+        &[dca_order.bump],
+    ];
+
+    if amount_refunded > 0 {
+        transfer_from_pda_with_hook(
+            &ctx.accounts.escrow_token_mint,
+            dca_order.to_account_info(),
+            dca_order_escrow,
+            &ctx.accounts.owner_token_account,
+            &ctx.accounts.escrow_token_program,
+            amount_refunded,
+            &dca_order_seeds,
+            ctx.remaining_accounts,
+        )?;
+    }
+
+    close_pda_token_account(
+        dca_order.to_account_info(),
+        dca_order_escrow,
+        ctx.accounts.owner.to_account_info(),
+        &ctx.accounts.escrow_token_program,
+        &dca_order_seeds,
+    )?;
+
+    emit_cpi!(TokenMillDcaOrderCancellationEvent {
+        dca_order: dca_order.key(),
+        amount_refunded,
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use joelana_test_utils::joelana_env::{
+        actions::token_mill::{
+            tm_parse_error, CancelDcaOrderAction, CreateDcaOrderAction, ExecuteDcaAction,
+            TokenMillEnv,
+        },
+        make_address, SwapType, TokenMillError,
+    };
+
+    const ORDER_AMOUNT: u64 = 3_000_000_000;
+
+    fn setup_env() -> (TokenMillEnv, CreateDcaOrderAction) {
+        let mut testing_env = TokenMillEnv::default();
+        testing_env.svm.change_payer("bob");
+
+        let create_dca_order_action = CreateDcaOrderAction::new(
+            &testing_env,
+            0,
+            SwapType::Buy,
+            ORDER_AMOUNT,
+            1_000_000_000,
+            3_600,
+        );
+
+        testing_env
+            .svm
+            .execute_actions(&[&create_dca_order_action])
+            .unwrap();
+
+        (testing_env, create_dca_order_action)
+    }
+
+    #[test]
+    fn cancel_dca_order() {
+        let (mut testing_env, create_dca_order_action) = setup_env();
+        let bob = make_address("bob");
+        let quote_token_mint = testing_env.quote_token_mint.unwrap();
+
+        testing_env
+            .svm
+            .execute_actions(&[&ExecuteDcaAction::new(
+                &testing_env,
+                &create_dca_order_action,
+            )])
+            .unwrap();
+
+        let quote_balance_before = testing_env.svm.get_balance(&quote_token_mint, &bob);
+
+        testing_env
+            .svm
+            .execute_actions(&[&CancelDcaOrderAction::new(&create_dca_order_action)])
+            .unwrap();
+
+        assert_eq!(
+            testing_env.svm.get_balance(&quote_token_mint, &bob) - quote_balance_before,
+            ORDER_AMOUNT - 1_000_000_000
+        );
+        assert!(!testing_env
+            .svm
+            .account_exists(&create_dca_order_action.dca_order));
+        assert!(!testing_env
+            .svm
+            .account_exists(&create_dca_order_action.dca_order_escrow));
+    }
+
+    #[test]
+    fn cancel_dca_order_with_invalid_owner() {
+        let (mut testing_env, create_dca_order_action) = setup_env();
+        let mallory = testing_env.svm.change_payer("mallory");
+
+        let mut cancel_dca_order_action = CancelDcaOrderAction::new(&create_dca_order_action);
+        cancel_dca_order_action.signer = mallory;
+        cancel_dca_order_action.owner_token_account = testing_env
+            .svm
+            .get_ata_address(&create_dca_order_action.escrow_token_mint, &mallory);
+
+        let result = testing_env.svm.execute_actions(&[&cancel_dca_order_action]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::InvalidAuthority
+        );
+    }
+}
@@ -0,0 +1,228 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{
+    errors::TokenMillError,
+    events::TokenMillDcaOrderCreationEvent,
+    manager::{swap_manager::SwapType, token_manager::transfer_from_eoa_with_hook},
+    state::{DcaOrder, Market, DCA_ORDER_PDA_SEED},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(order_id: u64)]
+pub struct CreateDcaOrder<'info> {
+    pub market: AccountLoader<'info, Market>,
+
+    #[account(
+        init,
+        seeds = [
+            DCA_ORDER_PDA_SEED.as_bytes(),
+            market.key().as_ref(),
+            owner.key().as_ref(),
+            &order_id.to_le_bytes()
+        ],
+        bump,
+        payer = owner,
+        space = 8 + DcaOrder::INIT_SPACE
+    )]
+    pub dca_order: Account<'info, DcaOrder>,
+
+    pub escrow_token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = owner,
+        associated_token::mint = escrow_token_mint,
+        associated_token::authority = dca_order,
+        associated_token::token_program = escrow_token_program
+    )]
+    pub dca_order_escrow: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut, token::mint = escrow_token_mint)]
+    pub owner_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub escrow_token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+/// The first execution is possible right away.
+/// Executions are only possible while their average price satisfies `limit_price`.
+pub fn handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, CreateDcaOrder<'info>>,
+    order_id: u64,
+    swap_type: SwapType,
+    amount: u64,
+    amount_per_execution: u64,
+    interval: i64,
+    limit_price: u64,
+) -> Result<()> {
+    if amount == 0 || amount_per_execution == 0 {
+        return Err(TokenMillError::InvalidAmount.into());
+    }
+
+    if interval <= 0 {
+        return Err(TokenMillError::InvalidDcaInterval.into());
+    }
+
+    let escrow_token_mint = ctx.accounts.escrow_token_mint.key();
+
+    {
+        let market = ctx.accounts.market.load()?;
+
+        let expected_escrow_token_mint = match swap_type {
+            SwapType::Buy => market.quote_token_mint,
+            SwapType::Sell => market.base_token_mint,
+        };
+
+        if escrow_token_mint != expected_escrow_token_mint {
+            return Err(TokenMillError::InvalidMintAccount.into());
+        }
+    }
+
+    // Quote tokens with a transfer fee credit the escrow less than `amount`
+    let amount = transfer_from_eoa_with_hook(
+        &ctx.accounts.escrow_token_mint,
+        &ctx.accounts.owner,
+        &ctx.accounts.owner_token_account,
+        &ctx.accounts.dca_order_escrow,
+        &ctx.accounts.escrow_token_program,
+        amount,
+        ctx.remaining_accounts,
+    )?;
+
+    let dca_order = &mut ctx.accounts.dca_order;
+
+    dca_order.initialize(
+        ctx.bumps.dca_order,
+        ctx.accounts.market.key(),
+        ctx.accounts.owner.key(),
+        order_id,
+        swap_type,
+        escrow_token_mint,
+        amount,
+        amount_per_execution,
+        interval,
+        limit_price,
+        Clock::get()?.unix_timestamp,
+    )?;
+
+    emit_cpi!(TokenMillDcaOrderCreationEvent {
+        market: ctx.accounts.market.key(),
+        owner: ctx.accounts.owner.key(),
+        dca_order: ctx.accounts.dca_order.key(),
+        swap_type,
+        amount,
+        amount_per_execution,
+        interval,
+        limit_price,
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::state::DcaOrder;
+    use joelana_test_utils::joelana_env::{
+        actions::token_mill::{tm_parse_error, CreateDcaOrderAction, SwapAction, TokenMillEnv},
+        make_address, SwapAmountType, SwapType, TokenMillError,
+    };
+    use rstest::rstest;
+
+    const ORDER_AMOUNT: u64 = 3_000_000_000;
+    const CURRENT_TIME: i64 = 1_000;
+
+    fn setup_env() -> TokenMillEnv {
+        let mut testing_env = TokenMillEnv::default();
+        testing_env.svm.change_payer("bob");
+        testing_env.svm.warp(CURRENT_TIME);
+
+        // Gives bob some base tokens to sell
+        let swap_action = SwapAction::new(
+            &testing_env,
+            SwapType::Buy,
+            SwapAmountType::ExactOutput,
+            ORDER_AMOUNT,
+            u64::MAX,
+            None,
+        );
+
+        testing_env.svm.execute_actions(&[&swap_action]).unwrap();
+
+        testing_env
+    }
+
+    #[rstest]
+    fn create_dca_order(#[values(SwapType::Buy, SwapType::Sell)] swap_type: SwapType) {
+        let mut testing_env = setup_env();
+
+        let create_dca_order_action = CreateDcaOrderAction::new(
+            &testing_env,
+            3,
+            swap_type,
+            ORDER_AMOUNT,
+            1_000_000_000,
+            3_600,
+        );
+
+        testing_env
+            .svm
+            .execute_actions(&[&create_dca_order_action])
+            .unwrap();
+
+        let dca_order = testing_env
+            .svm
+            .get_parsed_account::<DcaOrder>(&create_dca_order_action.dca_order);
+
+        assert_eq!(dca_order.market, testing_env.market);
+        assert_eq!(dca_order.owner, make_address("bob"));
+        assert_eq!(dca_order.order_id, 3);
+        assert_eq!(dca_order.swap_type as u8, swap_type as u8);
+        assert_eq!(dca_order.amount_remaining, ORDER_AMOUNT);
+        assert_eq!(dca_order.amount_per_execution, 1_000_000_000);
+        assert_eq!(dca_order.interval, 3_600);
+        assert_eq!(dca_order.next_execution_ts, CURRENT_TIME);
+        assert_eq!(dca_order.limit_price, create_dca_order_action.limit_price);
+
+        assert_eq!(
+            testing_env.svm.get_balance(
+                &create_dca_order_action.escrow_token_mint,
+                &create_dca_order_action.dca_order
+            ),
+            ORDER_AMOUNT
+        );
+    }
+
+    #[test]
+    fn create_dca_order_with_invalid_parameters() {
+        let mut testing_env = setup_env();
+
+        let mut create_dca_order_action =
+            CreateDcaOrderAction::new(&testing_env, 0, SwapType::Buy, ORDER_AMOUNT, 0, 3_600);
+
+        let result = testing_env.svm.execute_actions(&[&create_dca_order_action]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::InvalidAmount
+        );
+
+        create_dca_order_action.amount_per_execution = 1_000_000_000;
+        create_dca_order_action.interval = 0;
+
+        let result = testing_env.svm.execute_actions(&[&create_dca_order_action]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::InvalidDcaInterval
+        );
+    }
+}
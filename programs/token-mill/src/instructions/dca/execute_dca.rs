@@ -0,0 +1,639 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::{
+    errors::TokenMillError,
+    events::{TokenMillDcaExecutionEvent, TokenMillSwapEvent},
+    manager::{
+        launch_manager::{check_presale, update_launch_allocation},
+        oracle_manager::update_market_oracle,
+        stats_manager::update_market_stats,
+        swap_manager::{self, SwapAmountType, SwapResult, SwapType},
+        token_manager::{get_epoch_transfer_fee, transfer_from_pda_with_hook},
+    },
+    state::{DcaOrder, Market, MarketOracle, MarketStats, TokenMillConfig, DCA_ORDER_PDA_SEED},
+    MARKET_PDA_SEED,
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ExecuteDca<'info> {
+    pub config: Account<'info, TokenMillConfig>,
+
+    #[account(
+        mut,
+        has_one = config @ TokenMillError::InvalidConfigAccount,
+        has_one = base_token_mint @ TokenMillError::InvalidMintAccount,
+        has_one = quote_token_mint @ TokenMillError::InvalidMintAccount
+    )]
+    pub market: AccountLoader<'info, Market>,
+
+    #[account(mut, has_one = market @ TokenMillError::InvalidMarket)]
+    pub dca_order: Account<'info, DcaOrder>,
+
+    pub base_token_mint: InterfaceAccount<'info, Mint>,
+
+    pub quote_token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = base_token_mint,
+        associated_token::authority = market,
+        associated_token::token_program = base_token_program
+    )]
+    pub market_base_token_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = quote_token_mint,
+        associated_token::authority = market,
+        associated_token::token_program = quote_token_program
+    )]
+    pub market_quote_token_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::authority = dca_order,
+        constraint = dca_order_escrow.mint == dca_order.escrow_token_mint @ TokenMillError::InvalidMintAccount
+    )]
+    pub dca_order_escrow: InterfaceAccount<'info, TokenAccount>,
+
+    /// Receives the base tokens bought or the quote tokens of the sale
+    #[account(
+        mut,
+        constraint = owner_token_account.owner == dca_order.owner @ TokenMillError::InvalidAuthority
+    )]
+    pub owner_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Required once the market has an oracle
+    #[account(mut, has_one = market @ TokenMillError::InvalidMarket)]
+    pub market_oracle: Option<Box<Account<'info, MarketOracle>>>,
+
+    /// Required once the market has stats
+    #[account(mut, has_one = market @ TokenMillError::InvalidMarket)]
+    pub market_stats: Option<Box<Account<'info, MarketStats>>>,
+
+    pub executor: Signer<'info>,
+
+    pub base_token_program: Interface<'info, TokenInterface>,
+
+    pub quote_token_program: Interface<'info, TokenInterface>,
+}
+
+/// Swaps the amount of the current interval, against the constant product pool once the market graduated
+pub fn handler<'info>(ctx: Context<'_, '_, 'info, 'info, ExecuteDca<'info>>) -> Result<()> {
+    let swap_type = ctx.accounts.dca_order.swap_type;
+
+    let mint_out = match swap_type {
+        SwapType::Buy => &ctx.accounts.base_token_mint,
+        SwapType::Sell => &ctx.accounts.quote_token_mint,
+    };
+
+    if ctx.accounts.owner_token_account.mint != mint_out.key() {
+        return Err(TokenMillError::InvalidMintAccount.into());
+    }
+
+    let current_time = Clock::get()?.unix_timestamp;
+
+    let amount = ctx
+        .accounts
+        .dca_order
+        .execute(current_time)
+        .ok_or(TokenMillError::DcaExecutionTooEarly)?;
+
+    if amount == 0 {
+        return Err(TokenMillError::InvalidAmount.into());
+    }
+
+    let quote_transfer_fee = get_epoch_transfer_fee(&ctx.accounts.quote_token_mint)?;
+
+    let base_amount;
+    let quote_amount;
+    let buy_tax;
+    let transfer_fee;
+    let market_bump;
+    let creator_fee;
+    let staking_fee;
+    let protocol_fee;
+
+    {
+        let market = &mut ctx.accounts.market.load_mut()?;
+
+        ctx.accounts.config.check_not_paused(swap_type.into())?;
+        market.check_swap_allowed(swap_type, current_time)?;
+
+        let swap_fee;
+        SwapResult {
+            base_amount,
+            quote_amount,
+            swap_fee,
+            buy_tax,
+            transfer_fee,
+            ..
+        } = swap_manager::swap_with_transfer_fee(
+            market,
+            swap_type,
+            SwapAmountType::ExactInput,
+            amount,
+            current_time,
+            quote_transfer_fee.as_ref(),
+            0,
+        )?;
+
+        if base_amount == 0 || quote_amount == 0 {
+            return Err(TokenMillError::InvalidAmount.into());
+        }
+
+        // Executions are permissionless, the order bounds the price they can be executed at
+        let owner_quote_amount = match swap_type {
+            SwapType::Buy => quote_amount + transfer_fee,
+            SwapType::Sell => quote_amount - transfer_fee,
+        };
+
+        if !ctx.accounts.dca_order.is_execution_price_met(
+            base_amount,
+            owner_quote_amount,
+            market.quote_token_decimals,
+        ) {
+            return Err(TokenMillError::PriceLimitExceeded.into());
+        }
+
+        (creator_fee, staking_fee, protocol_fee, _) =
+            market.distribute_fees(swap_fee, buy_tax, None)?;
+
+        update_market_oracle(market, &mut ctx.accounts.market_oracle)?;
+        update_market_stats(
+            market,
+            &mut ctx.accounts.market_stats,
+            swap_type,
+            base_amount,
+            quote_amount,
+            (creator_fee, staking_fee, protocol_fee, 0),
+        )?;
+        // Orders carry no launch allocation nor presale proof, they can't buy during the launch window or the presale
+        check_presale(
+            market,
+            &ctx.accounts.dca_order.owner,
+            None,
+            &mut None,
+            swap_type,
+            base_amount,
+            current_time,
+        )?;
+        update_launch_allocation(market, &mut None, swap_type, base_amount, current_time)?;
+
+        market_bump = market.bump;
+    }
+
+    let dca_order = &mut ctx.accounts.dca_order;
+
+    let (
+        amount_in,
+        amount_out,
+        mint_in,
+        market_account_in,
+        market_account_out,
+        token_program_in,
+        token_program_out,
+    ) = match swap_type {
+        SwapType::Buy => (
+            quote_amount + transfer_fee,
+            base_amount,
+            &ctx.accounts.quote_token_mint,
+            &ctx.accounts.market_quote_token_ata,
+            &ctx.accounts.market_base_token_ata,
+            &ctx.accounts.quote_token_program,
+            &ctx.accounts.base_token_program,
+        ),
+        SwapType::Sell => (
+            base_amount,
+            quote_amount,
+            &ctx.accounts.base_token_mint,
+            &ctx.accounts.market_base_token_ata,
+            &ctx.accounts.market_quote_token_ata,
+            &ctx.accounts.base_token_program,
+            &ctx.accounts.quote_token_program,
+        ),
+    };
+
+    dca_order.amount_remaining -= amount_in;
+
+    let base_token_mint_key = ctx.accounts.base_token_mint.key();
+    let market_seeds = [
+        MARKET_PDA_SEED.as_bytes(),
+        base_token_mint_key.as_ref(),
+        &[market_bump],
+    ];
+
+    let order_id_bytes = dca_order.order_id.to_le_bytes();
+    let dca_order_seeds = [
+        DCA_ORDER_PDA_SEED.as_bytes(),
+        dca_order.market.as_ref(),
+        dca_order.owner.as_ref(),
+        order_id_bytes.as_ref(),
+        &[dca_order.bump],
+    ];
+
+    transfer_from_pda_with_hook(
+        mint_in,
+        dca_order.to_account_info(),
+        &ctx.accounts.dca_order_escrow,
+        market_account_in,
+        token_program_in,
+        amount_in,
+        &dca_order_seeds,
+        ctx.remaining_accounts,
+    )?;
+
+    transfer_from_pda_with_hook(
+        mint_out,
+        ctx.accounts.market.to_account_info(),
+        market_account_out,
+        &ctx.accounts.owner_token_account,
+        token_program_out,
+        amount_out,
+        &market_seeds,
+        ctx.remaining_accounts,
+    )?;
+
+    emit_cpi!(TokenMillSwapEvent {
+        user: dca_order.owner,
+        market: ctx.accounts.market.key(),
+        swap_type,
+        base_amount,
+        quote_amount,
+        referral_token_account: None,
+        creator_fee,
+        staking_fee,
+        protocol_fee,
+        referral_fee: 0,
+        sub_referral_fee: 0,
+        buy_tax,
+        fee_discount: 0,
+    });
+
+    emit_cpi!(TokenMillDcaExecutionEvent {
+        dca_order: dca_order.key(),
+        executor: ctx.accounts.executor.key(),
+        base_amount,
+        quote_amount,
+        amount_remaining: dca_order.amount_remaining,
+        next_execution_ts: dca_order.next_execution_ts,
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        manager::swap_manager,
+        state::{DcaOrder, Market},
+    };
+    use joelana_test_utils::joelana_env::{
+        actions::token_mill::{
+            get_transfer_counter_address, tm_parse_error, CancelDcaOrderAction,
+            CreateDcaOrderAction, ExecuteDcaAction, GraduateMarketAction, SwapAction, TokenMillEnv,
+            DEFAULT_TOTAL_SUPPLY,
+        },
+        make_address, SwapAmountType, SwapType, TokenMillError,
+    };
+    use mock_transfer_hook::TransferCounter;
+    use rstest::rstest;
+
+    const CURRENT_TIME: i64 = 1_000;
+    const INTERVAL: i64 = 3_600;
+
+    fn setup_env() -> TokenMillEnv {
+        setup_env_with(TokenMillEnv::default())
+    }
+
+    fn setup_env_with(mut testing_env: TokenMillEnv) -> TokenMillEnv {
+        testing_env.svm.change_payer("bob");
+        testing_env.svm.warp(CURRENT_TIME);
+
+        // Gives bob some base tokens to sell
+        let swap_action = SwapAction::new(
+            &testing_env,
+            SwapType::Buy,
+            SwapAmountType::ExactOutput,
+            10_000_000_000,
+            u64::MAX,
+            None,
+        );
+
+        testing_env.svm.execute_actions(&[&swap_action]).unwrap();
+
+        testing_env
+    }
+
+    #[rstest]
+    fn execute_dca(#[values(SwapType::Buy, SwapType::Sell)] swap_type: SwapType) {
+        let mut testing_env = setup_env();
+        let bob = make_address("bob");
+
+        let create_dca_order_action = CreateDcaOrderAction::new(
+            &testing_env,
+            0,
+            swap_type,
+            2_500_000_000,
+            1_000_000_000,
+            INTERVAL,
+        );
+
+        testing_env
+            .svm
+            .execute_actions(&[&create_dca_order_action])
+            .unwrap();
+
+        let execute_dca_action = ExecuteDcaAction::new(&testing_env, &create_dca_order_action);
+
+        let mint_out = match swap_type {
+            SwapType::Buy => testing_env.base_token_mint.unwrap(),
+            SwapType::Sell => testing_env.quote_token_mint.unwrap(),
+        };
+
+        // The last execution swaps what is left
+        for (i, amount_remaining) in [1_500_000_000, 500_000_000, 0].into_iter().enumerate() {
+            let balance_out_before = testing_env.svm.get_balance(&mint_out, &bob);
+            let creator_fees_before = testing_env
+                .svm
+                .get_parsed_account::<Market>(&testing_env.market)
+                .fees
+                .pending_creator_fees;
+
+            testing_env
+                .svm
+                .execute_actions(&[&execute_dca_action])
+                .unwrap();
+
+            let dca_order = testing_env
+                .svm
+                .get_parsed_account::<DcaOrder>(&create_dca_order_action.dca_order);
+            let market = testing_env
+                .svm
+                .get_parsed_account::<Market>(&testing_env.market);
+
+            assert_eq!(dca_order.amount_remaining, amount_remaining);
+            assert_eq!(
+                dca_order.next_execution_ts,
+                CURRENT_TIME + (i as i64 + 1) * INTERVAL
+            );
+            assert!(testing_env.svm.get_balance(&mint_out, &bob) > balance_out_before);
+            assert!(market.fees.pending_creator_fees > creator_fees_before);
+
+            // A single execution per interval
+            let result = testing_env.svm.execute_actions(&[&execute_dca_action]);
+
+            assert_eq!(
+                tm_parse_error(result).unwrap(),
+                TokenMillError::DcaExecutionTooEarly
+            );
+
+            testing_env.svm.warp(INTERVAL);
+        }
+
+        assert_eq!(
+            testing_env.svm.get_balance(
+                &create_dca_order_action.escrow_token_mint,
+                &create_dca_order_action.dca_order
+            ),
+            0
+        );
+
+        let result = testing_env.svm.execute_actions(&[&execute_dca_action]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::InvalidAmount
+        );
+    }
+
+    #[rstest]
+    fn execute_dca_after_graduation(#[values(SwapType::Buy, SwapType::Sell)] swap_type: SwapType) {
+        const SUPPLY_THRESHOLD: u64 = DEFAULT_TOTAL_SUPPLY / 2;
+
+        let mut testing_env = setup_env_with(
+            TokenMillEnv::new()
+                .with_default_quote_token_mint()
+                .with_graduation(SUPPLY_THRESHOLD, 0)
+                .with_default_market(),
+        );
+
+        // Bob buys up to the graduation
+        testing_env
+            .svm
+            .execute_actions(&[
+                &SwapAction::new(
+                    &testing_env,
+                    SwapType::Buy,
+                    SwapAmountType::ExactOutput,
+                    SUPPLY_THRESHOLD - 10_000_000_000,
+                    u64::MAX,
+                    None,
+                ),
+                &GraduateMarketAction::new(&testing_env),
+            ])
+            .unwrap();
+
+        let create_dca_order_action = CreateDcaOrderAction::new(
+            &testing_env,
+            0,
+            swap_type,
+            2_500_000_000,
+            1_000_000_000,
+            INTERVAL,
+        );
+
+        testing_env
+            .svm
+            .execute_actions(&[&create_dca_order_action])
+            .unwrap();
+
+        let quote_reserve_before = testing_env
+            .svm
+            .get_parsed_account::<Market>(&testing_env.market)
+            .graduation
+            .quote_reserve;
+
+        testing_env
+            .svm
+            .execute_actions(&[&ExecuteDcaAction::new(
+                &testing_env,
+                &create_dca_order_action,
+            )])
+            .unwrap();
+
+        let dca_order = testing_env
+            .svm
+            .get_parsed_account::<DcaOrder>(&create_dca_order_action.dca_order);
+        let quote_reserve = testing_env
+            .svm
+            .get_parsed_account::<Market>(&testing_env.market)
+            .graduation
+            .quote_reserve;
+
+        // The execution swaps against the pool, like limit order fills
+        assert_eq!(dca_order.amount_remaining, 1_500_000_000);
+        assert_eq!(
+            quote_reserve > quote_reserve_before,
+            swap_type == SwapType::Buy
+        );
+        assert_ne!(quote_reserve, quote_reserve_before);
+    }
+
+    #[test]
+    fn execute_dca_after_missed_executions() {
+        let mut testing_env = setup_env();
+
+        let create_dca_order_action = CreateDcaOrderAction::new(
+            &testing_env,
+            0,
+            SwapType::Buy,
+            5_000_000_000,
+            1_000_000_000,
+            INTERVAL,
+        );
+
+        let execute_dca_action = ExecuteDcaAction::new(&testing_env, &create_dca_order_action);
+
+        testing_env
+            .svm
+            .execute_actions(&[&create_dca_order_action, &execute_dca_action])
+            .unwrap();
+
+        testing_env.svm.warp(3 * INTERVAL);
+
+        testing_env
+            .svm
+            .execute_actions(&[&execute_dca_action])
+            .unwrap();
+
+        let dca_order = testing_env
+            .svm
+            .get_parsed_account::<DcaOrder>(&create_dca_order_action.dca_order);
+
+        // The missed executions aren't caught up
+        assert_eq!(dca_order.amount_remaining, 3_000_000_000);
+        assert_eq!(dca_order.next_execution_ts, CURRENT_TIME + 4 * INTERVAL);
+
+        let result = testing_env.svm.execute_actions(&[&execute_dca_action]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::DcaExecutionTooEarly
+        );
+    }
+
+    #[rstest]
+    fn execute_dca_with_price_limit_exceeded(
+        #[values(SwapType::Buy, SwapType::Sell)] swap_type: SwapType,
+    ) {
+        let mut testing_env = setup_env();
+
+        let mut create_dca_order_action = CreateDcaOrderAction::new(
+            &testing_env,
+            0,
+            swap_type,
+            2_000_000_000,
+            1_000_000_000,
+            INTERVAL,
+        );
+
+        // The spot price meets the limit price, but the execution moves the curve and pays the fees
+        create_dca_order_action.limit_price = testing_env
+            .svm
+            .get_parsed_account::<Market>(&testing_env.market)
+            .get_spot_price(match swap_type {
+                SwapType::Buy => swap_manager::SwapType::Buy,
+                SwapType::Sell => swap_manager::SwapType::Sell,
+            })
+            .unwrap();
+
+        testing_env
+            .svm
+            .execute_actions(&[&create_dca_order_action])
+            .unwrap();
+
+        let result = testing_env.svm.execute_actions(&[&ExecuteDcaAction::new(
+            &testing_env,
+            &create_dca_order_action,
+        )]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::PriceLimitExceeded
+        );
+    }
+
+    #[test]
+    fn execute_dca_with_transfer_hook() {
+        let mut testing_env = setup_env_with(
+            TokenMillEnv::new()
+                .with_default_quote_token_mint()
+                .with_transfer_hook()
+                .with_default_market(),
+        );
+
+        let create_dca_order_action = CreateDcaOrderAction::new(
+            &testing_env,
+            0,
+            SwapType::Sell,
+            2_500_000_000,
+            1_000_000_000,
+            INTERVAL,
+        );
+
+        testing_env
+            .svm
+            .execute_actions(&[
+                &create_dca_order_action,
+                &ExecuteDcaAction::new(&testing_env, &create_dca_order_action),
+                &CancelDcaOrderAction::new(&create_dca_order_action),
+            ])
+            .unwrap();
+
+        // The buy of the setup, the escrow, the execution and the refund
+        let counter =
+            testing_env
+                .svm
+                .get_parsed_account::<TransferCounter>(&get_transfer_counter_address(
+                    &create_dca_order_action.escrow_token_mint,
+                ));
+
+        assert_eq!(counter.transfers, 4);
+        assert_eq!(counter.amount, 15_000_000_000);
+    }
+
+    #[test]
+    fn execute_dca_with_invalid_owner_token_account() {
+        let mut testing_env = setup_env();
+
+        let create_dca_order_action = CreateDcaOrderAction::new(
+            &testing_env,
+            0,
+            SwapType::Buy,
+            2_000_000_000,
+            1_000_000_000,
+            INTERVAL,
+        );
+
+        testing_env
+            .svm
+            .execute_actions(&[&create_dca_order_action])
+            .unwrap();
+
+        let mut execute_dca_action = ExecuteDcaAction::new(&testing_env, &create_dca_order_action);
+        execute_dca_action.owner_token_account = testing_env.svm.get_ata_address(
+            &testing_env.base_token_mint.unwrap(),
+            &make_address("carol"),
+        );
+
+        let result = testing_env.svm.execute_actions(&[&execute_dca_action]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::InvalidAuthority
+        );
+    }
+}
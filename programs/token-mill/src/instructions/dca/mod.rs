@@ -0,0 +1,7 @@
+pub mod cancel_dca_order;
+pub mod create_dca_order;
+pub mod execute_dca;
+
+pub use cancel_dca_order::*;
+pub use create_dca_order::*;
+pub use execute_dca::*;
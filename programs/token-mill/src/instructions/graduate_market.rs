@@ -0,0 +1,326 @@
+use anchor_lang::prelude::*;
+
+use crate::{events::TokenMillGraduationEvent, state::Market};
+
+/// Permissionless, anyone can graduate a market once its graduation threshold is reached.
+#[event_cpi]
+#[derive(Accounts)]
+pub struct GraduateMarket<'info> {
+    #[account(mut)]
+    pub market: AccountLoader<'info, Market>,
+}
+
+pub fn handler(ctx: Context<GraduateMarket>) -> Result<()> {
+    let market = &mut ctx.accounts.market.load_mut()?;
+
+    market.graduate()?;
+
+    emit_cpi!(TokenMillGraduationEvent {
+        market: ctx.accounts.market.key(),
+        circulating_supply: market.circulating_supply(),
+        base_reserve: market.base_reserve,
+        quote_reserve: market.graduation.quote_reserve,
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Market;
+    use joelana_test_utils::joelana_env::{
+        actions::token_mill::{
+            tm_parse_error, GraduateMarketAction, SwapAction, TokenMillEnv, DEFAULT_TOTAL_SUPPLY,
+        },
+        SwapAmountType, SwapType, TokenMillError,
+    };
+    use rstest::rstest;
+
+    const SUPPLY_THRESHOLD: u64 = DEFAULT_TOTAL_SUPPLY / 2;
+
+    fn setup_env() -> (TokenMillEnv, SwapAction) {
+        let mut testing_env = TokenMillEnv::new()
+            .with_default_quote_token_mint()
+            .with_graduation(SUPPLY_THRESHOLD, 0)
+            .with_default_market();
+        testing_env.svm.change_payer("bob");
+
+        let action = SwapAction::new(
+            &testing_env,
+            SwapType::Buy,
+            SwapAmountType::ExactOutput,
+            SUPPLY_THRESHOLD,
+            u64::MAX,
+            None,
+        );
+
+        (testing_env, action)
+    }
+
+    #[test]
+    fn graduate_market() {
+        let (mut testing_env, swap_action) = setup_env();
+
+        testing_env.svm.execute_actions(&[&swap_action]).unwrap();
+
+        let market = testing_env
+            .svm
+            .get_parsed_account::<Market>(&testing_env.market);
+        let quote_raised = market.quote_raised().unwrap();
+
+        let market_quote_balance = testing_env
+            .svm
+            .get_balance(&testing_env.quote_token_mint.unwrap(), &testing_env.market);
+
+        assert!(quote_raised > 0);
+        assert!(quote_raised <= market_quote_balance);
+
+        let result = testing_env
+            .svm
+            .execute_actions(&[&GraduateMarketAction::new(&testing_env)]);
+
+        assert!(result.is_ok());
+
+        let market = testing_env
+            .svm
+            .get_parsed_account::<Market>(&testing_env.market);
+
+        assert!(market.is_graduated());
+        assert_eq!(market.base_reserve, DEFAULT_TOTAL_SUPPLY - SUPPLY_THRESHOLD);
+        assert_eq!(market.graduation.quote_reserve, quote_raised);
+    }
+
+    #[test]
+    fn graduate_market_twice() {
+        let (mut testing_env, swap_action) = setup_env();
+
+        testing_env.svm.execute_actions(&[&swap_action]).unwrap();
+
+        let action = GraduateMarketAction::new(&testing_env);
+
+        testing_env.svm.execute_actions(&[&action]).unwrap();
+
+        let result = testing_env.svm.execute_actions(&[&action]);
+
+        assert!(result.is_err());
+
+        let error = tm_parse_error(result).unwrap();
+
+        assert_eq!(error, TokenMillError::MarketGraduated);
+    }
+
+    #[test]
+    fn graduate_market_before_threshold() {
+        let (mut testing_env, mut swap_action) = setup_env();
+
+        swap_action.amount = SUPPLY_THRESHOLD - 1;
+
+        testing_env.svm.execute_actions(&[&swap_action]).unwrap();
+
+        let result = testing_env
+            .svm
+            .execute_actions(&[&GraduateMarketAction::new(&testing_env)]);
+
+        assert!(result.is_err());
+
+        let error = tm_parse_error(result).unwrap();
+
+        assert_eq!(error, TokenMillError::GraduationThresholdNotReached);
+    }
+
+    #[test]
+    fn graduate_market_with_quote_threshold() {
+        let mut testing_env = TokenMillEnv::new()
+            .with_default_quote_token_mint()
+            .with_graduation(0, 1_000_000_000)
+            .with_default_market();
+        testing_env.svm.change_payer("bob");
+
+        let swap_action = SwapAction::new(
+            &testing_env,
+            SwapType::Buy,
+            SwapAmountType::ExactInput,
+            2_000_000_000,
+            0,
+            None,
+        );
+
+        testing_env.svm.execute_actions(&[&swap_action]).unwrap();
+
+        let market = testing_env
+            .svm
+            .get_parsed_account::<Market>(&testing_env.market);
+
+        assert!(market.quote_raised().unwrap() >= 1_000_000_000);
+
+        let result = testing_env
+            .svm
+            .execute_actions(&[&GraduateMarketAction::new(&testing_env)]);
+
+        assert!(result.is_ok());
+    }
+
+    #[rstest]
+    #[case(SUPPLY_THRESHOLD, 0, DEFAULT_TOTAL_SUPPLY - SUPPLY_THRESHOLD)]
+    #[case(0, 1_000_000_000, 1)]
+    fn graduate_market_after_buying_out_supply(
+        #[case] supply_threshold: u64,
+        #[case] quote_threshold: u64,
+        #[case] expected_base_reserve: u64,
+    ) {
+        let mut testing_env = TokenMillEnv::new()
+            .with_default_quote_token_mint()
+            .with_graduation(supply_threshold, quote_threshold)
+            .with_default_market();
+        testing_env.svm.change_payer("bob");
+
+        // The buy is capped, leaving the base reserve the pool is seeded with
+        testing_env
+            .svm
+            .execute_actions(&[&SwapAction::new(
+                &testing_env,
+                SwapType::Buy,
+                SwapAmountType::ExactOutput,
+                DEFAULT_TOTAL_SUPPLY,
+                u64::MAX,
+                None,
+            )])
+            .unwrap();
+
+        let market = testing_env
+            .svm
+            .get_parsed_account::<Market>(&testing_env.market);
+
+        assert_eq!(market.base_reserve, expected_base_reserve);
+
+        testing_env
+            .svm
+            .execute_actions(&[&GraduateMarketAction::new(&testing_env)])
+            .unwrap();
+
+        let market = testing_env
+            .svm
+            .get_parsed_account::<Market>(&testing_env.market);
+
+        assert!(market.is_graduated());
+        assert_eq!(market.base_reserve, expected_base_reserve);
+    }
+
+    #[test]
+    fn sell_before_graduation() {
+        let (mut testing_env, mut swap_action) = setup_env();
+
+        testing_env.svm.execute_actions(&[&swap_action]).unwrap();
+
+        // Sells go through while the graduation is pending, bringing the market back below its threshold
+        swap_action.swap_type = SwapType::Sell;
+        swap_action.swap_amount_type = SwapAmountType::ExactInput;
+        swap_action.amount = 1_000_000;
+        swap_action.other_amount_threshold = 0;
+
+        testing_env.svm.execute_actions(&[&swap_action]).unwrap();
+
+        let market = testing_env
+            .svm
+            .get_parsed_account::<Market>(&testing_env.market);
+
+        assert!(!market.is_graduation_threshold_reached().unwrap());
+
+        let result = testing_env
+            .svm
+            .execute_actions(&[&GraduateMarketAction::new(&testing_env)]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::GraduationThresholdNotReached
+        );
+    }
+
+    #[test]
+    fn swap_across_graduation() {
+        let (mut testing_env, mut swap_action) = setup_env();
+
+        // The buy reaching the threshold goes through, then buys wait for the graduation
+        testing_env.svm.execute_actions(&[&swap_action]).unwrap();
+
+        swap_action.amount = 1_000_000;
+
+        let result = testing_env.svm.execute_actions(&[&swap_action]);
+
+        assert!(result.is_err());
+
+        let error = tm_parse_error(result).unwrap();
+
+        assert_eq!(error, TokenMillError::MarketGraduationPending);
+
+        testing_env
+            .svm
+            .execute_actions(&[&GraduateMarketAction::new(&testing_env)])
+            .unwrap();
+
+        let market_before = testing_env
+            .svm
+            .get_parsed_account::<Market>(&testing_env.market);
+        let k_before = u128::from(market_before.base_reserve)
+            * u128::from(market_before.graduation.quote_reserve);
+
+        for (swap_type, swap_amount_type, amount) in [
+            (SwapType::Buy, SwapAmountType::ExactOutput, 1_000_000_000),
+            (SwapType::Buy, SwapAmountType::ExactInput, 1_000_000_000),
+            (SwapType::Sell, SwapAmountType::ExactInput, 1_000_000_000),
+            (SwapType::Sell, SwapAmountType::ExactOutput, 1_000_000),
+        ] {
+            swap_action.swap_type = swap_type;
+            swap_action.swap_amount_type = swap_amount_type;
+            swap_action.amount = amount;
+            swap_action.other_amount_threshold = match swap_amount_type {
+                SwapAmountType::ExactInput => 0,
+                SwapAmountType::ExactOutput => u64::MAX,
+            };
+
+            testing_env.svm.execute_actions(&[&swap_action]).unwrap();
+        }
+
+        let market_after = testing_env
+            .svm
+            .get_parsed_account::<Market>(&testing_env.market);
+        let k_after = u128::from(market_after.base_reserve)
+            * u128::from(market_after.graduation.quote_reserve);
+
+        assert!(k_after >= k_before);
+
+        // Pool reserves and pending fees are always backed by the market balance
+        let market_quote_balance = testing_env
+            .svm
+            .get_balance(&testing_env.quote_token_mint.unwrap(), &testing_env.market);
+
+        assert!(
+            market_after.graduation.quote_reserve
+                + market_after.fees.pending_creator_fees
+                + market_after.fees.pending_staking_fees
+                + market_after.fees.pending_protocol_fees
+                <= market_quote_balance
+        );
+    }
+
+    #[test]
+    fn swap_on_graduated_market_with_invalid_amount_out() {
+        let (mut testing_env, mut swap_action) = setup_env();
+
+        testing_env.svm.execute_actions(&[&swap_action]).unwrap();
+        testing_env
+            .svm
+            .execute_actions(&[&GraduateMarketAction::new(&testing_env)])
+            .unwrap();
+
+        swap_action.swap_amount_type = SwapAmountType::ExactInput;
+        swap_action.amount = 1_000_000_000;
+        swap_action.other_amount_threshold = u64::MAX;
+
+        let result = testing_env.svm.execute_actions(&[&swap_action]);
+
+        let error = tm_parse_error(result).unwrap();
+
+        assert_eq!(error, TokenMillError::AmountThresholdNotMet);
+    }
+}
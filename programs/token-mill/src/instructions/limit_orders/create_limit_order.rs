@@ -0,0 +1,271 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{
+    errors::TokenMillError,
+    events::TokenMillLimitOrderCreationEvent,
+    manager::{swap_manager::SwapType, token_manager::transfer_from_eoa_with_hook},
+    state::{CurveKind, LimitOrder, Market, LIMIT_ORDER_PDA_SEED},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(order_id: u64)]
+pub struct CreateLimitOrder<'info> {
+    pub market: AccountLoader<'info, Market>,
+
+    #[account(
+        init,
+        seeds = [
+            LIMIT_ORDER_PDA_SEED.as_bytes(),
+            market.key().as_ref(),
+            owner.key().as_ref(),
+            &order_id.to_le_bytes()
+        ],
+        bump,
+        payer = owner,
+        space = 8 + LimitOrder::INIT_SPACE
+    )]
+    pub limit_order: Account<'info, LimitOrder>,
+
+    pub escrow_token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = owner,
+        associated_token::mint = escrow_token_mint,
+        associated_token::authority = limit_order,
+        associated_token::token_program = escrow_token_program
+    )]
+    pub limit_order_escrow: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut, token::mint = escrow_token_mint)]
+    pub owner_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub escrow_token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, CreateLimitOrder<'info>>,
+    order_id: u64,
+    swap_type: SwapType,
+    amount: u64,
+    limit_price: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Err(TokenMillError::InvalidAmount.into());
+    }
+
+    let escrow_token_mint = ctx.accounts.escrow_token_mint.key();
+
+    {
+        let market = ctx.accounts.market.load()?;
+
+        // Orders are triggered by the spot price of the price curves, or of the pool once the market graduated
+        if market.curve_kind() != CurveKind::PiecewiseLinear || !market.are_prices_set() {
+            return Err(TokenMillError::InvalidMarket.into());
+        }
+
+        let expected_escrow_token_mint = match swap_type {
+            SwapType::Buy => market.quote_token_mint,
+            SwapType::Sell => market.base_token_mint,
+        };
+
+        if escrow_token_mint != expected_escrow_token_mint {
+            return Err(TokenMillError::InvalidMintAccount.into());
+        }
+    }
+
+    // Quote tokens with a transfer fee credit the escrow less than `amount`
+    let amount = transfer_from_eoa_with_hook(
+        &ctx.accounts.escrow_token_mint,
+        &ctx.accounts.owner,
+        &ctx.accounts.owner_token_account,
+        &ctx.accounts.limit_order_escrow,
+        &ctx.accounts.escrow_token_program,
+        amount,
+        ctx.remaining_accounts,
+    )?;
+
+    let limit_order = &mut ctx.accounts.limit_order;
+
+    limit_order.initialize(
+        ctx.bumps.limit_order,
+        ctx.accounts.market.key(),
+        ctx.accounts.owner.key(),
+        order_id,
+        swap_type,
+        escrow_token_mint,
+        amount,
+        limit_price,
+    )?;
+
+    emit_cpi!(TokenMillLimitOrderCreationEvent {
+        market: ctx.accounts.market.key(),
+        owner: ctx.accounts.owner.key(),
+        limit_order: ctx.accounts.limit_order.key(),
+        swap_type,
+        amount,
+        limit_price,
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::state::LimitOrder;
+    use joelana_test_utils::joelana_env::{
+        actions::token_mill::{
+            get_transfer_counter_address, tm_parse_error, CancelLimitOrderAction,
+            CreateLimitOrderAction, SwapAction, TokenMillEnv,
+        },
+        make_address, CurveKind, SwapAmountType, SwapType, TokenMillError,
+    };
+    use mock_transfer_hook::TransferCounter;
+    use rstest::rstest;
+
+    const ORDER_AMOUNT: u64 = 1_000_000_000;
+
+    fn setup_env() -> TokenMillEnv {
+        setup_env_with(TokenMillEnv::default())
+    }
+
+    fn setup_env_with(mut testing_env: TokenMillEnv) -> TokenMillEnv {
+        testing_env.svm.change_payer("bob");
+
+        // Gives bob some base tokens to sell
+        let swap_action = SwapAction::new(
+            &testing_env,
+            SwapType::Buy,
+            SwapAmountType::ExactOutput,
+            ORDER_AMOUNT,
+            u64::MAX,
+            None,
+        );
+
+        testing_env.svm.execute_actions(&[&swap_action]).unwrap();
+
+        testing_env
+    }
+
+    #[rstest]
+    fn create_limit_order(#[values(SwapType::Buy, SwapType::Sell)] swap_type: SwapType) {
+        let mut testing_env = setup_env();
+
+        let create_limit_order_action =
+            CreateLimitOrderAction::new(&testing_env, 7, swap_type, ORDER_AMOUNT, 1_000_000);
+
+        testing_env
+            .svm
+            .execute_actions(&[&create_limit_order_action])
+            .unwrap();
+
+        let limit_order = testing_env
+            .svm
+            .get_parsed_account::<LimitOrder>(&create_limit_order_action.limit_order);
+
+        assert_eq!(limit_order.market, testing_env.market);
+        assert_eq!(limit_order.owner, make_address("bob"));
+        assert_eq!(limit_order.order_id, 7);
+        assert_eq!(limit_order.swap_type as u8, swap_type as u8);
+        assert_eq!(
+            limit_order.escrow_token_mint,
+            create_limit_order_action.escrow_token_mint
+        );
+        assert_eq!(limit_order.amount_remaining, ORDER_AMOUNT);
+        assert_eq!(limit_order.limit_price, 1_000_000);
+
+        assert_eq!(
+            testing_env.svm.get_balance(
+                &create_limit_order_action.escrow_token_mint,
+                &create_limit_order_action.limit_order
+            ),
+            ORDER_AMOUNT
+        );
+    }
+
+    #[test]
+    fn limit_order_with_transfer_hook() {
+        let mut testing_env = setup_env_with(
+            TokenMillEnv::new()
+                .with_default_quote_token_mint()
+                .with_transfer_hook()
+                .with_default_market(),
+        );
+
+        let create_limit_order_action =
+            CreateLimitOrderAction::new(&testing_env, 0, SwapType::Sell, ORDER_AMOUNT, 1_000_000);
+
+        testing_env
+            .svm
+            .execute_actions(&[
+                &create_limit_order_action,
+                &CancelLimitOrderAction::new(&create_limit_order_action),
+            ])
+            .unwrap();
+
+        // The buy of the setup, the escrow and the refund
+        let counter =
+            testing_env
+                .svm
+                .get_parsed_account::<TransferCounter>(&get_transfer_counter_address(
+                    &create_limit_order_action.escrow_token_mint,
+                ));
+
+        assert_eq!(counter.transfers, 3);
+        assert_eq!(counter.amount, 3 * ORDER_AMOUNT);
+    }
+
+    #[test]
+    fn create_limit_order_with_invalid_escrow_token_mint() {
+        let mut testing_env = setup_env();
+
+        // Escrows base tokens for a buy order
+        let mut create_limit_order_action =
+            CreateLimitOrderAction::new(&testing_env, 0, SwapType::Sell, ORDER_AMOUNT, 1_000_000);
+        create_limit_order_action.swap_type = SwapType::Buy;
+
+        let result = testing_env
+            .svm
+            .execute_actions(&[&create_limit_order_action]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::InvalidMintAccount
+        );
+    }
+
+    #[test]
+    fn create_limit_order_with_virtual_constant_product() {
+        let mut testing_env = TokenMillEnv::new()
+            .with_default_quote_token_mint()
+            .with_curve_kind(CurveKind::VirtualConstantProduct {
+                virtual_base: 1_100_000_000_000,
+                virtual_quote: 30_000_000_000,
+                spread_bps: 100,
+            })
+            .with_default_market();
+        testing_env.svm.change_payer("bob");
+
+        let create_limit_order_action =
+            CreateLimitOrderAction::new(&testing_env, 0, SwapType::Buy, ORDER_AMOUNT, 1_000_000);
+
+        let result = testing_env
+            .svm
+            .execute_actions(&[&create_limit_order_action]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::InvalidMarket
+        );
+    }
+}
@@ -0,0 +1,742 @@
+use std::cmp::min;
+
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::{
+    constant::{LIMIT_ORDER_KEEPER_TIP_BPS, MAX_BPS},
+    errors::TokenMillError,
+    events::{TokenMillLimitOrderFillEvent, TokenMillSwapEvent},
+    manager::{
+        launch_manager::{check_presale, update_launch_allocation},
+        oracle_manager::update_market_oracle,
+        stats_manager::update_market_stats,
+        swap_manager::{self, SwapAmountType, SwapResult, SwapType},
+        token_manager::{
+            get_epoch_transfer_fee, get_transfer_fee_amount, transfer_from_pda,
+            transfer_from_pda_with_hook,
+        },
+    },
+    math::{mul_div, Rounding},
+    state::{LimitOrder, Market, MarketOracle, MarketStats, TokenMillConfig, LIMIT_ORDER_PDA_SEED},
+    MARKET_PDA_SEED,
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct FillLimitOrder<'info> {
+    pub config: Account<'info, TokenMillConfig>,
+
+    #[account(
+        mut,
+        has_one = config @ TokenMillError::InvalidConfigAccount,
+        has_one = base_token_mint @ TokenMillError::InvalidMintAccount,
+        has_one = quote_token_mint @ TokenMillError::InvalidMintAccount
+    )]
+    pub market: AccountLoader<'info, Market>,
+
+    #[account(mut, has_one = market @ TokenMillError::InvalidMarket)]
+    pub limit_order: Account<'info, LimitOrder>,
+
+    pub base_token_mint: InterfaceAccount<'info, Mint>,
+
+    pub quote_token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = base_token_mint,
+        associated_token::authority = market,
+        associated_token::token_program = base_token_program
+    )]
+    pub market_base_token_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = quote_token_mint,
+        associated_token::authority = market,
+        associated_token::token_program = quote_token_program
+    )]
+    pub market_quote_token_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::authority = limit_order,
+        constraint = limit_order_escrow.mint == limit_order.escrow_token_mint @ TokenMillError::InvalidMintAccount
+    )]
+    pub limit_order_escrow: InterfaceAccount<'info, TokenAccount>,
+
+    /// Receives the base tokens bought or the quote tokens of the sale
+    #[account(
+        mut,
+        constraint = owner_token_account.owner == limit_order.owner @ TokenMillError::InvalidAuthority
+    )]
+    pub owner_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut, token::mint = quote_token_mint)]
+    pub keeper_quote_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Required once the market has an oracle
+    #[account(mut, has_one = market @ TokenMillError::InvalidMarket)]
+    pub market_oracle: Option<Box<Account<'info, MarketOracle>>>,
+
+    /// Required once the market has stats
+    #[account(mut, has_one = market @ TokenMillError::InvalidMarket)]
+    pub market_stats: Option<Box<Account<'info, MarketStats>>>,
+
+    pub keeper: Signer<'info>,
+
+    pub base_token_program: Interface<'info, TokenInterface>,
+
+    pub quote_token_program: Interface<'info, TokenInterface>,
+}
+
+/// Fills up to `amount` of the escrowed tokens, against the constant product pool once the market graduated.
+/// The keeper tip is paid in quote tokens, out of the escrowed quote tokens for buys and out of the sale proceeds for sells.
+pub fn handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, FillLimitOrder<'info>>,
+    amount: u64,
+) -> Result<()> {
+    let swap_type = ctx.accounts.limit_order.swap_type;
+    let amount = min(amount, ctx.accounts.limit_order.amount_remaining);
+
+    if amount == 0 {
+        return Err(TokenMillError::InvalidAmount.into());
+    }
+
+    let mint_out = match swap_type {
+        SwapType::Buy => &ctx.accounts.base_token_mint,
+        SwapType::Sell => &ctx.accounts.quote_token_mint,
+    };
+
+    if ctx.accounts.owner_token_account.mint != mint_out.key() {
+        return Err(TokenMillError::InvalidMintAccount.into());
+    }
+
+    let quote_transfer_fee = get_epoch_transfer_fee(&ctx.accounts.quote_token_mint)?;
+
+    let base_amount;
+    let quote_amount;
+    let swap_fee;
+    let buy_tax;
+    let transfer_fee;
+    let keeper_tip;
+    let market_bump;
+    let creator_fee;
+    let staking_fee;
+    let protocol_fee;
+
+    {
+        let limit_order = &ctx.accounts.limit_order;
+        let market = &mut ctx.accounts.market.load_mut()?;
+
+        let current_time = Clock::get()?.unix_timestamp;
+
+        ctx.accounts.config.check_not_paused(swap_type.into())?;
+        market.check_swap_allowed(swap_type, current_time)?;
+
+        if !limit_order.is_fillable(market.get_spot_price(swap_type)?) {
+            return Err(TokenMillError::LimitOrderNotFillable.into());
+        }
+
+        let owner_quote_amount = match swap_type {
+            SwapType::Buy => {
+                keeper_tip = get_keeper_tip(amount)?;
+
+                if keeper_tip >= amount {
+                    return Err(TokenMillError::InvalidAmount.into());
+                }
+
+                SwapResult {
+                    base_amount,
+                    quote_amount,
+                    swap_fee,
+                    buy_tax,
+                    transfer_fee,
+                    ..
+                } = swap_manager::swap_with_transfer_fee(
+                    market,
+                    swap_type,
+                    SwapAmountType::ExactInput,
+                    amount - keeper_tip,
+                    current_time,
+                    quote_transfer_fee.as_ref(),
+                    0,
+                )?;
+
+                quote_amount + transfer_fee + keeper_tip
+            }
+            SwapType::Sell => {
+                (base_amount, quote_amount, swap_fee) =
+                    swap_manager::swap(market, swap_type, SwapAmountType::ExactInput, amount)?;
+                buy_tax = 0;
+
+                keeper_tip = get_keeper_tip(quote_amount)?;
+
+                // The owner bears the transfer fee on the proceeds sent out of the market
+                transfer_fee = get_transfer_fee_amount(
+                    quote_transfer_fee.as_ref(),
+                    quote_amount - keeper_tip,
+                )?;
+
+                quote_amount - keeper_tip - transfer_fee
+            }
+        };
+
+        if base_amount == 0 || owner_quote_amount == 0 {
+            return Err(TokenMillError::InvalidAmount.into());
+        }
+
+        // The curve moves along the fill, its average price must also satisfy the order
+        if !limit_order.is_fill_price_met(
+            base_amount,
+            owner_quote_amount,
+            market.quote_token_decimals,
+        ) {
+            return Err(TokenMillError::PriceLimitExceeded.into());
+        }
+
+        (creator_fee, staking_fee, protocol_fee, _) =
+            market.distribute_fees(swap_fee, buy_tax, None)?;
+
+        update_market_oracle(market, &mut ctx.accounts.market_oracle)?;
+        update_market_stats(
+            market,
+            &mut ctx.accounts.market_stats,
+            swap_type,
+            base_amount,
+            quote_amount,
+            (creator_fee, staking_fee, protocol_fee, 0),
+        )?;
+        // Orders carry no launch allocation nor presale proof, they can't buy during the launch window or the presale
+        check_presale(
+            market,
+            &ctx.accounts.limit_order.owner,
+            None,
+            &mut None,
+            swap_type,
+            base_amount,
+            current_time,
+        )?;
+        update_launch_allocation(market, &mut None, swap_type, base_amount, current_time)?;
+
+        market_bump = market.bump;
+    }
+
+    let limit_order = &mut ctx.accounts.limit_order;
+
+    limit_order.amount_remaining -= match swap_type {
+        SwapType::Buy => quote_amount + transfer_fee + keeper_tip,
+        SwapType::Sell => base_amount,
+    };
+
+    let base_token_mint_key = ctx.accounts.base_token_mint.key();
+    let market_seeds = [
+        MARKET_PDA_SEED.as_bytes(),
+        base_token_mint_key.as_ref(),
+        &[market_bump],
+    ];
+
+    let order_id_bytes = limit_order.order_id.to_le_bytes();
+    let limit_order_seeds = [
+        LIMIT_ORDER_PDA_SEED.as_bytes(),
+        limit_order.market.as_ref(),
+        limit_order.owner.as_ref(),
+        order_id_bytes.as_ref(),
+        &[limit_order.bump],
+    ];
+
+    match swap_type {
+        SwapType::Buy => {
+            transfer_from_pda(
+                &ctx.accounts.quote_token_mint,
+                limit_order.to_account_info(),
+                &ctx.accounts.limit_order_escrow,
+                &ctx.accounts.market_quote_token_ata,
+                &ctx.accounts.quote_token_program,
+                quote_amount + transfer_fee,
+                &limit_order_seeds,
+            )?;
+
+            transfer_from_pda_with_hook(
+                &ctx.accounts.base_token_mint,
+                ctx.accounts.market.to_account_info(),
+                &ctx.accounts.market_base_token_ata,
+                &ctx.accounts.owner_token_account,
+                &ctx.accounts.base_token_program,
+                base_amount,
+                &market_seeds,
+                ctx.remaining_accounts,
+            )?;
+
+            if keeper_tip > 0 {
+                transfer_from_pda(
+                    &ctx.accounts.quote_token_mint,
+                    limit_order.to_account_info(),
+                    &ctx.accounts.limit_order_escrow,
+                    &ctx.accounts.keeper_quote_token_account,
+                    &ctx.accounts.quote_token_program,
+                    keeper_tip,
+                    &limit_order_seeds,
+                )?;
+            }
+        }
+        SwapType::Sell => {
+            transfer_from_pda_with_hook(
+                &ctx.accounts.base_token_mint,
+                limit_order.to_account_info(),
+                &ctx.accounts.limit_order_escrow,
+                &ctx.accounts.market_base_token_ata,
+                &ctx.accounts.base_token_program,
+                base_amount,
+                &limit_order_seeds,
+                ctx.remaining_accounts,
+            )?;
+
+            transfer_from_pda(
+                &ctx.accounts.quote_token_mint,
+                ctx.accounts.market.to_account_info(),
+                &ctx.accounts.market_quote_token_ata,
+                &ctx.accounts.owner_token_account,
+                &ctx.accounts.quote_token_program,
+                quote_amount - keeper_tip,
+                &market_seeds,
+            )?;
+
+            if keeper_tip > 0 {
+                transfer_from_pda(
+                    &ctx.accounts.quote_token_mint,
+                    ctx.accounts.market.to_account_info(),
+                    &ctx.accounts.market_quote_token_ata,
+                    &ctx.accounts.keeper_quote_token_account,
+                    &ctx.accounts.quote_token_program,
+                    keeper_tip,
+                    &market_seeds,
+                )?;
+            }
+        }
+    }
+
+    emit_cpi!(TokenMillSwapEvent {
+        user: limit_order.owner,
+        market: ctx.accounts.market.key(),
+        swap_type,
+        base_amount,
+        quote_amount,
+        referral_token_account: None,
+        creator_fee,
+        staking_fee,
+        protocol_fee,
+        referral_fee: 0,
+        sub_referral_fee: 0,
+        buy_tax,
+        fee_discount: 0,
+    });
+
+    emit_cpi!(TokenMillLimitOrderFillEvent {
+        limit_order: limit_order.key(),
+        keeper: ctx.accounts.keeper.key(),
+        base_amount,
+        quote_amount,
+        keeper_tip,
+        amount_remaining: limit_order.amount_remaining,
+    });
+
+    Ok(())
+}
+
+/// Keeper tip taken from `amount`, rounded down in favor of the order owner.
+fn get_keeper_tip(amount: u64) -> Result<u64> {
+    let keeper_tip = mul_div(
+        amount.into(),
+        LIMIT_ORDER_KEEPER_TIP_BPS.into(),
+        MAX_BPS.into(),
+        Rounding::Down,
+    )
+    .ok_or(TokenMillError::MathError)?;
+
+    u64::try_from(keeper_tip).map_err(|_| TokenMillError::MathError.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        manager::swap_manager,
+        state::{LimitOrder, Market},
+    };
+    use joelana_test_utils::joelana_env::{
+        actions::token_mill::{
+            tm_parse_error, CreateLimitOrderAction, FillLimitOrderAction, GraduateMarketAction,
+            SwapAction, TokenMillEnv, DEFAULT_TOTAL_SUPPLY,
+        },
+        make_address, PriceInterpolation, SwapAmountType, SwapType, TokenMillError,
+    };
+    use rstest::rstest;
+
+    fn setup_env(price_interpolation: PriceInterpolation) -> TokenMillEnv {
+        let mut testing_env = TokenMillEnv::new()
+            .with_default_quote_token_mint()
+            .with_price_interpolation(price_interpolation)
+            .with_default_market();
+
+        testing_env.svm.change_payer("bob");
+
+        testing_env
+    }
+
+    fn swap(
+        testing_env: &mut TokenMillEnv,
+        signer: &str,
+        swap_type: SwapType,
+        swap_amount_type: SwapAmountType,
+        amount: u64,
+    ) {
+        let mut swap_action = SwapAction::new(
+            testing_env,
+            swap_type,
+            swap_amount_type,
+            amount,
+            match swap_amount_type {
+                SwapAmountType::ExactInput => 0,
+                SwapAmountType::ExactOutput => u64::MAX,
+            },
+            None,
+        );
+
+        swap_action.signer = make_address(signer);
+        swap_action.user_base_token_ata = testing_env
+            .svm
+            .get_ata_address(&swap_action.base_token_mint, &swap_action.signer);
+        swap_action.user_quote_token_ata = testing_env
+            .svm
+            .get_ata_address(&swap_action.quote_token_mint, &swap_action.signer);
+
+        testing_env.svm.execute_actions(&[&swap_action]).unwrap();
+    }
+
+    fn get_spot_price(testing_env: &TokenMillEnv, swap_type: swap_manager::SwapType) -> u64 {
+        testing_env
+            .svm
+            .get_parsed_account::<Market>(&testing_env.market)
+            .get_spot_price(swap_type)
+            .unwrap()
+    }
+
+    #[rstest]
+    fn fill_buy_limit_order(
+        #[values(PriceInterpolation::Linear, PriceInterpolation::Exponential)]
+        price_interpolation: PriceInterpolation,
+    ) {
+        let mut testing_env = setup_env(price_interpolation);
+        let bob = make_address("bob");
+        let carol = make_address("carol");
+        let base_token_mint = testing_env.base_token_mint.unwrap();
+        let quote_token_mint = testing_env.quote_token_mint.unwrap();
+
+        swap(
+            &mut testing_env,
+            "carol",
+            SwapType::Buy,
+            SwapAmountType::ExactOutput,
+            200_000_000_000,
+        );
+
+        let limit_price = get_spot_price(&testing_env, swap_manager::SwapType::Buy) * 4 / 5;
+
+        let create_limit_order_action =
+            CreateLimitOrderAction::new(&testing_env, 0, SwapType::Buy, 2_000_000_000, limit_price);
+
+        testing_env
+            .svm
+            .execute_actions(&[&create_limit_order_action])
+            .unwrap();
+
+        let mut fill_limit_order_action =
+            FillLimitOrderAction::new(&testing_env, &create_limit_order_action, u64::MAX);
+
+        let result = testing_env.svm.execute_actions(&[&fill_limit_order_action]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::LimitOrderNotFillable
+        );
+
+        // The ask price drops below the limit price
+        swap(
+            &mut testing_env,
+            "carol",
+            SwapType::Sell,
+            SwapAmountType::ExactInput,
+            100_000_000_000,
+        );
+
+        let carol_quote_balance_before = testing_env.svm.get_balance(&quote_token_mint, &carol);
+
+        // Partial fill, the keeper tip being rounded down
+        fill_limit_order_action.amount = 1_000_009_999;
+
+        testing_env
+            .svm
+            .execute_actions(&[&fill_limit_order_action])
+            .unwrap();
+
+        let limit_order = testing_env
+            .svm
+            .get_parsed_account::<LimitOrder>(&create_limit_order_action.limit_order);
+
+        assert_eq!(limit_order.amount_remaining, 999_990_001);
+        assert_eq!(
+            testing_env
+                .svm
+                .get_balance(&quote_token_mint, &create_limit_order_action.limit_order),
+            999_990_001
+        );
+        assert!(testing_env.svm.get_balance(&base_token_mint, &bob) > 0);
+        assert_eq!(
+            testing_env.svm.get_balance(&quote_token_mint, &carol) - carol_quote_balance_before,
+            1_000_009
+        );
+
+        // Full fill
+        fill_limit_order_action.amount = u64::MAX;
+
+        testing_env
+            .svm
+            .execute_actions(&[&fill_limit_order_action])
+            .unwrap();
+
+        let limit_order = testing_env
+            .svm
+            .get_parsed_account::<LimitOrder>(&create_limit_order_action.limit_order);
+
+        assert_eq!(limit_order.amount_remaining, 0);
+        assert_eq!(
+            testing_env
+                .svm
+                .get_balance(&quote_token_mint, &create_limit_order_action.limit_order),
+            0
+        );
+
+        let result = testing_env.svm.execute_actions(&[&fill_limit_order_action]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::InvalidAmount
+        );
+    }
+
+    #[rstest]
+    fn fill_sell_limit_order(
+        #[values(PriceInterpolation::Linear, PriceInterpolation::Exponential)]
+        price_interpolation: PriceInterpolation,
+    ) {
+        let mut testing_env = setup_env(price_interpolation);
+        let bob = make_address("bob");
+        let carol = make_address("carol");
+        let base_token_mint = testing_env.base_token_mint.unwrap();
+        let quote_token_mint = testing_env.quote_token_mint.unwrap();
+
+        swap(
+            &mut testing_env,
+            "bob",
+            SwapType::Buy,
+            SwapAmountType::ExactOutput,
+            100_000_000_000,
+        );
+
+        let limit_price = get_spot_price(&testing_env, swap_manager::SwapType::Sell) * 2;
+
+        let create_limit_order_action = CreateLimitOrderAction::new(
+            &testing_env,
+            0,
+            SwapType::Sell,
+            10_000_000_000,
+            limit_price,
+        );
+
+        testing_env
+            .svm
+            .execute_actions(&[&create_limit_order_action])
+            .unwrap();
+
+        assert_eq!(
+            testing_env.svm.get_balance(&base_token_mint, &bob),
+            90_000_000_000
+        );
+
+        let mut fill_limit_order_action =
+            FillLimitOrderAction::new(&testing_env, &create_limit_order_action, u64::MAX);
+
+        let result = testing_env.svm.execute_actions(&[&fill_limit_order_action]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::LimitOrderNotFillable
+        );
+
+        // The bid price rises above the limit price
+        swap(
+            &mut testing_env,
+            "carol",
+            SwapType::Buy,
+            SwapAmountType::ExactOutput,
+            400_000_000_000,
+        );
+
+        let bob_quote_balance_before = testing_env.svm.get_balance(&quote_token_mint, &bob);
+        let carol_quote_balance_before = testing_env.svm.get_balance(&quote_token_mint, &carol);
+
+        // Partial fill
+        fill_limit_order_action.amount = 4_000_000_000;
+
+        testing_env
+            .svm
+            .execute_actions(&[&fill_limit_order_action])
+            .unwrap();
+
+        let limit_order = testing_env
+            .svm
+            .get_parsed_account::<LimitOrder>(&create_limit_order_action.limit_order);
+
+        let bob_quote_amount =
+            testing_env.svm.get_balance(&quote_token_mint, &bob) - bob_quote_balance_before;
+        let keeper_tip =
+            testing_env.svm.get_balance(&quote_token_mint, &carol) - carol_quote_balance_before;
+
+        assert_eq!(limit_order.amount_remaining, 6_000_000_000);
+        assert!(bob_quote_amount > 0);
+        assert_eq!(
+            keeper_tip,
+            super::get_keeper_tip(bob_quote_amount + keeper_tip).unwrap()
+        );
+
+        // Full fill
+        fill_limit_order_action.amount = u64::MAX;
+
+        testing_env
+            .svm
+            .execute_actions(&[&fill_limit_order_action])
+            .unwrap();
+
+        let limit_order = testing_env
+            .svm
+            .get_parsed_account::<LimitOrder>(&create_limit_order_action.limit_order);
+
+        assert_eq!(limit_order.amount_remaining, 0);
+        assert_eq!(
+            testing_env
+                .svm
+                .get_balance(&base_token_mint, &create_limit_order_action.limit_order),
+            0
+        );
+    }
+
+    #[test]
+    fn fill_limit_order_with_price_limit_exceeded() {
+        let mut testing_env = setup_env(PriceInterpolation::Linear);
+
+        swap(
+            &mut testing_env,
+            "carol",
+            SwapType::Buy,
+            SwapAmountType::ExactOutput,
+            500_000_000_000,
+        );
+
+        // The spot price meets the limit price, but the fill moves the curve and pays the keeper tip
+        let limit_price = get_spot_price(&testing_env, swap_manager::SwapType::Buy);
+
+        let create_limit_order_action =
+            CreateLimitOrderAction::new(&testing_env, 0, SwapType::Buy, 1_000_000_000, limit_price);
+
+        testing_env
+            .svm
+            .execute_actions(&[&create_limit_order_action])
+            .unwrap();
+
+        let fill_limit_order_action =
+            FillLimitOrderAction::new(&testing_env, &create_limit_order_action, u64::MAX);
+
+        let result = testing_env.svm.execute_actions(&[&fill_limit_order_action]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::PriceLimitExceeded
+        );
+    }
+
+    #[test]
+    fn fill_limit_order_after_graduation() {
+        const SUPPLY_THRESHOLD: u64 = DEFAULT_TOTAL_SUPPLY / 2;
+
+        let mut testing_env = TokenMillEnv::new()
+            .with_default_quote_token_mint()
+            .with_graduation(SUPPLY_THRESHOLD, 0)
+            .with_default_market();
+        let bob = testing_env.svm.change_payer("bob");
+        let quote_token_mint = testing_env.quote_token_mint.unwrap();
+
+        swap(
+            &mut testing_env,
+            "bob",
+            SwapType::Buy,
+            SwapAmountType::ExactOutput,
+            10_000_000_000,
+        );
+
+        let limit_price = get_spot_price(&testing_env, swap_manager::SwapType::Sell);
+
+        let create_limit_order_action = CreateLimitOrderAction::new(
+            &testing_env,
+            0,
+            SwapType::Sell,
+            1_000_000_000,
+            limit_price,
+        );
+
+        testing_env
+            .svm
+            .execute_actions(&[&create_limit_order_action])
+            .unwrap();
+
+        // The market graduates while the order rests, the pool then filling it
+        swap(
+            &mut testing_env,
+            "carol",
+            SwapType::Buy,
+            SwapAmountType::ExactOutput,
+            SUPPLY_THRESHOLD - 10_000_000_000,
+        );
+
+        testing_env
+            .svm
+            .execute_actions(&[&GraduateMarketAction::new(&testing_env)])
+            .unwrap();
+
+        let quote_reserve_before = testing_env
+            .svm
+            .get_parsed_account::<Market>(&testing_env.market)
+            .graduation
+            .quote_reserve;
+        let bob_quote_balance_before = testing_env.svm.get_balance(&quote_token_mint, &bob);
+
+        testing_env
+            .svm
+            .execute_actions(&[&FillLimitOrderAction::new(
+                &testing_env,
+                &create_limit_order_action,
+                u64::MAX,
+            )])
+            .unwrap();
+
+        let limit_order = testing_env
+            .svm
+            .get_parsed_account::<LimitOrder>(&create_limit_order_action.limit_order);
+        let market = testing_env
+            .svm
+            .get_parsed_account::<Market>(&testing_env.market);
+
+        assert_eq!(limit_order.amount_remaining, 0);
+        assert!(testing_env.svm.get_balance(&quote_token_mint, &bob) > bob_quote_balance_before);
+        assert!(market.graduation.quote_reserve < quote_reserve_before);
+    }
+}
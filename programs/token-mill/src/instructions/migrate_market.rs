@@ -0,0 +1,264 @@
+use anchor_lang::{prelude::*, Discriminator};
+
+use crate::{
+    errors::TokenMillError,
+    manager::migration_manager::realloc_account,
+    state::{Market, MarketStatus, PriceInterpolation},
+};
+
+/// Number of evenly spaced points of the price curves of legacy markets
+pub const LEGACY_PRICES_LENGTH: usize = 11;
+
+/// Size of the markets created before the graduation was added
+pub const LEGACY_MARKET_SIZE: usize = 8 + LegacyMarket::INIT_SPACE;
+
+#[zero_copy]
+#[derive(Debug, InitSpace)]
+pub struct LegacyMarketFees {
+    pub staking_fee_share: u16,
+    pub creator_fee_share: u16,
+    _space: u32,
+
+    pub pending_staking_fees: u64,
+    pub pending_creator_fees: u64,
+}
+
+/// Layout of the legacy markets, prices applying every `width_scaled` of circulating supply
+#[zero_copy]
+#[derive(Debug, InitSpace)]
+pub struct LegacyMarket {
+    pub config: Pubkey,
+    pub creator: Pubkey,
+
+    pub base_token_mint: Pubkey,
+    pub quote_token_mint: Pubkey,
+
+    pub base_reserve: u64,
+
+    pub bid_prices: [u64; LEGACY_PRICES_LENGTH],
+    pub ask_prices: [u64; LEGACY_PRICES_LENGTH],
+
+    pub width_scaled: u64,
+    pub total_supply: u64,
+
+    pub fees: LegacyMarketFees,
+
+    pub quote_token_decimals: u8,
+    pub bump: u8,
+
+    _space: [u8; 6],
+}
+
+impl LegacyMarket {
+    pub fn are_prices_set(&self) -> bool {
+        self.ask_prices[LEGACY_PRICES_LENGTH - 1] != 0
+    }
+
+    /// Current market with the same state, the features added since being disabled
+    pub fn to_market(self) -> Market {
+        let mut market = <Market as bytemuck::Zeroable>::zeroed();
+
+        market.config = self.config;
+        market.creator = self.creator;
+        market.base_token_mint = self.base_token_mint;
+        market.quote_token_mint = self.quote_token_mint;
+        market.base_reserve = self.base_reserve;
+        market.total_supply = self.total_supply;
+
+        market.fees.staking_fee_share = self.fees.staking_fee_share;
+        market.fees.creator_fee_share = self.fees.creator_fee_share;
+        market.fees.pending_staking_fees = self.fees.pending_staking_fees;
+        market.fees.pending_creator_fees = self.fees.pending_creator_fees;
+
+        market.quote_token_decimals = self.quote_token_decimals;
+        market.bump = self.bump;
+        market.status = MarketStatus::Created as u8;
+
+        if self.are_prices_set() {
+            // Legacy total supplies are a multiple of the number of intervals
+            let interval_supply = self.total_supply / (LEGACY_PRICES_LENGTH as u64 - 1);
+
+            for i in 0..LEGACY_PRICES_LENGTH {
+                market.bid_prices[i] = self.bid_prices[i];
+                market.ask_prices[i] = self.ask_prices[i];
+                market.supply_breakpoints[i] = interval_supply * i as u64;
+            }
+
+            market.prices_length = LEGACY_PRICES_LENGTH as u8;
+            market.price_interpolation = PriceInterpolation::Linear as u8;
+            market.status = MarketStatus::PricesSet as u8;
+        }
+
+        market
+    }
+}
+
+#[derive(Accounts)]
+pub struct MigrateMarket<'info> {
+    /// CHECK: Legacy markets can't be loaded, the discriminator and size are checked in the handler
+    #[account(mut, owner = crate::ID @ TokenMillError::InvalidMarket)]
+    pub market: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Grows a legacy market to the current size and converts it to the current layout
+pub fn handler(ctx: Context<MigrateMarket>) -> Result<()> {
+    let market = ctx.accounts.market.to_account_info();
+
+    let legacy_market = {
+        let data = market.try_borrow_data()?;
+
+        require!(
+            data.starts_with(&Market::DISCRIMINATOR),
+            TokenMillError::InvalidMarket
+        );
+        require!(
+            data.len() == LEGACY_MARKET_SIZE,
+            TokenMillError::AccountAlreadyMigrated
+        );
+
+        bytemuck::pod_read_unaligned::<LegacyMarket>(&data[8..])
+    };
+
+    realloc_account(
+        &market,
+        &ctx.accounts.payer,
+        &ctx.accounts.system_program,
+        8 + Market::INIT_SPACE,
+    )?;
+
+    let mut data = market.try_borrow_mut_data()?;
+
+    *bytemuck::from_bytes_mut::<Market>(&mut data[8..8 + Market::INIT_SPACE]) =
+        legacy_market.to_market();
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::{Discriminator, Space};
+    use joelana_test_utils::joelana_env::{
+        actions::token_mill::{tm_parse_error, MigrateMarketAction, SwapAction, TokenMillEnv},
+        SwapAmountType, SwapType,
+    };
+
+    use super::{LegacyMarket, LEGACY_MARKET_SIZE, LEGACY_PRICES_LENGTH};
+    use crate::{errors::TokenMillError, state::MarketStatus, Market};
+
+    /// Legacy market in the state of `market`, whose curve has the legacy shape
+    fn to_legacy_market(market: &Market) -> LegacyMarket {
+        let mut legacy_market = <LegacyMarket as bytemuck::Zeroable>::zeroed();
+
+        legacy_market.config = market.config;
+        legacy_market.creator = market.creator;
+        legacy_market.base_token_mint = market.base_token_mint;
+        legacy_market.quote_token_mint = market.quote_token_mint;
+        legacy_market.base_reserve = market.base_reserve;
+        legacy_market
+            .bid_prices
+            .copy_from_slice(&market.bid_prices[..LEGACY_PRICES_LENGTH]);
+        legacy_market
+            .ask_prices
+            .copy_from_slice(&market.ask_prices[..LEGACY_PRICES_LENGTH]);
+        legacy_market.total_supply = market.total_supply;
+        legacy_market.fees.staking_fee_share = market.fees.staking_fee_share;
+        legacy_market.fees.creator_fee_share = market.fees.creator_fee_share;
+        legacy_market.fees.pending_staking_fees = market.fees.pending_staking_fees;
+        legacy_market.fees.pending_creator_fees = market.fees.pending_creator_fees;
+        legacy_market.quote_token_decimals = market.quote_token_decimals;
+        legacy_market.bump = market.bump;
+
+        legacy_market
+    }
+
+    #[test]
+    fn migrate_market() {
+        let mut testing_env = TokenMillEnv::default();
+        testing_env.svm.change_payer("bob");
+
+        let swap_action = SwapAction::new(
+            &testing_env,
+            SwapType::Buy,
+            SwapAmountType::ExactOutput,
+            10_000_000_000,
+            u64::MAX,
+            None,
+        );
+
+        testing_env.svm.execute_actions(&[&swap_action]).unwrap();
+
+        let market = testing_env
+            .svm
+            .get_parsed_account::<Market>(&testing_env.market);
+
+        // Rewrites the market in the legacy layout
+        let mut account = testing_env.svm.get_account(&testing_env.market);
+        account.data = [
+            Market::DISCRIMINATOR.as_slice(),
+            bytemuck::bytes_of(&to_legacy_market(&market)),
+        ]
+        .concat();
+        testing_env.svm.set_account(&testing_env.market, account);
+
+        assert_eq!(
+            testing_env.svm.get_account(&testing_env.market).data.len(),
+            LEGACY_MARKET_SIZE
+        );
+
+        let mut migrate_market_action = MigrateMarketAction::new(&testing_env);
+        migrate_market_action.signer = testing_env.svm.change_payer("admin");
+
+        testing_env
+            .svm
+            .execute_actions(&[&migrate_market_action])
+            .unwrap();
+
+        assert_eq!(
+            testing_env.svm.get_account(&testing_env.market).data.len(),
+            8 + Market::INIT_SPACE
+        );
+
+        let migrated_market = testing_env
+            .svm
+            .get_parsed_account::<Market>(&testing_env.market);
+
+        assert_eq!(migrated_market.base_reserve, market.base_reserve);
+        assert_eq!(migrated_market.bid_prices, market.bid_prices);
+        assert_eq!(migrated_market.ask_prices, market.ask_prices);
+        assert_eq!(
+            migrated_market.supply_breakpoints,
+            market.supply_breakpoints
+        );
+        assert_eq!(migrated_market.prices_length, market.prices_length);
+        assert_eq!(
+            migrated_market.price_interpolation,
+            market.price_interpolation
+        );
+        assert_eq!(
+            migrated_market.fees.pending_creator_fees,
+            market.fees.pending_creator_fees
+        );
+        assert_eq!(
+            migrated_market.fees.pending_staking_fees,
+            market.fees.pending_staking_fees
+        );
+        assert_eq!(migrated_market.get_status(0), MarketStatus::Trading);
+
+        let result = testing_env.svm.execute_actions(&[&migrate_market_action]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::AccountAlreadyMigrated
+        );
+
+        // The migrated market trades as before
+        testing_env.svm.change_payer("bob");
+
+        testing_env.svm.execute_actions(&[&swap_action]).unwrap();
+    }
+}
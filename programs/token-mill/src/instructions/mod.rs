@@ -1,17 +1,37 @@
 pub mod admin;
+pub mod create_launch_allocation;
 pub mod create_market;
+pub mod create_market_stats;
 pub mod create_market_with_spl;
 pub mod creator;
+pub mod dca;
+pub mod graduate_market;
+pub mod limit_orders;
+pub mod migrate_market;
+pub mod oracle;
+pub mod quote_swap;
 pub mod referrals;
 pub mod staking;
 pub mod swap;
+pub mod swap_batch;
+pub mod swap_route;
 pub mod vesting;
 
 pub use admin::*;
+pub use create_launch_allocation::*;
 pub use create_market::*;
+pub use create_market_stats::*;
 pub use create_market_with_spl::*;
 pub use creator::*;
+pub use dca::*;
+pub use graduate_market::*;
+pub use limit_orders::*;
+pub use migrate_market::*;
+pub use oracle::*;
+pub use quote_swap::*;
 pub use referrals::*;
 pub use staking::*;
 pub use swap::*;
+pub use swap_batch::*;
+pub use swap_route::*;
 pub use vesting::*;
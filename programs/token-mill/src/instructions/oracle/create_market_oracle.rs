@@ -0,0 +1,159 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::TokenMillError,
+    state::{CurveKind, Market, MarketOracle, TokenMillConfig, MARKET_ORACLE_PDA_SEED},
+};
+
+#[derive(Accounts)]
+pub struct CreateMarketOracle<'info> {
+    pub config: Account<'info, TokenMillConfig>,
+
+    #[account(mut, has_one = config @ TokenMillError::InvalidConfigAccount)]
+    pub market: AccountLoader<'info, Market>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + MarketOracle::INIT_SPACE,
+        seeds = [MARKET_ORACLE_PDA_SEED.as_bytes(), market.key().as_ref()],
+        bump
+    )]
+    pub market_oracle: Account<'info, MarketOracle>,
+
+    /// Market creator or config authority, as every swap then pays for the oracle update
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<CreateMarketOracle>) -> Result<()> {
+    let market = &mut ctx.accounts.market.load_mut()?;
+    let payer = ctx.accounts.payer.key();
+
+    require!(
+        payer == market.creator || payer == ctx.accounts.config.authority,
+        TokenMillError::InvalidAuthority
+    );
+
+    // Only price curves and graduated pools are tracked
+    if !market.is_graduated()
+        && (market.curve_kind() != CurveKind::PiecewiseLinear || !market.are_prices_set())
+    {
+        return Err(TokenMillError::InvalidMarket.into());
+    }
+
+    ctx.accounts.market_oracle.initialize(
+        ctx.bumps.market_oracle,
+        ctx.accounts.market.key(),
+        market,
+        Clock::get()?.unix_timestamp,
+    )?;
+
+    market.has_oracle = 1;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        manager::swap_manager::SwapType,
+        state::{Market, MarketOracle},
+    };
+    use joelana_test_utils::joelana_env::{
+        actions::token_mill::{tm_parse_error, CreateMarketOracleAction, TokenMillEnv},
+        CurveKind, TokenMillError,
+    };
+
+    #[test]
+    fn create_market_oracle() {
+        let mut testing_env = TokenMillEnv::default();
+        testing_env.svm.warp(1_000);
+
+        let create_market_oracle_action = CreateMarketOracleAction::new(&testing_env);
+
+        testing_env
+            .svm
+            .execute_actions(&[&create_market_oracle_action])
+            .unwrap();
+
+        let market = testing_env
+            .svm
+            .get_parsed_account::<Market>(&testing_env.market);
+        let market_oracle = testing_env
+            .svm
+            .get_parsed_account::<MarketOracle>(&create_market_oracle_action.market_oracle);
+
+        assert!(market.is_oracle_enabled());
+        assert_eq!(market_oracle.market, testing_env.market);
+        assert_eq!(market_oracle.observation_index, 0);
+        assert_eq!(market_oracle.observation_count, 1);
+        assert_eq!(market_oracle.observations[0].timestamp, 1_000);
+        assert_eq!(market_oracle.observations[0].cumulative_ask_price, 0);
+        assert_eq!(
+            market_oracle.ask_price,
+            market.get_spot_price(SwapType::Buy).unwrap()
+        );
+        assert_eq!(
+            market_oracle.bid_price,
+            market.get_spot_price(SwapType::Sell).unwrap()
+        );
+
+        // A single oracle per market
+        let result = testing_env
+            .svm
+            .execute_actions(&[&create_market_oracle_action]);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn create_market_oracle_with_invalid_signer() {
+        let mut testing_env = TokenMillEnv::default();
+
+        let mut create_market_oracle_action = CreateMarketOracleAction::new(&testing_env);
+        create_market_oracle_action.signer = testing_env.svm.change_payer("mallory");
+
+        let result = testing_env
+            .svm
+            .execute_actions(&[&create_market_oracle_action]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::InvalidAuthority
+        );
+
+        // The config authority can also create it
+        create_market_oracle_action.signer = testing_env.svm.change_payer("admin");
+
+        testing_env
+            .svm
+            .execute_actions(&[&create_market_oracle_action])
+            .unwrap();
+    }
+
+    #[test]
+    fn create_market_oracle_with_virtual_constant_product() {
+        let mut testing_env = TokenMillEnv::new()
+            .with_default_quote_token_mint()
+            .with_curve_kind(CurveKind::VirtualConstantProduct {
+                virtual_base: 1_100_000_000_000,
+                virtual_quote: 30_000_000_000,
+                spread_bps: 100,
+            })
+            .with_default_market();
+
+        let create_market_oracle_action = CreateMarketOracleAction::new(&testing_env);
+
+        let result = testing_env
+            .svm
+            .execute_actions(&[&create_market_oracle_action]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::InvalidMarket
+        );
+    }
+}
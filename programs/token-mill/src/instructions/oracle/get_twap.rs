@@ -0,0 +1,205 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{MarketOracle, MarketTwap};
+
+/// Read-only, averages the spot prices of the market over the last `window` seconds.
+#[derive(Accounts)]
+pub struct GetTwap<'info> {
+    pub market_oracle: Account<'info, MarketOracle>,
+}
+
+pub fn handler(ctx: Context<GetTwap>, window: i64) -> Result<MarketTwap> {
+    ctx.accounts
+        .market_oracle
+        .get_twap(Clock::get()?.unix_timestamp, window)
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::AnchorDeserialize;
+    use joelana_test_utils::joelana_env::{
+        actions::token_mill::{
+            tm_parse_error, CreateMarketOracleAction, GetTwapAction, SwapAction, TokenMillEnv,
+        },
+        SwapAmountType, SwapType, TokenMillError,
+    };
+
+    use crate::{
+        constant::MARKET_ORACLE_OBSERVATIONS,
+        manager::swap_manager,
+        state::{Market, MarketOracle, MarketTwap},
+    };
+
+    fn setup_env() -> TokenMillEnv {
+        let mut testing_env = TokenMillEnv::default();
+        testing_env.svm.change_payer("bob");
+
+        // Moves the market away from the start of the curve
+        let swap_action = SwapAction::new(
+            &testing_env,
+            SwapType::Buy,
+            SwapAmountType::ExactOutput,
+            50_000_000_000,
+            u64::MAX,
+            None,
+        );
+
+        testing_env.svm.execute_actions(&[&swap_action]).unwrap();
+
+        testing_env.svm.warp(1_000);
+
+        testing_env.svm.change_payer("alice");
+
+        testing_env
+            .svm
+            .execute_actions(&[&CreateMarketOracleAction::new(&testing_env)])
+            .unwrap();
+
+        testing_env.svm.change_payer("bob");
+
+        testing_env
+    }
+
+    fn buy(testing_env: &mut TokenMillEnv, amount: u64) {
+        let swap_action = SwapAction::new(
+            testing_env,
+            SwapType::Buy,
+            SwapAmountType::ExactOutput,
+            amount,
+            u64::MAX,
+            None,
+        )
+        .with_market_oracle();
+
+        testing_env.svm.execute_actions(&[&swap_action]).unwrap();
+    }
+
+    fn get_twap(testing_env: &mut TokenMillEnv, window: i64) -> MarketTwap {
+        let get_twap_action = GetTwapAction::new(testing_env, window);
+
+        let result = testing_env
+            .svm
+            .execute_actions(&[&get_twap_action])
+            .unwrap();
+
+        MarketTwap::try_from_slice(&result.return_data.data).unwrap()
+    }
+
+    fn get_spot_prices(testing_env: &TokenMillEnv) -> (u64, u64) {
+        let market = testing_env
+            .svm
+            .get_parsed_account::<Market>(&testing_env.market);
+
+        (
+            market.get_spot_price(swap_manager::SwapType::Sell).unwrap(),
+            market.get_spot_price(swap_manager::SwapType::Buy).unwrap(),
+        )
+    }
+
+    #[test]
+    fn get_twap_after_large_swap() {
+        let mut testing_env = setup_env();
+
+        let (bid_price_before, ask_price_before) = get_spot_prices(&testing_env);
+
+        testing_env.svm.warp(1_000);
+
+        // Prices were constant since the oracle was created
+        let twap = get_twap(&mut testing_env, 1_000);
+
+        assert_eq!(twap.bid_price, bid_price_before);
+        assert_eq!(twap.ask_price, ask_price_before);
+
+        buy(&mut testing_env, 400_000_000_000);
+        testing_env.svm.warp(10);
+
+        let (bid_price_after, ask_price_after) = get_spot_prices(&testing_env);
+
+        assert!(ask_price_after > 2 * ask_price_before);
+
+        let twap = get_twap(&mut testing_env, 1_010);
+
+        assert_eq!(
+            u128::from(twap.ask_price),
+            (u128::from(ask_price_before) * 1_000 + u128::from(ask_price_after) * 10) / 1_010
+        );
+        assert_eq!(
+            u128::from(twap.bid_price),
+            (u128::from(bid_price_before) * 1_000 + u128::from(bid_price_after) * 10) / 1_010
+        );
+
+        // The swap barely moves the TWAP compared to the spot price
+        assert!(twap.ask_price - ask_price_before < (ask_price_after - ask_price_before) / 50);
+
+        // Windows starting after the swap only see the new prices
+        let twap = get_twap(&mut testing_env, 5);
+
+        assert_eq!(twap.bid_price, bid_price_after);
+        assert_eq!(twap.ask_price, ask_price_after);
+
+        // Windows starting between two observations are interpolated
+        let twap = get_twap(&mut testing_env, 510);
+
+        assert_eq!(
+            u128::from(twap.ask_price),
+            (u128::from(ask_price_before) * 500 + u128::from(ask_price_after) * 10) / 510
+        );
+    }
+
+    #[test]
+    fn get_twap_with_overwritten_observations() {
+        let mut testing_env = setup_env();
+
+        for _ in 0..MARKET_ORACLE_OBSERVATIONS + 8 {
+            testing_env.svm.warp(10);
+            buy(&mut testing_env, 1_000_000_000);
+        }
+
+        let market_oracle = testing_env
+            .svm
+            .get_parsed_account::<MarketOracle>(&GetTwapAction::new(&testing_env, 0).market_oracle);
+
+        assert_eq!(
+            usize::from(market_oracle.observation_count),
+            MARKET_ORACLE_OBSERVATIONS
+        );
+
+        // The oldest observation left was written by the 9th swap
+        let window = (MARKET_ORACLE_OBSERVATIONS as i64 - 1) * 10;
+
+        get_twap(&mut testing_env, window);
+
+        let get_twap_action = GetTwapAction::new(&testing_env, window + 1);
+
+        let result = testing_env.svm.execute_actions(&[&get_twap_action]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::InvalidTwapWindow
+        );
+    }
+
+    #[test]
+    fn get_twap_with_invalid_window() {
+        let mut testing_env = setup_env();
+
+        let mut get_twap_action = GetTwapAction::new(&testing_env, 0);
+
+        let result = testing_env.svm.execute_actions(&[&get_twap_action]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::InvalidTwapWindow
+        );
+
+        // The window starts before the oracle was created
+        get_twap_action.window = 1_001;
+
+        let result = testing_env.svm.execute_actions(&[&get_twap_action]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::InvalidTwapWindow
+        );
+    }
+}
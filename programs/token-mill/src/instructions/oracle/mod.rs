@@ -0,0 +1,5 @@
+pub mod create_market_oracle;
+pub mod get_twap;
+
+pub use create_market_oracle::*;
+pub use get_twap::*;
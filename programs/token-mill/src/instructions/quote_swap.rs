@@ -0,0 +1,435 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::{
+    errors::TokenMillError,
+    manager::{
+        swap_manager::{SwapAmountType, SwapType},
+        token_manager::get_epoch_transfer_fee,
+    },
+    state::{Market, ReferralAccount, StakePosition},
+    TokenMillConfig,
+};
+
+/// Read-only, simulates a swap without requiring any token account.
+/// Only the market is required, quotes without the config ignoring paused operations
+/// and quotes without the quote token mint ignoring its transfer fee.
+#[derive(Accounts)]
+pub struct QuoteSwap<'info> {
+    /// Required to quote a referred swap or a fee discount
+    #[account(address = market.load()?.config @ TokenMillError::InvalidConfigAccount)]
+    pub config: Option<Account<'info, TokenMillConfig>>,
+
+    pub market: AccountLoader<'info, Market>,
+
+    /// Its transfer fee is included in the quote
+    #[account(address = market.load()?.quote_token_mint @ TokenMillError::InvalidMintAccount)]
+    pub quote_token_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// Quotes a swap referred by this account, with its fee share override and its parent's share
+    #[account(has_one = config @ TokenMillError::InvalidConfigAccount)]
+    pub referral_account: Option<Box<Account<'info, ReferralAccount>>>,
+
+    /// Quotes the fee discount of this stake position on buys
+    #[account(has_one = market @ TokenMillError::InvalidMarket)]
+    pub stake_position: Option<Box<Account<'info, StakePosition>>>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone, Copy, PartialEq)]
+pub struct SwapQuote {
+    pub base_amount: u64,
+    /// Sent or received by the user
+    pub quote_amount: u64,
+    pub swap_fee: u64,
+    pub creator_fee: u64,
+    pub staking_fee: u64,
+    pub protocol_fee: u64,
+    pub referral_fee: u64,
+    /// Part of `referral_fee` paid to the parent of the referral account
+    pub sub_referral_fee: u64,
+    /// Part of `quote_amount` paid as buy tax, included in the fees it is distributed to
+    pub buy_tax: u64,
+    /// Quote token transfer fee paid by the user, part of `quote_amount` on buys and deducted from it on sells
+    pub transfer_fee: u64,
+    /// Spread fee discounted from `quote_amount` and `swap_fee` by the stake position
+    pub fee_discount: u64,
+    /// Buys are reserved to allowlisted users, the swap requiring their presale proof
+    pub presale_proof_required: bool,
+    /// Buys are capped per wallet, the swap requiring the user's launch allocation
+    pub launch_allocation_required: bool,
+}
+
+pub fn handler(
+    ctx: Context<QuoteSwap>,
+    swap_type: SwapType,
+    swap_amount_type: SwapAmountType,
+    amount: u64,
+) -> Result<SwapQuote> {
+    let config = ctx.accounts.config.as_ref();
+    let market = ctx.accounts.market.load()?;
+
+    let current_time = Clock::get()?.unix_timestamp;
+
+    if let Some(config) = config {
+        config.check_not_paused(swap_type.into())?;
+    }
+
+    market.check_swap_allowed(swap_type, current_time)?;
+
+    let fee_discount_bps = match &ctx.accounts.stake_position {
+        Some(stake_position) => config
+            .ok_or(TokenMillError::InvalidConfigAccount)?
+            .get_stake_position_fee_discount_bps(stake_position),
+        None => 0,
+    };
+
+    // Same math as off-chain quotes, the market account is left untouched
+    let quote = token_mill_sdk::quote_swap(
+        market.sdk(),
+        swap_type.into(),
+        swap_amount_type.into(),
+        amount,
+        ctx.accounts
+            .referral_account
+            .as_ref()
+            .zip(config)
+            .map(|(referral_account, config)| referral_account.get_fee_shares(config)),
+        current_time,
+        match &ctx.accounts.quote_token_mint {
+            Some(quote_token_mint) => get_epoch_transfer_fee(quote_token_mint)?,
+            None => None,
+        }
+        .as_ref(),
+        fee_discount_bps,
+    )?;
+
+    Ok(SwapQuote {
+        base_amount: quote.base_amount,
+        quote_amount: quote.quote_amount,
+        swap_fee: quote.swap_fee,
+        creator_fee: quote.creator_fee,
+        staking_fee: quote.staking_fee,
+        protocol_fee: quote.protocol_fee,
+        referral_fee: quote.referral_fee,
+        sub_referral_fee: quote.sub_referral_fee,
+        buy_tax: quote.buy_tax,
+        transfer_fee: quote.transfer_fee,
+        fee_discount: quote.fee_discount,
+        presale_proof_required: quote.presale_proof_required,
+        launch_allocation_required: quote.launch_allocation_required,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::AnchorDeserialize;
+    use joelana_test_utils::joelana_env::{
+        actions::token_mill::{
+            get_referral_account_address, tm_parse_error, CreateReferralAccountAction,
+            QuoteSwapAction, SetReferralFeeShareOverrideAction, SwapAction, TokenMillEnv,
+            UpdateSubReferralFeeShareAction,
+        },
+        make_address, SwapAmountType, SwapType, TokenMillError,
+    };
+    use rstest::rstest;
+
+    use super::SwapQuote;
+    use crate::Market;
+
+    fn quote(testing_env: &mut TokenMillEnv, action: &QuoteSwapAction) -> SwapQuote {
+        let result = testing_env.svm.execute_actions(&[action]).unwrap();
+
+        SwapQuote::try_from_slice(&result.return_data.data).unwrap()
+    }
+
+    #[rstest]
+    fn quote_swap(
+        #[values(SwapType::Buy, SwapType::Sell)] swap_type: SwapType,
+        #[values(SwapAmountType::ExactInput, SwapAmountType::ExactOutput)]
+        swap_amount_type: SwapAmountType,
+    ) {
+        let mut testing_env = TokenMillEnv::default();
+        let bob = testing_env.svm.change_payer("bob");
+
+        // Gives bob some base tokens to sell
+        testing_env
+            .svm
+            .execute_actions(&[&SwapAction::new(
+                &testing_env,
+                SwapType::Buy,
+                SwapAmountType::ExactOutput,
+                100_000_000_000,
+                u64::MAX,
+                None,
+            )])
+            .unwrap();
+
+        let amount = match (swap_type, swap_amount_type) {
+            (SwapType::Buy, SwapAmountType::ExactInput) => 1_000_000_000,
+            (SwapType::Sell, SwapAmountType::ExactOutput) => 10_000_000,
+            _ => 10_000_000_000,
+        };
+
+        let market_before = testing_env.svm.get_account(&testing_env.market);
+
+        let quote_action = QuoteSwapAction::new(&testing_env, swap_type, swap_amount_type, amount);
+        let swap_quote = quote(&mut testing_env, &quote_action);
+
+        // Quoting doesn't change the market
+        assert_eq!(
+            testing_env.svm.get_account(&testing_env.market),
+            market_before
+        );
+
+        assert_eq!(
+            swap_quote.swap_fee,
+            swap_quote.creator_fee + swap_quote.staking_fee + swap_quote.protocol_fee
+        );
+        assert_eq!(swap_quote.referral_fee, 0);
+
+        if swap_type == SwapType::Buy {
+            assert!(swap_quote.swap_fee > 0);
+        }
+
+        let base_token_mint = testing_env.base_token_mint.unwrap();
+        let quote_token_mint = testing_env.quote_token_mint.unwrap();
+
+        let base_balance_before = testing_env.svm.get_balance(&base_token_mint, &bob);
+        let quote_balance_before = testing_env.svm.get_balance(&quote_token_mint, &bob);
+
+        testing_env
+            .svm
+            .execute_actions(&[&SwapAction::new(
+                &testing_env,
+                swap_type,
+                swap_amount_type,
+                amount,
+                match swap_amount_type {
+                    SwapAmountType::ExactInput => 0,
+                    SwapAmountType::ExactOutput => u64::MAX,
+                },
+                None,
+            )])
+            .unwrap();
+
+        let base_balance_delta = testing_env
+            .svm
+            .get_balance(&base_token_mint, &bob)
+            .abs_diff(base_balance_before);
+        let quote_balance_delta = testing_env
+            .svm
+            .get_balance(&quote_token_mint, &bob)
+            .abs_diff(quote_balance_before);
+
+        assert_eq!(base_balance_delta, swap_quote.base_amount);
+        assert_eq!(quote_balance_delta, swap_quote.quote_amount);
+    }
+
+    #[test]
+    fn quote_swap_with_referral() {
+        let mut testing_env = TokenMillEnv::default();
+        testing_env.svm.change_payer("bob");
+
+        let quote_action = QuoteSwapAction::new(
+            &testing_env,
+            SwapType::Buy,
+            SwapAmountType::ExactOutput,
+            10_000_000_000,
+        )
+        .with_referral_account(make_address("carol"));
+        let swap_quote = quote(&mut testing_env, &quote_action);
+
+        assert!(swap_quote.referral_fee > 0);
+        assert_eq!(
+            swap_quote.swap_fee,
+            swap_quote.creator_fee
+                + swap_quote.staking_fee
+                + swap_quote.protocol_fee
+                + swap_quote.referral_fee
+        );
+
+        // Quotes the same as a referred swap
+        testing_env
+            .svm
+            .execute_actions(&[&SwapAction::new(
+                &testing_env,
+                SwapType::Buy,
+                SwapAmountType::ExactOutput,
+                10_000_000_000,
+                u64::MAX,
+                Some(make_address("carol")),
+            )
+            .with_referral_account(make_address("carol"))])
+            .unwrap();
+
+        let market = testing_env
+            .svm
+            .get_parsed_account::<Market>(&testing_env.market);
+
+        assert_eq!(market.fees.pending_protocol_fees, swap_quote.protocol_fee);
+    }
+
+    #[test]
+    fn quote_swap_with_referral_fee_shares() {
+        let mut testing_env = TokenMillEnv::default();
+        let quote_token_mint = testing_env.quote_token_mint.unwrap();
+        let carol = make_address("carol");
+        let dave = testing_env.svm.change_payer("dave");
+
+        // Dave's referral account has carol's one as parent and a fee share override
+        testing_env
+            .svm
+            .execute_actions(&[&CreateReferralAccountAction::new()
+                .with_referrer(dave)
+                .with_parent_referrer(carol)])
+            .unwrap();
+
+        testing_env
+            .svm
+            .create_ata(
+                &get_referral_account_address(&dave),
+                &quote_token_mint,
+                testing_env.quote_token_type,
+            )
+            .unwrap();
+
+        testing_env.svm.change_payer("admin");
+
+        testing_env
+            .svm
+            .execute_actions(&[
+                &UpdateSubReferralFeeShareAction::new(2_000),
+                &SetReferralFeeShareOverrideAction::new(dave, Some(5_000)),
+            ])
+            .unwrap();
+
+        testing_env.svm.change_payer("bob");
+
+        let quote_action = QuoteSwapAction::new(
+            &testing_env,
+            SwapType::Buy,
+            SwapAmountType::ExactOutput,
+            10_000_000_000,
+        )
+        .with_referral_account(dave);
+        let swap_quote = quote(&mut testing_env, &quote_action);
+
+        assert!(swap_quote.sub_referral_fee > 0);
+
+        // Quotes the fees the referral accounts receive
+        testing_env
+            .svm
+            .execute_actions(&[&SwapAction::new(
+                &testing_env,
+                SwapType::Buy,
+                SwapAmountType::ExactOutput,
+                10_000_000_000,
+                u64::MAX,
+                Some(dave),
+            )
+            .with_parent_referrer(dave, carol)])
+            .unwrap();
+
+        let referral_fee = testing_env
+            .svm
+            .get_balance(&quote_token_mint, &get_referral_account_address(&dave));
+        let sub_referral_fee = testing_env
+            .svm
+            .get_balance(&quote_token_mint, &get_referral_account_address(&carol));
+
+        assert_eq!(referral_fee + sub_referral_fee, swap_quote.referral_fee);
+        assert_eq!(sub_referral_fee, swap_quote.sub_referral_fee);
+    }
+
+    #[test]
+    fn quote_swap_with_market_only() {
+        let mut testing_env = TokenMillEnv::default();
+        testing_env.svm.change_payer("bob");
+
+        let quote_action = QuoteSwapAction::new(
+            &testing_env,
+            SwapType::Buy,
+            SwapAmountType::ExactOutput,
+            10_000_000_000,
+        );
+        let swap_quote = quote(&mut testing_env, &quote_action);
+
+        // The quote token has no transfer fee, so the quote is the same
+        let market_only_action = QuoteSwapAction::new(
+            &testing_env,
+            SwapType::Buy,
+            SwapAmountType::ExactOutput,
+            10_000_000_000,
+        )
+        .without_config()
+        .without_quote_token_mint();
+
+        assert_eq!(quote(&mut testing_env, &market_only_action), swap_quote);
+
+        // Referred quotes require the config
+        let result = testing_env
+            .svm
+            .execute_actions(&[&market_only_action.with_referral_account(make_address("carol"))]);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn quote_swap_with_launch_requirements() {
+        const PRESALE_END_TS: i64 = 1_000;
+        const LAUNCH_WINDOW: i64 = 2_000;
+
+        let mut testing_env = TokenMillEnv::new()
+            .with_default_quote_token_mint()
+            .with_launch_protection(LAUNCH_WINDOW, 1_000_000_000)
+            .with_presale([1; 32], PRESALE_END_TS)
+            .with_default_market();
+        testing_env.svm.change_payer("bob");
+
+        let buy_action = |testing_env: &TokenMillEnv| {
+            QuoteSwapAction::new(
+                testing_env,
+                SwapType::Buy,
+                SwapAmountType::ExactOutput,
+                1_000_000,
+            )
+        };
+
+        // Presale buys require a proof
+        let swap_quote = quote(&mut testing_env, &buy_action(&testing_env));
+
+        assert!(swap_quote.presale_proof_required);
+        assert!(!swap_quote.launch_allocation_required);
+
+        // Then buys require a launch allocation until the window ends
+        testing_env.svm.warp(PRESALE_END_TS);
+
+        let swap_quote = quote(&mut testing_env, &buy_action(&testing_env));
+
+        assert!(!swap_quote.presale_proof_required);
+        assert!(swap_quote.launch_allocation_required);
+
+        testing_env.svm.warp(LAUNCH_WINDOW - PRESALE_END_TS);
+
+        let swap_quote = quote(&mut testing_env, &buy_action(&testing_env));
+
+        assert!(!swap_quote.presale_proof_required);
+        assert!(!swap_quote.launch_allocation_required);
+    }
+
+    #[test]
+    fn quote_swap_with_invalid_amount() {
+        let mut testing_env = TokenMillEnv::default();
+
+        let quote_action =
+            QuoteSwapAction::new(&testing_env, SwapType::Buy, SwapAmountType::ExactInput, 0);
+
+        let result = testing_env.svm.execute_actions(&[&quote_action]);
+
+        assert!(result.is_err());
+
+        let error = tm_parse_error(result).unwrap();
+
+        assert_eq!(error, TokenMillError::InvalidAmount);
+    }
+}
@@ -1,6 +1,6 @@
 use anchor_lang::prelude::*;
 
-use crate::{ReferralAccount, TokenMillConfig, REFERRAL_ACCOUNT_PDA_SEED};
+use crate::{errors::TokenMillError, ReferralAccount, TokenMillConfig, REFERRAL_ACCOUNT_PDA_SEED};
 
 #[derive(Accounts)]
 #[instruction(referrer: Pubkey)]
@@ -16,6 +16,10 @@ pub struct CreateReferralAccount<'info> {
     )]
     pub referral_account: Account<'info, ReferralAccount>,
 
+    /// Referral account of the referrer's own referrer, set once at creation
+    #[account(has_one = config @ TokenMillError::InvalidConfigAccount)]
+    pub parent_referral_account: Option<Account<'info, ReferralAccount>>,
+
     #[account(mut)]
     pub user: Signer<'info>,
 
@@ -26,7 +30,15 @@ pub fn handler(ctx: Context<CreateReferralAccount>, referrer: Pubkey) -> Result<
     let config = &ctx.accounts.config;
     let referral_account = &mut ctx.accounts.referral_account;
 
-    referral_account.initialize(ctx.bumps.referral_account, config.key(), referrer)?;
+    referral_account.initialize(
+        ctx.bumps.referral_account,
+        config.key(),
+        referrer,
+        ctx.accounts
+            .parent_referral_account
+            .as_ref()
+            .map(|parent_referral_account| parent_referral_account.key()),
+    )?;
 
     Ok(())
 }
@@ -55,5 +67,32 @@ mod tests {
 
         assert_eq!(referral_account.referrer, make_address("carol"));
         assert_eq!(referral_account.config, testing_env.config);
+        assert_eq!(referral_account.parent, None);
+    }
+
+    #[test]
+    fn create_referral_account_with_parent() {
+        let mut testing_env = TokenMillEnv::new();
+
+        testing_env.svm.change_payer("dave");
+
+        let create_referral_account_action = CreateReferralAccountAction::new()
+            .with_referrer(make_address("dave"))
+            .with_parent_referrer(make_address("carol"));
+
+        testing_env
+            .svm
+            .execute_actions(&[&create_referral_account_action])
+            .unwrap();
+
+        let referral_account = testing_env.svm.get_parsed_account::<ReferralAccount>(
+            &create_referral_account_action.referral_account,
+        );
+
+        assert_eq!(referral_account.referrer, make_address("dave"));
+        assert_eq!(
+            referral_account.parent,
+            Some(create_referral_account_action.parent_referral_account)
+        );
     }
 }
@@ -0,0 +1,103 @@
+use anchor_lang::{prelude::*, Discriminator};
+
+use crate::{errors::TokenMillError, manager::migration_manager::realloc_account, ReferralAccount};
+
+/// Size of the referral accounts created before `parent` and `fee_share_override` were added
+pub const LEGACY_REFERRAL_ACCOUNT_SIZE: usize = 8 + 1 + 32 + 32;
+
+#[derive(Accounts)]
+pub struct MigrateReferralAccount<'info> {
+    /// CHECK: Legacy referral accounts can't be deserialized, the discriminator and size are checked in the handler
+    #[account(mut, owner = crate::ID @ TokenMillError::InvalidReferralAccount)]
+    pub referral_account: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Grows a legacy referral account to the current size, the zeroed tail decodes as no parent and no fee share override
+pub fn handler(ctx: Context<MigrateReferralAccount>) -> Result<()> {
+    let referral_account = ctx.accounts.referral_account.to_account_info();
+
+    {
+        let data = referral_account.try_borrow_data()?;
+
+        require!(
+            data.starts_with(&ReferralAccount::DISCRIMINATOR),
+            TokenMillError::InvalidReferralAccount
+        );
+        require!(
+            data.len() == LEGACY_REFERRAL_ACCOUNT_SIZE,
+            TokenMillError::AccountAlreadyMigrated
+        );
+    }
+
+    realloc_account(
+        &referral_account,
+        &ctx.accounts.payer,
+        &ctx.accounts.system_program,
+        8 + ReferralAccount::INIT_SPACE,
+    )?;
+
+    ReferralAccount::try_deserialize(&mut &referral_account.try_borrow_data()?[..])?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::Space;
+    use joelana_test_utils::joelana_env::{
+        actions::token_mill::{
+            tm_parse_error, CreateReferralAccountAction, MigrateReferralAccountAction, TokenMillEnv,
+        },
+        make_address,
+    };
+
+    use super::LEGACY_REFERRAL_ACCOUNT_SIZE;
+    use crate::{errors::TokenMillError, ReferralAccount};
+
+    #[test]
+    fn migrate_referral_account() {
+        let mut testing_env = TokenMillEnv::new();
+
+        let referral_account = CreateReferralAccountAction::new().referral_account;
+
+        // Truncates the referral account created on setup back to its legacy layout
+        let mut account = testing_env.svm.get_account(&referral_account);
+        account.data.truncate(LEGACY_REFERRAL_ACCOUNT_SIZE);
+        testing_env.svm.set_account(&referral_account, account);
+
+        let migrate_referral_account_action =
+            MigrateReferralAccountAction::new(make_address("carol"));
+
+        testing_env
+            .svm
+            .execute_actions(&[&migrate_referral_account_action])
+            .unwrap();
+
+        assert_eq!(
+            testing_env.svm.get_account(&referral_account).data.len(),
+            8 + ReferralAccount::INIT_SPACE
+        );
+
+        let referral_account = testing_env
+            .svm
+            .get_parsed_account::<ReferralAccount>(&referral_account);
+
+        assert_eq!(referral_account.referrer, make_address("carol"));
+        assert_eq!(referral_account.parent, None);
+        assert_eq!(referral_account.fee_share_override, None);
+
+        let result = testing_env
+            .svm
+            .execute_actions(&[&migrate_referral_account_action]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::AccountAlreadyMigrated
+        );
+    }
+}
@@ -1,5 +1,7 @@
 pub mod claim_referral_fees;
 pub mod create_referral_account;
+pub mod migrate_referral_account;
 
 pub use claim_referral_fees::*;
 pub use create_referral_account::*;
+pub use migrate_referral_account::*;
@@ -1,8 +1,8 @@
 use crate::{
     errors::TokenMillError,
     events::TokenMillStakingDepositEvent,
-    manager::{staking_manager, token_manager::transfer_from_eoa},
-    state::{Market, MarketStaking, StakePosition},
+    manager::{staking_manager, token_manager::transfer_from_eoa_with_hook},
+    state::{Market, MarketStaking, PausableOperation, StakePosition, TokenMillConfig},
 };
 use anchor_lang::prelude::*;
 use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
@@ -10,7 +10,13 @@ use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
 #[event_cpi]
 #[derive(Accounts)]
 pub struct StakeUpdate<'info> {
-    #[account(mut, has_one = base_token_mint @ TokenMillError::InvalidMintAccount)]
+    pub config: Account<'info, TokenMillConfig>,
+
+    #[account(
+        mut,
+        has_one = config @ TokenMillError::InvalidConfigAccount,
+        has_one = base_token_mint @ TokenMillError::InvalidMintAccount
+    )]
     pub market: AccountLoader<'info, Market>,
 
     #[account(mut, has_one = market @ TokenMillError::InvalidMarket)]
@@ -46,20 +52,29 @@ pub struct StakeUpdate<'info> {
     pub base_token_program: Interface<'info, TokenInterface>,
 }
 
-pub fn handler(ctx: Context<StakeUpdate>, amount: u64) -> Result<()> {
+/// Remaining accounts are the extra accounts of the base token's transfer hook, if any.
+pub fn handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, StakeUpdate<'info>>,
+    amount: u64,
+) -> Result<()> {
+    ctx.accounts
+        .config
+        .check_not_paused(PausableOperation::Staking)?;
+
     let market = &mut ctx.accounts.market.load_mut()?;
     let staking = &mut ctx.accounts.staking;
     let stake_position = &mut ctx.accounts.stake_position;
 
     staking_manager::deposit(market, staking, stake_position, amount)?;
 
-    transfer_from_eoa(
+    transfer_from_eoa_with_hook(
         &ctx.accounts.base_token_mint,
         &ctx.accounts.user,
         &ctx.accounts.user_base_token_ata,
         &ctx.accounts.market_base_token_ata,
         &ctx.accounts.base_token_program,
         amount,
+        ctx.remaining_accounts,
     )?;
 
     emit_cpi!(TokenMillStakingDepositEvent {
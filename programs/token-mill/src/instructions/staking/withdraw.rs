@@ -1,14 +1,18 @@
 use crate::{
     errors::TokenMillError,
     events::TokenMillStakingWithdrawalEvent,
-    manager::{staking_manager, token_manager::transfer_from_pda},
+    manager::{staking_manager, token_manager::transfer_from_pda_with_hook},
     MARKET_PDA_SEED,
 };
 use anchor_lang::prelude::*;
 
 use super::StakeUpdate;
 
-pub fn handler(ctx: Context<StakeUpdate>, amount: u64) -> Result<()> {
+/// Remaining accounts are the extra accounts of the base token's transfer hook, if any.
+pub fn handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, StakeUpdate<'info>>,
+    amount: u64,
+) -> Result<()> {
     let staking = &mut ctx.accounts.staking;
     let stake_position = &mut ctx.accounts.stake_position;
 
@@ -34,7 +38,7 @@ pub fn handler(ctx: Context<StakeUpdate>, amount: u64) -> Result<()> {
         &[market_bump],
     ];
 
-    transfer_from_pda(
+    transfer_from_pda_with_hook(
         base_token_mint,
         ctx.accounts.market.to_account_info(),
         &ctx.accounts.market_base_token_ata,
@@ -42,6 +46,7 @@ pub fn handler(ctx: Context<StakeUpdate>, amount: u64) -> Result<()> {
         &ctx.accounts.base_token_program,
         amount,
         &seeds,
+        ctx.remaining_accounts,
     )?;
 
     emit_cpi!(TokenMillStakingWithdrawalEvent {
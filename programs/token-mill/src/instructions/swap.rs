@@ -1,14 +1,28 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use token_mill_sdk::ReferralFeeShares;
 
 use crate::{
     errors::TokenMillError,
     events::TokenMillSwapEvent,
     manager::{
-        swap_manager::{self, SwapAmountType, SwapType},
-        token_manager::{transfer_from_eoa, transfer_from_pda},
+        launch_manager::{check_presale, update_launch_allocation},
+        oracle_manager::{check_market_oracle, update_market_oracle},
+        referral_manager::{
+            check_referral_accounts, get_referral_fee_shares, get_sub_referral_fee,
+            transfer_referral_fees,
+        },
+        stats_manager::{check_market_stats, update_market_stats},
+        swap_manager::{self, SwapAmountType, SwapResult, SwapType},
+        token_manager::{
+            check_transfer_hook_accounts, get_epoch_transfer_fee, transfer_from_eoa_with_hook,
+            transfer_from_pda_with_hook,
+        },
+    },
+    state::{
+        is_average_price_met, LaunchAllocation, Market, MarketOracle, MarketStats, PresaleProof,
+        ReferralAccount, StakePosition,
     },
-    state::Market,
     TokenMillConfig, MARKET_PDA_SEED,
 };
 
@@ -51,19 +65,42 @@ pub struct Swap<'info> {
     #[account(mut, token::mint = quote_token_mint)]
     pub user_quote_token_account: InterfaceAccount<'info, TokenAccount>,
 
-    #[account(
-        mut,
-        associated_token::mint = quote_token_mint,
-        associated_token::authority = config.protocol_fee_recipient,
-        associated_token::token_program = quote_token_program
-    )]
-    pub protocol_quote_token_ata: InterfaceAccount<'info, TokenAccount>,
-
     // Referral account can be any token account
     // For UX purposes, LFJ's UI provides the ATA of the `ReferralAccount`, requiring the referrer to claim all the fees he receives
     #[account(mut)]
     pub referral_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
 
+    /// Referral account owning `referral_token_account`, required to pay its parent or apply its fee share override
+    #[account(has_one = config @ TokenMillError::InvalidConfigAccount)]
+    pub referral_account: Option<Box<Account<'info, ReferralAccount>>>,
+
+    /// Token account of the parent of `referral_account`, paid the config's `sub_referral_fee_share` of the referral fee
+    #[account(mut, token::mint = quote_token_mint)]
+    pub parent_referral_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Required once the market has an oracle
+    #[account(mut, has_one = market @ TokenMillError::InvalidMarket)]
+    pub market_oracle: Option<Box<Account<'info, MarketOracle>>>,
+
+    /// Required once the market has stats
+    #[account(mut, has_one = market @ TokenMillError::InvalidMarket)]
+    pub market_stats: Option<Box<Account<'info, MarketStats>>>,
+
+    /// Required to buy while the market's launch window is open, or during its presale with a capped proof
+    #[account(
+        mut,
+        has_one = market @ TokenMillError::InvalidMarket,
+        has_one = user @ TokenMillError::InvalidAuthority
+    )]
+    pub launch_allocation: Option<Box<Account<'info, LaunchAllocation>>>,
+
+    /// Discounts the spread fee of buys according to the config's fee discount tiers
+    #[account(
+        has_one = market @ TokenMillError::InvalidMarket,
+        has_one = user @ TokenMillError::InvalidAuthority
+    )]
+    pub stake_position: Option<Box<Account<'info, StakePosition>>>,
+
     pub user: Signer<'info>,
 
     pub base_token_program: Interface<'info, TokenInterface>,
@@ -71,43 +108,144 @@ pub struct Swap<'info> {
     pub quote_token_program: Interface<'info, TokenInterface>,
 }
 
-pub fn handler(
-    ctx: Context<Swap>,
+impl<'info> Swap<'info> {
+    /// Checks run before the market is swapped, shared with the legs of swap batches
+    pub fn validate(
+        &self,
+        swap_type: SwapType,
+        amount: u64,
+        current_time: i64,
+        transfer_hook_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        if amount == 0 {
+            return Err(TokenMillError::InvalidAmount.into());
+        }
+
+        check_referral_accounts(
+            self.referral_token_account.as_ref(),
+            self.referral_account.as_deref(),
+            self.parent_referral_token_account.as_deref(),
+        )?;
+
+        self.config.check_not_paused(swap_type.into())?;
+
+        let market = self.market.load()?;
+
+        market.check_swap_allowed(swap_type, current_time)?;
+        check_market_oracle(&market, &self.market_oracle)?;
+        check_market_stats(&market, &self.market_stats)?;
+        check_transfer_hook_accounts(&self.base_token_mint, transfer_hook_accounts)
+    }
+
+    pub fn get_referral_fee_shares(&self) -> Option<ReferralFeeShares> {
+        get_referral_fee_shares(&self.config, self.referral_account.as_deref())
+    }
+
+    pub fn get_fee_discount_bps(&self) -> u64 {
+        self.stake_position.as_ref().map_or(0, |stake_position| {
+            self.config
+                .get_stake_position_fee_discount_bps(stake_position)
+        })
+    }
+}
+
+/// `presale_proof` is required to buy while the market's presale is open.
+/// Remaining accounts are the extra accounts of the base token's transfer hook, if any.
+#[allow(clippy::too_many_arguments)]
+pub fn handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, Swap<'info>>,
     swap_type: SwapType,
     swap_amount_type: SwapAmountType,
     amount: u64,
     other_amount_threshold: u64,
+    valid_until: Option<i64>,
+    limit_price: Option<u64>,
+    presale_proof: Option<PresaleProof>,
 ) -> Result<(u64, u64)> {
-    if amount == 0 {
-        return Err(TokenMillError::InvalidAmount.into());
+    let current_time = Clock::get()?.unix_timestamp;
+
+    if let Some(valid_until) = valid_until {
+        if current_time > valid_until {
+            return Err(TokenMillError::SwapExpired.into());
+        }
     }
 
+    ctx.accounts
+        .validate(swap_type, amount, current_time, ctx.remaining_accounts)?;
+
+    let referral_fee_shares = ctx.accounts.get_referral_fee_shares();
     let referral_token_account = &ctx.accounts.referral_token_account;
 
     let base_amount;
     let quote_amount;
     let market_bump;
+    let quote_token_decimals;
     let swap_fee;
+    let buy_tax;
+    let transfer_fee;
+    let fee_discount;
     let creator_fee;
     let staking_fee;
     let protocol_fee;
     let referral_fee;
+    let sub_referral_fee;
 
     {
-        let config = &ctx.accounts.config;
         let market = &mut ctx.accounts.market.load_mut()?;
 
-        (base_amount, quote_amount, swap_fee) =
-            swap_manager::swap(market, swap_type, swap_amount_type, amount)?;
+        SwapResult {
+            base_amount,
+            quote_amount,
+            swap_fee,
+            buy_tax,
+            transfer_fee,
+            fee_discount,
+        } = swap_manager::swap_with_transfer_fee(
+            market,
+            swap_type,
+            swap_amount_type,
+            amount,
+            current_time,
+            get_epoch_transfer_fee(&ctx.accounts.quote_token_mint)?.as_ref(),
+            ctx.accounts.get_fee_discount_bps(),
+        )?;
 
-        (creator_fee, staking_fee, protocol_fee, referral_fee) = market.fees.distribute_fee(
+        (creator_fee, staking_fee, protocol_fee, referral_fee) = market.distribute_fees(
             swap_fee,
-            referral_token_account
-                .as_ref()
-                .map(|_| config.referral_fee_share),
+            buy_tax,
+            referral_fee_shares.map(|shares| shares.referral_fee_share),
+        )?;
+
+        sub_referral_fee = get_sub_referral_fee(referral_fee_shares, referral_fee)?;
+
+        update_market_oracle(market, &mut ctx.accounts.market_oracle)?;
+        update_market_stats(
+            market,
+            &mut ctx.accounts.market_stats,
+            swap_type,
+            base_amount,
+            quote_amount,
+            (creator_fee, staking_fee, protocol_fee, referral_fee),
+        )?;
+        check_presale(
+            market,
+            &ctx.accounts.user.key(),
+            presale_proof.as_ref(),
+            &mut ctx.accounts.launch_allocation,
+            swap_type,
+            base_amount,
+            current_time,
+        )?;
+        update_launch_allocation(
+            market,
+            &mut ctx.accounts.launch_allocation,
+            swap_type,
+            base_amount,
+            current_time,
         )?;
 
         market_bump = market.bump;
+        quote_token_decimals = market.quote_token_decimals;
     };
 
     let user = &ctx.accounts.user;
@@ -118,6 +256,12 @@ pub fn handler(
         &[market_bump],
     ];
 
+    // Quote amount sent or received by the user, the market sending the quote amount on sells
+    let user_quote_amount = match swap_type {
+        SwapType::Buy => quote_amount + transfer_fee,
+        SwapType::Sell => quote_amount - transfer_fee,
+    };
+
     let (
         amount_in,
         amount_out,
@@ -131,7 +275,7 @@ pub fn handler(
         token_program_out,
     ) = match swap_type {
         SwapType::Buy => (
-            quote_amount,
+            user_quote_amount,
             base_amount,
             &ctx.accounts.quote_token_mint,
             &ctx.accounts.base_token_mint,
@@ -158,7 +302,12 @@ pub fn handler(
 
     match swap_amount_type {
         SwapAmountType::ExactInput => {
-            if amount_out < other_amount_threshold {
+            let amount_received = match swap_type {
+                SwapType::Buy => base_amount,
+                SwapType::Sell => user_quote_amount,
+            };
+
+            if amount_received < other_amount_threshold {
                 return Err(TokenMillError::AmountThresholdNotMet.into());
             }
         }
@@ -169,16 +318,30 @@ pub fn handler(
         }
     }
 
-    transfer_from_eoa(
+    // The limit price is the worst acceptable average price, in the units of the market price curves
+    if let Some(limit_price) = limit_price {
+        if !is_average_price_met(
+            swap_type,
+            limit_price,
+            base_amount,
+            user_quote_amount,
+            quote_token_decimals,
+        ) {
+            return Err(TokenMillError::PriceLimitExceeded.into());
+        }
+    }
+
+    transfer_from_eoa_with_hook(
         mint_in,
         user,
         user_account_in,
         market_account_in,
         token_program_in,
         amount_in,
+        ctx.remaining_accounts,
     )?;
 
-    transfer_from_pda(
+    transfer_from_pda_with_hook(
         mint_out,
         ctx.accounts.market.to_account_info(),
         market_account_out,
@@ -186,33 +349,20 @@ pub fn handler(
         token_program_out,
         amount_out,
         &seeds,
+        ctx.remaining_accounts,
     )?;
 
-    if protocol_fee > 0 {
-        transfer_from_pda(
-            &ctx.accounts.quote_token_mint,
-            ctx.accounts.market.to_account_info(),
-            &ctx.accounts.market_quote_token_ata,
-            &ctx.accounts.protocol_quote_token_ata,
-            &ctx.accounts.quote_token_program,
-            protocol_fee,
-            &seeds,
-        )?;
-    }
-
-    if let Some(referral_token_account) = referral_token_account {
-        if referral_fee > 0 {
-            transfer_from_pda(
-                &ctx.accounts.quote_token_mint,
-                ctx.accounts.market.to_account_info(),
-                &ctx.accounts.market_quote_token_ata,
-                referral_token_account,
-                &ctx.accounts.quote_token_program,
-                referral_fee,
-                &seeds,
-            )?;
-        }
-    }
+    transfer_referral_fees(
+        &ctx.accounts.quote_token_mint,
+        ctx.accounts.market.to_account_info(),
+        &ctx.accounts.market_quote_token_ata,
+        referral_token_account.as_ref(),
+        ctx.accounts.parent_referral_token_account.as_deref(),
+        &ctx.accounts.quote_token_program,
+        referral_fee,
+        sub_referral_fee,
+        &seeds,
+    )?;
 
     emit_cpi!(TokenMillSwapEvent {
         user: ctx.accounts.user.key(),
@@ -225,21 +375,44 @@ pub fn handler(
         staking_fee,
         protocol_fee,
         referral_fee,
+        sub_referral_fee,
+        buy_tax,
+        fee_discount,
     });
 
-    Ok((base_amount, quote_amount))
+    Ok((base_amount, user_quote_amount))
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{manager::swap_manager, Market};
+    use crate::{
+        constant::{BASE_PRECISION, MAX_BPS, MAX_EXP_INTERVALS_PER_SWAP, MAX_PRICES_LENGTH, SCALE},
+        instructions::SwapQuote,
+        manager::swap_manager,
+        math::{get_fee_amount, Rounding},
+        Market, MarketOracle, MarketStats,
+    };
+    use anchor_lang::AnchorDeserialize;
     use joelana_test_utils::joelana_env::{
-        actions::token_mill::{tm_parse_error, SwapAction, TokenMillEnv},
-        make_address, SwapAmountType, SwapType, TokenMillError, TokenType,
+        actions::token_mill::{
+            get_referral_account_address, get_transfer_counter_address, tm_parse_error,
+            ClaimReferralFeesAction, CreateMarketOracleAction, CreateMarketStatsAction,
+            CreateReferralAccountAction, DepositAction, QuoteSwapAction,
+            SetReferralFeeShareOverrideAction, SwapAction, TokenMillEnv,
+            UpdateFeeDiscountTiersAction, UpdateSubReferralFeeShareAction,
+        },
+        make_address, CurveKind, FeeDiscountTier, PriceInterpolation, SwapAmountType, SwapType,
+        TokenMillError, TokenType,
     };
+    use mock_transfer_hook::TransferCounter;
     use rstest::rstest;
 
     const TOTAL_SUPPLY: u64 = 1_000_000_000_000;
+    const VIRTUAL_CONSTANT_PRODUCT: CurveKind = CurveKind::VirtualConstantProduct {
+        virtual_base: TOTAL_SUPPLY * 11 / 10,
+        virtual_quote: 30_000_000_000,
+        spread_bps: 100,
+    };
 
     fn setup_env() -> (TokenMillEnv, SwapAction) {
         let mut testing_env = TokenMillEnv::default();
@@ -301,6 +474,298 @@ mod tests {
         testing_env.svm.execute_actions(&[&swap_action]).unwrap();
     }
 
+    #[test]
+    fn swap_with_transfer_hook() {
+        let mut testing_env = TokenMillEnv::new()
+            .with_default_quote_token_mint()
+            .with_transfer_hook()
+            .with_default_market();
+        testing_env.svm.change_payer("bob");
+
+        let mut swap_action = SwapAction::new(
+            &testing_env,
+            SwapType::Buy,
+            SwapAmountType::ExactOutput,
+            1_000_000_000,
+            u64::MAX,
+            None,
+        );
+
+        testing_env.svm.execute_actions(&[&swap_action]).unwrap();
+
+        swap_action.swap_type = SwapType::Sell;
+        swap_action.swap_amount_type = SwapAmountType::ExactInput;
+        swap_action.amount = 1_000_000;
+        swap_action.other_amount_threshold = 0;
+
+        testing_env.svm.execute_actions(&[&swap_action]).unwrap();
+
+        let counter =
+            testing_env
+                .svm
+                .get_parsed_account::<TransferCounter>(&get_transfer_counter_address(
+                    &testing_env.base_token_mint.unwrap(),
+                ));
+
+        assert_eq!(counter.transfers, 2);
+        assert_eq!(counter.amount, 1_001_000_000);
+
+        swap_action.transfer_hook_accounts.clear();
+
+        let result = testing_env.svm.execute_actions(&[&swap_action]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::MissingTransferHookAccounts
+        );
+    }
+
+    /// Buys then sells back the whole balance, checking that the round trip doesn't make a profit
+    fn round_trip(
+        testing_env: &mut TokenMillEnv,
+        buy_amount_type: SwapAmountType,
+        sell_amount_type: SwapAmountType,
+        buy_amount: u64,
+    ) {
+        let bob = testing_env.svm.change_payer("bob");
+        let quote_token_mint = testing_env.quote_token_mint.unwrap();
+
+        let initial_quote_balance = testing_env.svm.get_balance(&quote_token_mint, &bob);
+
+        let mut swap_action = SwapAction::new(
+            testing_env,
+            SwapType::Buy,
+            buy_amount_type,
+            buy_amount,
+            match buy_amount_type {
+                SwapAmountType::ExactInput => 0,
+                SwapAmountType::ExactOutput => u64::MAX,
+            },
+            None,
+        );
+
+        testing_env.svm.execute_actions(&[&swap_action]).unwrap();
+
+        let market = testing_env
+            .svm
+            .get_parsed_account::<Market>(&testing_env.market);
+        let base_balance = TOTAL_SUPPLY - market.base_reserve;
+
+        assert!(base_balance > 0);
+
+        swap_action.swap_type = SwapType::Sell;
+        swap_action.swap_amount_type = sell_amount_type;
+
+        match sell_amount_type {
+            SwapAmountType::ExactInput => {
+                swap_action.amount = base_balance;
+                swap_action.other_amount_threshold = 0;
+            }
+            SwapAmountType::ExactOutput => {
+                let quote_balance = testing_env.svm.get_balance(&quote_token_mint, &bob);
+
+                // Quote amount the bid curve pays for the whole balance, minus a small buffer
+                let (_, quote_amount) = market
+                    .get_curve_quote_amount_with_parameters(
+                        0,
+                        base_balance,
+                        SwapAmountType::ExactInput,
+                        Rounding::Down,
+                    )
+                    .unwrap();
+
+                swap_action.amount = quote_amount - quote_amount / 1_000;
+                swap_action.other_amount_threshold = u64::MAX;
+
+                assert!(quote_balance + swap_action.amount < initial_quote_balance);
+            }
+        }
+
+        testing_env.svm.execute_actions(&[&swap_action]).unwrap();
+
+        assert!(testing_env.svm.get_balance(&quote_token_mint, &bob) < initial_quote_balance);
+    }
+
+    #[rstest]
+    fn swap_with_exponential_interpolation(
+        #[values(SwapAmountType::ExactInput, SwapAmountType::ExactOutput)]
+        buy_amount_type: SwapAmountType,
+        #[values(SwapAmountType::ExactInput, SwapAmountType::ExactOutput)]
+        sell_amount_type: SwapAmountType,
+    ) {
+        let mut testing_env = TokenMillEnv::new()
+            .with_default_quote_token_mint()
+            .with_price_interpolation(PriceInterpolation::Exponential)
+            .with_default_market();
+
+        // Crosses as many intervals as a swap can
+        let buy_amount = match buy_amount_type {
+            SwapAmountType::ExactInput => 100_000_000_000,
+            SwapAmountType::ExactOutput => 400_000_000_000,
+        };
+
+        round_trip(
+            &mut testing_env,
+            buy_amount_type,
+            sell_amount_type,
+            buy_amount,
+        );
+    }
+
+    #[rstest]
+    fn swap_across_exponential_intervals_within_compute_budget(
+        #[values(SwapAmountType::ExactInput, SwapAmountType::ExactOutput)]
+        swap_amount_type: SwapAmountType,
+    ) {
+        let mut testing_env = TokenMillEnv::new()
+            .with_default_quote_token_mint()
+            .with_prices_length(MAX_PRICES_LENGTH)
+            .with_price_interpolation(PriceInterpolation::Exponential)
+            .with_default_market();
+        let bob = testing_env.svm.change_payer("bob");
+
+        let other_amount_threshold = match swap_amount_type {
+            SwapAmountType::ExactInput => 0,
+            SwapAmountType::ExactOutput => u64::MAX,
+        };
+
+        let market = testing_env
+            .svm
+            .get_parsed_account::<Market>(&testing_env.market);
+
+        // Tries to buy 90% of the supply, the swap stopping after `MAX_EXP_INTERVALS_PER_SWAP` intervals
+        let (_, quote_amount) = market
+            .get_quote_amount_with_parameters(
+                0,
+                TOTAL_SUPPLY * 9 / 10,
+                swap_manager::SwapAmountType::ExactOutput,
+                Rounding::Up,
+            )
+            .unwrap();
+
+        let buy_amount = match swap_amount_type {
+            SwapAmountType::ExactInput => quote_amount,
+            SwapAmountType::ExactOutput => TOTAL_SUPPLY * 9 / 10,
+        };
+
+        let swap_action = SwapAction::new(
+            &testing_env,
+            SwapType::Buy,
+            swap_amount_type,
+            buy_amount,
+            other_amount_threshold,
+            None,
+        );
+
+        let metadata = testing_env.svm.execute_actions(&[&swap_action]).unwrap();
+
+        assert!(metadata.compute_units_consumed < 200_000);
+
+        let base_token_mint = testing_env.base_token_mint.unwrap();
+        let base_balance = testing_env.svm.get_balance(&base_token_mint, &bob);
+
+        assert!(base_balance > market.supply_breakpoints[MAX_EXP_INTERVALS_PER_SWAP - 1]);
+        assert!(base_balance <= market.supply_breakpoints[MAX_EXP_INTERVALS_PER_SWAP]);
+
+        // Sells it back, crossing the same intervals
+        let (_, quote_amount) = market
+            .get_quote_amount_with_parameters(
+                0,
+                base_balance,
+                swap_manager::SwapAmountType::ExactInput,
+                Rounding::Down,
+            )
+            .unwrap();
+
+        let sell_amount = match swap_amount_type {
+            SwapAmountType::ExactInput => base_balance,
+            SwapAmountType::ExactOutput => quote_amount * 9 / 10,
+        };
+
+        let swap_action = SwapAction::new(
+            &testing_env,
+            SwapType::Sell,
+            swap_amount_type,
+            sell_amount,
+            other_amount_threshold,
+            None,
+        );
+
+        let metadata = testing_env.svm.execute_actions(&[&swap_action]).unwrap();
+
+        assert!(metadata.compute_units_consumed < 200_000);
+    }
+
+    #[rstest]
+    fn swap_with_virtual_constant_product(
+        #[values(SwapAmountType::ExactInput, SwapAmountType::ExactOutput)]
+        buy_amount_type: SwapAmountType,
+        #[values(SwapAmountType::ExactInput, SwapAmountType::ExactOutput)]
+        sell_amount_type: SwapAmountType,
+    ) {
+        let mut testing_env = TokenMillEnv::new()
+            .with_default_quote_token_mint()
+            .with_curve_kind(VIRTUAL_CONSTANT_PRODUCT)
+            .with_default_market();
+
+        let buy_amount = match buy_amount_type {
+            SwapAmountType::ExactInput => 10_000_000_000,
+            SwapAmountType::ExactOutput => 400_000_000_000,
+        };
+
+        round_trip(
+            &mut testing_env,
+            buy_amount_type,
+            sell_amount_type,
+            buy_amount,
+        );
+    }
+
+    #[test]
+    fn swap_fee_with_virtual_constant_product() {
+        let testing_env = TokenMillEnv::new()
+            .with_default_quote_token_mint()
+            .with_curve_kind(VIRTUAL_CONSTANT_PRODUCT)
+            .with_default_market();
+
+        let mut market = testing_env
+            .svm
+            .get_parsed_account::<Market>(&testing_env.market);
+
+        let base_amount = TOTAL_SUPPLY / 10;
+
+        let (base_amount_out, quote_amount, swap_fee) = swap_manager::swap(
+            &mut market,
+            swap_manager::SwapType::Buy,
+            swap_manager::SwapAmountType::ExactOutput,
+            base_amount,
+        )
+        .unwrap();
+
+        // k / (x - dx) - k / x, with x = 1.1e12 and dx = 1e11
+        let expected_quote_amount = (30_000_000_000u128 * 1_100_000_000_000 * base_amount as u128)
+            .div_ceil(1_100_000_000_000 * 1_000_000_000_000)
+            as u64;
+
+        assert_eq!(base_amount_out, base_amount);
+        assert_eq!(quote_amount, expected_quote_amount);
+
+        // The fee is the 1% spread between the ask and buyback quotes
+        assert!(swap_fee.abs_diff(quote_amount / 100) <= 1);
+
+        // The whole supply can be bought
+        let (base_amount_out, _, _) = swap_manager::swap(
+            &mut market,
+            swap_manager::SwapType::Buy,
+            swap_manager::SwapAmountType::ExactInput,
+            u64::MAX / 2,
+        )
+        .unwrap();
+
+        assert_eq!(base_amount_out, TOTAL_SUPPLY - base_amount);
+        assert_eq!(market.base_reserve, 0);
+    }
+
     #[test]
     fn swap_with_invalid_amount_in() {
         let (mut testing_env, mut swap_action) = setup_env();
@@ -375,6 +840,105 @@ mod tests {
         assert_eq!(error, TokenMillError::InvalidAmount);
     }
 
+    #[test]
+    fn swap_with_expired_deadline() {
+        let (mut testing_env, mut swap_action) = setup_env();
+
+        swap_action.valid_until = Some(100);
+
+        testing_env.svm.execute_actions(&[&swap_action]).unwrap();
+
+        testing_env.svm.warp(100);
+
+        testing_env.svm.execute_actions(&[&swap_action]).unwrap();
+
+        testing_env.svm.warp(1);
+
+        let result = testing_env.svm.execute_actions(&[&swap_action]);
+
+        let error = tm_parse_error(result).unwrap();
+
+        assert_eq!(error, TokenMillError::SwapExpired);
+    }
+
+    /// Returns the average price of the swap in the units of the market price curves
+    fn get_average_price(
+        testing_env: &TokenMillEnv,
+        swap_type: swap_manager::SwapType,
+        swap_amount_type: swap_manager::SwapAmountType,
+        amount: u64,
+        rounding: Rounding,
+    ) -> u64 {
+        let mut market = testing_env
+            .svm
+            .get_parsed_account::<Market>(&testing_env.market);
+
+        let (base_amount, quote_amount, _) =
+            swap_manager::swap(&mut market, swap_type, swap_amount_type, amount).unwrap();
+
+        let scaled_quote_amount = u128::from(quote_amount) * SCALE * u128::from(BASE_PRECISION);
+        let normalized_base_amount =
+            u128::from(base_amount) * 10u128.pow(u32::from(market.quote_token_decimals));
+
+        let average_price = match rounding {
+            Rounding::Up => scaled_quote_amount.div_ceil(normalized_base_amount),
+            Rounding::Down => scaled_quote_amount / normalized_base_amount,
+        };
+
+        u64::try_from(average_price).unwrap()
+    }
+
+    #[test]
+    fn swap_with_limit_price() {
+        let (mut testing_env, mut swap_action) = setup_env();
+
+        // Buys are limited by the highest acceptable average price
+        let average_price = get_average_price(
+            &testing_env,
+            swap_manager::SwapType::Buy,
+            swap_manager::SwapAmountType::ExactOutput,
+            1_000_000_000,
+            Rounding::Up,
+        );
+
+        swap_action.limit_price = Some(average_price - 1);
+
+        let result = testing_env.svm.execute_actions(&[&swap_action]);
+
+        let error = tm_parse_error(result).unwrap();
+
+        assert_eq!(error, TokenMillError::PriceLimitExceeded);
+
+        swap_action.limit_price = Some(average_price);
+
+        testing_env.svm.execute_actions(&[&swap_action]).unwrap();
+
+        // Sells are limited by the lowest acceptable average price
+        let average_price = get_average_price(
+            &testing_env,
+            swap_manager::SwapType::Sell,
+            swap_manager::SwapAmountType::ExactInput,
+            500_000_000,
+            Rounding::Down,
+        );
+
+        swap_action.swap_type = SwapType::Sell;
+        swap_action.swap_amount_type = SwapAmountType::ExactInput;
+        swap_action.amount = 500_000_000;
+        swap_action.other_amount_threshold = 0;
+        swap_action.limit_price = Some(average_price + 1);
+
+        let result = testing_env.svm.execute_actions(&[&swap_action]);
+
+        let error = tm_parse_error(result).unwrap();
+
+        assert_eq!(error, TokenMillError::PriceLimitExceeded);
+
+        swap_action.limit_price = Some(average_price);
+
+        testing_env.svm.execute_actions(&[&swap_action]).unwrap();
+    }
+
     #[test]
     fn swap_more_than_total_supply_with_exact_output() {
         let (mut testing_env, mut swap_action) = setup_env();
@@ -483,4 +1047,575 @@ mod tests {
 
         testing_env.svm.execute_actions(&[&swap_action]).unwrap();
     }
+
+    #[test]
+    fn swap_with_referral_fee_share_override() {
+        let (mut testing_env, _) = setup_env();
+
+        let carol = make_address("carol");
+        let quote_token_mint = testing_env.quote_token_mint.unwrap();
+        let referral_account = get_referral_account_address(&carol);
+
+        // Spending a fixed quote amount keeps the swap fee constant across swaps
+        let swap_action = SwapAction::new(
+            &testing_env,
+            SwapType::Buy,
+            SwapAmountType::ExactInput,
+            1_000_000_000,
+            0,
+            Some(carol),
+        );
+
+        testing_env.svm.execute_actions(&[&swap_action]).unwrap();
+
+        let default_referral_fee = testing_env
+            .svm
+            .get_balance(&quote_token_mint, &referral_account);
+
+        testing_env.svm.change_payer("admin");
+        testing_env
+            .svm
+            .execute_actions(&[&SetReferralFeeShareOverrideAction::new(
+                carol,
+                Some(MAX_BPS as u16),
+            )])
+            .unwrap();
+        testing_env.svm.change_payer("bob");
+
+        testing_env.svm.execute_actions(&[&swap_action]).unwrap();
+
+        let referral_fee = testing_env
+            .svm
+            .get_balance(&quote_token_mint, &referral_account)
+            - default_referral_fee;
+
+        assert!(referral_fee > default_referral_fee);
+    }
+
+    #[test]
+    fn swap_without_referral_account() {
+        let (mut testing_env, _) = setup_env();
+
+        // Referral fees can't be paid to a token account without its referral account
+        let swap_action = SwapAction::new(
+            &testing_env,
+            SwapType::Buy,
+            SwapAmountType::ExactOutput,
+            1_000_000_000,
+            u64::MAX,
+            Some(make_address("carol")),
+        )
+        .without_referral_account();
+
+        let result = testing_env.svm.execute_actions(&[&swap_action]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::InvalidReferralAccount
+        );
+    }
+
+    #[test]
+    fn swap_with_invalid_referral_account() {
+        let (mut testing_env, _) = setup_env();
+
+        let mut swap_action = SwapAction::new(
+            &testing_env,
+            SwapType::Buy,
+            SwapAmountType::ExactOutput,
+            1_000_000_000,
+            u64::MAX,
+            Some(make_address("carol")),
+        );
+
+        // The referral token account isn't owned by carol's referral account
+        swap_action.referral_quote_token_ata = swap_action.user_quote_token_ata;
+
+        let result = testing_env.svm.execute_actions(&[&swap_action]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::InvalidReferralAccount
+        );
+    }
+
+    /// Creates the referral account of dave, whose parent is the one of carol
+    fn setup_parent_referral(testing_env: &mut TokenMillEnv, sub_referral_fee_share: u16) {
+        testing_env.svm.change_payer("admin");
+
+        testing_env
+            .svm
+            .execute_actions(&[&UpdateSubReferralFeeShareAction::new(
+                sub_referral_fee_share,
+            )])
+            .unwrap();
+
+        let dave = testing_env.svm.change_payer("dave");
+
+        testing_env
+            .svm
+            .execute_actions(&[&CreateReferralAccountAction::new()
+                .with_referrer(dave)
+                .with_parent_referrer(make_address("carol"))])
+            .unwrap();
+
+        testing_env
+            .svm
+            .create_ata(
+                &get_referral_account_address(&dave),
+                &testing_env.quote_token_mint.unwrap(),
+                testing_env.quote_token_type,
+            )
+            .unwrap();
+
+        testing_env.svm.change_payer("bob");
+    }
+
+    #[test]
+    fn swap_with_parent_referral() {
+        const SUB_REFERRAL_FEE_SHARE: u16 = 2_000;
+
+        let (mut testing_env, _) = setup_env();
+
+        setup_parent_referral(&mut testing_env, SUB_REFERRAL_FEE_SHARE);
+
+        let carol = make_address("carol");
+        let dave = make_address("dave");
+        let quote_token_mint = testing_env.quote_token_mint.unwrap();
+
+        let swap_action = SwapAction::new(
+            &testing_env,
+            SwapType::Buy,
+            SwapAmountType::ExactOutput,
+            1_000_000_000,
+            u64::MAX,
+            Some(dave),
+        )
+        .with_parent_referrer(dave, carol);
+
+        testing_env.svm.execute_actions(&[&swap_action]).unwrap();
+
+        let referral_fee = testing_env
+            .svm
+            .get_balance(&quote_token_mint, &get_referral_account_address(&dave));
+        let sub_referral_fee = testing_env
+            .svm
+            .get_balance(&quote_token_mint, &get_referral_account_address(&carol));
+        let total_referral_fee = referral_fee + sub_referral_fee;
+
+        assert!(sub_referral_fee > 0);
+        assert_eq!(
+            sub_referral_fee,
+            total_referral_fee * u64::from(SUB_REFERRAL_FEE_SHARE) / MAX_BPS
+        );
+
+        // Both referrers claim their share from their referral account
+        let carol_balance_before = testing_env.svm.get_balance(&quote_token_mint, &carol);
+        let dave_balance_before = testing_env.svm.get_balance(&quote_token_mint, &dave);
+
+        testing_env.svm.change_payer("carol");
+        testing_env
+            .svm
+            .execute_actions(&[&ClaimReferralFeesAction::new(&testing_env)])
+            .unwrap();
+
+        testing_env.svm.change_payer("dave");
+        testing_env
+            .svm
+            .execute_actions(&[&ClaimReferralFeesAction::new(&testing_env).with_referrer(dave)])
+            .unwrap();
+
+        assert_eq!(
+            testing_env.svm.get_balance(&quote_token_mint, &carol) - carol_balance_before,
+            sub_referral_fee
+        );
+        assert_eq!(
+            testing_env.svm.get_balance(&quote_token_mint, &dave) - dave_balance_before,
+            referral_fee
+        );
+    }
+
+    #[test]
+    fn swap_with_invalid_parent_referral() {
+        let (mut testing_env, _) = setup_env();
+
+        setup_parent_referral(&mut testing_env, 2_000);
+
+        // Carol's referral account has no parent
+        let swap_action = SwapAction::new(
+            &testing_env,
+            SwapType::Buy,
+            SwapAmountType::ExactOutput,
+            1_000_000_000,
+            u64::MAX,
+            Some(make_address("carol")),
+        )
+        .with_parent_referrer(make_address("carol"), make_address("dave"));
+
+        let result = testing_env.svm.execute_actions(&[&swap_action]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::InvalidReferralAccount
+        );
+    }
+
+    #[test]
+    fn swap_without_parent_referral() {
+        let (mut testing_env, _) = setup_env();
+
+        setup_parent_referral(&mut testing_env, 2_000);
+
+        let dave = make_address("dave");
+
+        // Dave's referral account has a parent, which can't be left out
+        let swap_action = SwapAction::new(
+            &testing_env,
+            SwapType::Buy,
+            SwapAmountType::ExactOutput,
+            1_000_000_000,
+            u64::MAX,
+            Some(dave),
+        );
+
+        let result = testing_env.svm.execute_actions(&[&swap_action]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::InvalidReferralAccount
+        );
+
+        let swap_action = swap_action.with_parent_referrer(dave, make_address("carol"));
+
+        testing_env.svm.execute_actions(&[&swap_action]).unwrap();
+    }
+
+    #[test]
+    fn swap_with_market_oracle() {
+        let (mut testing_env, swap_action) = setup_env();
+
+        let create_market_oracle_action = CreateMarketOracleAction::new(&testing_env);
+
+        testing_env.svm.change_payer("alice");
+
+        testing_env
+            .svm
+            .execute_actions(&[&create_market_oracle_action])
+            .unwrap();
+
+        testing_env.svm.change_payer("bob");
+
+        testing_env.svm.warp(60);
+
+        // Swaps must update the oracle once the market has one
+        let result = testing_env.svm.execute_actions(&[&swap_action]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::MissingMarketOracle
+        );
+
+        let swap_action = swap_action.with_market_oracle();
+
+        testing_env.svm.execute_actions(&[&swap_action]).unwrap();
+
+        let market = testing_env
+            .svm
+            .get_parsed_account::<Market>(&testing_env.market);
+        let market_oracle = testing_env
+            .svm
+            .get_parsed_account::<MarketOracle>(&create_market_oracle_action.market_oracle);
+
+        assert_eq!(market_oracle.observation_count, 2);
+        assert_eq!(market_oracle.observations[1].timestamp, 60);
+        assert_eq!(
+            market_oracle.ask_price,
+            market.get_spot_price(swap_manager::SwapType::Buy).unwrap()
+        );
+        assert_eq!(
+            market_oracle.bid_price,
+            market.get_spot_price(swap_manager::SwapType::Sell).unwrap()
+        );
+    }
+
+    #[test]
+    fn swap_with_market_stats() {
+        let (mut testing_env, _) = setup_env();
+        let bob = make_address("bob");
+        let quote_token_mint = testing_env.quote_token_mint.unwrap();
+
+        let create_market_stats_action = CreateMarketStatsAction::new(&testing_env);
+
+        testing_env.svm.change_payer("alice");
+
+        testing_env
+            .svm
+            .execute_actions(&[&create_market_stats_action])
+            .unwrap();
+
+        testing_env.svm.change_payer("bob");
+
+        testing_env.svm.warp(100);
+
+        let buy_action = SwapAction::new(
+            &testing_env,
+            SwapType::Buy,
+            SwapAmountType::ExactOutput,
+            10_000_000_000,
+            u64::MAX,
+            Some(make_address("carol")),
+        );
+
+        // Swaps must update the stats once the market has some
+        let result = testing_env.svm.execute_actions(&[&buy_action]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::MissingMarketStats
+        );
+
+        let buy_action = buy_action.with_market_stats();
+
+        let quote_balance_before = testing_env.svm.get_balance(&quote_token_mint, &bob);
+        testing_env.svm.execute_actions(&[&buy_action]).unwrap();
+        let buy_quote_amount =
+            quote_balance_before - testing_env.svm.get_balance(&quote_token_mint, &bob);
+
+        let max_supply_ask_price = testing_env
+            .svm
+            .get_parsed_account::<Market>(&testing_env.market)
+            .get_spot_price(swap_manager::SwapType::Buy)
+            .unwrap();
+
+        testing_env.svm.warp(50);
+
+        let sell_action = SwapAction::new(
+            &testing_env,
+            SwapType::Sell,
+            SwapAmountType::ExactInput,
+            4_000_000_000,
+            0,
+            None,
+        )
+        .with_market_stats();
+
+        let quote_balance_before = testing_env.svm.get_balance(&quote_token_mint, &bob);
+        testing_env.svm.execute_actions(&[&sell_action]).unwrap();
+        let sell_quote_amount =
+            testing_env.svm.get_balance(&quote_token_mint, &bob) - quote_balance_before;
+
+        let market = testing_env
+            .svm
+            .get_parsed_account::<Market>(&testing_env.market);
+        let market_stats = testing_env
+            .svm
+            .get_parsed_account::<MarketStats>(&create_market_stats_action.market_stats);
+
+        assert_eq!(market_stats.trade_count, 2);
+        assert_eq!(market_stats.buy_base_volume, 10_000_000_000);
+        assert_eq!(market_stats.buy_quote_volume, u128::from(buy_quote_amount));
+        assert_eq!(market_stats.sell_base_volume, 4_000_000_000);
+        assert_eq!(
+            market_stats.sell_quote_volume,
+            u128::from(sell_quote_amount)
+        );
+
+        // The market had no fees before the stats were created
+        assert_eq!(
+            market_stats.creator_fees,
+            u128::from(market.fees.pending_creator_fees)
+        );
+        assert_eq!(
+            market_stats.staking_fees,
+            u128::from(market.fees.pending_staking_fees)
+        );
+        assert_eq!(
+            market_stats.protocol_fees,
+            u128::from(market.fees.pending_protocol_fees)
+        );
+        assert!(market_stats.referral_fees > 0);
+
+        assert_eq!(market_stats.max_circulating_supply, 10_000_000_000);
+        assert_eq!(
+            market_stats.max_circulating_supply_ask_price,
+            max_supply_ask_price
+        );
+        assert_eq!(market_stats.last_trade_ts, 150);
+    }
+
+    #[rstest]
+    fn swap_with_transfer_fee_quote_token(
+        #[values(SwapType::Buy, SwapType::Sell)] swap_type: SwapType,
+        #[values(SwapAmountType::ExactInput, SwapAmountType::ExactOutput)]
+        swap_amount_type: SwapAmountType,
+        #[values(u64::MAX, 5_000_000)] maximum_fee: u64,
+    ) {
+        let mut testing_env = TokenMillEnv::new()
+            .with_transfer_fee_quote_token_mint(9, 150, maximum_fee)
+            .with_default_market();
+        let bob = testing_env.svm.change_payer("bob");
+
+        // Gives bob some base tokens to sell
+        testing_env
+            .svm
+            .execute_actions(&[&SwapAction::new(
+                &testing_env,
+                SwapType::Buy,
+                SwapAmountType::ExactOutput,
+                100_000_000_000,
+                u64::MAX,
+                None,
+            )])
+            .unwrap();
+
+        let amount = match (swap_type, swap_amount_type) {
+            (SwapType::Buy, SwapAmountType::ExactInput) => 1_000_000_000,
+            (SwapType::Sell, SwapAmountType::ExactOutput) => 10_000_000,
+            _ => 10_000_000_000,
+        };
+
+        let quote_action = QuoteSwapAction::new(&testing_env, swap_type, swap_amount_type, amount);
+        let result = testing_env.svm.execute_actions(&[&quote_action]).unwrap();
+        let swap_quote = SwapQuote::try_from_slice(&result.return_data.data).unwrap();
+
+        assert!(swap_quote.transfer_fee > 0);
+
+        let market = testing_env.market;
+        let base_token_mint = testing_env.base_token_mint.unwrap();
+        let quote_token_mint = testing_env.quote_token_mint.unwrap();
+
+        let base_balance_before = testing_env.svm.get_balance(&base_token_mint, &bob);
+        let quote_balance_before = testing_env.svm.get_balance(&quote_token_mint, &bob);
+        let market_quote_balance_before = testing_env.svm.get_balance(&quote_token_mint, &market);
+
+        testing_env
+            .svm
+            .execute_actions(&[&SwapAction::new(
+                &testing_env,
+                swap_type,
+                swap_amount_type,
+                amount,
+                match swap_amount_type {
+                    SwapAmountType::ExactInput => 0,
+                    SwapAmountType::ExactOutput => u64::MAX,
+                },
+                None,
+            )])
+            .unwrap();
+
+        let base_balance_delta = testing_env
+            .svm
+            .get_balance(&base_token_mint, &bob)
+            .abs_diff(base_balance_before);
+        let quote_balance_delta = testing_env
+            .svm
+            .get_balance(&quote_token_mint, &bob)
+            .abs_diff(quote_balance_before);
+        let market_quote_balance_delta = testing_env
+            .svm
+            .get_balance(&quote_token_mint, &market)
+            .abs_diff(market_quote_balance_before);
+
+        // The user pays the transfer fee on top of buys and receives sells net of it
+        assert_eq!(base_balance_delta, swap_quote.base_amount);
+        assert_eq!(quote_balance_delta, swap_quote.quote_amount);
+        assert_eq!(
+            market_quote_balance_delta,
+            match swap_type {
+                SwapType::Buy => swap_quote.quote_amount - swap_quote.transfer_fee,
+                SwapType::Sell => swap_quote.quote_amount + swap_quote.transfer_fee,
+            }
+        );
+
+        match (swap_type, swap_amount_type) {
+            (SwapType::Buy, SwapAmountType::ExactInput) => assert!(quote_balance_delta <= amount),
+            (SwapType::Sell, SwapAmountType::ExactOutput) => {
+                assert!(quote_balance_delta >= amount)
+            }
+            _ => assert_eq!(base_balance_delta, amount),
+        }
+    }
+
+    #[rstest]
+    fn swap_with_fee_discount(
+        #[values(SwapAmountType::ExactInput, SwapAmountType::ExactOutput)]
+        swap_amount_type: SwapAmountType,
+    ) {
+        const STAKE_AMOUNT: u64 = 100_000_000;
+        const DISCOUNT_BPS: u16 = 5_000;
+
+        let mut testing_env = TokenMillEnv::new()
+            .with_default_quote_token_mint()
+            .with_default_market()
+            .with_staking(STAKE_AMOUNT);
+
+        testing_env
+            .svm
+            .execute_actions(&[&DepositAction::new(&testing_env, STAKE_AMOUNT)])
+            .unwrap();
+
+        testing_env.svm.change_payer("admin");
+
+        testing_env
+            .svm
+            .execute_actions(&[&UpdateFeeDiscountTiersAction::new(vec![FeeDiscountTier {
+                min_amount: STAKE_AMOUNT,
+                discount_bps: DISCOUNT_BPS,
+            }])])
+            .unwrap();
+
+        let bob = testing_env.svm.change_payer("bob");
+
+        let amount = match swap_amount_type {
+            SwapAmountType::ExactInput => 1_000_000_000,
+            SwapAmountType::ExactOutput => 10_000_000_000,
+        };
+
+        let quote_action =
+            QuoteSwapAction::new(&testing_env, SwapType::Buy, swap_amount_type, amount);
+        let result = testing_env.svm.execute_actions(&[&quote_action]).unwrap();
+        let swap_quote = SwapQuote::try_from_slice(&result.return_data.data).unwrap();
+
+        let quote_action = quote_action.with_stake_position(bob);
+        let result = testing_env.svm.execute_actions(&[&quote_action]).unwrap();
+        let discounted_swap_quote = SwapQuote::try_from_slice(&result.return_data.data).unwrap();
+
+        let base_token_mint = testing_env.base_token_mint.unwrap();
+        let quote_token_mint = testing_env.quote_token_mint.unwrap();
+
+        let base_balance_before = testing_env.svm.get_balance(&base_token_mint, &bob);
+        let quote_balance_before = testing_env.svm.get_balance(&quote_token_mint, &bob);
+
+        let swap_action = SwapAction::new(
+            &testing_env,
+            SwapType::Buy,
+            swap_amount_type,
+            amount,
+            match swap_amount_type {
+                SwapAmountType::ExactInput => 0,
+                SwapAmountType::ExactOutput => u64::MAX,
+            },
+            None,
+        )
+        .with_stake_position();
+
+        testing_env.svm.execute_actions(&[&swap_action]).unwrap();
+
+        let base_balance_delta =
+            testing_env.svm.get_balance(&base_token_mint, &bob) - base_balance_before;
+        let quote_balance_delta =
+            quote_balance_before - testing_env.svm.get_balance(&quote_token_mint, &bob);
+
+        // The same base amount is bought, the discounted part of the spread fee isn't paid
+        let fee_discount = get_fee_amount(swap_quote.swap_fee, DISCOUNT_BPS.into()).unwrap();
+
+        assert!(fee_discount > 0);
+        assert_eq!(base_balance_delta, swap_quote.base_amount);
+        assert_eq!(quote_balance_delta, swap_quote.quote_amount - fee_discount);
+
+        // Quotes with the stake position match the discounted swap
+        assert_eq!(discounted_swap_quote.fee_discount, fee_discount);
+        assert_eq!(base_balance_delta, discounted_swap_quote.base_amount);
+        assert_eq!(quote_balance_delta, discounted_swap_quote.quote_amount);
+    }
 }
@@ -0,0 +1,528 @@
+use std::collections::BTreeSet;
+
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::TokenMillError,
+    manager::{
+        launch_manager::{check_presale, update_launch_allocation},
+        swap_manager::{
+            self, SwapAmountType, SwapBatchMode, SwapLeg, SwapLegResult, SwapResult, SwapType,
+        },
+        token_manager::get_epoch_transfer_fee,
+    },
+};
+
+use super::swap::{self, Swap, SwapBumps};
+
+/// Each leg reads the accounts of a `Swap` instruction from the remaining accounts, in order,
+/// followed by the extra accounts of its base token's transfer hook.
+#[derive(Accounts)]
+pub struct SwapBatch<'info> {
+    pub user: Signer<'info>,
+}
+
+pub fn handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, SwapBatch<'info>>,
+    legs: Vec<SwapLeg>,
+    mode: SwapBatchMode,
+) -> Result<Vec<SwapLegResult>> {
+    if legs.is_empty() {
+        return Err(TokenMillError::InvalidAmount.into());
+    }
+
+    let mut remaining_accounts = ctx.remaining_accounts;
+    let mut results = Vec::with_capacity(legs.len());
+
+    for leg in legs {
+        // Runs the same account validation as the `Swap` instruction
+        let mut bumps = SwapBumps::default();
+        let mut swap_accounts = Box::new(Swap::try_accounts(
+            ctx.program_id,
+            &mut remaining_accounts,
+            &[],
+            &mut bumps,
+            &mut BTreeSet::new(),
+        )?);
+
+        if swap_accounts.user.key() != ctx.accounts.user.key() {
+            return Err(TokenMillError::InvalidAuthority.into());
+        }
+
+        let transfer_hook_accounts_count = usize::from(leg.transfer_hook_accounts);
+
+        if remaining_accounts.len() < transfer_hook_accounts_count {
+            return Err(ErrorCode::AccountNotEnoughKeys.into());
+        }
+
+        let transfer_hook_accounts;
+        (transfer_hook_accounts, remaining_accounts) =
+            remaining_accounts.split_at(transfer_hook_accounts_count);
+
+        // Swapping mutates the market before the threshold check, failing legs must be skipped beforehand
+        if mode == SwapBatchMode::BestEffort
+            && simulate_leg(&swap_accounts, &leg, transfer_hook_accounts).is_err()
+        {
+            results.push(SwapLegResult {
+                executed: false,
+                base_amount: 0,
+                quote_amount: 0,
+            });
+
+            continue;
+        }
+
+        let (base_amount, quote_amount) = swap::handler(
+            Context::new(
+                ctx.program_id,
+                &mut swap_accounts,
+                transfer_hook_accounts,
+                bumps,
+            ),
+            leg.swap_type,
+            leg.swap_amount_type,
+            leg.amount,
+            leg.other_amount_threshold,
+            None,
+            None,
+            None,
+        )?;
+
+        swap_accounts.exit(ctx.program_id)?;
+
+        results.push(SwapLegResult {
+            executed: true,
+            base_amount,
+            quote_amount,
+        });
+    }
+
+    Ok(results)
+}
+
+/// Runs the leg against a copy of the market after the checks of the `Swap` instruction,
+/// also checking its amount threshold and the user balance.
+fn simulate_leg<'info>(
+    swap_accounts: &Swap<'info>,
+    leg: &SwapLeg,
+    transfer_hook_accounts: &[AccountInfo<'info>],
+) -> Result<()> {
+    let current_time = Clock::get()?.unix_timestamp;
+
+    swap_accounts.validate(
+        leg.swap_type,
+        leg.amount,
+        current_time,
+        transfer_hook_accounts,
+    )?;
+
+    let mut market = Box::new(*swap_accounts.market.load()?);
+
+    let SwapResult {
+        base_amount,
+        quote_amount,
+        transfer_fee,
+        ..
+    } = swap_manager::swap_with_transfer_fee(
+        &mut market,
+        leg.swap_type,
+        leg.swap_amount_type,
+        leg.amount,
+        current_time,
+        get_epoch_transfer_fee(&swap_accounts.quote_token_mint)?.as_ref(),
+        swap_accounts.get_fee_discount_bps(),
+    )?;
+
+    // Legs carry no presale proof, buying is impossible during the presale
+    check_presale(
+        &market,
+        &swap_accounts.user.key(),
+        None,
+        &mut None,
+        leg.swap_type,
+        base_amount,
+        current_time,
+    )?;
+    update_launch_allocation(
+        &market,
+        &mut swap_accounts.launch_allocation.clone(),
+        leg.swap_type,
+        base_amount,
+        current_time,
+    )?;
+
+    let (amount_in, amount_out, user_balance_in) = match leg.swap_type {
+        SwapType::Buy => (
+            quote_amount + transfer_fee,
+            base_amount,
+            swap_accounts.user_quote_token_account.amount,
+        ),
+        SwapType::Sell => (
+            base_amount,
+            quote_amount - transfer_fee,
+            swap_accounts.user_base_token_account.amount,
+        ),
+    };
+
+    let threshold_met = match leg.swap_amount_type {
+        SwapAmountType::ExactInput => amount_out >= leg.other_amount_threshold,
+        SwapAmountType::ExactOutput => amount_in <= leg.other_amount_threshold,
+    };
+
+    if !threshold_met || amount_in > user_balance_in {
+        return Err(TokenMillError::AmountThresholdNotMet.into());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::{prelude::Pubkey, AnchorDeserialize};
+    use joelana_test_utils::joelana_env::{
+        actions::token_mill::{
+            get_transfer_counter_address, tm_parse_error, CreateMarketStatsAction,
+            SetOperationPausedAction, SwapAction, SwapBatchAction, TokenMillEnv,
+            DEFAULT_TOTAL_SUPPLY,
+        },
+        make_address, InstructionGenerator, PausableOperation, SwapAmountType, SwapBatchMode,
+        SwapLegResult, SwapType, TokenMillError,
+    };
+    use mock_transfer_hook::TransferCounter;
+    use rstest::rstest;
+
+    fn setup_env() -> (TokenMillEnv, Pubkey, Pubkey) {
+        setup_env_with(TokenMillEnv::default())
+    }
+
+    fn setup_env_with(testing_env: TokenMillEnv) -> (TokenMillEnv, Pubkey, Pubkey) {
+        let base_token_mint_a = testing_env.base_token_mint.unwrap();
+
+        let mut testing_env =
+            testing_env.with_market(make_address("base_token_mint_b"), DEFAULT_TOTAL_SUPPLY);
+        let base_token_mint_b = testing_env.base_token_mint.unwrap();
+
+        testing_env.svm.change_payer("bob");
+
+        (testing_env, base_token_mint_a, base_token_mint_b)
+    }
+
+    fn buy_leg(testing_env: &TokenMillEnv, base_token_mint: Pubkey, amount: u64) -> SwapAction {
+        SwapAction::new(
+            testing_env,
+            SwapType::Buy,
+            SwapAmountType::ExactOutput,
+            amount,
+            u64::MAX,
+            None,
+        )
+        .with_custom_base_token_mint(base_token_mint)
+    }
+
+    fn with_signer(testing_env: &TokenMillEnv, mut leg: SwapAction, signer: &str) -> SwapAction {
+        leg.signer = make_address(signer);
+        leg.user_base_token_ata = testing_env
+            .svm
+            .get_ata_address(&leg.base_token_mint, &leg.signer);
+        leg.user_quote_token_ata = testing_env
+            .svm
+            .get_ata_address(&leg.quote_token_mint, &leg.signer);
+
+        leg
+    }
+
+    #[rstest]
+    fn swap_batch(
+        #[values(SwapBatchMode::AllOrNothing, SwapBatchMode::BestEffort)] mode: SwapBatchMode,
+    ) {
+        let (mut testing_env, base_token_mint_a, base_token_mint_b) = setup_env();
+        let bob = make_address("bob");
+
+        let mut sell_leg = buy_leg(&testing_env, base_token_mint_a, 1_000_000_000);
+        sell_leg.swap_type = SwapType::Sell;
+        sell_leg.swap_amount_type = SwapAmountType::ExactInput;
+        sell_leg.other_amount_threshold = 0;
+
+        // Legs can trade the same market several times
+        let swap_batch_action = SwapBatchAction::new(
+            vec![
+                buy_leg(&testing_env, base_token_mint_a, 10_000_000_000),
+                buy_leg(&testing_env, base_token_mint_b, 20_000_000_000),
+                sell_leg,
+            ],
+            mode,
+        );
+
+        let result = testing_env
+            .svm
+            .execute_actions(&[&swap_batch_action])
+            .unwrap();
+
+        let results = Vec::<SwapLegResult>::try_from_slice(&result.return_data.data).unwrap();
+
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(|result| result.executed));
+        assert_eq!(results[0].base_amount, 10_000_000_000);
+        assert_eq!(results[1].base_amount, 20_000_000_000);
+        assert_eq!(results[2].base_amount, 1_000_000_000);
+        assert!(results.iter().all(|result| result.quote_amount > 0));
+
+        assert_eq!(
+            testing_env.svm.get_balance(&base_token_mint_a, &bob),
+            9_000_000_000
+        );
+        assert_eq!(
+            testing_env.svm.get_balance(&base_token_mint_b, &bob),
+            20_000_000_000
+        );
+    }
+
+    #[test]
+    fn swap_batch_with_transfer_hook() {
+        let (mut testing_env, base_token_mint_a, base_token_mint_b) = setup_env_with(
+            TokenMillEnv::new()
+                .with_default_quote_token_mint()
+                .with_transfer_hook()
+                .with_default_market(),
+        );
+
+        // Each leg carries the hook accounts of its base token
+        let swap_batch_action = SwapBatchAction::new(
+            vec![
+                buy_leg(&testing_env, base_token_mint_a, 10_000_000_000),
+                buy_leg(&testing_env, base_token_mint_b, 20_000_000_000),
+            ],
+            SwapBatchMode::AllOrNothing,
+        );
+
+        testing_env
+            .svm
+            .execute_actions(&[&swap_batch_action])
+            .unwrap();
+
+        for (base_token_mint, amount) in [
+            (base_token_mint_a, 10_000_000_000),
+            (base_token_mint_b, 20_000_000_000),
+        ] {
+            let counter = testing_env.svm.get_parsed_account::<TransferCounter>(
+                &get_transfer_counter_address(&base_token_mint),
+            );
+
+            assert_eq!(counter.transfers, 1);
+            assert_eq!(counter.amount, amount);
+        }
+    }
+
+    #[test]
+    fn swap_batch_all_or_nothing_with_failing_leg() {
+        let (mut testing_env, base_token_mint_a, base_token_mint_b) = setup_env();
+        let bob = make_address("bob");
+
+        let mut failing_leg = buy_leg(&testing_env, base_token_mint_b, 20_000_000_000);
+        failing_leg.other_amount_threshold = 1;
+
+        let swap_batch_action = SwapBatchAction::new(
+            vec![
+                buy_leg(&testing_env, base_token_mint_a, 10_000_000_000),
+                failing_leg,
+            ],
+            SwapBatchMode::AllOrNothing,
+        );
+
+        let result = testing_env.svm.execute_actions(&[&swap_batch_action]);
+
+        assert!(result.is_err());
+
+        let error = tm_parse_error(result).unwrap();
+
+        assert_eq!(error, TokenMillError::AmountThresholdNotMet);
+        assert_eq!(testing_env.svm.get_balance(&base_token_mint_a, &bob), 0);
+    }
+
+    #[test]
+    fn swap_batch_best_effort_with_failing_legs() {
+        let (mut testing_env, base_token_mint_a, base_token_mint_b) = setup_env();
+        let bob = make_address("bob");
+
+        let mut failing_threshold_leg = buy_leg(&testing_env, base_token_mint_b, 20_000_000_000);
+        failing_threshold_leg.other_amount_threshold = 1;
+
+        // Bob doesn't hold any of the base tokens bought by carol
+        let carol_buy_action = with_signer(
+            &testing_env,
+            buy_leg(&testing_env, base_token_mint_b, 50_000_000_000),
+            "carol",
+        );
+
+        testing_env
+            .svm
+            .execute_actions(&[&carol_buy_action])
+            .unwrap();
+
+        let mut failing_balance_leg = buy_leg(&testing_env, base_token_mint_b, 1_000_000_000);
+        failing_balance_leg.swap_type = SwapType::Sell;
+        failing_balance_leg.swap_amount_type = SwapAmountType::ExactInput;
+        failing_balance_leg.other_amount_threshold = 0;
+
+        let swap_batch_action = SwapBatchAction::new(
+            vec![
+                failing_threshold_leg,
+                buy_leg(&testing_env, base_token_mint_a, 10_000_000_000),
+                failing_balance_leg,
+            ],
+            SwapBatchMode::BestEffort,
+        );
+
+        let result = testing_env
+            .svm
+            .execute_actions(&[&swap_batch_action])
+            .unwrap();
+
+        let results = Vec::<SwapLegResult>::try_from_slice(&result.return_data.data).unwrap();
+
+        assert_eq!(
+            results.iter().map(|r| r.executed).collect::<Vec<_>>(),
+            vec![false, true, false]
+        );
+        assert_eq!(results[1].base_amount, 10_000_000_000);
+
+        assert_eq!(
+            testing_env.svm.get_balance(&base_token_mint_a, &bob),
+            10_000_000_000
+        );
+        assert_eq!(testing_env.svm.get_balance(&base_token_mint_b, &bob), 0);
+    }
+
+    #[test]
+    fn swap_batch_best_effort_with_invalid_legs() {
+        let (mut testing_env, base_token_mint_a, base_token_mint_b) = setup_env();
+        let bob = make_address("bob");
+
+        // Swaps on market b must update its stats from now on
+        testing_env.svm.change_payer("alice");
+        testing_env
+            .svm
+            .execute_actions(&[&CreateMarketStatsAction::new(&testing_env)])
+            .unwrap();
+        testing_env.svm.change_payer("bob");
+
+        let missing_stats_leg = buy_leg(&testing_env, base_token_mint_b, 20_000_000_000);
+
+        let mut invalid_referral_leg = buy_leg(&testing_env, base_token_mint_a, 10_000_000_000);
+        invalid_referral_leg.referral_quote_token_ata = invalid_referral_leg.user_quote_token_ata;
+
+        let swap_batch_action = SwapBatchAction::new(
+            vec![
+                missing_stats_leg,
+                invalid_referral_leg,
+                buy_leg(&testing_env, base_token_mint_a, 10_000_000_000),
+            ],
+            SwapBatchMode::BestEffort,
+        );
+
+        let result = testing_env
+            .svm
+            .execute_actions(&[&swap_batch_action])
+            .unwrap();
+
+        let results = Vec::<SwapLegResult>::try_from_slice(&result.return_data.data).unwrap();
+
+        assert_eq!(
+            results.iter().map(|r| r.executed).collect::<Vec<_>>(),
+            vec![false, false, true]
+        );
+        assert_eq!(
+            testing_env.svm.get_balance(&base_token_mint_a, &bob),
+            10_000_000_000
+        );
+        assert_eq!(testing_env.svm.get_balance(&base_token_mint_b, &bob), 0);
+    }
+
+    #[test]
+    fn swap_batch_best_effort_with_paused_buys() {
+        let (mut testing_env, base_token_mint_a, _) = setup_env();
+        let bob = make_address("bob");
+
+        testing_env
+            .svm
+            .execute_actions(&[&buy_leg(&testing_env, base_token_mint_a, 10_000_000_000)])
+            .unwrap();
+
+        testing_env.svm.change_payer("admin");
+        testing_env
+            .svm
+            .execute_actions(&[&SetOperationPausedAction::new(PausableOperation::Buy, true)])
+            .unwrap();
+        testing_env.svm.change_payer("bob");
+
+        let mut sell_leg = buy_leg(&testing_env, base_token_mint_a, 1_000_000_000);
+        sell_leg.swap_type = SwapType::Sell;
+        sell_leg.swap_amount_type = SwapAmountType::ExactInput;
+        sell_leg.other_amount_threshold = 0;
+
+        let swap_batch_action = SwapBatchAction::new(
+            vec![
+                buy_leg(&testing_env, base_token_mint_a, 10_000_000_000),
+                sell_leg,
+            ],
+            SwapBatchMode::BestEffort,
+        );
+
+        let result = testing_env
+            .svm
+            .execute_actions(&[&swap_batch_action])
+            .unwrap();
+
+        let results = Vec::<SwapLegResult>::try_from_slice(&result.return_data.data).unwrap();
+
+        assert_eq!(
+            results.iter().map(|r| r.executed).collect::<Vec<_>>(),
+            vec![false, true]
+        );
+        assert_eq!(
+            testing_env.svm.get_balance(&base_token_mint_a, &bob),
+            9_000_000_000
+        );
+    }
+
+    #[test]
+    fn swap_batch_with_invalid_user() {
+        let (mut testing_env, base_token_mint_a, _) = setup_env();
+
+        let leg = with_signer(
+            &testing_env,
+            buy_leg(&testing_env, base_token_mint_a, 10_000_000_000),
+            "alice",
+        );
+
+        let swap_batch_action = SwapBatchAction::new(vec![leg], SwapBatchMode::AllOrNothing);
+
+        let result = testing_env.svm.execute_actions(&[&swap_batch_action]);
+
+        assert!(result.is_err());
+
+        let error = tm_parse_error(result).unwrap();
+
+        assert_eq!(error, TokenMillError::InvalidAuthority);
+    }
+
+    #[test]
+    fn swap_batch_with_missing_accounts() {
+        let (mut testing_env, base_token_mint_a, _) = setup_env();
+
+        let mut swap_batch_action = SwapBatchAction::new(
+            vec![buy_leg(&testing_env, base_token_mint_a, 10_000_000_000)],
+            SwapBatchMode::AllOrNothing,
+        );
+
+        // Declares a second leg without its accounts
+        let leg = buy_leg(&testing_env, base_token_mint_a, 10_000_000_000);
+        let mut instruction = swap_batch_action.instruction();
+        swap_batch_action.legs.push(leg);
+        instruction.data = swap_batch_action.instruction().data;
+
+        let result = testing_env.svm.execute(&[instruction]);
+
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,1136 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::{
+    errors::TokenMillError,
+    events::TokenMillSwapEvent,
+    manager::{
+        launch_manager::{check_presale, update_launch_allocation},
+        oracle_manager::update_market_oracle,
+        referral_manager::{
+            check_referral_accounts, get_referral_fee_shares, get_sub_referral_fee,
+            transfer_referral_fees,
+        },
+        stats_manager::update_market_stats,
+        swap_manager::{self, SwapAmountType, SwapResult, SwapType},
+        token_manager::{
+            get_epoch_transfer_fee, transfer_from_eoa_with_hook, transfer_from_pda,
+            transfer_from_pda_with_hook,
+        },
+    },
+    state::{Market, MarketOracle, MarketStats, PausableOperation, ReferralAccount, StakePosition},
+    TokenMillConfig, MARKET_PDA_SEED,
+};
+
+/// Sells the base token of `market_in` and buys the base token of `market_out` with the proceeds.
+/// The quote tokens are moved from one market to the other, only those the buy doesn't use reach the user.
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SwapRoute<'info> {
+    pub config: Account<'info, TokenMillConfig>,
+
+    #[account(
+        mut,
+        has_one = config @ TokenMillError::InvalidConfigAccount,
+        has_one = quote_token_mint @ TokenMillError::InvalidMintAccount,
+        constraint = market_in.load()?.base_token_mint == base_token_mint_in.key() @ TokenMillError::InvalidMintAccount
+    )]
+    pub market_in: AccountLoader<'info, Market>,
+
+    #[account(
+        mut,
+        has_one = config @ TokenMillError::InvalidConfigAccount,
+        has_one = quote_token_mint @ TokenMillError::InvalidMintAccount,
+        constraint = market_out.load()?.base_token_mint == base_token_mint_out.key() @ TokenMillError::InvalidMintAccount,
+        constraint = market_out.key() != market_in.key() @ TokenMillError::InvalidMarket
+    )]
+    pub market_out: AccountLoader<'info, Market>,
+
+    pub base_token_mint_in: Box<InterfaceAccount<'info, Mint>>,
+
+    pub base_token_mint_out: Box<InterfaceAccount<'info, Mint>>,
+
+    pub quote_token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = base_token_mint_in,
+        associated_token::authority = market_in,
+        associated_token::token_program = base_token_program_in
+    )]
+    pub market_in_base_token_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = quote_token_mint,
+        associated_token::authority = market_in,
+        associated_token::token_program = quote_token_program
+    )]
+    pub market_in_quote_token_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = base_token_mint_out,
+        associated_token::authority = market_out,
+        associated_token::token_program = base_token_program_out
+    )]
+    pub market_out_base_token_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = quote_token_mint,
+        associated_token::authority = market_out,
+        associated_token::token_program = quote_token_program
+    )]
+    pub market_out_quote_token_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut, token::mint = base_token_mint_in)]
+    pub user_base_token_account_in: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut, token::mint = base_token_mint_out)]
+    pub user_base_token_account_out: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Receives the quote tokens of the sell that the buy doesn't use
+    #[account(mut, token::mint = quote_token_mint)]
+    pub user_quote_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    // Referral token account of the `ReferralAccount`, it receives the referral fees of both legs
+    #[account(mut)]
+    pub referral_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Referral account owning `referral_token_account`, required to pay its parent or apply its fee share override
+    #[account(has_one = config @ TokenMillError::InvalidConfigAccount)]
+    pub referral_account: Option<Box<Account<'info, ReferralAccount>>>,
+
+    /// Token account of the parent of `referral_account`, paid the config's `sub_referral_fee_share` of the referral fees
+    #[account(mut, token::mint = quote_token_mint)]
+    pub parent_referral_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Required once `market_in` has an oracle
+    #[account(
+        mut,
+        constraint = market_oracle_in.market == market_in.key() @ TokenMillError::InvalidMarket
+    )]
+    pub market_oracle_in: Option<Box<Account<'info, MarketOracle>>>,
+
+    /// Required once `market_out` has an oracle
+    #[account(
+        mut,
+        constraint = market_oracle_out.market == market_out.key() @ TokenMillError::InvalidMarket
+    )]
+    pub market_oracle_out: Option<Box<Account<'info, MarketOracle>>>,
+
+    /// Required once `market_in` has stats
+    #[account(
+        mut,
+        constraint = market_stats_in.market == market_in.key() @ TokenMillError::InvalidMarket
+    )]
+    pub market_stats_in: Option<Box<Account<'info, MarketStats>>>,
+
+    /// Required once `market_out` has stats
+    #[account(
+        mut,
+        constraint = market_stats_out.market == market_out.key() @ TokenMillError::InvalidMarket
+    )]
+    pub market_stats_out: Option<Box<Account<'info, MarketStats>>>,
+
+    /// Discounts the spread fee of the buy on `market_out` according to the config's fee discount tiers
+    #[account(
+        constraint = stake_position_out.market == market_out.key() @ TokenMillError::InvalidMarket,
+        has_one = user @ TokenMillError::InvalidAuthority
+    )]
+    pub stake_position_out: Option<Box<Account<'info, StakePosition>>>,
+
+    pub user: Signer<'info>,
+
+    pub base_token_program_in: Interface<'info, TokenInterface>,
+
+    pub base_token_program_out: Interface<'info, TokenInterface>,
+
+    pub quote_token_program: Interface<'info, TokenInterface>,
+}
+
+/// Returns (base_amount_in, base_amount_out).
+/// `other_amount_threshold` applies to the whole route, it is the minimum base amount out for
+/// exact input routes and the maximum base amount in for exact output routes.
+/// Exact input routes sell exactly `amount`, the fee discount of `stake_position_out` buying more base tokens.
+/// Remaining accounts are the extra accounts of the transfer hooks of both base tokens, if any.
+pub fn handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, SwapRoute<'info>>,
+    swap_amount_type: SwapAmountType,
+    amount: u64,
+    other_amount_threshold: u64,
+) -> Result<(u64, u64)> {
+    if amount == 0 {
+        return Err(TokenMillError::InvalidAmount.into());
+    }
+
+    let config = &ctx.accounts.config;
+
+    // Routes sell the base token in and buy the base token out
+    config.check_not_paused(PausableOperation::Sell)?;
+    config.check_not_paused(PausableOperation::Buy)?;
+
+    let referral_token_account = &ctx.accounts.referral_token_account;
+    let parent_referral_token_account = &ctx.accounts.parent_referral_token_account;
+
+    check_referral_accounts(
+        referral_token_account.as_deref(),
+        ctx.accounts.referral_account.as_deref(),
+        parent_referral_token_account.as_deref(),
+    )?;
+
+    let referral_fee_shares =
+        get_referral_fee_shares(config, ctx.accounts.referral_account.as_deref());
+    let referral_fee_share = referral_fee_shares.map(|shares| shares.referral_fee_share);
+
+    // Sells aren't discounted, only the buy leg is
+    let fee_discount_bps = ctx
+        .accounts
+        .stake_position_out
+        .as_ref()
+        .map_or(0, |stake_position| {
+            config.get_stake_position_fee_discount_bps(stake_position)
+        });
+
+    let quote_transfer_fee = get_epoch_transfer_fee(&ctx.accounts.quote_token_mint)?;
+
+    let (base_amount_in, quote_amount_out, swap_fee_in);
+    let (base_amount_out, quote_amount_in, swap_fee_out, buy_tax_out, transfer_fee);
+    let fee_discount_out;
+    let (creator_fee_in, staking_fee_in, protocol_fee_in, referral_fee_in);
+    let (creator_fee_out, staking_fee_out, protocol_fee_out, referral_fee_out);
+    let (sub_referral_fee_in, sub_referral_fee_out);
+    let quote_surplus;
+    let market_in_bump;
+    let market_out_bump;
+
+    {
+        let market_in = &mut ctx.accounts.market_in.load_mut()?;
+        let market_out = &mut ctx.accounts.market_out.load_mut()?;
+
+        let current_time = Clock::get()?.unix_timestamp;
+
+        market_in.check_swap_allowed(SwapType::Sell, current_time)?;
+        market_out.check_swap_allowed(SwapType::Buy, current_time)?;
+
+        match swap_amount_type {
+            SwapAmountType::ExactInput => {
+                (base_amount_in, quote_amount_out, swap_fee_in) =
+                    swap_manager::swap(market_in, SwapType::Sell, swap_amount_type, amount)?;
+
+                let buy = |market_out: &mut Market, quote_amount: u64| {
+                    swap_manager::swap_with_transfer_fee(
+                        market_out,
+                        SwapType::Buy,
+                        swap_amount_type,
+                        quote_amount,
+                        current_time,
+                        quote_transfer_fee.as_ref(),
+                        fee_discount_bps,
+                    )
+                };
+
+                // The fee discount is spent on more base tokens, buying on copies of `market_out` to size the buy
+                let mut bought_market_out = Box::new(**market_out);
+                let mut swap_result = buy(&mut bought_market_out, quote_amount_out)?;
+
+                if swap_result.fee_discount > 0 {
+                    let mut discounted_market_out = Box::new(**market_out);
+                    let discounted_swap_result = buy(
+                        &mut discounted_market_out,
+                        quote_amount_out + swap_result.fee_discount,
+                    )?;
+
+                    // Rounding may make the larger buy cost more than the proceeds of the sell
+                    if discounted_swap_result.quote_amount + discounted_swap_result.transfer_fee
+                        <= quote_amount_out
+                    {
+                        (bought_market_out, swap_result) =
+                            (discounted_market_out, discounted_swap_result);
+                    }
+                }
+
+                **market_out = *bought_market_out;
+
+                SwapResult {
+                    base_amount: base_amount_out,
+                    quote_amount: quote_amount_in,
+                    swap_fee: swap_fee_out,
+                    buy_tax: buy_tax_out,
+                    transfer_fee,
+                    fee_discount: fee_discount_out,
+                } = swap_result;
+            }
+            SwapAmountType::ExactOutput => {
+                SwapResult {
+                    base_amount: base_amount_out,
+                    quote_amount: quote_amount_in,
+                    swap_fee: swap_fee_out,
+                    buy_tax: buy_tax_out,
+                    transfer_fee,
+                    fee_discount: fee_discount_out,
+                } = swap_manager::swap_with_transfer_fee(
+                    market_out,
+                    SwapType::Buy,
+                    swap_amount_type,
+                    amount,
+                    current_time,
+                    quote_transfer_fee.as_ref(),
+                    fee_discount_bps,
+                )?;
+                (base_amount_in, quote_amount_out, swap_fee_in) = swap_manager::swap(
+                    market_in,
+                    SwapType::Sell,
+                    swap_amount_type,
+                    quote_amount_in + transfer_fee,
+                )?;
+            }
+        }
+
+        // A sell capped by the market supply can't fund the buy, the surplus of the sell is sent to the user.
+        // The transfer fee is withheld when the quote tokens move from `market_in` to `market_out`.
+        quote_surplus = quote_amount_out
+            .checked_sub(quote_amount_in + transfer_fee)
+            .ok_or(TokenMillError::InvalidAmount)?;
+
+        (
+            creator_fee_in,
+            staking_fee_in,
+            protocol_fee_in,
+            referral_fee_in,
+        ) = market_in
+            .fees
+            .distribute_fee(swap_fee_in, referral_fee_share)?;
+        (
+            creator_fee_out,
+            staking_fee_out,
+            protocol_fee_out,
+            referral_fee_out,
+        ) = market_out.distribute_fees(swap_fee_out, buy_tax_out, referral_fee_share)?;
+
+        sub_referral_fee_in = get_sub_referral_fee(referral_fee_shares, referral_fee_in)?;
+        sub_referral_fee_out = get_sub_referral_fee(referral_fee_shares, referral_fee_out)?;
+
+        update_market_oracle(market_in, &mut ctx.accounts.market_oracle_in)?;
+        update_market_oracle(market_out, &mut ctx.accounts.market_oracle_out)?;
+        update_market_stats(
+            market_in,
+            &mut ctx.accounts.market_stats_in,
+            SwapType::Sell,
+            base_amount_in,
+            quote_amount_out,
+            (
+                creator_fee_in,
+                staking_fee_in,
+                protocol_fee_in,
+                referral_fee_in,
+            ),
+        )?;
+        update_market_stats(
+            market_out,
+            &mut ctx.accounts.market_stats_out,
+            SwapType::Buy,
+            base_amount_out,
+            quote_amount_in,
+            (
+                creator_fee_out,
+                staking_fee_out,
+                protocol_fee_out,
+                referral_fee_out,
+            ),
+        )?;
+        // Routes carry no launch allocation nor presale proof, they can't buy during the launch window or the presale
+        check_presale(
+            market_out,
+            &ctx.accounts.user.key(),
+            None,
+            &mut None,
+            SwapType::Buy,
+            base_amount_out,
+            current_time,
+        )?;
+        update_launch_allocation(
+            market_out,
+            &mut None,
+            SwapType::Buy,
+            base_amount_out,
+            current_time,
+        )?;
+
+        market_in_bump = market_in.bump;
+        market_out_bump = market_out.bump;
+    }
+
+    match swap_amount_type {
+        SwapAmountType::ExactInput => {
+            if base_amount_out < other_amount_threshold {
+                return Err(TokenMillError::AmountThresholdNotMet.into());
+            }
+        }
+        SwapAmountType::ExactOutput => {
+            if base_amount_in > other_amount_threshold {
+                return Err(TokenMillError::AmountThresholdNotMet.into());
+            }
+        }
+    }
+
+    let base_token_mint_in_key = ctx.accounts.base_token_mint_in.key();
+    let market_in_seeds = [
+        MARKET_PDA_SEED.as_bytes(),
+        base_token_mint_in_key.as_ref(),
+        &[market_in_bump],
+    ];
+
+    let base_token_mint_out_key = ctx.accounts.base_token_mint_out.key();
+    let market_out_seeds = [
+        MARKET_PDA_SEED.as_bytes(),
+        base_token_mint_out_key.as_ref(),
+        &[market_out_bump],
+    ];
+
+    transfer_from_eoa_with_hook(
+        &ctx.accounts.base_token_mint_in,
+        &ctx.accounts.user,
+        &ctx.accounts.user_base_token_account_in,
+        &ctx.accounts.market_in_base_token_ata,
+        &ctx.accounts.base_token_program_in,
+        base_amount_in,
+        ctx.remaining_accounts,
+    )?;
+
+    transfer_from_pda(
+        &ctx.accounts.quote_token_mint,
+        ctx.accounts.market_in.to_account_info(),
+        &ctx.accounts.market_in_quote_token_ata,
+        &ctx.accounts.market_out_quote_token_ata,
+        &ctx.accounts.quote_token_program,
+        quote_amount_in + transfer_fee,
+        &market_in_seeds,
+    )?;
+
+    if quote_surplus > 0 {
+        transfer_from_pda(
+            &ctx.accounts.quote_token_mint,
+            ctx.accounts.market_in.to_account_info(),
+            &ctx.accounts.market_in_quote_token_ata,
+            &ctx.accounts.user_quote_token_account,
+            &ctx.accounts.quote_token_program,
+            quote_surplus,
+            &market_in_seeds,
+        )?;
+    }
+
+    transfer_from_pda_with_hook(
+        &ctx.accounts.base_token_mint_out,
+        ctx.accounts.market_out.to_account_info(),
+        &ctx.accounts.market_out_base_token_ata,
+        &ctx.accounts.user_base_token_account_out,
+        &ctx.accounts.base_token_program_out,
+        base_amount_out,
+        &market_out_seeds,
+        ctx.remaining_accounts,
+    )?;
+
+    transfer_referral_fees(
+        &ctx.accounts.quote_token_mint,
+        ctx.accounts.market_in.to_account_info(),
+        &ctx.accounts.market_in_quote_token_ata,
+        referral_token_account.as_deref(),
+        parent_referral_token_account.as_deref(),
+        &ctx.accounts.quote_token_program,
+        referral_fee_in,
+        sub_referral_fee_in,
+        &market_in_seeds,
+    )?;
+
+    transfer_referral_fees(
+        &ctx.accounts.quote_token_mint,
+        ctx.accounts.market_out.to_account_info(),
+        &ctx.accounts.market_out_quote_token_ata,
+        referral_token_account.as_deref(),
+        parent_referral_token_account.as_deref(),
+        &ctx.accounts.quote_token_program,
+        referral_fee_out,
+        sub_referral_fee_out,
+        &market_out_seeds,
+    )?;
+
+    emit_cpi!(TokenMillSwapEvent {
+        user: ctx.accounts.user.key(),
+        market: ctx.accounts.market_in.key(),
+        swap_type: SwapType::Sell,
+        base_amount: base_amount_in,
+        quote_amount: quote_amount_out,
+        referral_token_account: referral_token_account.as_ref().map(|a| a.key()),
+        creator_fee: creator_fee_in,
+        staking_fee: staking_fee_in,
+        protocol_fee: protocol_fee_in,
+        referral_fee: referral_fee_in,
+        sub_referral_fee: sub_referral_fee_in,
+        buy_tax: 0,
+        fee_discount: 0,
+    });
+
+    emit_cpi!(TokenMillSwapEvent {
+        user: ctx.accounts.user.key(),
+        market: ctx.accounts.market_out.key(),
+        swap_type: SwapType::Buy,
+        base_amount: base_amount_out,
+        quote_amount: quote_amount_in,
+        referral_token_account: referral_token_account.as_ref().map(|a| a.key()),
+        creator_fee: creator_fee_out,
+        staking_fee: staking_fee_out,
+        protocol_fee: protocol_fee_out,
+        referral_fee: referral_fee_out,
+        sub_referral_fee: sub_referral_fee_out,
+        buy_tax: buy_tax_out,
+        fee_discount: fee_discount_out,
+    });
+
+    Ok((base_amount_in, base_amount_out))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{constant::MAX_BPS, manager::swap_manager, Market, MARKET_PDA_SEED};
+    use anchor_lang::prelude::Pubkey;
+    use joelana_test_utils::{
+        joelana_env::{
+            actions::token_mill::{
+                get_referral_account_address, tm_parse_error, CreateReferralAccountAction,
+                DepositAction, GraduateMarketAction, SwapAction, SwapRouteAction, TokenMillEnv,
+                UpdateFeeDiscountTiersAction, UpdateSubReferralFeeShareAction,
+                DEFAULT_TOTAL_SUPPLY,
+            },
+            make_address, FeeDiscountTier, SwapAmountType, SwapType, TokenMillError,
+        },
+        utils::token_mill::constants::DEFAULT_REFERRAL_FEE_SHARE,
+    };
+    use rstest::rstest;
+
+    fn setup_env() -> (TokenMillEnv, Pubkey, Pubkey) {
+        let testing_env = TokenMillEnv::default();
+        let base_token_mint_in = testing_env.base_token_mint.unwrap();
+
+        let mut testing_env =
+            testing_env.with_market(make_address("base_token_mint_out"), DEFAULT_TOTAL_SUPPLY);
+        let base_token_mint_out = testing_env.base_token_mint.unwrap();
+
+        testing_env.svm.change_payer("bob");
+
+        // Gives bob some base tokens to sell, and some supply to the market in to bid against
+        let buy_action = SwapAction::new(
+            &testing_env,
+            SwapType::Buy,
+            SwapAmountType::ExactOutput,
+            100_000_000_000,
+            u64::MAX,
+            None,
+        )
+        .with_custom_base_token_mint(base_token_mint_in);
+
+        testing_env.svm.execute_actions(&[&buy_action]).unwrap();
+
+        (testing_env, base_token_mint_in, base_token_mint_out)
+    }
+
+    fn get_market(testing_env: &TokenMillEnv, base_token_mint: &Pubkey) -> Market {
+        let market = Pubkey::find_program_address(
+            &[MARKET_PDA_SEED.as_bytes(), &base_token_mint.to_bytes()],
+            &crate::ID,
+        )
+        .0;
+
+        testing_env.svm.get_parsed_account::<Market>(&market)
+    }
+
+    #[rstest]
+    fn swap_route(
+        #[values(SwapAmountType::ExactInput, SwapAmountType::ExactOutput)]
+        swap_amount_type: SwapAmountType,
+        #[values(None, Some(make_address("carol")))] referrer: Option<Pubkey>,
+    ) {
+        let (mut testing_env, base_token_mint_in, base_token_mint_out) = setup_env();
+        let bob = make_address("bob");
+        let quote_token_mint = testing_env.quote_token_mint.unwrap();
+
+        let amount = match swap_amount_type {
+            SwapAmountType::ExactInput => 10_000_000_000,
+            SwapAmountType::ExactOutput => 1_000_000_000,
+        };
+
+        // Expected amounts of both legs
+        let mut market_in = get_market(&testing_env, &base_token_mint_in);
+        let mut market_out = get_market(&testing_env, &base_token_mint_out);
+
+        let ((base_amount_in, _, swap_fee_in), (base_amount_out, _, swap_fee_out)) =
+            match swap_amount_type {
+                SwapAmountType::ExactInput => {
+                    let sell = swap_manager::swap(
+                        &mut market_in,
+                        swap_manager::SwapType::Sell,
+                        swap_manager::SwapAmountType::ExactInput,
+                        amount,
+                    )
+                    .unwrap();
+                    let buy = swap_manager::swap(
+                        &mut market_out,
+                        swap_manager::SwapType::Buy,
+                        swap_manager::SwapAmountType::ExactInput,
+                        sell.1,
+                    )
+                    .unwrap();
+
+                    (sell, buy)
+                }
+                SwapAmountType::ExactOutput => {
+                    let buy = swap_manager::swap(
+                        &mut market_out,
+                        swap_manager::SwapType::Buy,
+                        swap_manager::SwapAmountType::ExactOutput,
+                        amount,
+                    )
+                    .unwrap();
+                    let sell = swap_manager::swap(
+                        &mut market_in,
+                        swap_manager::SwapType::Sell,
+                        swap_manager::SwapAmountType::ExactOutput,
+                        buy.1,
+                    )
+                    .unwrap();
+
+                    (sell, buy)
+                }
+            };
+
+        let referral_fee_share = referrer.map(|_| DEFAULT_REFERRAL_FEE_SHARE);
+        let (_, _, protocol_fee_in, referral_fee_in) = market_in
+            .fees
+            .distribute_fee(swap_fee_in, referral_fee_share)
+            .unwrap();
+        let (_, _, protocol_fee_out, referral_fee_out) = market_out
+            .fees
+            .distribute_fee(swap_fee_out, referral_fee_share)
+            .unwrap();
+
+        assert!(swap_fee_out > 0);
+
+        let base_balance_in_before = testing_env.svm.get_balance(&base_token_mint_in, &bob);
+        let base_balance_out_before = testing_env.svm.get_balance(&base_token_mint_out, &bob);
+        let quote_balance_before = testing_env.svm.get_balance(&quote_token_mint, &bob);
+
+        let swap_route_action = SwapRouteAction::new(
+            &testing_env,
+            base_token_mint_in,
+            base_token_mint_out,
+            swap_amount_type,
+            amount,
+            match swap_amount_type {
+                SwapAmountType::ExactInput => base_amount_out,
+                SwapAmountType::ExactOutput => base_amount_in,
+            },
+            referrer,
+        );
+
+        testing_env
+            .svm
+            .execute_actions(&[&swap_route_action])
+            .unwrap();
+
+        assert_eq!(
+            base_balance_in_before - testing_env.svm.get_balance(&base_token_mint_in, &bob),
+            base_amount_in
+        );
+        assert_eq!(
+            testing_env.svm.get_balance(&base_token_mint_out, &bob) - base_balance_out_before,
+            base_amount_out
+        );
+        // The quote tokens never reach the user
+        assert_eq!(
+            testing_env.svm.get_balance(&quote_token_mint, &bob),
+            quote_balance_before
+        );
+        if referrer.is_some() {
+            assert!(referral_fee_in + referral_fee_out > 0);
+        }
+
+        let market_in_after = get_market(&testing_env, &base_token_mint_in);
+        let market_out_after = get_market(&testing_env, &base_token_mint_out);
+
+        assert_eq!(market_in_after.base_reserve, market_in.base_reserve);
+        assert_eq!(market_out_after.base_reserve, market_out.base_reserve);
+        assert_eq!(
+            market_in_after.fees.pending_creator_fees,
+            market_in.fees.pending_creator_fees
+        );
+        assert_eq!(
+            market_out_after.fees.pending_creator_fees,
+            market_out.fees.pending_creator_fees
+        );
+        // Both legs distribute their fees
+        assert_eq!(
+            market_in_after.fees.pending_protocol_fees,
+            market_in.fees.pending_protocol_fees
+        );
+        assert_eq!(
+            market_out_after.fees.pending_protocol_fees,
+            market_out.fees.pending_protocol_fees
+        );
+        assert!(protocol_fee_in + protocol_fee_out > 0);
+    }
+
+    #[rstest]
+    fn swap_route_with_threshold_not_met(
+        #[values(SwapAmountType::ExactInput, SwapAmountType::ExactOutput)]
+        swap_amount_type: SwapAmountType,
+    ) {
+        let (mut testing_env, base_token_mint_in, base_token_mint_out) = setup_env();
+
+        let mut swap_route_action = SwapRouteAction::new(
+            &testing_env,
+            base_token_mint_in,
+            base_token_mint_out,
+            swap_amount_type,
+            1_000_000_000,
+            match swap_amount_type {
+                SwapAmountType::ExactInput => 0,
+                SwapAmountType::ExactOutput => u64::MAX,
+            },
+            None,
+        );
+
+        // Simulates the route to get its exact amounts
+        let mut market_in = get_market(&testing_env, &base_token_mint_in);
+        let mut market_out = get_market(&testing_env, &base_token_mint_out);
+
+        swap_route_action.other_amount_threshold = match swap_amount_type {
+            SwapAmountType::ExactInput => {
+                let (_, quote_amount, _) = swap_manager::swap(
+                    &mut market_in,
+                    swap_manager::SwapType::Sell,
+                    swap_manager::SwapAmountType::ExactInput,
+                    1_000_000_000,
+                )
+                .unwrap();
+                let (base_amount_out, _, _) = swap_manager::swap(
+                    &mut market_out,
+                    swap_manager::SwapType::Buy,
+                    swap_manager::SwapAmountType::ExactInput,
+                    quote_amount,
+                )
+                .unwrap();
+
+                base_amount_out + 1
+            }
+            SwapAmountType::ExactOutput => {
+                let (_, quote_amount, _) = swap_manager::swap(
+                    &mut market_out,
+                    swap_manager::SwapType::Buy,
+                    swap_manager::SwapAmountType::ExactOutput,
+                    1_000_000_000,
+                )
+                .unwrap();
+                let (base_amount_in, _, _) = swap_manager::swap(
+                    &mut market_in,
+                    swap_manager::SwapType::Sell,
+                    swap_manager::SwapAmountType::ExactOutput,
+                    quote_amount,
+                )
+                .unwrap();
+
+                base_amount_in - 1
+            }
+        };
+
+        let result = testing_env.svm.execute_actions(&[&swap_route_action]);
+
+        assert!(result.is_err());
+
+        let error = tm_parse_error(result).unwrap();
+
+        assert_eq!(error, TokenMillError::AmountThresholdNotMet);
+    }
+
+    #[test]
+    fn swap_route_with_same_market() {
+        let (mut testing_env, base_token_mint_in, _) = setup_env();
+
+        let swap_route_action = SwapRouteAction::new(
+            &testing_env,
+            base_token_mint_in,
+            base_token_mint_in,
+            SwapAmountType::ExactInput,
+            1_000_000_000,
+            0,
+            None,
+        );
+
+        let result = testing_env.svm.execute_actions(&[&swap_route_action]);
+
+        assert!(result.is_err());
+
+        let error = tm_parse_error(result).unwrap();
+
+        assert_eq!(error, TokenMillError::InvalidMarket);
+    }
+
+    #[test]
+    fn swap_route_with_invalid_amount() {
+        let (mut testing_env, base_token_mint_in, base_token_mint_out) = setup_env();
+
+        let swap_route_action = SwapRouteAction::new(
+            &testing_env,
+            base_token_mint_in,
+            base_token_mint_out,
+            SwapAmountType::ExactInput,
+            0,
+            0,
+            None,
+        );
+
+        let result = testing_env.svm.execute_actions(&[&swap_route_action]);
+
+        assert!(result.is_err());
+
+        let error = tm_parse_error(result).unwrap();
+
+        assert_eq!(error, TokenMillError::InvalidAmount);
+    }
+
+    #[test]
+    fn swap_route_with_parent_referral() {
+        const SUB_REFERRAL_FEE_SHARE: u16 = 2_000;
+
+        let (mut testing_env, base_token_mint_in, base_token_mint_out) = setup_env();
+        let carol = make_address("carol");
+        let quote_token_mint = testing_env.quote_token_mint.unwrap();
+
+        testing_env.svm.change_payer("admin");
+        testing_env
+            .svm
+            .execute_actions(&[&UpdateSubReferralFeeShareAction::new(
+                SUB_REFERRAL_FEE_SHARE,
+            )])
+            .unwrap();
+
+        let dave = testing_env.svm.change_payer("dave");
+
+        testing_env
+            .svm
+            .execute_actions(&[&CreateReferralAccountAction::new()
+                .with_referrer(dave)
+                .with_parent_referrer(carol)])
+            .unwrap();
+        testing_env
+            .svm
+            .create_ata(
+                &get_referral_account_address(&dave),
+                &quote_token_mint,
+                testing_env.quote_token_type,
+            )
+            .unwrap();
+
+        testing_env.svm.change_payer("bob");
+
+        let swap_route_action = SwapRouteAction::new(
+            &testing_env,
+            base_token_mint_in,
+            base_token_mint_out,
+            SwapAmountType::ExactInput,
+            10_000_000_000,
+            0,
+            Some(dave),
+        );
+
+        // Dave's referral account has a parent, which can't be left out
+        let result = testing_env.svm.execute_actions(&[&swap_route_action]);
+
+        assert_eq!(
+            tm_parse_error(result).unwrap(),
+            TokenMillError::InvalidReferralAccount
+        );
+
+        testing_env
+            .svm
+            .execute_actions(&[&swap_route_action.with_parent_referrer(dave, carol)])
+            .unwrap();
+
+        let referral_fee = testing_env
+            .svm
+            .get_balance(&quote_token_mint, &get_referral_account_address(&dave));
+        let sub_referral_fee = testing_env
+            .svm
+            .get_balance(&quote_token_mint, &get_referral_account_address(&carol));
+        let total_referral_fee = referral_fee + sub_referral_fee;
+
+        // Each leg pays the parent its share of its own referral fee
+        assert!(sub_referral_fee > 0);
+        assert!(
+            total_referral_fee * u64::from(SUB_REFERRAL_FEE_SHARE) / MAX_BPS - sub_referral_fee
+                <= 1
+        );
+    }
+
+    #[rstest]
+    fn swap_route_with_fee_discount(
+        #[values(SwapAmountType::ExactInput, SwapAmountType::ExactOutput)]
+        swap_amount_type: SwapAmountType,
+    ) {
+        const STAKE_AMOUNT: u64 = 100_000_000;
+        const DISCOUNT_BPS: u16 = 5_000;
+
+        let (testing_env, base_token_mint_in, base_token_mint_out) = setup_env();
+        let bob = make_address("bob");
+
+        // Bob stakes in the market out, the last one created
+        let mut testing_env = testing_env.with_staking(STAKE_AMOUNT);
+
+        testing_env
+            .svm
+            .execute_actions(&[&DepositAction::new(&testing_env, STAKE_AMOUNT)])
+            .unwrap();
+
+        testing_env.svm.change_payer("admin");
+        testing_env
+            .svm
+            .execute_actions(&[&UpdateFeeDiscountTiersAction::new(vec![FeeDiscountTier {
+                min_amount: STAKE_AMOUNT,
+                discount_bps: DISCOUNT_BPS,
+            }])])
+            .unwrap();
+        testing_env.svm.change_payer("bob");
+
+        let amount = match swap_amount_type {
+            SwapAmountType::ExactInput => 10_000_000_000,
+            SwapAmountType::ExactOutput => 1_000_000_000,
+        };
+
+        // Expected amounts of both legs
+        let mut market_in = get_market(&testing_env, &base_token_mint_in);
+        let mut market_out = get_market(&testing_env, &base_token_mint_out);
+
+        let (base_amount_in, buy, quote_surplus) = match swap_amount_type {
+            SwapAmountType::ExactInput => {
+                let (_, quote_amount, _) = swap_manager::swap(
+                    &mut market_in,
+                    swap_manager::SwapType::Sell,
+                    swap_manager::SwapAmountType::ExactInput,
+                    amount,
+                )
+                .unwrap();
+
+                // The discount of a buy of the whole proceeds is spent on more base tokens
+                let mut market_out_copy = market_out;
+                let undiscounted_buy = swap_manager::swap_with_fee_discount(
+                    &mut market_out_copy,
+                    swap_manager::SwapType::Buy,
+                    swap_manager::SwapAmountType::ExactInput,
+                    quote_amount,
+                    DISCOUNT_BPS.into(),
+                )
+                .unwrap();
+                let buy = swap_manager::swap_with_fee_discount(
+                    &mut market_out,
+                    swap_manager::SwapType::Buy,
+                    swap_manager::SwapAmountType::ExactInput,
+                    quote_amount + undiscounted_buy.fee_discount,
+                    DISCOUNT_BPS.into(),
+                )
+                .unwrap();
+
+                assert!(buy.base_amount > undiscounted_buy.base_amount);
+
+                (amount, buy, quote_amount - buy.quote_amount)
+            }
+            SwapAmountType::ExactOutput => {
+                let buy = swap_manager::swap_with_fee_discount(
+                    &mut market_out,
+                    swap_manager::SwapType::Buy,
+                    swap_manager::SwapAmountType::ExactOutput,
+                    amount,
+                    DISCOUNT_BPS.into(),
+                )
+                .unwrap();
+                let (base_amount_in, quote_amount, _) = swap_manager::swap(
+                    &mut market_in,
+                    swap_manager::SwapType::Sell,
+                    swap_manager::SwapAmountType::ExactOutput,
+                    buy.quote_amount,
+                )
+                .unwrap();
+
+                (base_amount_in, buy, quote_amount - buy.quote_amount)
+            }
+        };
+
+        assert!(buy.fee_discount > 0);
+
+        let quote_token_mint = testing_env.quote_token_mint.unwrap();
+        let base_balance_in_before = testing_env.svm.get_balance(&base_token_mint_in, &bob);
+        let base_balance_out_before = testing_env.svm.get_balance(&base_token_mint_out, &bob);
+        let quote_balance_before = testing_env.svm.get_balance(&quote_token_mint, &bob);
+
+        let swap_route_action = SwapRouteAction::new(
+            &testing_env,
+            base_token_mint_in,
+            base_token_mint_out,
+            swap_amount_type,
+            amount,
+            match swap_amount_type {
+                SwapAmountType::ExactInput => 0,
+                SwapAmountType::ExactOutput => u64::MAX,
+            },
+            None,
+        )
+        .with_stake_position();
+
+        testing_env
+            .svm
+            .execute_actions(&[&swap_route_action])
+            .unwrap();
+
+        assert_eq!(
+            base_balance_in_before - testing_env.svm.get_balance(&base_token_mint_in, &bob),
+            base_amount_in
+        );
+        assert_eq!(
+            testing_env.svm.get_balance(&base_token_mint_out, &bob) - base_balance_out_before,
+            buy.base_amount
+        );
+        assert_eq!(
+            testing_env.svm.get_balance(&quote_token_mint, &bob) - quote_balance_before,
+            quote_surplus
+        );
+    }
+
+    #[rstest]
+    fn swap_route_with_graduated_market_in(
+        #[values(SwapAmountType::ExactInput, SwapAmountType::ExactOutput)]
+        swap_amount_type: SwapAmountType,
+    ) {
+        const SUPPLY_THRESHOLD: u64 = DEFAULT_TOTAL_SUPPLY / 2;
+
+        let mut testing_env = TokenMillEnv::new()
+            .with_default_quote_token_mint()
+            .with_graduation(SUPPLY_THRESHOLD, 0)
+            .with_default_market();
+        let base_token_mint_in = testing_env.base_token_mint.unwrap();
+        let bob = testing_env.svm.change_payer("bob");
+
+        // Bob buys the market in up to its graduation, the pool then bidding for his base tokens
+        testing_env
+            .svm
+            .execute_actions(&[
+                &SwapAction::new(
+                    &testing_env,
+                    SwapType::Buy,
+                    SwapAmountType::ExactOutput,
+                    SUPPLY_THRESHOLD,
+                    u64::MAX,
+                    None,
+                ),
+                &GraduateMarketAction::new(&testing_env),
+            ])
+            .unwrap();
+
+        let mut testing_env =
+            testing_env.with_market(make_address("base_token_mint_out"), DEFAULT_TOTAL_SUPPLY);
+        let base_token_mint_out = testing_env.base_token_mint.unwrap();
+        let quote_token_mint = testing_env.quote_token_mint.unwrap();
+
+        testing_env.svm.change_payer("bob");
+
+        let amount = match swap_amount_type {
+            SwapAmountType::ExactInput => 10_000_000_000,
+            SwapAmountType::ExactOutput => 1_000_000_000,
+        };
+
+        // Expected amounts of both legs
+        let mut market_in = get_market(&testing_env, &base_token_mint_in);
+        let mut market_out = get_market(&testing_env, &base_token_mint_out);
+
+        assert!(market_in.is_graduated());
+
+        let ((base_amount_in, quote_amount_out, _), (base_amount_out, quote_amount_in, _)) =
+            match swap_amount_type {
+                SwapAmountType::ExactInput => {
+                    let sell = swap_manager::swap(
+                        &mut market_in,
+                        swap_manager::SwapType::Sell,
+                        swap_manager::SwapAmountType::ExactInput,
+                        amount,
+                    )
+                    .unwrap();
+                    let buy = swap_manager::swap(
+                        &mut market_out,
+                        swap_manager::SwapType::Buy,
+                        swap_manager::SwapAmountType::ExactInput,
+                        sell.1,
+                    )
+                    .unwrap();
+
+                    (sell, buy)
+                }
+                SwapAmountType::ExactOutput => {
+                    let buy = swap_manager::swap(
+                        &mut market_out,
+                        swap_manager::SwapType::Buy,
+                        swap_manager::SwapAmountType::ExactOutput,
+                        amount,
+                    )
+                    .unwrap();
+                    let sell = swap_manager::swap(
+                        &mut market_in,
+                        swap_manager::SwapType::Sell,
+                        swap_manager::SwapAmountType::ExactOutput,
+                        buy.1,
+                    )
+                    .unwrap();
+
+                    (sell, buy)
+                }
+            };
+
+        let base_balance_in_before = testing_env.svm.get_balance(&base_token_mint_in, &bob);
+        let base_balance_out_before = testing_env.svm.get_balance(&base_token_mint_out, &bob);
+        let quote_balance_before = testing_env.svm.get_balance(&quote_token_mint, &bob);
+
+        testing_env
+            .svm
+            .execute_actions(&[&SwapRouteAction::new(
+                &testing_env,
+                base_token_mint_in,
+                base_token_mint_out,
+                swap_amount_type,
+                amount,
+                match swap_amount_type {
+                    SwapAmountType::ExactInput => base_amount_out,
+                    SwapAmountType::ExactOutput => base_amount_in,
+                },
+                None,
+            )])
+            .unwrap();
+
+        assert_eq!(
+            base_balance_in_before - testing_env.svm.get_balance(&base_token_mint_in, &bob),
+            base_amount_in
+        );
+        assert_eq!(
+            testing_env.svm.get_balance(&base_token_mint_out, &bob) - base_balance_out_before,
+            base_amount_out
+        );
+        // The pool may pay more than the buy needs, the surplus reaching the user
+        assert_eq!(
+            testing_env.svm.get_balance(&quote_token_mint, &bob) - quote_balance_before,
+            quote_amount_out - quote_amount_in
+        );
+
+        let market_in_after = get_market(&testing_env, &base_token_mint_in);
+
+        assert_eq!(
+            market_in_after.graduation.quote_reserve,
+            market_in.graduation.quote_reserve
+        );
+    }
+}
@@ -1,8 +1,8 @@
 use crate::{
     errors::TokenMillError,
     events::TokenMillVestingPlanCreationEvent,
-    manager::{staking_manager, token_manager::transfer_from_eoa},
-    state::{Market, MarketStaking, StakePosition},
+    manager::{staking_manager, token_manager::transfer_from_eoa_with_hook},
+    state::{Market, MarketStaking, PausableOperation, StakePosition, TokenMillConfig},
     VestingPlan,
 };
 use anchor_lang::prelude::*;
@@ -11,7 +11,13 @@ use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
 #[event_cpi]
 #[derive(Accounts)]
 pub struct CreateVestingPlan<'info> {
-    #[account(mut, has_one = base_token_mint @ TokenMillError::InvalidMintAccount)]
+    pub config: Account<'info, TokenMillConfig>,
+
+    #[account(
+        mut,
+        has_one = config @ TokenMillError::InvalidConfigAccount,
+        has_one = base_token_mint @ TokenMillError::InvalidMintAccount
+    )]
     pub market: AccountLoader<'info, Market>,
 
     #[account(mut, has_one = market @ TokenMillError::InvalidMarket)]
@@ -52,13 +58,17 @@ pub struct CreateVestingPlan<'info> {
     pub system_program: Program<'info, System>,
 }
 
-pub fn handler(
-    ctx: Context<CreateVestingPlan>,
+pub fn handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, CreateVestingPlan<'info>>,
     start: i64,
     vesting_amount: u64,
     vesting_duration: i64,
     cliff_duration: i64,
 ) -> Result<()> {
+    ctx.accounts
+        .config
+        .check_not_paused(PausableOperation::Vesting)?;
+
     require!(
         start > 0 && vesting_duration > 0 && cliff_duration > 0,
         TokenMillError::InvalidVestingDuration
@@ -89,13 +99,14 @@ pub fn handler(
 
     staking_manager::deposit_vested(market, staking, stake_position, vesting_amount)?;
 
-    transfer_from_eoa(
+    transfer_from_eoa_with_hook(
         &ctx.accounts.base_token_mint,
         &ctx.accounts.user,
         &ctx.accounts.user_base_token_ata,
         &ctx.accounts.market_base_token_ata,
         &ctx.accounts.base_token_program,
         vesting_amount,
+        ctx.remaining_accounts,
     )?;
 
     emit_cpi!(TokenMillVestingPlanCreationEvent {
@@ -115,9 +126,12 @@ pub fn handler(
 mod tests {
     use crate::VestingPlan;
     use joelana_test_utils::joelana_env::{
-        actions::token_mill::{CreateVestingPlanAction, TokenMillEnv},
+        actions::token_mill::{
+            get_transfer_counter_address, CreateVestingPlanAction, TokenMillEnv,
+        },
         TokenType,
     };
+    use mock_transfer_hook::TransferCounter;
     use rstest::rstest;
 
     const VESTING_AMOUNT: u64 = 1_000_000_000;
@@ -154,4 +168,36 @@ mod tests {
         assert_eq!(vesting_plan.vesting_duration, 300);
         assert_eq!(vesting_plan.cliff_duration, 60);
     }
+
+    #[test]
+    fn create_vesting_plan_with_transfer_hook() {
+        let mut testing_env = TokenMillEnv::new()
+            .with_default_quote_token_mint()
+            .with_transfer_hook()
+            .with_default_market()
+            .with_staking(VESTING_AMOUNT);
+
+        testing_env.svm.warp(STARTING_SLOT);
+
+        testing_env.svm.change_payer("bob");
+
+        let create_vesting_action =
+            CreateVestingPlanAction::new(&testing_env, VESTING_AMOUNT, STARTING_SLOT, 300, 60);
+
+        testing_env
+            .svm
+            .execute_actions(&[&create_vesting_action])
+            .unwrap();
+
+        // The buy of the setup, then the vested deposit
+        let counter =
+            testing_env
+                .svm
+                .get_parsed_account::<TransferCounter>(&get_transfer_counter_address(
+                    &create_vesting_action.base_token_mint,
+                ));
+
+        assert_eq!(counter.transfers, 2);
+        assert_eq!(counter.amount, 2 * VESTING_AMOUNT);
+    }
 }
@@ -1,7 +1,7 @@
 use crate::{
     errors::TokenMillError,
     events::TokenMillVestingPlanReleaseEvent,
-    manager::{staking_manager, token_manager::transfer_from_pda},
+    manager::{staking_manager, token_manager::transfer_from_pda_with_hook},
     state::{Market, MarketStaking, StakePosition},
     VestingPlan, MARKET_PDA_SEED,
 };
@@ -50,7 +50,8 @@ pub struct Release<'info> {
     pub base_token_program: Interface<'info, TokenInterface>,
 }
 
-pub fn handler(ctx: Context<Release>) -> Result<u64> {
+/// Remaining accounts are the extra accounts of the base token's transfer hook, if any.
+pub fn handler<'info>(ctx: Context<'_, '_, 'info, 'info, Release<'info>>) -> Result<u64> {
     let staking = &mut ctx.accounts.staking;
     let stake_position = &mut ctx.accounts.stake_position;
     let vesting_plan = &mut ctx.accounts.vesting_plan;
@@ -76,7 +77,7 @@ pub fn handler(ctx: Context<Release>) -> Result<u64> {
             &[market_bump],
         ];
 
-        transfer_from_pda(
+        transfer_from_pda_with_hook(
             base_token_mint,
             ctx.accounts.market.to_account_info(),
             &ctx.accounts.market_base_token_ata,
@@ -84,6 +85,7 @@ pub fn handler(ctx: Context<Release>) -> Result<u64> {
             &ctx.accounts.base_token_program,
             amount_released,
             &seeds,
+            ctx.remaining_accounts,
         )?;
     }
 
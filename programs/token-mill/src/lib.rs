@@ -7,12 +7,12 @@ pub mod errors;
 mod events;
 mod instructions;
 pub mod manager;
-mod math;
 pub mod state;
 
 use instructions::*;
-use manager::swap_manager::{SwapAmountType, SwapType};
+use manager::swap_manager::{SwapAmountType, SwapBatchMode, SwapLeg, SwapLegResult, SwapType};
 use state::*;
+use token_mill_sdk::math;
 
 #[program]
 pub mod token_mill {
@@ -34,6 +34,7 @@ pub mod token_mill {
         )
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn create_market(
         ctx: Context<CreateMarket>,
         name: String,
@@ -42,6 +43,9 @@ pub mod token_mill {
         total_supply: u64,
         creator_fee_share: u16,
         staking_fee_share: u16,
+        trading_start_ts: Option<i64>,
+        launch_protection: Option<LaunchProtection>,
+        transfer_hook_program_id: Option<Pubkey>,
     ) -> Result<()> {
         instructions::create_market::handler(
             ctx,
@@ -51,9 +55,13 @@ pub mod token_mill {
             total_supply,
             creator_fee_share,
             staking_fee_share,
+            trading_start_ts,
+            launch_protection,
+            transfer_hook_program_id,
         )
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn create_market_with_spl(
         ctx: Context<CreateMarketWithSpl>,
         name: String,
@@ -62,6 +70,8 @@ pub mod token_mill {
         total_supply: u64,
         creator_fee_share: u16,
         staking_fee_share: u16,
+        trading_start_ts: Option<i64>,
+        launch_protection: Option<LaunchProtection>,
     ) -> Result<()> {
         instructions::create_market_with_spl::handler(
             ctx,
@@ -71,23 +81,45 @@ pub mod token_mill {
             total_supply,
             creator_fee_share,
             staking_fee_share,
+            trading_start_ts,
+            launch_protection,
         )
     }
 
     pub fn set_market_prices(
         ctx: Context<MarketSettingsUpdate>,
-        bid_prices: [u64; constant::PRICES_LENGTH],
-        ask_prices: [u64; constant::PRICES_LENGTH],
+        bid_prices: Vec<u64>,
+        ask_prices: Vec<u64>,
+        supply_breakpoints: Vec<u64>,
+        price_interpolation: PriceInterpolation,
     ) -> Result<()> {
-        instructions::set_market_prices::handler(ctx, bid_prices, ask_prices)
+        instructions::set_market_prices::handler(
+            ctx,
+            bid_prices,
+            ask_prices,
+            supply_breakpoints,
+            price_interpolation,
+        )
+    }
+
+    pub fn migrate_market(ctx: Context<MigrateMarket>) -> Result<()> {
+        instructions::migrate_market::handler(ctx)
     }
 
-    pub fn swap(
-        ctx: Context<Swap>,
+    pub fn graduate_market(ctx: Context<GraduateMarket>) -> Result<()> {
+        instructions::graduate_market::handler(ctx)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn swap<'info>(
+        ctx: Context<'_, '_, 'info, 'info, Swap<'info>>,
         swap_type: SwapType,
         swap_amount_type: SwapAmountType,
         amount: u64,
         other_amount_threshold: u64,
+        valid_until: Option<i64>,
+        limit_price: Option<u64>,
+        presale_proof: Option<PresaleProof>,
     ) -> Result<(u64, u64)> {
         instructions::swap::handler(
             ctx,
@@ -95,9 +127,118 @@ pub mod token_mill {
             swap_amount_type,
             amount,
             other_amount_threshold,
+            valid_until,
+            limit_price,
+            presale_proof,
+        )
+    }
+
+    pub fn swap_route<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SwapRoute<'info>>,
+        swap_amount_type: SwapAmountType,
+        amount: u64,
+        other_amount_threshold: u64,
+    ) -> Result<(u64, u64)> {
+        instructions::swap_route::handler(ctx, swap_amount_type, amount, other_amount_threshold)
+    }
+
+    pub fn swap_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SwapBatch<'info>>,
+        legs: Vec<SwapLeg>,
+        mode: SwapBatchMode,
+    ) -> Result<Vec<SwapLegResult>> {
+        instructions::swap_batch::handler(ctx, legs, mode)
+    }
+
+    pub fn quote_swap(
+        ctx: Context<QuoteSwap>,
+        swap_type: SwapType,
+        swap_amount_type: SwapAmountType,
+        amount: u64,
+    ) -> Result<SwapQuote> {
+        instructions::quote_swap::handler(ctx, swap_type, swap_amount_type, amount)
+    }
+
+    // Limit orders
+    pub fn create_limit_order<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CreateLimitOrder<'info>>,
+        order_id: u64,
+        swap_type: SwapType,
+        amount: u64,
+        limit_price: u64,
+    ) -> Result<()> {
+        instructions::limit_orders::create_limit_order::handler(
+            ctx,
+            order_id,
+            swap_type,
+            amount,
+            limit_price,
         )
     }
 
+    pub fn fill_limit_order<'info>(
+        ctx: Context<'_, '_, 'info, 'info, FillLimitOrder<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        instructions::limit_orders::fill_limit_order::handler(ctx, amount)
+    }
+
+    pub fn cancel_limit_order<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CancelLimitOrder<'info>>,
+    ) -> Result<()> {
+        instructions::limit_orders::cancel_limit_order::handler(ctx)
+    }
+
+    // DCA orders
+    pub fn create_dca_order<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CreateDcaOrder<'info>>,
+        order_id: u64,
+        swap_type: SwapType,
+        amount: u64,
+        amount_per_execution: u64,
+        interval: i64,
+        limit_price: u64,
+    ) -> Result<()> {
+        instructions::dca::create_dca_order::handler(
+            ctx,
+            order_id,
+            swap_type,
+            amount,
+            amount_per_execution,
+            interval,
+            limit_price,
+        )
+    }
+
+    pub fn execute_dca<'info>(ctx: Context<'_, '_, 'info, 'info, ExecuteDca<'info>>) -> Result<()> {
+        instructions::dca::execute_dca::handler(ctx)
+    }
+
+    pub fn cancel_dca_order<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CancelDcaOrder<'info>>,
+    ) -> Result<()> {
+        instructions::dca::cancel_dca_order::handler(ctx)
+    }
+
+    // Oracle
+    pub fn create_market_oracle(ctx: Context<CreateMarketOracle>) -> Result<()> {
+        instructions::oracle::create_market_oracle::handler(ctx)
+    }
+
+    pub fn get_twap(ctx: Context<GetTwap>, window: i64) -> Result<MarketTwap> {
+        instructions::oracle::get_twap::handler(ctx, window)
+    }
+
+    // Market stats
+    pub fn create_market_stats(ctx: Context<CreateMarketStats>) -> Result<()> {
+        instructions::create_market_stats::handler(ctx)
+    }
+
+    // Launch protection
+    pub fn create_launch_allocation(ctx: Context<CreateLaunchAllocation>) -> Result<()> {
+        instructions::create_launch_allocation::handler(ctx)
+    }
+
     // Staking
     pub fn create_staking(ctx: Context<CreateStaking>) -> Result<()> {
         instructions::staking::create_staking::handler(ctx)
@@ -107,11 +248,17 @@ pub mod token_mill {
         instructions::staking::create_stake_position::handler(ctx)
     }
 
-    pub fn deposit(ctx: Context<StakeUpdate>, amount: u64) -> Result<()> {
+    pub fn deposit<'info>(
+        ctx: Context<'_, '_, 'info, 'info, StakeUpdate<'info>>,
+        amount: u64,
+    ) -> Result<()> {
         instructions::staking::deposit::handler(ctx, amount)
     }
 
-    pub fn withdraw(ctx: Context<StakeUpdate>, amount: u64) -> Result<()> {
+    pub fn withdraw<'info>(
+        ctx: Context<'_, '_, 'info, 'info, StakeUpdate<'info>>,
+        amount: u64,
+    ) -> Result<()> {
         instructions::staking::withdraw::handler(ctx, amount)
     }
 
@@ -120,8 +267,8 @@ pub mod token_mill {
     }
 
     // Vesting
-    pub fn create_vesting_plan(
-        ctx: Context<CreateVestingPlan>,
+    pub fn create_vesting_plan<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CreateVestingPlan<'info>>,
         start: i64,
         vesting_amount: u64,
         vesting_duration: i64,
@@ -136,7 +283,7 @@ pub mod token_mill {
         )
     }
 
-    pub fn release(ctx: Context<Release>) -> Result<u64> {
+    pub fn release<'info>(ctx: Context<'_, '_, 'info, 'info, Release<'info>>) -> Result<u64> {
         instructions::vesting::release::handler(ctx)
     }
 
@@ -152,6 +299,10 @@ pub mod token_mill {
         instructions::referrals::claim_referral_fees::handler(ctx)
     }
 
+    pub fn migrate_referral_account(ctx: Context<MigrateReferralAccount>) -> Result<()> {
+        instructions::referrals::migrate_referral_account::handler(ctx)
+    }
+
     // Creator instructions
     pub fn update_creator(ctx: Context<MarketSettingsUpdate>, new_creator: Pubkey) -> Result<()> {
         instructions::update_creator::handler(ctx, new_creator)
@@ -169,6 +320,52 @@ pub mod token_mill {
         )
     }
 
+    pub fn set_market_graduation(
+        ctx: Context<MarketSettingsUpdate>,
+        supply_threshold: u64,
+        quote_threshold: u64,
+    ) -> Result<()> {
+        instructions::set_market_graduation::handler(ctx, supply_threshold, quote_threshold)
+    }
+
+    pub fn set_market_buy_tax(
+        ctx: Context<MarketSettingsUpdate>,
+        start_bps: u16,
+        duration: i64,
+        recipient: BuyTaxRecipient,
+    ) -> Result<()> {
+        instructions::set_market_buy_tax::handler(ctx, start_bps, duration, recipient)
+    }
+
+    pub fn set_market_presale(
+        ctx: Context<MarketSettingsUpdate>,
+        merkle_root: [u8; 32],
+        end_ts: i64,
+    ) -> Result<()> {
+        instructions::set_market_presale::handler(ctx, merkle_root, end_ts)
+    }
+
+    pub fn set_market_virtual_reserves(
+        ctx: Context<MarketSettingsUpdate>,
+        virtual_base: u64,
+        virtual_quote: u64,
+        spread_bps: u16,
+    ) -> Result<()> {
+        instructions::set_market_virtual_reserves::handler(
+            ctx,
+            virtual_base,
+            virtual_quote,
+            spread_bps,
+        )
+    }
+
+    pub fn update_market_status(
+        ctx: Context<MarketSettingsUpdate>,
+        new_status: MarketStatus,
+    ) -> Result<()> {
+        instructions::update_market_status::handler(ctx, new_status)
+    }
+
     pub fn claim_creator_fees(ctx: Context<ClaimCreatorFees>) -> Result<()> {
         instructions::claim_creator_fees::handler(ctx)
     }
@@ -185,6 +382,17 @@ pub mod token_mill {
         instructions::update_quote_asset_badge::handler(ctx, status)
     }
 
+    pub fn create_transfer_hook_badge(ctx: Context<CreateTransferHookBadge>) -> Result<()> {
+        instructions::create_transfer_hook_badge::handler(ctx)
+    }
+
+    pub fn update_transfer_hook_badge(
+        ctx: Context<UpdateTransferHookBadge>,
+        status: TransferHookBadgeStatus,
+    ) -> Result<()> {
+        instructions::update_transfer_hook_badge::handler(ctx, status)
+    }
+
     pub fn update_default_fee_shares(
         ctx: Context<ConfigUpdate>,
         new_default_protocol_fee_share: u16,
@@ -197,6 +405,13 @@ pub mod token_mill {
         )
     }
 
+    pub fn update_sub_referral_fee_share(
+        ctx: Context<ConfigUpdate>,
+        new_sub_referral_fee_share: u16,
+    ) -> Result<()> {
+        instructions::update_sub_referral_fee_share::handler(ctx, new_sub_referral_fee_share)
+    }
+
     pub fn update_protocol_fee_recipient(
         ctx: Context<ConfigUpdate>,
         new_protocol_fee_recipient: Pubkey,
@@ -204,6 +419,39 @@ pub mod token_mill {
         instructions::update_protocol_fee_recipient::handler(ctx, new_protocol_fee_recipient)
     }
 
+    pub fn set_market_status(
+        ctx: Context<MarketStatusUpdate>,
+        new_status: MarketStatus,
+    ) -> Result<()> {
+        instructions::set_market_status::handler(ctx, new_status)
+    }
+
+    pub fn set_referral_fee_share_override(
+        ctx: Context<ReferralFeeShareOverrideUpdate>,
+        fee_share_override: Option<u16>,
+    ) -> Result<()> {
+        instructions::set_referral_fee_share_override::handler(ctx, fee_share_override)
+    }
+
+    pub fn set_operation_paused(
+        ctx: Context<ConfigUpdate>,
+        operation: PausableOperation,
+        paused: bool,
+    ) -> Result<()> {
+        instructions::set_operation_paused::handler(ctx, operation, paused)
+    }
+
+    pub fn update_fee_discount_tiers(
+        ctx: Context<ConfigUpdate>,
+        fee_discount_tiers: Vec<FeeDiscountTier>,
+    ) -> Result<()> {
+        instructions::update_fee_discount_tiers::handler(ctx, fee_discount_tiers)
+    }
+
+    pub fn claim_protocol_fees(ctx: Context<ClaimProtocolFees>) -> Result<()> {
+        instructions::claim_protocol_fees::handler(ctx)
+    }
+
     pub fn transfer_config_ownership(
         ctx: Context<ConfigUpdate>,
         pending_authority: Option<Pubkey>,
@@ -214,4 +462,8 @@ pub mod token_mill {
     pub fn accept_config_ownership(ctx: Context<AcceptConfigOwnership>) -> Result<()> {
         instructions::accept_config_ownership::handler(ctx)
     }
+
+    pub fn migrate_config(ctx: Context<MigrateConfig>) -> Result<()> {
+        instructions::migrate_config::handler(ctx)
+    }
 }
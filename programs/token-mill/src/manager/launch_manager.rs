@@ -0,0 +1,65 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::TokenMillError,
+    manager::swap_manager::SwapType,
+    state::{LaunchAllocation, Market, PresaleProof},
+};
+
+/// Records a buy in the user's launch allocation, which is mandatory while the launch window is open.
+/// Swaps without an allocation pass `None`, buying is then impossible until the window ends.
+pub fn update_launch_allocation(
+    market: &Market,
+    launch_allocation: &mut Option<Box<Account<LaunchAllocation>>>,
+    swap_type: SwapType,
+    base_amount: u64,
+    current_time: i64,
+) -> Result<()> {
+    // Presale buys are capped by the allowlist instead
+    if swap_type == SwapType::Sell
+        || market.is_presale_open(current_time)
+        || !market.is_launch_window_open(current_time)
+    {
+        return Ok(());
+    }
+
+    match launch_allocation {
+        Some(launch_allocation) => {
+            launch_allocation.record_buy(base_amount, market.launch_protection.max_buy_per_wallet)
+        }
+        None => Err(TokenMillError::MissingLaunchAllocation.into()),
+    }
+}
+
+/// Checks the buyer is allowlisted while the presale is open, recording the buy in its launch allocation if capped.
+/// Swaps without a proof pass `None`, buying is then impossible until the presale ends.
+pub fn check_presale(
+    market: &Market,
+    user: &Pubkey,
+    presale_proof: Option<&PresaleProof>,
+    launch_allocation: &mut Option<Box<Account<LaunchAllocation>>>,
+    swap_type: SwapType,
+    base_amount: u64,
+    current_time: i64,
+) -> Result<()> {
+    if swap_type == SwapType::Sell || !market.is_presale_open(current_time) {
+        return Ok(());
+    }
+
+    let presale_proof = presale_proof.ok_or(TokenMillError::MissingPresaleProof)?;
+
+    if !presale_proof.verify(&market.presale.merkle_root, user) {
+        return Err(TokenMillError::InvalidPresaleProof.into());
+    }
+
+    if presale_proof.max_amount == 0 {
+        return Ok(());
+    }
+
+    match launch_allocation {
+        Some(launch_allocation) => {
+            launch_allocation.record_buy(base_amount, presale_proof.max_amount)
+        }
+        None => Err(TokenMillError::MissingLaunchAllocation.into()),
+    }
+}
@@ -0,0 +1,33 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+
+/// Grows a program account to `new_len` bytes, `payer` tops up its rent.
+/// The new bytes are zeroed, so appended fields must decode from zeros.
+pub fn realloc_account<'info>(
+    account: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    new_len: usize,
+) -> Result<()> {
+    let minimum_balance = Rent::get()?.minimum_balance(new_len);
+    let lamports = account.lamports();
+
+    if minimum_balance > lamports {
+        transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                Transfer {
+                    from: payer.to_account_info(),
+                    to: account.clone(),
+                },
+            ),
+            minimum_balance - lamports,
+        )?;
+    }
+
+    account.realloc(new_len, true)?;
+
+    Ok(())
+}
@@ -1,3 +1,8 @@
+pub mod launch_manager;
+pub mod migration_manager;
+pub mod oracle_manager;
+pub mod referral_manager;
 pub mod staking_manager;
+pub mod stats_manager;
 pub mod swap_manager;
 pub mod token_manager;
@@ -0,0 +1,32 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::TokenMillError,
+    state::{Market, MarketOracle},
+};
+
+/// Checks that the oracle is provided once enabled
+pub fn check_market_oracle(
+    market: &Market,
+    market_oracle: &Option<Box<Account<MarketOracle>>>,
+) -> Result<()> {
+    if market_oracle.is_none() && market.is_oracle_enabled() {
+        return Err(TokenMillError::MissingMarketOracle.into());
+    }
+
+    Ok(())
+}
+
+/// Records the market's spot prices after a swap, the oracle being mandatory once enabled.
+pub fn update_market_oracle(
+    market: &Market,
+    market_oracle: &mut Option<Box<Account<MarketOracle>>>,
+) -> Result<()> {
+    check_market_oracle(market, market_oracle)?;
+
+    if let Some(market_oracle) = market_oracle {
+        market_oracle.update(market, Clock::get()?.unix_timestamp)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,109 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use token_mill_sdk::ReferralFeeShares;
+
+use crate::{
+    errors::TokenMillError, manager::token_manager::transfer_from_pda, ReferralAccount,
+    TokenMillConfig,
+};
+
+/// Checks that the referral account owns the referral token account, and that the parent referral token account is owned by its parent.
+/// Referral fees are only paid to referral accounts, which always pay their parent its share.
+pub fn check_referral_accounts(
+    referral_token_account: Option<&InterfaceAccount<TokenAccount>>,
+    referral_account: Option<&Account<ReferralAccount>>,
+    parent_referral_token_account: Option<&InterfaceAccount<TokenAccount>>,
+) -> Result<()> {
+    require!(
+        referral_token_account.is_none() || referral_account.is_some(),
+        TokenMillError::InvalidReferralAccount
+    );
+
+    if let Some(referral_account) = referral_account {
+        let is_owner = referral_token_account.is_some_and(|referral_token_account| {
+            referral_token_account.owner == referral_account.key()
+        });
+
+        require!(is_owner, TokenMillError::InvalidReferralAccount);
+        require!(
+            referral_account.parent.is_none() || parent_referral_token_account.is_some(),
+            TokenMillError::InvalidReferralAccount
+        );
+    }
+
+    if let Some(parent_referral_token_account) = parent_referral_token_account {
+        let is_parent = referral_account.is_some_and(|referral_account| {
+            referral_account.parent == Some(parent_referral_token_account.owner)
+        });
+
+        require!(is_parent, TokenMillError::InvalidReferralAccount);
+    }
+
+    Ok(())
+}
+
+/// Fee shares of the checked referral account, `None` for swaps without referral
+pub fn get_referral_fee_shares(
+    config: &TokenMillConfig,
+    referral_account: Option<&Account<ReferralAccount>>,
+) -> Option<ReferralFeeShares> {
+    referral_account.map(|referral_account| referral_account.get_fee_shares(config))
+}
+
+/// Part of `referral_fee` paid to the parent of the referral account
+pub fn get_sub_referral_fee(
+    referral_fee_shares: Option<ReferralFeeShares>,
+    referral_fee: u64,
+) -> Result<u64> {
+    match referral_fee_shares {
+        Some(referral_fee_shares) => Ok(token_mill_sdk::get_sub_referral_fee(
+            referral_fee,
+            referral_fee_shares.sub_referral_fee_share,
+        )?),
+        None => Ok(0),
+    }
+}
+
+/// Pays the referral fee held by the market, `sub_referral_fee` of it going to the parent referral token account
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_referral_fees<'info>(
+    quote_token_mint: &InterfaceAccount<'info, Mint>,
+    market: AccountInfo<'info>,
+    market_quote_token_ata: &InterfaceAccount<'info, TokenAccount>,
+    referral_token_account: Option<&InterfaceAccount<'info, TokenAccount>>,
+    parent_referral_token_account: Option<&InterfaceAccount<'info, TokenAccount>>,
+    quote_token_program: &Interface<'info, TokenInterface>,
+    referral_fee: u64,
+    sub_referral_fee: u64,
+    market_seeds: &[&[u8]],
+) -> Result<()> {
+    if let Some(referral_token_account) = referral_token_account {
+        if referral_fee > sub_referral_fee {
+            transfer_from_pda(
+                quote_token_mint,
+                market.clone(),
+                market_quote_token_ata,
+                referral_token_account,
+                quote_token_program,
+                referral_fee - sub_referral_fee,
+                market_seeds,
+            )?;
+        }
+    }
+
+    if let Some(parent_referral_token_account) = parent_referral_token_account {
+        if sub_referral_fee > 0 {
+            transfer_from_pda(
+                quote_token_mint,
+                market,
+                market_quote_token_ata,
+                parent_referral_token_account,
+                quote_token_program,
+                sub_referral_fee,
+                market_seeds,
+            )?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,45 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::TokenMillError,
+    manager::swap_manager::SwapType,
+    state::{Market, MarketStats},
+};
+
+/// Checks that the stats are provided once enabled
+pub fn check_market_stats(
+    market: &Market,
+    market_stats: &Option<Box<Account<MarketStats>>>,
+) -> Result<()> {
+    if market_stats.is_none() && market.are_stats_enabled() {
+        return Err(TokenMillError::MissingMarketStats.into());
+    }
+
+    Ok(())
+}
+
+/// Records a swap in the market's stats, which are mandatory once enabled.
+/// `fees` are the (creator, staking, protocol, referral) fees of the swap.
+pub fn update_market_stats(
+    market: &Market,
+    market_stats: &mut Option<Box<Account<MarketStats>>>,
+    swap_type: SwapType,
+    base_amount: u64,
+    quote_amount: u64,
+    fees: (u64, u64, u64, u64),
+) -> Result<()> {
+    check_market_stats(market, market_stats)?;
+
+    if let Some(market_stats) = market_stats {
+        market_stats.record_swap(
+            market,
+            swap_type,
+            base_amount,
+            quote_amount,
+            fees,
+            Clock::get()?.unix_timestamp,
+        )?;
+    }
+
+    Ok(())
+}
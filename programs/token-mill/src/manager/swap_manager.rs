@@ -1,8 +1,10 @@
 use anchor_lang::prelude::*;
+pub use token_mill_sdk::SwapResult;
+use token_mill_sdk::TransferFee;
 
-use crate::{math::Rounding, state::Market};
+use crate::state::Market;
 
-#[derive(Debug, AnchorSerialize, AnchorDeserialize, Copy, Clone, PartialEq)]
+#[derive(Debug, AnchorSerialize, AnchorDeserialize, InitSpace, Copy, Clone, PartialEq)]
 pub enum SwapType {
     Buy,  // Buy base token
     Sell, // Sell base token
@@ -14,44 +16,124 @@ pub enum SwapAmountType {
     ExactOutput,
 }
 
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone, Copy, PartialEq)]
+pub struct SwapLeg {
+    pub swap_type: SwapType,
+    pub swap_amount_type: SwapAmountType,
+    pub amount: u64,
+    pub other_amount_threshold: u64,
+    /// Number of extra accounts of the base token's transfer hook, following the accounts of the leg
+    pub transfer_hook_accounts: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone, Copy, PartialEq)]
+pub enum SwapBatchMode {
+    /// Any failing leg reverts the whole batch
+    AllOrNothing,
+    /// Legs that would fail are skipped
+    BestEffort,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone, Copy, PartialEq)]
+pub struct SwapLegResult {
+    pub executed: bool,
+    pub base_amount: u64,
+    pub quote_amount: u64,
+}
+
+impl From<SwapType> for token_mill_sdk::SwapType {
+    fn from(swap_type: SwapType) -> Self {
+        match swap_type {
+            SwapType::Buy => token_mill_sdk::SwapType::Buy,
+            SwapType::Sell => token_mill_sdk::SwapType::Sell,
+        }
+    }
+}
+
+impl From<SwapAmountType> for token_mill_sdk::SwapAmountType {
+    fn from(swap_amount_type: SwapAmountType) -> Self {
+        match swap_amount_type {
+            SwapAmountType::ExactInput => token_mill_sdk::SwapAmountType::ExactInput,
+            SwapAmountType::ExactOutput => token_mill_sdk::SwapAmountType::ExactOutput,
+        }
+    }
+}
+
+/// Swaps against the bonding curve, or the constant product pool once graduated.
+/// Returns the base amount, the quote amount and the swap fee.
 pub fn swap(
     market: &mut Market,
     swap_type: SwapType,
     swap_amount_type: SwapAmountType,
     amount: u64,
 ) -> Result<(u64, u64, u64)> {
-    let (base_amount, quote_amount) = match (swap_type, swap_amount_type) {
-        (SwapType::Buy, SwapAmountType::ExactInput) => market.get_base_amount_out(amount)?,
-        (SwapType::Buy, SwapAmountType::ExactOutput) => {
-            market.get_quote_amount(amount, swap_amount_type)?
-        }
-        (SwapType::Sell, SwapAmountType::ExactInput) => {
-            market.get_quote_amount(amount, swap_amount_type)?
-        }
-        (SwapType::Sell, SwapAmountType::ExactOutput) => market.get_base_amount_in(amount)?,
-    };
-
-    let mut swap_fee = 0;
-
-    match swap_type {
-        SwapType::Buy => {
-            let (_, buyback_amount) = market.get_quote_amount_with_parameters(
-                market.circulating_supply(),
-                base_amount,
-                SwapAmountType::ExactInput,
-                Rounding::Up,
-            )?;
+    Ok(token_mill_sdk::swap(
+        market.sdk_mut(),
+        swap_type.into(),
+        swap_amount_type.into(),
+        amount,
+    )?)
+}
 
-            if quote_amount > buyback_amount {
-                swap_fee = quote_amount - buyback_amount;
-            }
+/// Swaps like `swap`, buyers on the curve being discounted `fee_discount_bps` of the spread fee.
+/// The discount lowers the price paid, the buyback amount of the bid curve is left untouched.
+/// Returns the amounts of `swap`, net of the discount, and the discount.
+pub fn swap_with_fee_discount(
+    market: &mut Market,
+    swap_type: SwapType,
+    swap_amount_type: SwapAmountType,
+    amount: u64,
+    fee_discount_bps: u64,
+) -> Result<SwapResult> {
+    Ok(token_mill_sdk::swap_with_fee_discount(
+        market.sdk_mut(),
+        swap_type.into(),
+        swap_amount_type.into(),
+        amount,
+        fee_discount_bps,
+    )?)
+}
 
-            market.base_reserve -= base_amount;
-        }
-        SwapType::Sell => {
-            market.base_reserve += base_amount;
-        }
-    }
+/// Swaps like `swap_with_fee_discount`, buyers also paying the market's buy tax at `current_time` on top of the curve.
+/// Returns the amounts of `swap_with_fee_discount`, the quote amount including the buy tax.
+pub fn swap_with_buy_tax(
+    market: &mut Market,
+    swap_type: SwapType,
+    swap_amount_type: SwapAmountType,
+    amount: u64,
+    current_time: i64,
+    fee_discount_bps: u64,
+) -> Result<SwapResult> {
+    Ok(token_mill_sdk::swap_with_buy_tax(
+        market.sdk_mut(),
+        swap_type.into(),
+        swap_amount_type.into(),
+        amount,
+        current_time,
+        fee_discount_bps,
+    )?)
+}
 
-    Ok((base_amount, quote_amount, swap_fee))
+/// Swaps like `swap_with_buy_tax`, the quote tokens sent or received by the user paying `quote_transfer_fee`.
+/// `amount` is the amount sent or received by the user, the curve only sees the quote amount reaching or leaving the market.
+/// Returns the amounts of `swap_with_buy_tax`, the quote amount being the market's one, and the transfer fee paid by the user,
+/// who sends the quote amount plus the transfer fee on buys and receives the quote amount minus the transfer fee on sells.
+pub fn swap_with_transfer_fee(
+    market: &mut Market,
+    swap_type: SwapType,
+    swap_amount_type: SwapAmountType,
+    amount: u64,
+    current_time: i64,
+    quote_transfer_fee: Option<&TransferFee>,
+    fee_discount_bps: u64,
+) -> Result<SwapResult> {
+    Ok(token_mill_sdk::swap_with_transfer_fee(
+        market.sdk_mut(),
+        swap_type.into(),
+        swap_amount_type.into(),
+        amount,
+        current_time,
+        quote_transfer_fee,
+        fee_discount_bps,
+    )?)
 }
@@ -3,14 +3,28 @@ use anchor_spl::{
     token::Token,
     token_2022::spl_token_2022::{
         self,
-        extension::{BaseStateWithExtensions, ExtensionType, StateWithExtensions},
+        extension::{
+            transfer_fee::TransferFeeConfig, transfer_hook, BaseStateWithExtensions, ExtensionType,
+            StateWithExtensions,
+        },
+        onchain::invoke_transfer_checked,
+    },
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
     },
-    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
 };
+use spl_transfer_hook_interface::get_extra_account_metas_address;
+use token_mill_sdk::TransferFee;
+
+use crate::errors::TokenMillError;
 
 /// Checks that the mint account only has allowed extensions.
-/// Tax-transfer quote tokens for example would cause some issues with the current implementation.
-pub fn check_mint_extensions(mint_account: &InterfaceAccount<Mint>) -> Result<bool> {
+/// Transfer fees are accounted for by swaps, `allow_transfer_fee` being set for badged quote tokens only.
+pub fn check_mint_extensions(
+    mint_account: &InterfaceAccount<Mint>,
+    allow_transfer_fee: bool,
+) -> Result<bool> {
     let mint_account_info = mint_account.to_account_info();
     if *mint_account_info.owner == Token::id() {
         return Ok(true);
@@ -21,7 +35,15 @@ pub fn check_mint_extensions(mint_account: &InterfaceAccount<Mint>) -> Result<bo
     let extensions = mint.get_extension_types()?;
 
     for e in extensions {
-        if e != ExtensionType::MetadataPointer && e != ExtensionType::TokenMetadata {
+        let allowed = match e {
+            ExtensionType::MetadataPointer | ExtensionType::TokenMetadata => true,
+            ExtensionType::TransferFeeConfig => allow_transfer_fee,
+            // Mill tokens always carry the extension, only the ones without a hook program are allowed
+            ExtensionType::TransferHook => transfer_hook::get_program_id(&mint).is_none(),
+            _ => false,
+        };
+
+        if !allowed {
             return Ok(false);
         }
     }
@@ -29,6 +51,70 @@ pub fn check_mint_extensions(mint_account: &InterfaceAccount<Mint>) -> Result<bo
     Ok(true)
 }
 
+/// Checks that `transfer_hook_accounts` hold the program and the extra account metas of the mint's transfer hook, if any
+pub fn check_transfer_hook_accounts(
+    mint_account: &InterfaceAccount<Mint>,
+    transfer_hook_accounts: &[AccountInfo],
+) -> Result<()> {
+    let mint_account_info = mint_account.to_account_info();
+    if *mint_account_info.owner == Token::id() {
+        return Ok(());
+    }
+
+    let mint_data = mint_account_info.try_borrow_data()?;
+    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)?;
+
+    if let Some(program_id) = transfer_hook::get_program_id(&mint) {
+        let extra_account_metas = get_extra_account_metas_address(&mint_account.key(), &program_id);
+
+        let has_accounts = [program_id, extra_account_metas].iter().all(|key| {
+            transfer_hook_accounts
+                .iter()
+                .any(|account_info| account_info.key == key)
+        });
+
+        require!(has_accounts, TokenMillError::MissingTransferHookAccounts);
+    }
+
+    Ok(())
+}
+
+/// Transfer fee of the mint at the current epoch, `None` if the mint has no TransferFee extension
+pub fn get_epoch_transfer_fee(
+    mint_account: &InterfaceAccount<Mint>,
+) -> Result<Option<TransferFee>> {
+    let mint_account_info = mint_account.to_account_info();
+    if *mint_account_info.owner == Token::id() {
+        return Ok(None);
+    }
+
+    let mint_data = mint_account_info.try_borrow_data()?;
+    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)?;
+
+    let transfer_fee = match mint.get_extension::<TransferFeeConfig>() {
+        Ok(transfer_fee_config) => {
+            let epoch_fee = transfer_fee_config.get_epoch_fee(Clock::get()?.epoch);
+
+            Some(TransferFee {
+                transfer_fee_basis_points: epoch_fee.transfer_fee_basis_points.into(),
+                maximum_fee: epoch_fee.maximum_fee.into(),
+            })
+        }
+        Err(_) => None,
+    };
+
+    Ok(transfer_fee)
+}
+
+/// Fee withheld from a transfer of `amount`
+pub fn get_transfer_fee_amount(transfer_fee: Option<&TransferFee>, amount: u64) -> Result<u64> {
+    match transfer_fee {
+        Some(transfer_fee) => Ok(transfer_fee.get_fee_amount(amount)?),
+        None => Ok(0),
+    }
+}
+
+/// The transfer fee of the mint, if any, is withheld from `amount`
 pub fn transfer_from_pda<'info>(
     mint: &InterfaceAccount<'info, Mint>,
     pda: AccountInfo<'info>,
@@ -54,6 +140,51 @@ pub fn transfer_from_pda<'info>(
     )
 }
 
+/// Like `transfer_from_pda`, resolving the extra accounts of the mint's transfer hook from `transfer_hook_accounts`
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_from_pda_with_hook<'info>(
+    mint: &InterfaceAccount<'info, Mint>,
+    pda: AccountInfo<'info>,
+    pda_token_account: &InterfaceAccount<'info, TokenAccount>,
+    recipient_token_account: &InterfaceAccount<'info, TokenAccount>,
+    token_program: &Interface<'info, TokenInterface>,
+    amount: u64,
+    pda_seeds: &[&[u8]],
+    transfer_hook_accounts: &[AccountInfo<'info>],
+) -> Result<()> {
+    invoke_transfer_checked(
+        token_program.key,
+        pda_token_account.to_account_info(),
+        mint.to_account_info(),
+        recipient_token_account.to_account_info(),
+        pda,
+        transfer_hook_accounts,
+        amount,
+        mint.decimals,
+        &[pda_seeds],
+    )
+    .map_err(Into::into)
+}
+
+pub fn close_pda_token_account<'info>(
+    pda: AccountInfo<'info>,
+    pda_token_account: &InterfaceAccount<'info, TokenAccount>,
+    destination: AccountInfo<'info>,
+    token_program: &Interface<'info, TokenInterface>,
+    pda_seeds: &[&[u8]],
+) -> Result<()> {
+    close_account(CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        CloseAccount {
+            account: pda_token_account.to_account_info(),
+            destination,
+            authority: pda,
+        },
+        &[pda_seeds],
+    ))
+}
+
+/// Returns the amount received, i.e. `amount` minus the transfer fee of the mint
 pub fn transfer_from_eoa<'info>(
     mint: &InterfaceAccount<'info, Mint>,
     eoa: &Signer<'info>,
@@ -61,7 +192,9 @@ pub fn transfer_from_eoa<'info>(
     recipient_token_account: &InterfaceAccount<'info, TokenAccount>,
     token_program: &Interface<'info, TokenInterface>,
     amount: u64,
-) -> Result<()> {
+) -> Result<u64> {
+    let transfer_fee = get_epoch_transfer_fee(mint)?;
+
     transfer_checked(
         CpiContext::new(
             token_program.to_account_info(),
@@ -74,5 +207,34 @@ pub fn transfer_from_eoa<'info>(
         ),
         amount,
         mint.decimals,
-    )
+    )?;
+
+    Ok(amount - get_transfer_fee_amount(transfer_fee.as_ref(), amount)?)
+}
+
+/// Like `transfer_from_eoa`, resolving the extra accounts of the mint's transfer hook from `transfer_hook_accounts`
+pub fn transfer_from_eoa_with_hook<'info>(
+    mint: &InterfaceAccount<'info, Mint>,
+    eoa: &Signer<'info>,
+    eoa_token_account: &InterfaceAccount<'info, TokenAccount>,
+    recipient_token_account: &InterfaceAccount<'info, TokenAccount>,
+    token_program: &Interface<'info, TokenInterface>,
+    amount: u64,
+    transfer_hook_accounts: &[AccountInfo<'info>],
+) -> Result<u64> {
+    let transfer_fee = get_epoch_transfer_fee(mint)?;
+
+    invoke_transfer_checked(
+        token_program.key,
+        eoa_token_account.to_account_info(),
+        mint.to_account_info(),
+        recipient_token_account.to_account_info(),
+        eoa.to_account_info(),
+        transfer_hook_accounts,
+        amount,
+        mint.decimals,
+        &[],
+    )?;
+
+    Ok(amount - get_transfer_fee_amount(transfer_fee.as_ref(), amount)?)
 }
@@ -1,5 +1,39 @@
 use anchor_lang::prelude::*;
 
+use crate::{
+    constant::{MAX_BPS, MAX_FEE_DISCOUNT_TIERS},
+    errors::TokenMillError,
+    manager::swap_manager::SwapType,
+    state::StakePosition,
+};
+
+#[derive(Debug, AnchorSerialize, AnchorDeserialize, Copy, Clone, PartialEq)]
+pub enum PausableOperation {
+    Buy,
+    Sell,
+    MarketCreation,
+    /// Staking deposits, withdrawals are never paused
+    Staking,
+    Vesting,
+}
+
+impl From<SwapType> for PausableOperation {
+    fn from(swap_type: SwapType) -> Self {
+        match swap_type {
+            SwapType::Buy => PausableOperation::Buy,
+            SwapType::Sell => PausableOperation::Sell,
+        }
+    }
+}
+
+/// Spread fee discount of the swaps made with a stake position of at least `min_amount`
+#[derive(Debug, AnchorSerialize, AnchorDeserialize, InitSpace, Default, Copy, Clone, PartialEq)]
+pub struct FeeDiscountTier {
+    /// Minimum `amount_staked + total_amount_vested` of the stake position
+    pub min_amount: u64,
+    pub discount_bps: u16,
+}
+
 #[account]
 #[derive(InitSpace)]
 pub struct TokenMillConfig {
@@ -8,6 +42,12 @@ pub struct TokenMillConfig {
     pub protocol_fee_recipient: Pubkey,
     pub default_protocol_fee_share: u16,
     pub referral_fee_share: u16,
+    /// Share of the referral fee paid to the parent of the referral account
+    pub sub_referral_fee_share: u16,
+    /// Bitmask of the paused `PausableOperation`s, toggled by the authority
+    pub paused_operations: u8,
+    /// Sorted by increasing `min_amount` and `discount_bps`, unused tiers are zeroed
+    pub fee_discount_tiers: [FeeDiscountTier; MAX_FEE_DISCOUNT_TIERS],
 }
 
 impl TokenMillConfig {
@@ -23,6 +63,68 @@ impl TokenMillConfig {
         self.protocol_fee_recipient = protocol_fee_recipient;
         self.default_protocol_fee_share = protocol_fee_share;
         self.referral_fee_share = referral_fee_share;
+        self.sub_referral_fee_share = 0;
+        self.paused_operations = 0;
+        self.fee_discount_tiers = [FeeDiscountTier::default(); MAX_FEE_DISCOUNT_TIERS];
+
+        Ok(())
+    }
+
+    pub fn is_paused(&self, operation: PausableOperation) -> bool {
+        self.paused_operations & (1 << operation as u8) != 0
+    }
+
+    pub fn set_paused(&mut self, operation: PausableOperation, paused: bool) {
+        if paused {
+            self.paused_operations |= 1 << operation as u8;
+        } else {
+            self.paused_operations &= !(1 << operation as u8);
+        }
+    }
+
+    pub fn set_fee_discount_tiers(&mut self, fee_discount_tiers: &[FeeDiscountTier]) -> Result<()> {
+        require!(
+            fee_discount_tiers.len() <= MAX_FEE_DISCOUNT_TIERS,
+            TokenMillError::InvalidFeeDiscountTiers
+        );
+
+        let mut previous_tier = FeeDiscountTier::default();
+
+        for tier in fee_discount_tiers {
+            require!(
+                tier.min_amount > previous_tier.min_amount
+                    && tier.discount_bps > previous_tier.discount_bps
+                    && u64::from(tier.discount_bps) <= MAX_BPS,
+                TokenMillError::InvalidFeeDiscountTiers
+            );
+
+            previous_tier = *tier;
+        }
+
+        self.fee_discount_tiers = [FeeDiscountTier::default(); MAX_FEE_DISCOUNT_TIERS];
+        self.fee_discount_tiers[..fee_discount_tiers.len()].copy_from_slice(fee_discount_tiers);
+
+        Ok(())
+    }
+
+    /// Discount of the highest tier reached by `staked_amount`, 0 if none is
+    pub fn get_fee_discount_bps(&self, staked_amount: u64) -> u64 {
+        self.fee_discount_tiers
+            .iter()
+            .rev()
+            .find(|tier| tier.discount_bps > 0 && tier.min_amount <= staked_amount)
+            .map_or(0, |tier| tier.discount_bps.into())
+    }
+
+    /// Fee discount of the stake position, its vested amount counting as staked
+    pub fn get_stake_position_fee_discount_bps(&self, stake_position: &StakePosition) -> u64 {
+        self.get_fee_discount_bps(stake_position.amount_staked + stake_position.total_amount_vested)
+    }
+
+    pub fn check_not_paused(&self, operation: PausableOperation) -> Result<()> {
+        if self.is_paused(operation) {
+            return Err(TokenMillError::OperationPaused.into());
+        }
 
         Ok(())
     }
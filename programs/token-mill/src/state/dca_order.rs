@@ -0,0 +1,86 @@
+use anchor_lang::prelude::*;
+
+use crate::{manager::swap_manager::SwapType, state::is_average_price_met};
+
+pub const DCA_ORDER_PDA_SEED: &str = "dca_order";
+
+#[account]
+#[derive(Debug, InitSpace)]
+pub struct DcaOrder {
+    pub bump: u8,
+    pub market: Pubkey,
+    pub owner: Pubkey,
+    pub order_id: u64,
+    pub swap_type: SwapType,
+    /// Quote token mint for buys, base token mint for sells
+    pub escrow_token_mint: Pubkey,
+    /// Escrowed amount left, in quote tokens for buys and in base tokens for sells
+    pub amount_remaining: u64,
+    /// Escrowed amount swapped by each execution, the last one swapping what is left
+    pub amount_per_execution: u64,
+    /// Seconds between two executions
+    pub interval: i64,
+    pub next_execution_ts: i64,
+    /// Highest average price of an execution for buys and lowest for sells, in the units of the market price curves
+    pub limit_price: u64,
+}
+
+impl DcaOrder {
+    #[allow(clippy::too_many_arguments)]
+    pub fn initialize(
+        &mut self,
+        bump: u8,
+        market: Pubkey,
+        owner: Pubkey,
+        order_id: u64,
+        swap_type: SwapType,
+        escrow_token_mint: Pubkey,
+        amount: u64,
+        amount_per_execution: u64,
+        interval: i64,
+        limit_price: u64,
+        current_time: i64,
+    ) -> Result<()> {
+        self.bump = bump;
+        self.market = market;
+        self.owner = owner;
+        self.order_id = order_id;
+        self.swap_type = swap_type;
+        self.escrow_token_mint = escrow_token_mint;
+        self.amount_remaining = amount;
+        self.amount_per_execution = amount_per_execution;
+        self.interval = interval;
+        self.next_execution_ts = current_time;
+        self.limit_price = limit_price;
+
+        Ok(())
+    }
+
+    /// Returns the amount to swap, scheduling the next execution one interval from now.
+    /// Missed executions aren't caught up, a late execution only delays the following ones.
+    pub fn execute(&mut self, current_time: i64) -> Option<u64> {
+        if current_time < self.next_execution_ts {
+            return None;
+        }
+
+        self.next_execution_ts = current_time + self.interval;
+
+        Some(self.amount_per_execution.min(self.amount_remaining))
+    }
+
+    /// Checks the average price of an execution, `quote_amount` being paid or received by the owner.
+    pub fn is_execution_price_met(
+        &self,
+        base_amount: u64,
+        quote_amount: u64,
+        quote_token_decimals: u8,
+    ) -> bool {
+        is_average_price_met(
+            self.swap_type,
+            self.limit_price,
+            base_amount,
+            quote_amount,
+            quote_token_decimals,
+        )
+    }
+}
@@ -0,0 +1,37 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::TokenMillError;
+
+pub const LAUNCH_ALLOCATION_PDA_SEED: &str = "launch_allocation";
+
+/// Base amount bought by a user during the launch window of a market.
+#[account]
+#[derive(Debug, InitSpace)]
+pub struct LaunchAllocation {
+    pub bump: u8,
+    pub market: Pubkey,
+    pub user: Pubkey,
+    pub amount_bought: u64,
+}
+
+impl LaunchAllocation {
+    pub fn initialize(&mut self, bump: u8, market: Pubkey, user: Pubkey) -> Result<()> {
+        self.bump = bump;
+        self.market = market;
+        self.user = user;
+
+        Ok(())
+    }
+
+    pub fn record_buy(&mut self, base_amount: u64, max_buy_per_wallet: u64) -> Result<()> {
+        let amount_bought = self.amount_bought + base_amount;
+
+        if amount_bought > max_buy_per_wallet {
+            return Err(TokenMillError::LaunchAllocationExceeded.into());
+        }
+
+        self.amount_bought = amount_bought;
+
+        Ok(())
+    }
+}
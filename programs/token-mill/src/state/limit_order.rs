@@ -0,0 +1,95 @@
+use anchor_lang::prelude::*;
+use ruint::aliases::U256;
+
+use crate::{
+    constant::{BASE_PRECISION, SCALE},
+    manager::swap_manager::SwapType,
+};
+
+pub const LIMIT_ORDER_PDA_SEED: &str = "limit_order";
+
+#[account]
+#[derive(Debug, InitSpace)]
+pub struct LimitOrder {
+    pub bump: u8,
+    pub market: Pubkey,
+    pub owner: Pubkey,
+    pub order_id: u64,
+    pub swap_type: SwapType,
+    /// Quote token mint for buys, base token mint for sells
+    pub escrow_token_mint: Pubkey,
+    /// Escrowed amount left, in quote tokens for buys and in base tokens for sells
+    pub amount_remaining: u64,
+    /// Highest price for buys and lowest price for sells, in the units of the market price curves
+    pub limit_price: u64,
+}
+
+impl LimitOrder {
+    #[allow(clippy::too_many_arguments)]
+    pub fn initialize(
+        &mut self,
+        bump: u8,
+        market: Pubkey,
+        owner: Pubkey,
+        order_id: u64,
+        swap_type: SwapType,
+        escrow_token_mint: Pubkey,
+        amount: u64,
+        limit_price: u64,
+    ) -> Result<()> {
+        self.bump = bump;
+        self.market = market;
+        self.owner = owner;
+        self.order_id = order_id;
+        self.swap_type = swap_type;
+        self.escrow_token_mint = escrow_token_mint;
+        self.amount_remaining = amount;
+        self.limit_price = limit_price;
+
+        Ok(())
+    }
+
+    pub fn is_fillable(&self, spot_price: u64) -> bool {
+        match self.swap_type {
+            SwapType::Buy => spot_price <= self.limit_price,
+            SwapType::Sell => spot_price >= self.limit_price,
+        }
+    }
+
+    /// Checks the average price of a fill, `quote_amount` being paid or received by the owner.
+    pub fn is_fill_price_met(
+        &self,
+        base_amount: u64,
+        quote_amount: u64,
+        quote_token_decimals: u8,
+    ) -> bool {
+        is_average_price_met(
+            self.swap_type,
+            self.limit_price,
+            base_amount,
+            quote_amount,
+            quote_token_decimals,
+        )
+    }
+}
+
+/// Checks that `quote_amount` for `base_amount` is at most `limit_price` on buys and at least `limit_price` on sells.
+pub fn is_average_price_met(
+    swap_type: SwapType,
+    limit_price: u64,
+    base_amount: u64,
+    quote_amount: u64,
+    quote_token_decimals: u8,
+) -> bool {
+    // Prices are quote per whole base token, both normalized and scaled by `SCALE`
+    let scaled_quote_amount =
+        U256::from(quote_amount) * U256::from(SCALE) * U256::from(BASE_PRECISION);
+    let limit_quote_amount = U256::from(base_amount)
+        * U256::from(limit_price)
+        * U256::from(10u64).pow(U256::from(quote_token_decimals));
+
+    match swap_type {
+        SwapType::Buy => scaled_quote_amount <= limit_quote_amount,
+        SwapType::Sell => scaled_quote_amount >= limit_quote_amount,
+    }
+}
@@ -1,12 +1,10 @@
-use std::cmp::min;
-
-use anchor_lang::prelude::*;
+use anchor_lang::{prelude::*, solana_program::keccak};
 
 use crate::{
     constant::*,
     errors::TokenMillError,
-    manager::swap_manager::SwapAmountType,
-    math::{div, get_delta_base_in, get_delta_base_out, mul_div, Rounding},
+    manager::swap_manager::{SwapAmountType, SwapType},
+    math::Rounding,
 };
 
 pub const MARKET_PDA_SEED: &str = "market";
@@ -21,6 +19,152 @@ pub struct MarketFees {
 
     pub pending_staking_fees: u64,
     pub pending_creator_fees: u64,
+    /// Claimed by the config's protocol fee recipient
+    pub pending_protocol_fees: u64,
+}
+
+#[derive(Debug, AnchorSerialize, AnchorDeserialize, Copy, Clone, PartialEq)]
+pub enum CurveKind {
+    /// Bid and ask price tables, set with `set_market_prices`
+    PiecewiseLinear,
+    /// Constant product curve over virtual reserves, bid quotes being `spread_bps` lower than ask quotes
+    VirtualConstantProduct {
+        virtual_base: u64,
+        virtual_quote: u64,
+        spread_bps: u16,
+    },
+}
+
+#[zero_copy]
+#[derive(Debug, InitSpace)]
+pub struct MarketVirtualReserves {
+    /// Virtual base reserve when the circulating supply is 0, greater than the total supply
+    pub virtual_base: u64,
+    /// Virtual quote reserve when the circulating supply is 0
+    pub virtual_quote: u64,
+    pub spread_bps: u16,
+    _space: [u8; 6],
+}
+
+#[derive(Debug, AnchorSerialize, AnchorDeserialize, Copy, Clone, PartialEq)]
+pub enum PriceInterpolation {
+    /// Prices grow linearly between two breakpoints
+    Linear,
+    /// Prices grow geometrically between two breakpoints
+    Exponential,
+}
+
+#[derive(Debug, AnchorSerialize, AnchorDeserialize, Copy, Clone, PartialEq)]
+pub enum GraduationState {
+    BondingCurve,
+    Graduated,
+}
+
+#[derive(Debug, AnchorSerialize, AnchorDeserialize, Copy, Clone, PartialEq)]
+pub enum MarketStatus {
+    /// Waiting for the creator to set the prices
+    Created,
+    /// Prices are set, trading opens at `trading_start_ts`
+    PricesSet,
+    Trading,
+    /// Only sells are allowed
+    SellOnly,
+    /// No swap is allowed anymore
+    Closed,
+}
+
+#[zero_copy]
+#[derive(Debug, InitSpace)]
+pub struct MarketGraduation {
+    /// Circulating supply freezing the bonding curve, 0 if disabled
+    pub supply_threshold: u64,
+    /// Quote amount raised by the bonding curve freezing it, 0 if disabled
+    pub quote_threshold: u64,
+    /// Quote reserve of the constant product pool, only used once graduated
+    pub quote_reserve: u64,
+    /// `GraduationState` of the market
+    pub state: u8,
+    _space: [u8; 7],
+}
+
+/// Per-wallet buy cap applied for `window` seconds once trading opens
+#[derive(Debug, AnchorSerialize, AnchorDeserialize, Copy, Clone, PartialEq)]
+pub struct LaunchProtection {
+    pub window: i64,
+    pub max_buy_per_wallet: u64,
+}
+
+#[zero_copy]
+#[derive(Debug, InitSpace)]
+pub struct MarketLaunchProtection {
+    /// Duration of the launch window in seconds, 0 if disabled
+    pub window: i64,
+    /// Base amount each wallet can buy during the launch window
+    pub max_buy_per_wallet: u64,
+    /// Timestamp at which the launch window ends, set once the prices are set
+    pub end_ts: i64,
+}
+
+#[derive(Debug, AnchorSerialize, AnchorDeserialize, Copy, Clone, PartialEq)]
+pub enum BuyTaxRecipient {
+    /// Shared between the creator, stakers and the protocol like the swap fee
+    Fees,
+    Stakers,
+}
+
+#[zero_copy]
+#[derive(Debug, InitSpace)]
+pub struct MarketBuyTax {
+    /// Duration over which the tax decays linearly to 0, 0 if disabled
+    pub duration: i64,
+    /// Timestamp from which the tax decays, set once the prices are set
+    pub start_ts: i64,
+    /// Tax rate when trading opens, in bps of the quote amount paid by buyers
+    pub start_bps: u16,
+    /// `BuyTaxRecipient` of the tax
+    pub recipient: u8,
+    _space: [u8; 5],
+}
+
+#[zero_copy]
+#[derive(Debug, InitSpace)]
+pub struct MarketPresale {
+    /// Root of the merkle tree of the allowlisted `(user, max_amount)` leaves
+    pub merkle_root: [u8; 32],
+    /// Timestamp until which only allowlisted users can buy, 0 if disabled
+    pub end_ts: i64,
+}
+
+/// Proof that the signer is allowlisted for the presale of a market.
+/// `max_amount` is the base amount the user can buy during the presale, 0 if uncapped.
+#[derive(Debug, AnchorSerialize, AnchorDeserialize, Clone, PartialEq)]
+pub struct PresaleProof {
+    pub max_amount: u64,
+    pub proof: Vec<[u8; 32]>,
+}
+
+impl PresaleProof {
+    pub fn get_leaf(user: &Pubkey, max_amount: u64) -> [u8; 32] {
+        keccak::hashv(&[user.as_ref(), &max_amount.to_le_bytes()]).to_bytes()
+    }
+
+    /// Nodes are hashed in sorted order, proofs don't need the position of the leaf
+    pub fn hash_nodes(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+        let (left, right) = if a <= b { (a, b) } else { (b, a) };
+
+        keccak::hashv(&[left, right]).to_bytes()
+    }
+
+    pub fn verify(&self, merkle_root: &[u8; 32], user: &Pubkey) -> bool {
+        let root = self
+            .proof
+            .iter()
+            .fold(Self::get_leaf(user, self.max_amount), |node, sibling| {
+                Self::hash_nodes(&node, sibling)
+            });
+
+        root == *merkle_root
+    }
 }
 
 #[account(zero_copy)]
@@ -34,18 +178,38 @@ pub struct Market {
 
     pub base_reserve: u64,
 
-    pub bid_prices: [u64; PRICES_LENGTH],
-    pub ask_prices: [u64; PRICES_LENGTH],
+    /// Only the first `prices_length` prices and breakpoints are used
+    pub bid_prices: [u64; MAX_PRICES_LENGTH],
+    pub ask_prices: [u64; MAX_PRICES_LENGTH],
+    /// Circulating supply at which each price applies, from 0 to `total_supply`
+    pub supply_breakpoints: [u64; MAX_PRICES_LENGTH],
 
-    pub width_scaled: u64,
     pub total_supply: u64,
 
     pub fees: MarketFees,
+    pub graduation: MarketGraduation,
+    /// Only used by `CurveKind::VirtualConstantProduct` markets
+    pub virtual_reserves: MarketVirtualReserves,
+    /// Timestamp from which a `MarketStatus::PricesSet` market is trading, 0 if unscheduled
+    pub trading_start_ts: i64,
+    pub launch_protection: MarketLaunchProtection,
+    pub buy_tax: MarketBuyTax,
+    pub presale: MarketPresale,
 
     pub quote_token_decimals: u8,
     pub bump: u8,
-
-    _space: [u8; 6],
+    /// Number of points of the price curves, 0 until prices are set
+    pub prices_length: u8,
+    /// `PriceInterpolation` of the price curves
+    pub price_interpolation: u8,
+    /// 0 for `CurveKind::PiecewiseLinear`, 1 for `CurveKind::VirtualConstantProduct`
+    pub curve_kind: u8,
+    /// 1 once a `MarketOracle` tracks the market, swaps must then update it
+    pub has_oracle: u8,
+    /// 1 once a `MarketStats` tracks the market, swaps must then update it
+    pub has_stats: u8,
+    /// `MarketStatus` of the market, see `get_status` for the effective one
+    pub status: u8,
 }
 
 impl MarketFees {
@@ -54,28 +218,9 @@ impl MarketFees {
         swap_fee: u64,
         referral_fee_share: Option<u16>,
     ) -> Result<(u64, u64, u64, u64)> {
-        let creator_fee = u64::try_from(
-            u128::from(swap_fee) * u128::from(self.creator_fee_share) / MAX_BPS as u128,
-        )?;
-        let staking_fee = u64::try_from(
-            u128::from(swap_fee) * u128::from(self.staking_fee_share) / MAX_BPS as u128,
-        )?;
-        let remaining_fee = swap_fee - creator_fee - staking_fee;
-
-        let referral_fee = if let Some(referral_fee_share) = referral_fee_share {
-            u64::try_from(
-                u128::from(remaining_fee) * u128::from(referral_fee_share) / MAX_BPS as u128,
-            )?
-        } else {
-            0
-        };
-
-        let protocol_fee = remaining_fee - referral_fee;
+        let fees: &mut token_mill_sdk::MarketFees = bytemuck::cast_mut(self);
 
-        self.pending_creator_fees += creator_fee;
-        self.pending_staking_fees += staking_fee;
-
-        Ok((creator_fee, staking_fee, protocol_fee, referral_fee))
+        Ok(fees.distribute_fee(swap_fee, referral_fee_share)?)
     }
 }
 
@@ -92,14 +237,24 @@ impl Market {
         total_supply: u64,
         creator_fee_share: u16,
         staking_fee_share: u16,
+        trading_start_ts: Option<i64>,
+        launch_protection: Option<LaunchProtection>,
     ) -> Result<()> {
-        if total_supply > MAX_TOTAL_SUPPLY
-            || total_supply / INTERVAL_NUMBER < BASE_PRECISION
-            || (total_supply / INTERVAL_NUMBER) * INTERVAL_NUMBER != total_supply
-        {
+        if !(BASE_PRECISION..=MAX_TOTAL_SUPPLY).contains(&total_supply) {
             return Err(TokenMillError::InvalidTotalSupply.into());
         }
 
+        if let Some(launch_protection) = launch_protection {
+            if !(1..=MAX_LAUNCH_WINDOW).contains(&launch_protection.window)
+                || launch_protection.max_buy_per_wallet == 0
+            {
+                return Err(TokenMillError::InvalidLaunchProtection.into());
+            }
+
+            self.launch_protection.window = launch_protection.window;
+            self.launch_protection.max_buy_per_wallet = launch_protection.max_buy_per_wallet;
+        }
+
         self.bump = bump;
         self.config = config;
         self.creator = creator;
@@ -108,28 +263,48 @@ impl Market {
         self.quote_token_decimals = quote_token_decimals;
         self.total_supply = total_supply;
         self.base_reserve = total_supply;
-        self.width_scaled = u64::try_from(
-            u128::from(total_supply / INTERVAL_NUMBER) * SCALE / u128::from(BASE_PRECISION),
-        )?;
 
         self.fees.creator_fee_share = creator_fee_share;
         self.fees.staking_fee_share = staking_fee_share;
+        self.trading_start_ts = trading_start_ts.unwrap_or_default();
+        self.status = MarketStatus::Created as u8;
         Ok(())
     }
 
     pub fn check_and_set_prices(
         &mut self,
-        bid_prices: [u64; PRICES_LENGTH],
-        ask_prices: [u64; PRICES_LENGTH],
+        bid_prices: &[u64],
+        ask_prices: &[u64],
+        supply_breakpoints: &[u64],
+        price_interpolation: PriceInterpolation,
     ) -> Result<()> {
         if self.are_prices_set() {
             return Err(TokenMillError::PricesAlreadySet.into());
         }
 
-        for i in 0..PRICES_LENGTH {
+        let prices_length = bid_prices.len();
+
+        if prices_length != ask_prices.len()
+            || prices_length != supply_breakpoints.len()
+            || !(MIN_PRICES_LENGTH..=MAX_PRICES_LENGTH).contains(&prices_length)
+        {
+            return Err(TokenMillError::InvalidPricesLength.into());
+        }
+
+        if supply_breakpoints[0] != 0 || supply_breakpoints[prices_length - 1] != self.total_supply
+        {
+            return Err(TokenMillError::InvalidSupplyBreakpoints.into());
+        }
+
+        for i in 0..prices_length {
             let bid_price = bid_prices[i];
             let ask_price = ask_prices[i];
 
+            // Intervals must be at least one whole base token wide
+            if i > 0 && supply_breakpoints[i] < supply_breakpoints[i - 1] + BASE_PRECISION {
+                return Err(TokenMillError::InvalidSupplyBreakpoints.into());
+            }
+
             if bid_price > ask_price {
                 return Err(TokenMillError::BidAskMismatch.into());
             }
@@ -139,212 +314,326 @@ impl Market {
             }
         }
 
-        if ask_prices[INTERVAL_NUMBER as usize] > MAX_PRICE {
+        if ask_prices[prices_length - 1] > MAX_PRICE {
             return Err(TokenMillError::PriceTooHigh.into());
         }
 
-        self.bid_prices = bid_prices;
-        self.ask_prices = ask_prices;
+        // Geometric interpolation is undefined from a zero price
+        if price_interpolation == PriceInterpolation::Exponential && bid_prices[0] == 0 {
+            return Err(TokenMillError::InvalidPriceInterpolation.into());
+        }
+
+        self.bid_prices[..prices_length].copy_from_slice(bid_prices);
+        self.ask_prices[..prices_length].copy_from_slice(ask_prices);
+        self.supply_breakpoints[..prices_length].copy_from_slice(supply_breakpoints);
+        self.prices_length = u8::try_from(prices_length)?;
+        self.price_interpolation = price_interpolation as u8;
+        self.set_prices_status();
+
+        Ok(())
+    }
+
+    pub fn check_and_set_virtual_reserves(
+        &mut self,
+        virtual_base: u64,
+        virtual_quote: u64,
+        spread_bps: u16,
+    ) -> Result<()> {
+        if self.are_prices_set() {
+            return Err(TokenMillError::PricesAlreadySet.into());
+        }
+
+        // The virtual base reserve can't be depleted
+        if virtual_base <= self.total_supply || virtual_quote == 0 || spread_bps >= MAX_BPS as u16 {
+            return Err(TokenMillError::InvalidVirtualReserves.into());
+        }
+
+        self.virtual_reserves.virtual_base = virtual_base;
+        self.virtual_reserves.virtual_quote = virtual_quote;
+        self.virtual_reserves.spread_bps = spread_bps;
+        self.curve_kind = 1;
+        self.set_prices_status();
 
         Ok(())
     }
 
     pub fn are_prices_set(&self) -> bool {
-        self.ask_prices[INTERVAL_NUMBER as usize] != 0
+        self.prices_length != 0 || self.curve_kind != 0
     }
 
-    pub fn circulating_supply(&self) -> u64 {
-        self.total_supply - self.base_reserve
+    pub fn curve_kind(&self) -> CurveKind {
+        match self.curve_kind {
+            0 => CurveKind::PiecewiseLinear,
+            _ => CurveKind::VirtualConstantProduct {
+                virtual_base: self.virtual_reserves.virtual_base,
+                virtual_quote: self.virtual_reserves.virtual_quote,
+                spread_bps: self.virtual_reserves.spread_bps,
+            },
+        }
     }
 
-    pub fn get_quote_amount(
-        &self,
-        base_amount: u64,
-        swap_amount_type: SwapAmountType,
-    ) -> Result<(u64, u64)> {
-        let circulating_supply = self.circulating_supply();
+    pub fn set_graduation_thresholds(
+        &mut self,
+        supply_threshold: u64,
+        quote_threshold: u64,
+    ) -> Result<()> {
+        // Graduation parameters are locked as soon as the market opens for trading
+        if self.are_prices_set() {
+            return Err(TokenMillError::PricesAlreadySet.into());
+        }
 
-        let (supply, rounding) = match swap_amount_type {
-            SwapAmountType::ExactInput => (circulating_supply - base_amount, Rounding::Down),
-            SwapAmountType::ExactOutput => (circulating_supply, Rounding::Up),
-        };
+        if supply_threshold >= self.total_supply {
+            return Err(TokenMillError::InvalidGraduationThreshold.into());
+        }
 
-        self.get_quote_amount_with_parameters(supply, base_amount, swap_amount_type, rounding)
-    }
+        self.graduation.supply_threshold = supply_threshold;
+        self.graduation.quote_threshold = quote_threshold;
 
-    pub fn get_quote_amount_with_parameters(
-        &self,
-        supply: u64,
-        base_amount: u64,
-        swap_amount_type: SwapAmountType,
-        rounding: Rounding,
-    ) -> Result<(u64, u64)> {
-        let price_curve = match swap_amount_type {
-            SwapAmountType::ExactInput => &self.bid_prices,
-            SwapAmountType::ExactOutput => &self.ask_prices,
-        };
+        Ok(())
+    }
 
-        let normalized_supply = u128::from(supply) * SCALE / u128::from(BASE_PRECISION);
+    pub fn set_buy_tax(
+        &mut self,
+        start_bps: u16,
+        duration: i64,
+        recipient: BuyTaxRecipient,
+    ) -> Result<()> {
+        // The tax is part of the launch, it can't change once the market opens for trading
+        if self.are_prices_set() {
+            return Err(TokenMillError::PricesAlreadySet.into());
+        }
 
-        let mut normalized_base_amount_left =
-            u128::from(base_amount) * SCALE / u128::from(BASE_PRECISION);
+        if start_bps > MAX_BUY_TAX_BPS || !(0..=MAX_BUY_TAX_DURATION).contains(&duration) {
+            return Err(TokenMillError::InvalidBuyTax.into());
+        }
 
-        let mut normalized_quote_amount = 0;
+        self.buy_tax.start_bps = start_bps;
+        self.buy_tax.duration = duration;
+        self.buy_tax.recipient = recipient as u8;
 
-        let mut i = usize::try_from(normalized_supply / u128::from(self.width_scaled))?;
-        let mut interval_supply_already_used = normalized_supply % u128::from(self.width_scaled);
+        Ok(())
+    }
 
-        let mut price_0 = price_curve[i];
-        i += 1;
+    pub fn set_presale(
+        &mut self,
+        merkle_root: [u8; 32],
+        end_ts: i64,
+        current_time: i64,
+    ) -> Result<()> {
+        if self.are_prices_set() {
+            return Err(TokenMillError::PricesAlreadySet.into());
+        }
 
-        while normalized_base_amount_left > 0 && i < PRICES_LENGTH {
-            let price_1 = price_curve[i];
+        if merkle_root == [0; 32] || end_ts <= current_time {
+            return Err(TokenMillError::InvalidPresale.into());
+        }
 
-            let delta_base = min(
-                normalized_base_amount_left,
-                u128::from(self.width_scaled) - interval_supply_already_used,
-            );
+        self.presale.merkle_root = merkle_root;
+        self.presale.end_ts = end_ts;
 
-            let delta_quote = mul_div(
-                delta_base,
-                u128::from(price_1 - price_0) * (delta_base + 2 * interval_supply_already_used)
-                    + 2 * u128::from(price_0) * u128::from(self.width_scaled),
-                2 * SCALE * u128::from(self.width_scaled),
-                rounding,
-            )
-            .ok_or(TokenMillError::MathError)?;
+        Ok(())
+    }
 
-            normalized_base_amount_left -= delta_base;
-            normalized_quote_amount += delta_quote;
+    pub fn is_graduated(&self) -> bool {
+        self.graduation.state == GraduationState::Graduated as u8
+    }
 
-            interval_supply_already_used = 0;
-            price_0 = price_1;
+    /// Status of the market at `current_time`, a `PricesSet` market trades once its start is reached
+    pub fn get_status(&self, current_time: i64) -> MarketStatus {
+        match self.status {
+            0 => MarketStatus::Created,
+            1 if current_time < self.trading_start_ts => MarketStatus::PricesSet,
+            1 | 2 => MarketStatus::Trading,
+            3 => MarketStatus::SellOnly,
+            _ => MarketStatus::Closed,
+        }
+    }
 
-            i += 1;
+    pub fn check_swap_allowed(&self, swap_type: SwapType, current_time: i64) -> Result<()> {
+        match (self.get_status(current_time), swap_type) {
+            (MarketStatus::Trading, _) | (MarketStatus::SellOnly, SwapType::Sell) => Ok(()),
+            _ => Err(TokenMillError::MarketNotTrading.into()),
         }
+    }
 
-        let base_amount_swapped = base_amount
-            - div(
-                normalized_base_amount_left * u128::from(BASE_PRECISION),
-                SCALE,
-                rounding,
-            )?;
+    /// The creator can open trading before its scheduled start and wind the market down to sells only.
+    /// Returns the previous status.
+    pub fn update_status_as_creator(
+        &mut self,
+        new_status: MarketStatus,
+        current_time: i64,
+    ) -> Result<MarketStatus> {
+        let status = self.get_status(current_time);
+
+        match (status, new_status) {
+            (MarketStatus::PricesSet, MarketStatus::Trading) => {
+                // The launch window and the buy tax decay start with the early open
+                self.trading_start_ts = current_time;
+                self.schedule_launch(current_time);
+            }
+            (MarketStatus::Trading, MarketStatus::SellOnly) => {}
+            _ => return Err(TokenMillError::InvalidMarketStatus.into()),
+        }
 
-        let quote_amount_swapped = div(
-            normalized_quote_amount * u128::pow(10, u32::from(self.quote_token_decimals)),
-            SCALE,
-            rounding,
-        )?;
+        self.status = new_status as u8;
 
-        Ok((base_amount_swapped, quote_amount_swapped))
+        Ok(status)
     }
 
-    pub fn get_base_amount_in(&self, quote_amount: u64) -> Result<(u64, u64)> {
-        let price_curve = &self.bid_prices;
-        let circulating_supply = self.circulating_supply();
+    /// The config authority can halt a market to sells only, resume it or close a halted market.
+    /// Returns the previous status.
+    pub fn update_status_as_authority(
+        &mut self,
+        new_status: MarketStatus,
+        current_time: i64,
+    ) -> Result<MarketStatus> {
+        let status = self.get_status(current_time);
+
+        match (status, new_status) {
+            (MarketStatus::PricesSet | MarketStatus::Trading, MarketStatus::SellOnly)
+            | (MarketStatus::SellOnly, MarketStatus::Trading | MarketStatus::Closed) => {}
+            _ => return Err(TokenMillError::InvalidMarketStatus.into()),
+        }
 
-        let normalized_supply = u128::from(circulating_supply) * SCALE / u128::from(BASE_PRECISION);
+        self.status = new_status as u8;
 
-        let quote_precision = u128::pow(10, u32::from(self.quote_token_decimals));
-        let mut normalized_quote_amount_left = u128::from(quote_amount) * SCALE / quote_precision;
-        let mut normalized_base_amount = 0;
+        Ok(status)
+    }
 
-        let mut i = usize::try_from(normalized_supply / u128::from(self.width_scaled))?;
-        let mut interval_supply_available = normalized_supply % u128::from(self.width_scaled);
+    /// Starts the launch window and the buy tax decay when trading opens, at the scheduled start or right away.
+    pub fn schedule_launch(&mut self, current_time: i64) {
+        let launch_ts = self.trading_start_ts.max(current_time);
 
-        if interval_supply_available == 0 {
-            interval_supply_available = u128::from(self.width_scaled);
-        } else {
-            i += 1;
+        if self.launch_protection.window > 0 {
+            self.launch_protection.end_ts = launch_ts + self.launch_protection.window;
         }
 
-        let mut price_1 = price_curve[i];
-
-        while normalized_quote_amount_left > 0 && i > 0 {
-            let price_0 = price_curve[i - 1];
-
-            let (delta_base, delta_quote) = get_delta_base_in(
-                price_0.into(),
-                price_1.into(),
-                self.width_scaled.into(),
-                interval_supply_available,
-                normalized_quote_amount_left,
-            )?;
+        self.buy_tax.start_ts = launch_ts;
+    }
 
-            normalized_base_amount += delta_base;
-            normalized_quote_amount_left -= delta_quote;
+    /// Buys are capped per wallet until the launch window ends
+    pub fn is_launch_window_open(&self, current_time: i64) -> bool {
+        current_time < self.launch_protection.end_ts
+    }
 
-            interval_supply_available = u128::from(self.width_scaled);
-            price_1 = price_0;
+    /// Only allowlisted users can buy until the presale ends
+    pub fn is_presale_open(&self, current_time: i64) -> bool {
+        current_time < self.presale.end_ts
+    }
 
-            i -= 1;
+    fn set_prices_status(&mut self) {
+        // A market closed before its prices were set stays closed
+        if self.status == MarketStatus::Created as u8 {
+            self.status = MarketStatus::PricesSet as u8;
         }
+    }
 
-        let base_amount_swapped = div(
-            normalized_base_amount * u128::from(BASE_PRECISION),
-            SCALE,
-            Rounding::Up,
-        )?;
-
-        let quote_amount_swapped = quote_amount
-            - div(
-                normalized_quote_amount_left * quote_precision,
-                SCALE,
-                Rounding::Up,
-            )?;
+    pub fn is_oracle_enabled(&self) -> bool {
+        self.has_oracle == 1
+    }
 
-        Ok((base_amount_swapped, quote_amount_swapped))
+    pub fn are_stats_enabled(&self) -> bool {
+        self.has_stats == 1
     }
 
-    pub fn get_base_amount_out(&self, quote_amount: u64) -> Result<(u64, u64)> {
-        let price_curve = &self.ask_prices;
-        let circulating_supply = self.circulating_supply();
+    /// Freezes the bonding curve and seeds the constant product pool with the base reserve
+    /// and the quote amount raised.
+    pub fn graduate(&mut self) -> Result<()> {
+        if self.is_graduated() {
+            return Err(TokenMillError::MarketGraduated.into());
+        }
 
-        let normalized_supply = u128::from(circulating_supply) * SCALE / u128::from(BASE_PRECISION);
+        if !self.is_graduation_threshold_reached()? {
+            return Err(TokenMillError::GraduationThresholdNotReached.into());
+        }
 
-        let quote_precision = u128::pow(10, u32::from(self.quote_token_decimals));
-        let mut normalized_quote_amount_left = u128::from(quote_amount) * SCALE / quote_precision;
-        let mut normalized_base_amount = 0;
+        self.graduation.quote_reserve = self.quote_raised()?;
+        self.graduation.state = GraduationState::Graduated as u8;
 
-        let mut i = usize::try_from(normalized_supply / u128::from(self.width_scaled))?;
-        let mut interval_supply_already_used = normalized_supply % u128::from(self.width_scaled);
+        Ok(())
+    }
 
-        let mut price_0 = price_curve[i];
+    pub fn circulating_supply(&self) -> u64 {
+        self.total_supply - self.base_reserve
+    }
 
-        while normalized_quote_amount_left > 0 && i < PRICES_LENGTH - 1 {
-            let price_1 = price_curve[i + 1];
+    /// Swap math of the market, shared with off-chain quoting through the SDK
+    pub fn sdk(&self) -> &token_mill_sdk::Market {
+        bytemuck::cast_ref(self)
+    }
 
-            let (delta_base, delta_quote) = get_delta_base_out(
-                price_0.into(),
-                price_1.into(),
-                self.width_scaled.into(),
-                interval_supply_already_used,
-                normalized_quote_amount_left,
-            )?;
+    pub fn sdk_mut(&mut self) -> &mut token_mill_sdk::Market {
+        bytemuck::cast_mut(self)
+    }
 
-            normalized_base_amount += delta_base;
-            normalized_quote_amount_left -= delta_quote;
+    /// Distributes the swap fee and the buy tax, the tax going to stakers or being shared like the fee
+    pub fn distribute_fees(
+        &mut self,
+        swap_fee: u64,
+        buy_tax: u64,
+        referral_fee_share: Option<u16>,
+    ) -> Result<(u64, u64, u64, u64)> {
+        Ok(self
+            .sdk_mut()
+            .distribute_fees(swap_fee, buy_tax, referral_fee_share)?)
+    }
 
-            interval_supply_already_used = 0;
-            price_0 = price_1;
+    /// Quote amount backing the circulating supply, i.e. its value on the bid curve.
+    /// The rest of the market quote balance is made of fees.
+    pub fn quote_raised(&self) -> Result<u64> {
+        Ok(self.sdk().quote_raised()?)
+    }
 
-            i += 1;
-        }
+    /// Buys stop at the supply threshold and never sell out a market set to graduate, which always has a base reserve to seed the pool with.
+    pub fn is_graduation_threshold_reached(&self) -> Result<bool> {
+        Ok(self.sdk().is_graduation_threshold_reached()?)
+    }
 
-        let base_amount_swapped = div(
-            normalized_base_amount * u128::from(BASE_PRECISION),
-            SCALE,
-            Rounding::Down,
-        )?;
+    /// Price of the curve at the circulating supply, from the ask prices for buys and the bid prices for sells.
+    /// Only defined for markets with prices set. Once graduated, the price of the pool, excluding its fee.
+    pub fn get_spot_price(&self, swap_type: SwapType) -> Result<u64> {
+        Ok(self.sdk().get_spot_price(swap_type.into())?)
+    }
 
-        let quote_amount_swapped = quote_amount
-            - div(
-                normalized_quote_amount_left * quote_precision,
-                SCALE,
-                Rounding::Down,
-            )?;
+    pub fn get_quote_amount(
+        &self,
+        base_amount: u64,
+        swap_amount_type: SwapAmountType,
+    ) -> Result<(u64, u64)> {
+        Ok(self
+            .sdk()
+            .get_quote_amount(base_amount, swap_amount_type.into())?)
+    }
+
+    pub fn get_quote_amount_with_parameters(
+        &self,
+        supply: u64,
+        base_amount: u64,
+        swap_amount_type: SwapAmountType,
+        rounding: Rounding,
+    ) -> Result<(u64, u64)> {
+        Ok(self.sdk().get_quote_amount_with_parameters(
+            supply,
+            base_amount,
+            swap_amount_type.into(),
+            rounding,
+        )?)
+    }
 
-        Ok((base_amount_swapped, quote_amount_swapped))
+    /// Dispatches to the quote function of the market's `CurveKind`
+    pub fn get_curve_quote_amount_with_parameters(
+        &self,
+        supply: u64,
+        base_amount: u64,
+        swap_amount_type: SwapAmountType,
+        rounding: Rounding,
+    ) -> Result<(u64, u64)> {
+        Ok(self.sdk().get_curve_quote_amount_with_parameters(
+            supply,
+            base_amount,
+            swap_amount_type.into(),
+            rounding,
+        )?)
     }
 }
 
@@ -362,4 +651,16 @@ mod tests {
 
         assert!(size < 10_240);
     }
+
+    #[test]
+    fn sdk_layout() {
+        assert_eq!(
+            std::mem::size_of::<Market>(),
+            std::mem::size_of::<token_mill_sdk::Market>()
+        );
+        assert_eq!(
+            std::mem::align_of::<Market>(),
+            std::mem::align_of::<token_mill_sdk::Market>()
+        );
+    }
 }
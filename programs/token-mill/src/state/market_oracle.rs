@@ -0,0 +1,158 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    constant::MARKET_ORACLE_OBSERVATIONS, errors::TokenMillError, manager::swap_manager::SwapType,
+    state::Market,
+};
+
+pub const MARKET_ORACLE_PDA_SEED: &str = "market_oracle";
+
+#[derive(Debug, AnchorSerialize, AnchorDeserialize, InitSpace, Copy, Clone, Default, PartialEq)]
+pub struct Observation {
+    pub timestamp: i64,
+    /// Sum of the bid spot price multiplied by the seconds it lasted
+    pub cumulative_bid_price: u128,
+    /// Sum of the ask spot price multiplied by the seconds it lasted
+    pub cumulative_ask_price: u128,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone, Copy, PartialEq)]
+pub struct MarketTwap {
+    pub bid_price: u64,
+    pub ask_price: u64,
+}
+
+/// Ring buffer of the market's cumulative spot prices, written at most once per second.
+/// Every swap of the market updates it, prices are then constant between two observations.
+#[account]
+#[derive(Debug, InitSpace)]
+pub struct MarketOracle {
+    pub bump: u8,
+    pub market: Pubkey,
+    /// Spot prices since the latest observation
+    pub bid_price: u64,
+    pub ask_price: u64,
+    /// Index of the latest observation
+    pub observation_index: u16,
+    /// Number of observations written, up to `MARKET_ORACLE_OBSERVATIONS`
+    pub observation_count: u16,
+    pub observations: [Observation; MARKET_ORACLE_OBSERVATIONS],
+}
+
+impl MarketOracle {
+    pub fn initialize(
+        &mut self,
+        bump: u8,
+        market_key: Pubkey,
+        market: &Market,
+        current_time: i64,
+    ) -> Result<()> {
+        self.bump = bump;
+        self.market = market_key;
+        self.observation_index = 0;
+        self.observation_count = 1;
+        self.observations[0] = Observation {
+            timestamp: current_time,
+            cumulative_bid_price: 0,
+            cumulative_ask_price: 0,
+        };
+
+        self.set_prices(market)
+    }
+
+    /// Accrues the previous spot prices up to `current_time`, then records the market's new ones.
+    pub fn update(&mut self, market: &Market, current_time: i64) -> Result<()> {
+        let latest = self.observations[usize::from(self.observation_index)];
+
+        if current_time > latest.timestamp {
+            let (cumulative_bid_price, cumulative_ask_price) =
+                self.get_cumulative_prices_since(&latest, current_time);
+
+            self.observation_index =
+                (self.observation_index + 1) % MARKET_ORACLE_OBSERVATIONS as u16;
+            self.observation_count = self
+                .observation_count
+                .saturating_add(1)
+                .min(MARKET_ORACLE_OBSERVATIONS as u16);
+            self.observations[usize::from(self.observation_index)] = Observation {
+                timestamp: current_time,
+                cumulative_bid_price,
+                cumulative_ask_price,
+            };
+        }
+
+        self.set_prices(market)
+    }
+
+    /// Time-weighted average prices over the last `window` seconds.
+    pub fn get_twap(&self, current_time: i64, window: i64) -> Result<MarketTwap> {
+        if window <= 0 {
+            return Err(TokenMillError::InvalidTwapWindow.into());
+        }
+
+        let latest = self.observations[usize::from(self.observation_index)];
+        let start_time = current_time - window;
+
+        let (end_bid, end_ask) = self.get_cumulative_prices_since(&latest, current_time);
+        let (start_bid, start_ask) = if start_time >= latest.timestamp {
+            self.get_cumulative_prices_since(&latest, start_time)
+        } else {
+            self.get_cumulative_prices_before(start_time)?
+        };
+
+        let window = u128::from(window.unsigned_abs());
+
+        Ok(MarketTwap {
+            bid_price: u64::try_from((end_bid - start_bid) / window)?,
+            ask_price: u64::try_from((end_ask - start_ask) / window)?,
+        })
+    }
+
+    fn set_prices(&mut self, market: &Market) -> Result<()> {
+        self.bid_price = market.get_spot_price(SwapType::Sell)?;
+        self.ask_price = market.get_spot_price(SwapType::Buy)?;
+
+        Ok(())
+    }
+
+    fn get_cumulative_prices_since(
+        &self,
+        observation: &Observation,
+        timestamp: i64,
+    ) -> (u128, u128) {
+        let elapsed = u128::from((timestamp - observation.timestamp).unsigned_abs());
+
+        (
+            observation.cumulative_bid_price + u128::from(self.bid_price) * elapsed,
+            observation.cumulative_ask_price + u128::from(self.ask_price) * elapsed,
+        )
+    }
+
+    /// Interpolates the cumulative prices at `timestamp`, older than the latest observation.
+    fn get_cumulative_prices_before(&self, timestamp: i64) -> Result<(u128, u128)> {
+        let length = MARKET_ORACLE_OBSERVATIONS;
+        let latest_index = usize::from(self.observation_index);
+
+        for i in 1..usize::from(self.observation_count) {
+            let after = self.observations[(latest_index + length - i + 1) % length];
+            let before = self.observations[(latest_index + length - i) % length];
+
+            if before.timestamp <= timestamp {
+                let elapsed = u128::from((timestamp - before.timestamp).unsigned_abs());
+                let duration = u128::from((after.timestamp - before.timestamp).unsigned_abs());
+
+                return Ok((
+                    before.cumulative_bid_price
+                        + (after.cumulative_bid_price - before.cumulative_bid_price) * elapsed
+                            / duration,
+                    before.cumulative_ask_price
+                        + (after.cumulative_ask_price - before.cumulative_ask_price) * elapsed
+                            / duration,
+                ));
+            }
+        }
+
+        // The window starts before the oldest observation
+        Err(TokenMillError::InvalidTwapWindow.into())
+    }
+}
@@ -0,0 +1,88 @@
+use anchor_lang::prelude::*;
+
+use crate::{manager::swap_manager::SwapType, state::Market};
+
+pub const MARKET_STATS_PDA_SEED: &str = "market_stats";
+
+/// Trading statistics of a market since the account was created, updated by every swap of the market.
+#[account]
+#[derive(Debug, InitSpace)]
+pub struct MarketStats {
+    pub bump: u8,
+    pub market: Pubkey,
+
+    pub buy_base_volume: u128,
+    pub buy_quote_volume: u128,
+    pub sell_base_volume: u128,
+    pub sell_quote_volume: u128,
+    pub trade_count: u64,
+
+    pub creator_fees: u128,
+    pub staking_fees: u128,
+    pub protocol_fees: u128,
+    pub referral_fees: u128,
+
+    /// Highest circulating supply reached
+    pub max_circulating_supply: u64,
+    /// Ask price when the highest circulating supply was reached
+    pub max_circulating_supply_ask_price: u64,
+    /// 0 until the first swap
+    pub last_trade_ts: i64,
+}
+
+impl MarketStats {
+    pub fn initialize(&mut self, bump: u8, market_key: Pubkey, market: &Market) -> Result<()> {
+        self.bump = bump;
+        self.market = market_key;
+        self.max_circulating_supply = market.circulating_supply();
+
+        if market.are_prices_set() {
+            self.max_circulating_supply_ask_price = market.get_spot_price(SwapType::Buy)?;
+        }
+
+        Ok(())
+    }
+
+    /// Records a swap, `market` being the market after the swap.
+    /// `fees` are the (creator, staking, protocol, referral) fees of the swap.
+    pub fn record_swap(
+        &mut self,
+        market: &Market,
+        swap_type: SwapType,
+        base_amount: u64,
+        quote_amount: u64,
+        fees: (u64, u64, u64, u64),
+        current_time: i64,
+    ) -> Result<()> {
+        match swap_type {
+            SwapType::Buy => {
+                self.buy_base_volume += u128::from(base_amount);
+                self.buy_quote_volume += u128::from(quote_amount);
+            }
+            SwapType::Sell => {
+                self.sell_base_volume += u128::from(base_amount);
+                self.sell_quote_volume += u128::from(quote_amount);
+            }
+        }
+
+        self.trade_count += 1;
+
+        let (creator_fee, staking_fee, protocol_fee, referral_fee) = fees;
+
+        self.creator_fees += u128::from(creator_fee);
+        self.staking_fees += u128::from(staking_fee);
+        self.protocol_fees += u128::from(protocol_fee);
+        self.referral_fees += u128::from(referral_fee);
+
+        let circulating_supply = market.circulating_supply();
+
+        if circulating_supply > self.max_circulating_supply {
+            self.max_circulating_supply = circulating_supply;
+            self.max_circulating_supply_ask_price = market.get_spot_price(SwapType::Buy)?;
+        }
+
+        self.last_trade_ts = current_time;
+
+        Ok(())
+    }
+}
@@ -1,13 +1,25 @@
 pub mod config;
+pub mod dca_order;
+pub mod launch_allocation;
+pub mod limit_order;
 pub mod market;
+pub mod market_oracle;
+pub mod market_stats;
 pub mod quote_token_badge;
 pub mod referral;
 pub mod staking;
+pub mod transfer_hook_badge;
 pub mod vesting;
 
 pub use config::*;
+pub use dca_order::*;
+pub use launch_allocation::*;
+pub use limit_order::*;
 pub use market::*;
+pub use market_oracle::*;
+pub use market_stats::*;
 pub use quote_token_badge::*;
 pub use referral::*;
 pub use staking::*;
+pub use transfer_hook_badge::*;
 pub use vesting::*;
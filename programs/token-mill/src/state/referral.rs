@@ -1,4 +1,7 @@
 use anchor_lang::prelude::*;
+use token_mill_sdk::ReferralFeeShares;
+
+use crate::TokenMillConfig;
 
 pub const REFERRAL_ACCOUNT_PDA_SEED: &str = "referral";
 
@@ -8,13 +11,36 @@ pub struct ReferralAccount {
     pub bump: u8,
     pub config: Pubkey,
     pub referrer: Pubkey,
+    /// Referral account of the referrer's own referrer, paid the config's `sub_referral_fee_share` of the referral fees
+    pub parent: Option<Pubkey>,
+    /// Referral fee share negotiated with the referrer, replacing the config's `referral_fee_share`
+    pub fee_share_override: Option<u16>,
 }
 
 impl ReferralAccount {
-    pub fn initialize(&mut self, bump: u8, config: Pubkey, referrer: Pubkey) -> Result<()> {
+    pub fn initialize(
+        &mut self,
+        bump: u8,
+        config: Pubkey,
+        referrer: Pubkey,
+        parent: Option<Pubkey>,
+    ) -> Result<()> {
         self.bump = bump;
         self.config = config;
         self.referrer = referrer;
+        self.parent = parent;
+        self.fee_share_override = None;
         Ok(())
     }
+
+    /// Fee shares of the swaps it refers, its parent is only paid if it has one
+    pub fn get_fee_shares(&self, config: &TokenMillConfig) -> ReferralFeeShares {
+        ReferralFeeShares {
+            referral_fee_share: self.fee_share_override.unwrap_or(config.referral_fee_share),
+            sub_referral_fee_share: match self.parent {
+                Some(_) => config.sub_referral_fee_share,
+                None => 0,
+            },
+        }
+    }
 }
@@ -0,0 +1,26 @@
+use anchor_lang::prelude::*;
+
+pub const TRANSFER_HOOK_BADGE_PDA_SEED: &str = "transfer_hook_badge";
+
+#[derive(Debug, AnchorSerialize, AnchorDeserialize, Copy, Clone, InitSpace, PartialEq)]
+pub enum TransferHookBadgeStatus {
+    Disabled,
+    Enabled,
+}
+
+/// Allowlists a transfer hook program for the base tokens of new markets
+#[account]
+#[derive(InitSpace)]
+pub struct TransferHookBadge {
+    pub bump: u8,
+    pub status: TransferHookBadgeStatus,
+}
+
+impl TransferHookBadge {
+    pub fn initialize(&mut self, bump: u8) -> Result<()> {
+        self.bump = bump;
+        self.status = TransferHookBadgeStatus::Enabled;
+
+        Ok(())
+    }
+}
@@ -26,8 +26,18 @@ impl Fixture {
     }
 
     pub fn create_markets(&mut self, total_supply: u64, price_curve: Curve) {
-        self.evm_engine.create_market(total_supply, price_curve);
-        self.svm_engine.create_market(total_supply, price_curve);
+        self.create_markets_with_distinct_curves(total_supply, price_curve.clone(), price_curve);
+    }
+
+    /// `evm_price_curve` must describe the same prices as `svm_price_curve` with evenly spaced breakpoints
+    pub fn create_markets_with_distinct_curves(
+        &mut self,
+        total_supply: u64,
+        svm_price_curve: Curve,
+        evm_price_curve: Curve,
+    ) {
+        self.evm_engine.create_market(total_supply, evm_price_curve);
+        self.svm_engine.create_market(total_supply, svm_price_curve);
     }
 
     pub fn swap(&mut self, scenario: SwapScenario) -> (u64, u64, u64, u64) {
@@ -57,6 +67,13 @@ impl Fixture {
         assert!((amount_out_svm <= amount_out_evm && amount_out_svm + threshold >= amount_out_evm));
     }
 
+    pub fn swap_and_compare_with_tolerance(&mut self, scenario: SwapScenario, tolerance: u64) {
+        let (amount_in_svm, amount_out_svm, amount_in_evm, amount_out_evm) = self.swap(scenario);
+
+        assert!(amount_in_svm.abs_diff(amount_in_evm) <= tolerance);
+        assert!(amount_out_svm.abs_diff(amount_out_evm) <= tolerance);
+    }
+
     pub fn claim_fees_and_compare(&mut self) {
         let (creator_fee_evm, referral_fee_evm, protocol_fee_evm) = self.evm_engine.claim_fees();
         let (creator_fee_svm, referral_fee_svm, protocol_fee_svm) = self.svm_engine.claim_fees();
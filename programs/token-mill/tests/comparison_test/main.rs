@@ -15,26 +15,99 @@ mod evm_engine;
 pub mod fixture;
 mod svm_engine;
 
-fn fuzz_supply() -> impl Strategy<Value = u64> {
-    let minimal_interval_width = BASE_PRECISION;
-    let maximal_interval_width = MAX_TOTAL_SUPPLY / INTERVAL_NUMBER;
+fn fuzz_prices_length() -> impl Strategy<Value = usize> {
+    MIN_PRICES_LENGTH..=MAX_PRICES_LENGTH
+}
+
+fn fuzz_curve_and_supply() -> impl Strategy<Value = (Curve, u64)> {
+    fuzz_prices_length().prop_flat_map(|prices_length| {
+        let interval_number = prices_length as u64 - 1;
+
+        let minimal_interval_width = BASE_PRECISION;
+        let maximal_interval_width = MAX_TOTAL_SUPPLY / interval_number;
+
+        (minimal_interval_width..maximal_interval_width).prop_map(move |interval_width| {
+            let supply = interval_width * interval_number;
+
+            (Curve::new(prices_length, supply), supply)
+        })
+    })
+}
+
+/// Curve of fuzzed length, with the largest supply below `supply` that it can split evenly
+fn fuzz_curve_with_supply(supply: u64) -> impl Strategy<Value = (Curve, u64)> {
+    fuzz_prices_length().prop_map(move |prices_length| {
+        let interval_number = prices_length as u64 - 1;
+        let supply = supply / interval_number * interval_number;
+
+        (Curve::new(prices_length, supply), supply)
+    })
+}
 
-    (minimal_interval_width..maximal_interval_width)
-        .prop_map(|interval_width| interval_width * INTERVAL_NUMBER)
+/// Curve with breakpoints picked on a uniform grid, along with the same curve sampled on every
+/// grid point. The latter only has evenly spaced breakpoints so the EVM implementation supports it.
+fn fuzz_non_uniform_curve_with_supply(supply: u64) -> impl Strategy<Value = (Curve, Curve, u64)> {
+    (2..MAX_PRICES_LENGTH as u64)
+        .prop_flat_map(|interval_number| {
+            (
+                Just(interval_number),
+                prop::collection::vec(any::<bool>(), interval_number as usize - 1),
+                prop::collection::vec(10..1_000_000u64, interval_number as usize),
+            )
+        })
+        .prop_map(move |(interval_number, is_breakpoint, steps)| {
+            let width = supply / interval_number;
+
+            let mut grid_curve = Curve {
+                bid_prices: vec![0],
+                ask_prices: vec![0],
+                supply_breakpoints: vec![0],
+                ..Curve::default()
+            };
+            let mut curve = grid_curve.clone();
+
+            // Prices are linear between two breakpoints, i.e. the step is constant
+            let mut step = steps[0];
+
+            for i in 1..=interval_number as usize {
+                grid_curve
+                    .ask_prices
+                    .push(grid_curve.ask_prices[i - 1] + step);
+                grid_curve
+                    .bid_prices
+                    .push(grid_curve.bid_prices[i - 1] + step * 9 / 10);
+                grid_curve.supply_breakpoints.push(i as u64 * width);
+
+                if i == interval_number as usize || is_breakpoint[i - 1] {
+                    curve.ask_prices.push(grid_curve.ask_prices[i]);
+                    curve.bid_prices.push(grid_curve.bid_prices[i]);
+                    curve
+                        .supply_breakpoints
+                        .push(grid_curve.supply_breakpoints[i]);
+
+                    if i < interval_number as usize {
+                        step = steps[i];
+                    }
+                }
+            }
+
+            (curve, grid_curve, width * interval_number)
+        })
 }
 
 #[test]
 fn initial_buy_with_exact_out() {
-    fn fuzz_supply_and_swap_amount() -> impl Strategy<Value = (u64, u64)> {
-        fuzz_supply().prop_flat_map(|supply| (Just(supply), 1..supply - 1))
+    fn fuzz_supply_and_swap_amount() -> impl Strategy<Value = (Curve, u64, u64)> {
+        fuzz_curve_and_supply()
+            .prop_flat_map(|(curve, supply)| (Just(curve), Just(supply), 1..supply - 1))
     }
 
     let fixture_ref = RefCell::new(Fixture::new());
 
-    proptest!(|((supply, swap_amount) in fuzz_supply_and_swap_amount())| {
+    proptest!(|((curve, supply, swap_amount) in fuzz_supply_and_swap_amount())| {
         let mut fixture = fixture_ref.borrow_mut();
 
-        fixture.create_markets(supply, Curve::default());
+        fixture.create_markets(supply, curve);
 
         fixture.swap_and_compare(SwapScenario {
             swap_type: SwapType::Buy,
@@ -46,16 +119,17 @@ fn initial_buy_with_exact_out() {
 
 #[test]
 fn initial_buy_with_exact_in() {
-    fn fuzz_supply_and_swap_amount() -> impl Strategy<Value = (u64, u64)> {
-        fuzz_supply().prop_flat_map(|supply| (Just(supply), 1..supply - 1))
+    fn fuzz_supply_and_swap_amount() -> impl Strategy<Value = (Curve, u64, u64)> {
+        fuzz_curve_and_supply()
+            .prop_flat_map(|(curve, supply)| (Just(curve), Just(supply), 1..supply - 1))
     }
 
     let fixture_ref = RefCell::new(Fixture::new());
 
-    proptest!(|((supply, swap_amount_base) in fuzz_supply_and_swap_amount())| {
+    proptest!(|((curve, supply, swap_amount_base) in fuzz_supply_and_swap_amount())| {
         let mut fixture = fixture_ref.borrow_mut();
 
-        fixture.create_markets(supply, Curve::default());
+        fixture.create_markets(supply, curve);
 
         let market = fixture.svm_engine.get_market();
         let (_, swap_amount_quote) = market.get_quote_amount(swap_amount_base, SwapAmountType::ExactOutput)?;
@@ -72,16 +146,27 @@ fn initial_buy_with_exact_in() {
 fn sell_with_exact_in() {
     const SUPPLY: u64 = 1_000_000_000_000;
 
-    fn fuzz_position_and_swap_amount() -> impl Strategy<Value = (u64, u64)> {
-        (1..SUPPLY).prop_flat_map(|swap_position| (Just(swap_position), 0..swap_position - 1))
+    fn fuzz_position_and_swap_amount() -> impl Strategy<Value = (Curve, u64, u64, u64)> {
+        fuzz_curve_with_supply(SUPPLY).prop_flat_map(|(curve, supply)| {
+            (Just(curve), Just(supply), 1..supply).prop_flat_map(
+                |(curve, supply, swap_position)| {
+                    (
+                        Just(curve),
+                        Just(supply),
+                        Just(swap_position),
+                        0..swap_position - 1,
+                    )
+                },
+            )
+        })
     }
 
     let fixture_ref = RefCell::new(Fixture::new());
 
-    proptest!(|((curve_position, swap_amount) in fuzz_position_and_swap_amount())| {
+    proptest!(|((curve, supply, curve_position, swap_amount) in fuzz_position_and_swap_amount())| {
         let mut fixture = fixture_ref.borrow_mut();
 
-        fixture.create_markets(SUPPLY, Curve::default());
+        fixture.create_markets(supply, curve);
 
         fixture.swap_and_compare(SwapScenario {
             swap_type: SwapType::Buy,
@@ -101,16 +186,27 @@ fn sell_with_exact_in() {
 fn buy_with_exact_out() {
     const SUPPLY: u64 = 1_000_000_000_000;
 
-    fn fuzz_position_and_swap_amount() -> impl Strategy<Value = (u64, u64)> {
-        (1..SUPPLY).prop_flat_map(|swap_position| (Just(swap_position), 0..SUPPLY - swap_position))
+    fn fuzz_position_and_swap_amount() -> impl Strategy<Value = (Curve, u64, u64, u64)> {
+        fuzz_curve_with_supply(SUPPLY).prop_flat_map(|(curve, supply)| {
+            (Just(curve), Just(supply), 1..supply).prop_flat_map(
+                |(curve, supply, swap_position)| {
+                    (
+                        Just(curve),
+                        Just(supply),
+                        Just(swap_position),
+                        0..supply - swap_position,
+                    )
+                },
+            )
+        })
     }
 
     let fixture_ref = RefCell::new(Fixture::new());
 
-    proptest!(|((curve_position, swap_amount) in fuzz_position_and_swap_amount())| {
+    proptest!(|((curve, supply, curve_position, swap_amount) in fuzz_position_and_swap_amount())| {
         let mut fixture = fixture_ref.borrow_mut();
 
-        fixture.create_markets(SUPPLY, Curve::default());
+        fixture.create_markets(supply, curve);
 
         fixture.swap_and_compare(SwapScenario {
             swap_type: SwapType::Buy,
@@ -130,12 +226,17 @@ fn buy_with_exact_out() {
 fn sell_with_exact_out() {
     const SUPPLY: u64 = 1_000_000_000_000;
 
+    fn fuzz_position_and_swap_fraction() -> impl Strategy<Value = (Curve, u64, u64, u64)> {
+        fuzz_curve_with_supply(SUPPLY)
+            .prop_flat_map(|(curve, supply)| (Just(curve), Just(supply), 1..supply, 1..100_000u64))
+    }
+
     let fixture_ref = RefCell::new(Fixture::new());
 
-    proptest!(|((curve_position, swap_amount_fraction) in (1..SUPPLY, 1..100_000))| {
+    proptest!(|((curve, supply, curve_position, swap_amount_fraction) in fuzz_position_and_swap_fraction())| {
         let mut fixture = fixture_ref.borrow_mut();
 
-        fixture.create_markets(SUPPLY, Curve::default());
+        fixture.create_markets(supply, curve);
 
         fixture.swap_and_compare(SwapScenario {
             swap_type: SwapType::Buy,
@@ -186,12 +287,18 @@ fn sell_with_exact_out() {
 fn buy_with_exact_in() {
     const SUPPLY: u64 = 1_000_000_000_000;
 
+    fn fuzz_position_and_swap_fraction() -> impl Strategy<Value = (Curve, u64, u64, u64)> {
+        fuzz_curve_with_supply(SUPPLY).prop_flat_map(|(curve, supply)| {
+            (Just(curve), Just(supply), 0..supply - 1, 1..100_000u64)
+        })
+    }
+
     let fixture_ref = RefCell::new(Fixture::new());
 
-    proptest!(|((curve_position, swap_amount_fraction) in (0..SUPPLY-1, 1..100_000))| {
+    proptest!(|((curve, supply, curve_position, swap_amount_fraction) in fuzz_position_and_swap_fraction())| {
         let mut fixture = fixture_ref.borrow_mut();
 
-        fixture.create_markets(SUPPLY, Curve::default());
+        fixture.create_markets(supply, curve);
 
         fixture.swap_and_compare(SwapScenario {
             swap_type: SwapType::Buy,
@@ -213,6 +320,48 @@ fn buy_with_exact_in() {
     });
 }
 
+#[test]
+fn non_uniform_breakpoints() {
+    const SUPPLY: u64 = 1_000_000_000_000;
+
+    fn fuzz_position_and_swap_amount() -> impl Strategy<Value = (Curve, Curve, u64, u64, u64)> {
+        fuzz_non_uniform_curve_with_supply(SUPPLY).prop_flat_map(|(curve, grid_curve, supply)| {
+            (Just(curve), Just(grid_curve), Just(supply), 1..supply).prop_flat_map(
+                |(curve, grid_curve, supply, swap_position)| {
+                    (
+                        Just(curve),
+                        Just(grid_curve),
+                        Just(supply),
+                        Just(swap_position),
+                        1..=swap_position,
+                    )
+                },
+            )
+        })
+    }
+
+    let fixture_ref = RefCell::new(Fixture::new());
+
+    proptest!(|((curve, grid_curve, supply, curve_position, swap_amount) in fuzz_position_and_swap_amount())| {
+        let mut fixture = fixture_ref.borrow_mut();
+
+        fixture.create_markets_with_distinct_curves(supply, curve, grid_curve);
+
+        // Both markets describe the same prices, only the rounding of each interval differs
+        fixture.swap_and_compare_with_tolerance(SwapScenario {
+            swap_type: SwapType::Buy,
+            swap_amount_type: SwapAmountType::ExactOutput,
+            amount: curve_position,
+        }, 10);
+
+        fixture.swap_and_compare_with_tolerance(SwapScenario {
+            swap_type: SwapType::Sell,
+            swap_amount_type: SwapAmountType::ExactInput,
+            amount: swap_amount,
+        }, 10);
+    });
+}
+
 #[test]
 fn fees() {
     const SUPPLY: u64 = 1_000_000_000_000;
@@ -1,9 +1,10 @@
 use joelana_test_utils::{
     joelana_env::{
         actions::token_mill::{
-            ClaimCreatorFeesAction, ClaimReferralFeesAction, ClaimStakingRewardsAction,
-            CreateMarketAction, CreateStakePositionAction, CreateStakingAction, DepositAction,
-            SetMarketPricesAction, SwapAction, TokenMillEnv, WithdrawAction,
+            ClaimCreatorFeesAction, ClaimProtocolFeesAction, ClaimReferralFeesAction,
+            ClaimStakingRewardsAction, CreateMarketAction, CreateStakePositionAction,
+            CreateStakingAction, DepositAction, SetMarketPricesAction, SwapAction, TokenMillEnv,
+            WithdrawAction,
         },
         make_address, TokenType, ACTORS,
     },
@@ -17,22 +18,13 @@ use token_mill::{
 
 pub struct SvmEngine {
     testing_env: TokenMillEnv,
-    protocol_fee_recipient_balance: u64,
 }
 
 impl SvmEngine {
     pub fn new() -> Self {
         let testing_env = TokenMillEnv::new().with_default_quote_token_mint();
 
-        let protocol_fee_recipient_balance = testing_env.svm.get_balance(
-            &testing_env.quote_token_mint.unwrap(),
-            &make_address("dave"),
-        );
-
-        Self {
-            testing_env,
-            protocol_fee_recipient_balance,
-        }
+        Self { testing_env }
     }
 
     pub fn create_market(&mut self, total_supply: u64, price_curve: Curve) {
@@ -196,15 +188,26 @@ impl SvmEngine {
 
         let referral_fee = quote_balance_after - quote_balance_before;
 
-        let quote_balance_before = self.protocol_fee_recipient_balance;
+        self.testing_env.svm.change_payer("dave");
+
+        let quote_balance_before = self.testing_env.svm.get_balance(
+            &self.testing_env.quote_token_mint.unwrap(),
+            &self.testing_env.svm.payer,
+        );
+
+        let claim_protocol_fees_action = ClaimProtocolFeesAction::new(&self.testing_env);
+
+        self.testing_env
+            .svm
+            .execute_actions(&[&claim_protocol_fees_action])
+            .unwrap();
 
         let quote_balance_after = self.testing_env.svm.get_balance(
             &self.testing_env.quote_token_mint.unwrap(),
-            &make_address("dave"),
+            &self.testing_env.svm.payer,
         );
 
         let protocol_fee = quote_balance_after - quote_balance_before;
-        self.protocol_fee_recipient_balance = quote_balance_after;
 
         (creator_fee, referral_fee, protocol_fee)
     }
@@ -69,13 +69,24 @@ const marketBaseTokenAta = spl.getAssociatedTokenAddressSync(
 
 {
   const transaction = await program.methods
-    .createMarket("Test Market", "TM", "", new BN(1_000_000e6), 3_000, 4_000)
+    .createMarket(
+      "Test Market",
+      "TM",
+      "",
+      new BN(1_000_000e6),
+      3_000,
+      4_000,
+      null,
+      null,
+      null
+    )
     .accountsPartial({
       config,
       market,
       baseTokenMint,
       marketBaseTokenAta,
       quoteTokenBadge,
+      transferHookBadge: null,
       quoteTokenMint,
       creator: wallet.publicKey,
     })
@@ -137,14 +148,16 @@ console.log("User base token ATA created");
 {
   const bidPrices = [];
   const askPrices = [];
+  const supplyBreakpoints = [];
 
   for (let i = 0; i < 11; i++) {
     bidPrices.push(new BN(i * 9e5));
     askPrices.push(new BN(i * 1e6));
+    supplyBreakpoints.push(new BN(i * 100_000e6));
   }
 
   const transaction = await program.methods
-    .setMarketPrices(bidPrices, askPrices)
+    .setMarketPrices(bidPrices, askPrices, supplyBreakpoints, { linear: {} })
     .accountsPartial({
       market,
       creator: wallet.publicKey,
@@ -71,6 +71,7 @@ if (!userReferralAccountInfo) {
     .accountsPartial({
       config,
       referralAccount: userReferralAccount,
+      parentReferralAccount: program.programId,
       user: wallet.publicKey,
     })
     .signers([wallet.payer])
@@ -90,6 +91,33 @@ if (!userReferralAccountInfo) {
   console.log("Referral account created");
 }
 
+// Referral accounts created before the parent and fee share override were added
+const legacyReferralAccountSize = 8 + 1 + 32 + 32;
+
+if (userReferralAccountInfo?.data.length === legacyReferralAccountSize) {
+  const transaction = await program.methods
+    .migrateReferralAccount()
+    .accountsPartial({
+      referralAccount: userReferralAccount,
+      payer: wallet.publicKey,
+    })
+    .signers([wallet.payer])
+    .transaction();
+
+  const transactionSignature = await connection.sendTransaction(transaction, [
+    wallet.payer,
+  ]);
+
+  const result = await connection.confirmTransaction(transactionSignature);
+
+  if (result.value.err) {
+    console.log("Referral account migration failed:", result.value.err);
+    process.exit(1);
+  }
+
+  console.log("Referral account migrated");
+}
+
 const u64Max = new BN(2).pow(new BN(64)).sub(new BN(1));
 
 const swapActions = [];
@@ -101,7 +129,7 @@ swapActions.push([{ sell: {} }, { exactInput: {} }, new BN(100e6), new BN(0)]);
 
 for (const action of swapActions) {
   const transaction = await program.methods
-    .swap(...action)
+    .swap(...action, null, null, null)
     .accountsPartial({
       market,
       baseTokenMint,
@@ -110,8 +138,13 @@ for (const action of swapActions) {
       marketQuoteTokenAta,
       userBaseTokenAta,
       userQuoteTokenAta,
-      protocolQuoteTokenAta: userQuoteTokenAta,
       referralTokenAccount: referralAccountAta.address,
+      referralAccount: userReferralAccount,
+      parentReferralTokenAccount: program.programId,
+      marketOracle: program.programId,
+      marketStats: program.programId,
+      launchAllocation: program.programId,
+      stakePosition: program.programId,
       user: wallet.publicKey,
       baseTokenProgram: spl.TOKEN_2022_PROGRAM_ID,
       quoteTokenProgram: spl.TOKEN_PROGRAM_ID,
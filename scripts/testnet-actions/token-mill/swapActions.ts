@@ -59,7 +59,7 @@ swapActions.push([{ sell: {} }, { exactInput: {} }, new BN(100e6), new BN(0)]);
 
 for (const action of swapActions) {
   const transaction = await program.methods
-    .swap(...action)
+    .swap(...action, null, null, null)
     .accountsPartial({
       config,
       market,
@@ -69,8 +69,13 @@ for (const action of swapActions) {
       marketQuoteTokenAta,
       userBaseTokenAta,
       userQuoteTokenAta,
-      protocolQuoteTokenAta: userQuoteTokenAta, // Here protocol fee recipient is the user
       referralTokenAccount: program.programId,
+      referralAccount: program.programId,
+      parentReferralTokenAccount: program.programId,
+      marketOracle: program.programId,
+      marketStats: program.programId,
+      launchAllocation: program.programId,
+      stakePosition: program.programId,
       user: wallet.publicKey,
       baseTokenProgram: spl.TOKEN_2022_PROGRAM_ID,
       quoteTokenProgram: spl.TOKEN_PROGRAM_ID,
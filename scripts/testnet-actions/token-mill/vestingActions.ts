@@ -122,7 +122,7 @@ swapActions.push([{ buy: {} }, { exactOutput: {} }, new BN(100e6), u64Max]);
 
 for (const action of swapActions) {
   const transaction = await program.methods
-    .swap(...action)
+    .swap(...action, null, null, null)
     .accountsPartial({
       market,
       baseTokenMint,
@@ -131,8 +131,13 @@ for (const action of swapActions) {
       marketQuoteTokenAta,
       userBaseTokenAta,
       userQuoteTokenAta,
-      protocolQuoteTokenAta: userQuoteTokenAta,
       referralTokenAccount: program.programId,
+      referralAccount: program.programId,
+      parentReferralTokenAccount: program.programId,
+      marketOracle: program.programId,
+      marketStats: program.programId,
+      launchAllocation: program.programId,
+      stakePosition: program.programId,
       user: wallet.publicKey,
       baseTokenProgram: spl.TOKEN_2022_PROGRAM_ID,
       quoteTokenProgram: spl.TOKEN_PROGRAM_ID,